@@ -0,0 +1,283 @@
+//! Condition Matcher Benchmarks for Tooka
+//!
+//! This benchmark suite measures the cost of each condition type in
+//! `src/file/file_match.rs` in isolation, by evaluating rules with a single
+//! condition set against realistic files.
+//!
+//! ## Purpose
+//! - Rank condition types by cost, see the performance notes in `file_match.rs`
+//! - Show the gap between compiling a regex per call and reusing a compiled one
+//! - Compare cheap MIME detection by extension with magic-byte sniffing
+//! - Compare metadata lookups on files with and without EXIF data
+//!
+//! ## Adding New Benchmarks
+//! Add a `group.bench` call in `main()` with a `Conditions` that sets only the
+//! condition under test.
+
+use regex::Regex;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tooka::file::file_match::match_rule_matcher;
+use tooka::rules::rule::{Conditions, DateRange, MetadataField, Range};
+
+/// Minimum measurement time per benchmark
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// Result of a single benchmark
+struct Measurement {
+    name: String,
+    iterations: u32,
+    total: Duration,
+}
+
+impl Measurement {
+    fn per_iteration(&self) -> Duration {
+        self.total / self.iterations
+    }
+}
+
+/// Named collection of related measurements
+struct BenchmarkGroup {
+    name: String,
+    measurements: Vec<Measurement>,
+}
+
+impl BenchmarkGroup {
+    fn new(name: &str) -> Self {
+        println!("📊 {name}");
+        println!("─────────────────────────────────────────");
+        Self {
+            name: name.to_string(),
+            measurements: Vec::new(),
+        }
+    }
+
+    /// Runs `routine` repeatedly until `MEASUREMENT_TIME` has elapsed
+    fn bench<F: FnMut()>(&mut self, name: &str, mut routine: F) {
+        // Warm up caches and lazily initialized statics
+        routine();
+
+        let mut iterations = 0;
+        let start = Instant::now();
+        while start.elapsed() < MEASUREMENT_TIME || iterations == 0 {
+            routine();
+            iterations += 1;
+        }
+
+        let measurement = Measurement {
+            name: name.to_string(),
+            iterations,
+            total: start.elapsed(),
+        };
+        println!(
+            "  {:<28} {:>12?}/iter  ({} iterations)",
+            measurement.name,
+            measurement.per_iteration(),
+            measurement.iterations
+        );
+        self.measurements.push(measurement);
+    }
+
+    /// Prints every measurement relative to the fastest one
+    fn finish(self) {
+        let fastest = self
+            .measurements
+            .iter()
+            .map(Measurement::per_iteration)
+            .min()
+            .unwrap_or_default()
+            .max(Duration::from_nanos(1));
+        println!("Relative cost in group '{}':", self.name);
+        for m in &self.measurements {
+            println!(
+                "  {:<28} {:>8.1}x",
+                m.name,
+                m.per_iteration().as_secs_f64() / fastest.as_secs_f64()
+            );
+        }
+        println!();
+    }
+}
+
+// ============================================================================
+// Fixtures
+// ============================================================================
+
+/// Scratch directory under the system temp dir, removed on drop
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("tooka_bench_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path).expect("Failed to create scratch dir");
+        Self(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn empty_conditions() -> Conditions {
+    Conditions {
+        any: None,
+        filename: None,
+        extensions: None,
+        path: None,
+        path_any: None,
+        exclude_path: None,
+        min_depth: None,
+        max_depth: None,
+        size_kb: None,
+        is_empty_file: None,
+        mime_type: None,
+        created_date: None,
+        modified_date: None,
+        file_age_days: None,
+        is_symlink: None,
+        hardlink_count: None,
+        permissions: None,
+        case_sensitive: None,
+        content_matches: None,
+        content_max_bytes: None,
+        content_hash: None,
+        metadata: None,
+        all: None,
+        any_of: None,
+    }
+}
+
+/// Builds a minimal JPEG whose APP1 segment holds an EXIF `Make` tag of "Tooka"
+fn jpeg_with_exif() -> Vec<u8> {
+    let mut tiff = Vec::new();
+    tiff.extend_from_slice(b"MM\0\x2a\0\0\0\x08"); // big-endian header, IFD0 at offset 8
+    tiff.extend_from_slice(&1u16.to_be_bytes()); // one IFD entry
+    tiff.extend_from_slice(&0x010Fu16.to_be_bytes()); // Make
+    tiff.extend_from_slice(&2u16.to_be_bytes()); // ASCII
+    tiff.extend_from_slice(&6u32.to_be_bytes()); // "Tooka\0"
+    tiff.extend_from_slice(&26u32.to_be_bytes()); // value offset
+    tiff.extend_from_slice(&0u32.to_be_bytes()); // no next IFD
+    tiff.extend_from_slice(b"Tooka\0");
+
+    let mut app1 = b"Exif\0\0".to_vec();
+    app1.extend_from_slice(&tiff);
+
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&(app1.len() as u16 + 2).to_be_bytes());
+    jpeg.extend_from_slice(&app1);
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    jpeg
+}
+
+/// Builds a file of `len` bytes starting with the PNG magic bytes
+fn png_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+    bytes.resize(len, 0);
+    bytes
+}
+
+// ============================================================================
+// Main Benchmark Runner
+// ============================================================================
+
+fn main() {
+    println!("🚀 Tooka Condition Matcher Benchmarks\n");
+
+    let dir = ScratchDir::new("matcher");
+    let report = dir.path().join("report_2024_0042.pdf");
+    fs::write(&report, vec![0u8; 64 * 1024]).expect("Failed to write report");
+    let photo = dir.path().join("photo_exif.jpg");
+    fs::write(&photo, jpeg_with_exif()).expect("Failed to write photo");
+    let plain_photo = dir.path().join("photo_plain.jpg");
+    fs::write(&plain_photo, vec![0u8; 64 * 1024]).expect("Failed to write photo");
+    let no_extension = dir.path().join("download");
+    fs::write(&no_extension, png_bytes(64 * 1024)).expect("Failed to write download");
+
+    let run = |file: &Path, conditions: &Conditions| {
+        black_box(match_rule_matcher(black_box(file), conditions))
+    };
+
+    let mut group = BenchmarkGroup::new("cheap_conditions");
+    let mut when = empty_conditions();
+    when.extensions = Some(vec!["txt".into(), "md".into(), "pdf".into()]);
+    group.bench("extensions", || {
+        run(&report, &when);
+    });
+    let mut when = empty_conditions();
+    when.size_kb = Some(Range {
+        min: Some(16),
+        max: Some(1024),
+    });
+    group.bench("size_kb", || {
+        run(&report, &when);
+    });
+    let mut when = empty_conditions();
+    when.is_symlink = Some(false);
+    group.bench("is_symlink", || {
+        run(&report, &when);
+    });
+    let mut when = empty_conditions();
+    when.modified_date = Some(DateRange {
+        from: Some("2000-01-01".into()),
+        to: None,
+    });
+    group.bench("modified_date", || {
+        run(&report, &when);
+    });
+    group.finish();
+
+    let mut group = BenchmarkGroup::new("patterns");
+    let pattern = r"^report_\d{4}_\d+\.pdf$";
+    let mut when = empty_conditions();
+    when.filename = Some(pattern.into());
+    group.bench("filename_regex_uncached", || {
+        run(&report, &when);
+    });
+    let compiled = Regex::new(pattern).expect("Pattern should compile");
+    group.bench("filename_regex_compiled", || {
+        let name = report.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        black_box(compiled.is_match(black_box(name)));
+    });
+    let mut when = empty_conditions();
+    when.path = Some("**/report_*.pdf".into());
+    group.bench("path_glob", || {
+        run(&report, &when);
+    });
+    group.finish();
+
+    let mut group = BenchmarkGroup::new("content_conditions");
+    let mut when = empty_conditions();
+    when.mime_type = Some("image/*".into());
+    group.bench("mime_type_by_extension", || {
+        run(&plain_photo, &when);
+    });
+    group.bench("mime_type_sniffed", || {
+        run(&no_extension, &when);
+    });
+    let mut when = empty_conditions();
+    when.metadata = Some(vec![MetadataField {
+        key: "EXIF:Make".into(),
+        value: Some("Tooka".into()),
+        value_type: Default::default(),
+        comparison: Default::default(),
+    }]);
+    group.bench("metadata_exif_present", || {
+        run(&photo, &when);
+    });
+    group.bench("metadata_exif_absent", || {
+        run(&plain_photo, &when);
+    });
+    group.finish();
+
+    println!(
+        "💡 Every condition set on a rule is evaluated, so avoid expensive ones where cheap ones suffice"
+    );
+}
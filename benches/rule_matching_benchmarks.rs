@@ -0,0 +1,363 @@
+//! Rule Matching Benchmarks for Tooka
+//!
+//! This benchmark suite measures how `match_rule_matcher` and `sort_files`
+//! scale with the number of rules and files, and what the `Rule::applicable_to`
+//! extension pre-filter saves.
+//!
+//! ## Purpose
+//! - Detect regressions in the matching hot path (`src/file/file_match.rs`)
+//! - Compare the relative cost of different condition types
+//! - Track end-to-end sorting throughput on synthetic directory trees
+//!
+//! ## Adding New Benchmarks
+//! Add a new `BenchmarkGroup` in `main()` and call `bench` once per parameter,
+//! using a `BenchmarkId` to label the measured input size.
+
+use std::fmt;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tooka::core::sorter::sort_files;
+use tooka::file::file_match::match_rule_matcher;
+use tooka::rules::rule::{Action, Conditions, ConflictStrategy, MoveAction, Range, Rule};
+use tooka::rules::rules_file::RulesFile;
+
+/// Rule counts used for the scaling benchmarks
+const RULE_COUNTS: [usize; 4] = [1, 10, 50, 100];
+/// File counts used for the `sort_files` benchmarks
+const FILE_COUNTS: [usize; 2] = [1_000, 10_000];
+/// Rule count of the `prefilter` benchmarks
+const PREFILTER_RULES: usize = 50;
+/// File count of the `prefilter` benchmarks
+const PREFILTER_FILES: usize = 10_000;
+/// Minimum measurement time per benchmark
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// Builds a set of `count` rules for one matching scenario
+type RuleBuilder = fn(usize, &Path) -> Vec<Rule>;
+
+/// Identifies a single parameterized benchmark, e.g. `extension_only/50`
+struct BenchmarkId {
+    function: String,
+    parameter: usize,
+}
+
+impl BenchmarkId {
+    fn new(function: &str, parameter: usize) -> Self {
+        Self {
+            function: function.to_string(),
+            parameter,
+        }
+    }
+}
+
+impl fmt::Display for BenchmarkId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.function, self.parameter)
+    }
+}
+
+/// Result of a single parameterized benchmark
+struct Measurement {
+    id: BenchmarkId,
+    iterations: u32,
+    total: Duration,
+}
+
+impl Measurement {
+    fn per_iteration(&self) -> Duration {
+        self.total / self.iterations
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "  {:<28} {:>12?}/iter  ({} iterations)",
+            self.id.to_string(),
+            self.per_iteration(),
+            self.iterations
+        )
+    }
+}
+
+/// Named collection of related measurements
+struct BenchmarkGroup {
+    name: String,
+    measurements: Vec<Measurement>,
+}
+
+impl BenchmarkGroup {
+    fn new(name: &str) -> Self {
+        println!("📊 {name}");
+        println!("─────────────────────────────────────────");
+        Self {
+            name: name.to_string(),
+            measurements: Vec::new(),
+        }
+    }
+
+    /// Runs `routine` repeatedly until `MEASUREMENT_TIME` has elapsed
+    fn bench<F: FnMut()>(&mut self, id: BenchmarkId, mut routine: F) {
+        // Warm up caches and lazily initialized statics
+        routine();
+
+        let mut iterations = 0;
+        let start = Instant::now();
+        while start.elapsed() < MEASUREMENT_TIME || iterations == 0 {
+            routine();
+            iterations += 1;
+        }
+
+        let measurement = Measurement {
+            id,
+            iterations,
+            total: start.elapsed(),
+        };
+        println!("{measurement}");
+        self.measurements.push(measurement);
+    }
+
+    fn finish(self) {
+        println!(
+            "Finished group '{}' ({} benchmark(s))\n",
+            self.name,
+            self.measurements.len()
+        );
+    }
+}
+
+// ============================================================================
+// Fixtures
+// ============================================================================
+
+/// Scratch directory under the system temp dir, removed on drop
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("tooka_bench_{name}_{}", std::process::id()));
+        fs::create_dir_all(&path).expect("Failed to create scratch dir");
+        Self(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn empty_conditions() -> Conditions {
+    Conditions {
+        any: None,
+        filename: None,
+        extensions: None,
+        path: None,
+        path_any: None,
+        exclude_path: None,
+        min_depth: None,
+        max_depth: None,
+        size_kb: None,
+        is_empty_file: None,
+        mime_type: None,
+        created_date: None,
+        modified_date: None,
+        file_age_days: None,
+        is_symlink: None,
+        hardlink_count: None,
+        permissions: None,
+        case_sensitive: None,
+        content_matches: None,
+        content_max_bytes: None,
+        content_hash: None,
+        metadata: None,
+        all: None,
+        any_of: None,
+    }
+}
+
+/// Builds a rule that never matches the synthetic `.jpg` files,
+/// so every rule in the set is evaluated
+fn make_rule(index: usize, when: Conditions, dest: &Path) -> Rule {
+    Rule {
+        id: format!("rule_{index}"),
+        name: format!("Rule {index}"),
+        enabled: true,
+        description: None,
+        priority: 1,
+        when,
+        then: vec![Action::Move(MoveAction {
+            to: dest
+                .join(format!("rule_{index}"))
+                .to_string_lossy()
+                .to_string(),
+            preserve_structure: false,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict: ConflictStrategy::Overwrite,
+        })],
+        after: None,
+        tags: None,
+    }
+}
+
+fn extension_rules(count: usize, dest: &Path) -> Vec<Rule> {
+    (0..count)
+        .map(|i| {
+            let mut when = empty_conditions();
+            when.extensions = Some(vec![format!("ext{i}"), "png".to_string()]);
+            make_rule(i, when, dest)
+        })
+        .collect()
+}
+
+fn regex_rules(count: usize, dest: &Path) -> Vec<Rule> {
+    (0..count)
+        .map(|i| {
+            let mut when = empty_conditions();
+            when.filename = Some(format!(r"^report_{i}_\d+\.pdf$"));
+            make_rule(i, when, dest)
+        })
+        .collect()
+}
+
+fn mime_rules(count: usize, dest: &Path) -> Vec<Rule> {
+    (0..count)
+        .map(|i| {
+            let mut when = empty_conditions();
+            when.mime_type = Some(if i % 2 == 0 { "video/*" } else { "text/plain" }.to_string());
+            make_rule(i, when, dest)
+        })
+        .collect()
+}
+
+fn size_rules(count: usize, dest: &Path) -> Vec<Rule> {
+    (0..count)
+        .map(|i| {
+            let mut when = empty_conditions();
+            when.size_kb = Some(Range {
+                min: Some(1024 + i as u64),
+                max: None,
+            });
+            make_rule(i, when, dest)
+        })
+        .collect()
+}
+
+/// Rules that require an extension none of the synthetic files have, plus a
+/// MIME type so a full match has to look at the file
+fn prefilter_rules(count: usize, dest: &Path) -> Vec<Rule> {
+    extension_rules(count, dest)
+        .into_iter()
+        .map(|mut rule| {
+            rule.when.mime_type = Some("image/*".to_string());
+            rule
+        })
+        .collect()
+}
+
+/// Creates `count` small files spread over a few nested directories
+fn synthetic_tree(count: usize) -> (ScratchDir, Vec<PathBuf>) {
+    let dir = ScratchDir::new(&format!("tree_{count}"));
+    let files = (0..count)
+        .map(|i| {
+            let sub = dir.path().join(format!("dir_{}", i % 16));
+            fs::create_dir_all(&sub).expect("Failed to create directory");
+            let ext = ["jpg", "txt", "pdf", "log"][i % 4];
+            let path = sub.join(format!("file_{i}.{ext}"));
+            fs::write(&path, b"tooka benchmark content").expect("Failed to write file");
+            path
+        })
+        .collect();
+    (dir, files)
+}
+
+// ============================================================================
+// Main Benchmark Runner
+// ============================================================================
+
+fn main() {
+    println!("🚀 Tooka Rule Matching Benchmarks\n");
+
+    let dir = ScratchDir::new("matching");
+    let file = dir.path().join("photo_0001.jpg");
+    fs::write(&file, vec![0u8; 4096]).expect("Failed to write synthetic file");
+    let dest = dir.path().join("dest");
+
+    let scenarios: [(&str, RuleBuilder); 4] = [
+        ("extension_only", extension_rules),
+        ("regex_filename", regex_rules),
+        ("mime_type", mime_rules),
+        ("size_range", size_rules),
+    ];
+
+    let mut group = BenchmarkGroup::new("rule_matching");
+    for (name, build_rules) in scenarios {
+        for count in RULE_COUNTS {
+            let rules = build_rules(count, &dest);
+            group.bench(BenchmarkId::new(name, count), || {
+                let matched = rules
+                    .iter()
+                    .filter(|rule| match_rule_matcher(black_box(&file), &rule.when))
+                    .count();
+                black_box(matched);
+            });
+        }
+    }
+    group.finish();
+
+    let mut group = BenchmarkGroup::new("sort_files");
+    for count in FILE_COUNTS {
+        let (tree, files) = synthetic_tree(count);
+        let rules_file = RulesFile {
+            rules: extension_rules(10, &dest),
+        };
+        group.bench(BenchmarkId::new("dry_run_10_rules", count), || {
+            let results = sort_files(
+                &files,
+                tree.path(),
+                &rules_file,
+                true,
+                None::<fn(usize, usize)>,
+            )
+            .expect("sort_files should succeed");
+            black_box(results);
+        });
+    }
+    group.finish();
+
+    let mut group = BenchmarkGroup::new("prefilter");
+    let (_tree, files) = synthetic_tree(PREFILTER_FILES);
+    let rules = prefilter_rules(PREFILTER_RULES, &dest);
+    group.bench(BenchmarkId::new("full_match", PREFILTER_FILES), || {
+        let matched = files
+            .iter()
+            .flat_map(|file| rules.iter().map(move |rule| (file, rule)))
+            .filter(|(file, rule)| match_rule_matcher(black_box(file), &rule.when))
+            .count();
+        black_box(matched);
+    });
+    group.bench(BenchmarkId::new("applicable_to", PREFILTER_FILES), || {
+        let matched = files
+            .iter()
+            .flat_map(|file| rules.iter().map(move |rule| (file, rule)))
+            .filter(|(file, rule)| {
+                rule.applicable_to(black_box(file)) && match_rule_matcher(file, &rule.when)
+            })
+            .count();
+        black_box(matched);
+    });
+    group.finish();
+
+    println!("💡 Compare these numbers before and after changes to src/file/file_match.rs");
+}
@@ -0,0 +1,22 @@
+//! Fuzz target for rules file parsing.
+//!
+//! Feeds arbitrary input to `RulesFile::from_yaml_str` and deep-validates
+//! every rule that parses. Any panic or abort is a bug: malformed rule files
+//! must surface as `TookaError` values.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tooka::core::error::TookaError;
+use tooka::rules::rules_file::RulesFile;
+
+fuzz_target!(|data: &[u8]| {
+    let input = std::str::from_utf8(data).unwrap_or("");
+
+    let parsed: Result<RulesFile, TookaError> = RulesFile::from_yaml_str(input);
+    if let Ok(rules_file) = parsed {
+        for rule in &rules_file.rules {
+            let _ = rule.validate(true);
+        }
+    }
+});
@@ -51,15 +51,16 @@ pub fn header(title: &str) {
 
 pub fn rule_table_header() {
     println!(
-        "{} | {} | {}",
+        "{} | {} | {} | {}",
         "Rule ID".bright_cyan().bold(),
         "Name".bright_cyan().bold(),
+        "Conditions".bright_cyan().bold(),
         "Enabled".bright_cyan().bold()
     );
-    println!("{}", "─".repeat(80).bright_black());
+    println!("{}", "─".repeat(95).bright_black());
 }
 
-pub fn rule_table_row(id: &str, name: &str, enabled: bool) {
+pub fn rule_table_row(id: &str, name: &str, conditions: usize, enabled: bool) {
     let status = if enabled {
         "✓ Enabled".green()
     } else {
@@ -67,13 +68,40 @@ pub fn rule_table_row(id: &str, name: &str, enabled: bool) {
     };
 
     println!(
-        "{:<30} | {:<30} | {}",
+        "{:<30} | {:<30} | {:<10} | {}",
         id.bright_white(),
         name.white(),
+        conditions,
         status
     );
 }
 
+/// Asks a yes/no question on stdin, defaulting to no
+pub fn confirm(question: &str) -> std::io::Result<bool> {
+    print!(
+        "{} {} ",
+        "❓".yellow(),
+        format!("{question} [y/N]").bright_white()
+    );
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Formats a count with thousands separators, e.g. `1,247`
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
 pub fn progress_style() -> indicatif::ProgressStyle {
     indicatif::ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} {msg}")
@@ -1,19 +1,21 @@
 use crate::cli;
-use crate::core::context;
 use anyhow::Result;
 use clap::Args;
 use std::fs;
 use std::path::Path;
+use tooka::core::context;
 
 #[derive(Args)]
 #[command(about = "📝 Add a new rule by importing a YAML file or scanning a directory")]
 pub struct AddArgs {
-    /// Path to the rule YAML file or directory containing YAML files
+    /// Path to the rule YAML, TOML or JSON file, or directory containing YAML files
     #[arg(
         value_name = "PATH",
-        help = "Path to the YAML file or directory containing YAML files with rule definitions"
+        required_unless_present = "clone_from",
+        conflicts_with = "clone_from",
+        help = "Path to the YAML, TOML (.toml) or JSON (.json) file, or directory containing YAML files with rule definitions"
     )]
-    pub path: String,
+    pub path: Option<String>,
 
     /// Optional flag to overwrite existing rules
     #[arg(
@@ -22,36 +24,78 @@ pub struct AddArgs {
         help = "Overwrite existing rule if it already exists"
     )]
     pub overwrite: bool,
+
+    /// Optional priority override applied to every imported rule
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Override the priority of the imported rule(s)"
+    )]
+    pub priority: Option<u32>,
+
+    /// ID of an existing rule to duplicate instead of importing a file
+    #[arg(
+        long,
+        value_name = "RULE_ID",
+        requires = "id",
+        help = "Duplicate an existing rule instead of importing a file"
+    )]
+    pub clone_from: Option<String>,
+
+    /// ID of the duplicated rule
+    #[arg(
+        long,
+        value_name = "NEW_RULE_ID",
+        requires = "clone_from",
+        help = "ID for the rule created with --clone-from"
+    )]
+    pub id: Option<String>,
+
+    /// Name of the duplicated rule
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "clone_from",
+        help = "Name for the rule created with --clone-from (defaults to the original name)"
+    )]
+    pub name: Option<String>,
 }
 
 pub fn run(args: &AddArgs) -> Result<()> {
-    let path = Path::new(&args.path);
+    if let (Some(source_id), Some(new_id)) = (&args.clone_from, &args.id) {
+        return clone_rule(source_id, new_id, args.name.as_deref());
+    }
+
+    let Some(path_arg) = &args.path else {
+        return Err(anyhow::anyhow!("A rule file or directory path is required"));
+    };
+    let path = Path::new(path_arg);
 
     if path.is_file() {
         // Handle single file
-        cli::info(&format!("📝 Adding rule from file: {}", args.path));
-        log::info!("Adding rule from file: {}", args.path);
+        cli::info(&format!("📝 Adding rule from file: {}", path_arg));
+        log::info!("Adding rule from file: {}", path_arg);
 
         let mut rf = context::get_locked_rules_file()?;
 
-        rf.add_rule_from_file(&args.path, args.overwrite)
-            .map_err(|e| anyhow::anyhow!("Failed to add rule from file: {}: {}", args.path, e))?;
+        rf.add_rule_from_file(path_arg, args.overwrite, args.priority)
+            .map_err(|e| anyhow::anyhow!("Failed to add rule from file: {}: {}", path_arg, e))?;
 
         cli::success("Rule added successfully!");
-        log::info!("Rule added successfully from file: {}", args.path);
+        log::info!("Rule added successfully from file: {}", path_arg);
     } else if path.is_dir() {
         // Handle directory
         cli::info(&format!(
             "📂 Scanning directory for YAML files: {}",
-            args.path
+            path_arg
         ));
-        log::info!("Scanning directory for YAML files: {}", args.path);
+        log::info!("Scanning directory for YAML files: {}", path_arg);
 
         let yaml_files = find_yaml_files(path)?;
 
         if yaml_files.is_empty() {
             cli::warning("No YAML files found in the directory");
-            log::warn!("No YAML files found in directory: {}", args.path);
+            log::warn!("No YAML files found in directory: {}", path_arg);
             return Ok(());
         }
 
@@ -59,7 +103,7 @@ pub fn run(args: &AddArgs) -> Result<()> {
         log::info!(
             "Found {} YAML files in directory: {}",
             yaml_files.len(),
-            args.path
+            path_arg
         );
 
         let mut rf = context::get_locked_rules_file()?;
@@ -72,7 +116,7 @@ pub fn run(args: &AddArgs) -> Result<()> {
             let file_name = file_path.file_name().unwrap().to_string_lossy();
             log::info!("Processing file: {file_path_str}");
 
-            match rf.add_rule_from_file(&file_path_str, args.overwrite) {
+            match rf.add_rule_from_file(&file_path_str, args.overwrite, args.priority) {
                 Ok(()) => {
                     cli::success(&format!("  ✅ Added rules from: {file_name}"));
                     log::info!("Successfully added rules from: {file_path_str}");
@@ -106,13 +150,30 @@ pub fn run(args: &AddArgs) -> Result<()> {
     } else {
         return Err(anyhow::anyhow!(
             "Path is neither a file nor a directory: {}",
-            args.path
+            path_arg
         ));
     }
 
     Ok(())
 }
 
+/// Duplicate an existing rule under a new ID
+fn clone_rule(source_id: &str, new_id: &str, new_name: Option<&str>) -> Result<()> {
+    cli::info(&format!("📝 Cloning rule '{source_id}' as '{new_id}'"));
+    log::info!("Cloning rule '{source_id}' as '{new_id}'");
+
+    let mut rf = context::get_locked_rules_file()?;
+    match new_name {
+        Some(name) => rf.clone_rule_with_name(source_id, new_id, name),
+        None => rf.clone_rule(source_id, new_id),
+    }
+    .map_err(|e| anyhow::anyhow!("Failed to clone rule '{}': {}", source_id, e))?;
+
+    cli::success("Rule cloned successfully!");
+    log::info!("Rule '{source_id}' cloned as '{new_id}'");
+    Ok(())
+}
+
 /// Find all YAML files in a directory (non-recursive)
 fn find_yaml_files(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
     let mut yaml_files = Vec::new();
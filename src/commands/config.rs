@@ -1,7 +1,9 @@
-use crate::core::context;
-use crate::{cli, common::config::Config};
+use crate::cli;
 use anyhow::{Context, Result, anyhow};
 use clap::Args;
+use std::io::IsTerminal;
+use tooka::common::config::Config;
+use tooka::core::context;
 
 #[derive(Args)]
 #[command(about = "⚙️ Manage the Tooka configuration file")]
@@ -14,6 +16,23 @@ pub struct ConfigArgs {
     #[arg(long, help = "Reset configuration to default values")]
     pub reset: bool,
 
+    /// Keep the rules file path when resetting
+    #[arg(
+        long,
+        requires = "reset",
+        help = "Keep the current rules_file path when resetting"
+    )]
+    pub keep_rules_file: bool,
+
+    /// Skip the confirmation prompt of --reset
+    #[arg(
+        short,
+        long,
+        requires = "reset",
+        help = "Reset without asking for confirmation first"
+    )]
+    pub yes: bool,
+
     /// Flag to show the current configuration
     #[arg(long, help = "Display the current configuration")]
     pub show: bool,
@@ -48,7 +67,11 @@ pub fn run(args: &ConfigArgs) -> Result<()> {
         ));
     }
 
-    let mut conf = context::get_locked_config()?;
+    if args.reset {
+        return reset(args);
+    }
+
+    let conf = context::get_locked_config()?;
 
     if args.locate {
         cli::info("📍 Locating config file...");
@@ -56,20 +79,35 @@ pub fn run(args: &ConfigArgs) -> Result<()> {
         let path = Config::locate_config_file().context("Failed to locate config file")?;
         cli::success(&format!("Config file found at: {}", path.display()));
         log::info!("Config file found at: {}", path.display());
-    } else if args.reset {
-        cli::warning("🔄 Resetting config to default...");
-        log::info!("Resetting config to default...");
-        conf.reset_config()
-            .context("Failed to reset config to default")?;
-        cli::success("Config reset to default values.");
-        log::info!("Config reset complete.");
     } else if args.show {
         cli::header("📋 Current Configuration");
         log::info!("Showing current config...");
-        let config_str = conf.show_config(); // Assuming this can't fail
-        println!("{config_str}");
+        println!("{}", conf.print_table());
         log::info!("Current config displayed successfully.");
     }
 
     Ok(())
 }
+
+/// Overwrites the config file with the defaults. This doesn't need the global
+/// context, so it also works when the current config file can't be loaded.
+fn reset(args: &ConfigArgs) -> Result<()> {
+    // Scripts can't answer, so only ask interactive users
+    let ask = !args.yes && std::io::stdin().is_terminal();
+    if ask && !cli::confirm("Overwrite the config file with default values?")? {
+        cli::warning("Reset cancelled.");
+        log::info!("Config reset cancelled by user");
+        return Ok(());
+    }
+
+    cli::warning("🔄 Resetting config to default...");
+    log::info!("Resetting config to default...");
+    let config =
+        Config::reset(args.keep_rules_file).context("Failed to reset config to default")?;
+    if args.keep_rules_file {
+        cli::info(&format!("Kept rules file: {}", config.rules_file.display()));
+    }
+    cli::success("Config reset to default values.");
+    log::info!("Config reset complete.");
+    Ok(())
+}
@@ -1,9 +1,9 @@
-use crate::core::context;
 use anyhow::{Result, anyhow};
-use clap::Args;
+use clap::{Args, ValueEnum};
+use tooka::{core::context, rules::rules_file::RulesFormat};
 
 #[derive(Args)]
-#[command(about = "📤 Export a rule to a YAML file")]
+#[command(about = "📤 Export a rule to a YAML, TOML or JSON file")]
 pub struct ExportArgs {
     /// ID of the rule to export
     #[arg(
@@ -15,6 +15,36 @@ pub struct ExportArgs {
     /// Output file path, optional; defaults to stdout
     #[arg(long, help = "Output file path (defaults to stdout if not specified)")]
     pub output: Option<String>,
+
+    /// Format of the exported rule
+    #[arg(
+        long,
+        value_enum,
+        default_value = "yaml",
+        help = "Format of the exported rule"
+    )]
+    pub format: ExportFormat,
+}
+
+/// Supported formats for `tooka export`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// YAML, in the format of the rules file
+    Yaml,
+    /// TOML, which `tooka add` reads from `.toml` files
+    Toml,
+    /// JSON, which `tooka add` reads from `.json` files
+    Json,
+}
+
+impl From<ExportFormat> for RulesFormat {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Yaml => RulesFormat::Yaml,
+            ExportFormat::Toml => RulesFormat::Toml,
+            ExportFormat::Json => RulesFormat::Json,
+        }
+    }
 }
 
 pub fn run(args: ExportArgs) -> Result<()> {
@@ -24,7 +54,7 @@ pub fn run(args: ExportArgs) -> Result<()> {
 
     let rf = context::get_locked_rules_file()?;
 
-    rf.export_rule(&args.id, output_path.as_deref())
+    rf.export_rule_as(&args.id, output_path.as_deref(), args.format.into())
         .map_err(|e| anyhow!("Failed to export rule with ID {}: {}", args.id, e))?;
 
     if output_path.is_some() {
@@ -1,25 +1,117 @@
 use crate::cli;
-use crate::core::context;
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
+use tooka::core::context;
 
 #[derive(Args)]
 #[command(about = "📋 List all current rules with their metadata")]
-pub struct ListArgs;
+pub struct ListArgs {
+    /// Output format for the rule list
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ListFormat::Pretty,
+        help = "Output format (pretty, table, ids, yaml, json)"
+    )]
+    pub format: ListFormat,
 
-pub fn run(_args: ListArgs) -> Result<()> {
+    /// Show rule counts per action type instead of every rule
+    #[arg(long, help = "Show a summary of rule counts instead of the full list")]
+    pub summary: bool,
+
+    /// Show the full definition of every rule
+    #[arg(
+        long,
+        help = "Show the full definition of every rule (pretty format only)"
+    )]
+    pub verbose: bool,
+
+    /// Only list rules with this tag
+    #[arg(long, help = "Only list rules with this tag")]
+    pub tag: Option<String>,
+}
+
+/// Supported output formats for `tooka list`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListFormat {
+    /// Colored summary of each rule's conditions and actions
+    Pretty,
+    /// Human-readable table with rule metadata
+    Table,
+    /// One rule ID per line, used by shell completions
+    Ids,
+    /// Rules as YAML, in the format of the rules file
+    Yaml,
+    /// Rules as a JSON array
+    Json,
+}
+
+pub fn run(args: ListArgs) -> Result<()> {
     log::info!("Listing all rules...");
 
-    let rf = context::get_locked_rules_file()?;
+    let rf = match &args.tag {
+        Some(tag) => context::get_locked_rules_file()?.filter_by_tag(tag),
+        None => context::get_locked_rules_file()?.clone(),
+    };
     let rules_list = rf.list_rules();
 
+    match args.format {
+        ListFormat::Ids => {
+            for rule in &rules_list {
+                println!("{}", rule.id);
+            }
+            return Ok(());
+        }
+        ListFormat::Yaml => {
+            print!("{}", serde_yaml::to_string(&rules_list)?);
+            return Ok(());
+        }
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&rules_list)?);
+            return Ok(());
+        }
+        ListFormat::Pretty | ListFormat::Table => {}
+    }
+
     if rules_list.is_empty() {
         cli::warning("No rules found.");
         cli::info("Use `tooka add` to create your first rule.");
         return Ok(());
     }
 
+    if args.summary {
+        cli::header(&format!(
+            "📋 {} rules, {} enabled",
+            rf.total_rules(),
+            rf.total_enabled()
+        ));
+        let mut counts: Vec<_> = rf.rule_count_by_action().into_iter().collect();
+        counts.sort();
+        for (action, count) in counts {
+            println!("  {action:<10} {count}");
+        }
+        println!();
+        return Ok(());
+    }
+
     cli::header(&format!("📋 Found {} rules", rules_list.len()));
+
+    if let ListFormat::Pretty = args.format {
+        if args.verbose {
+            for rule in &rules_list {
+                print!("{}", rule.pretty_print());
+                for line in serde_yaml::to_string(rule)?.lines() {
+                    println!("    {line}");
+                }
+                println!();
+            }
+        } else {
+            print!("{}", rf.pretty_print(true));
+        }
+        println!();
+        return Ok(());
+    }
+
     cli::rule_table_header();
 
     for rule in &rules_list {
@@ -29,7 +121,12 @@ pub fn run(_args: ListArgs) -> Result<()> {
             rule.name,
             rule.enabled
         );
-        cli::rule_table_row(&rule.id, &rule.name, rule.enabled);
+        cli::rule_table_row(
+            &rule.id,
+            &rule.name,
+            rule.when.condition_count(),
+            rule.enabled,
+        );
     }
 
     println!();
@@ -0,0 +1,73 @@
+use crate::cli;
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use tooka::common::logger::{self, LogEntry, LogType};
+
+#[derive(Args)]
+#[command(about = "📜 Show recent log entries")]
+pub struct LogsArgs {
+    /// Number of most recent entries to show
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 50,
+        help = "Number of most recent entries to show"
+    )]
+    pub lines: usize,
+
+    /// Show entries containing this text instead of the most recent ones
+    #[arg(
+        long,
+        value_name = "QUERY",
+        help = "Show only entries containing QUERY"
+    )]
+    pub search: Option<String>,
+
+    /// Match the search query case-sensitively
+    #[arg(long, requires = "search", help = "Make --search case-sensitive")]
+    pub case_sensitive: bool,
+
+    /// Read the file operation logs instead of the main log
+    #[arg(long, help = "Show file operation logs instead of the main log")]
+    pub ops: bool,
+}
+
+pub fn run(args: &LogsArgs) -> Result<()> {
+    let log_type = if args.ops {
+        LogType::Ops
+    } else {
+        LogType::Main
+    };
+
+    let entries = match &args.search {
+        Some(query) => logger::search_logs(query, log_type, args.case_sensitive)?,
+        None => logger::read_recent_logs(args.lines, log_type)?,
+    };
+
+    if entries.is_empty() {
+        cli::warning("No log entries found.");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        print_entry(entry);
+    }
+    Ok(())
+}
+
+fn print_entry(entry: &LogEntry) {
+    let level = match entry.level.as_str() {
+        "ERROR" => entry.level.red().bold(),
+        "WARN" => entry.level.yellow().bold(),
+        "INFO" => entry.level.green(),
+        _ => entry.level.bright_black(),
+    };
+    println!(
+        "{} {:<5} {} {}",
+        entry.timestamp.bright_black(),
+        level,
+        entry.target.cyan(),
+        entry.message
+    );
+}
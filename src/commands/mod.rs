@@ -2,8 +2,11 @@ pub mod add;
 pub mod config;
 pub mod export;
 pub mod list;
+pub mod logs;
 pub mod remove;
+pub mod rollback;
 pub mod sort;
 pub mod template;
 pub mod toggle;
+pub mod update;
 pub mod validate;
@@ -0,0 +1,42 @@
+use crate::cli;
+use anyhow::Result;
+use clap::Args;
+use tooka::common::config::Config;
+use tooka::core::sorter;
+use tooka::file::journal;
+use tooka::utils::display;
+
+#[derive(Args)]
+#[command(about = "⏪ Undo the last sort run recorded with --journal")]
+pub struct RollbackArgs {
+    /// Simulate the rollback without making changes
+    #[arg(long, help = "Preview what would be undone without changing any files")]
+    pub dry_run: bool,
+}
+
+pub fn run(args: &RollbackArgs) -> Result<()> {
+    let config = Config::load()?;
+    let journal_path = config.logs_folder.join(journal::JOURNAL_FILE_NAME);
+    log::info!(
+        "Rolling back last run in {} (dry_run: {})",
+        journal_path.display(),
+        args.dry_run
+    );
+
+    let results = sorter::rollback_last_run(&journal_path, args.dry_run)?;
+    if results.is_empty() {
+        cli::warning("Nothing to roll back, only runs sorted with --journal can be undone.");
+        return Ok(());
+    }
+
+    cli::header(if args.dry_run {
+        "⏪ Operations That Would Be Undone"
+    } else {
+        "⏪ Undone Operations"
+    });
+    display::print_match_table(&results, &mut std::io::stdout().lock())?;
+    if !args.dry_run {
+        cli::success(&format!("Rolled back {} operation(s).", results.len()));
+    }
+    Ok(())
+}
@@ -1,35 +1,58 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
 use crate::cli;
-use crate::common::config::Config;
-use crate::core::{report, sorter};
-use crate::rules::rules_file::RulesFile;
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use colored::Colorize;
-use indicatif::ProgressBar;
+use indicatif::{HumanDuration, ProgressBar};
+use tooka::common::config::Config;
+use tooka::core::error::{self, TookaError};
+use tooka::core::{report, sorter};
+use tooka::file::journal;
+use tooka::utils::display;
 
 #[derive(Args)]
 #[command(about = "🚀 Sort files in the source folder using defined rules")]
 pub struct SortArgs {
-    /// Override default source folder
-    #[arg(long, help = "Override the default source folder path")]
-    pub source: Option<String>,
+    /// Override default source folders
+    #[arg(
+        long,
+        help = "Override the default source folder path (can be repeated to sort several folders)"
+    )]
+    pub source: Vec<String>,
     /// Comma-separated rule IDs to run
     #[arg(
         long,
         help = "Comma-separated list of rule IDs to execute (use '<all>' for all rules)"
     )]
     pub rules: Option<String>,
-    /// Output report format: pdf, csv, json
+    /// Only run rules with this tag
+    #[arg(long, help = "Only run rules with this tag")]
+    pub tag: Option<String>,
+    /// Extra rules file to merge in
+    #[arg(
+        long,
+        help = "Merge rules from this YAML, TOML or JSON file before sorting (they take precedence)"
+    )]
+    pub extra_rules: Option<String>,
+    /// Output report format: pdf, html, csv, json
     #[arg(
         long,
-        help = "Generate a report in the specified format (pdf, csv, json)"
+        help = "Generate a report in the specified format (pdf, html, csv, json)"
     )]
     pub report: Option<String>,
     /// Output directory for the report
     #[arg(long, help = "Directory where the report will be saved")]
     pub output: Option<String>,
+    /// Append to an existing report instead of replacing it
+    #[arg(
+        long,
+        requires = "report",
+        help = "Append results to the existing report instead of overwriting it"
+    )]
+    pub report_append: bool,
     /// Simulate the sorting without making changes
     #[arg(
         long,
@@ -37,12 +60,69 @@ pub struct SortArgs {
         help = "Preview what would happen without actually moving files"
     )]
     pub dry_run: bool,
+    /// Stop after this many files
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "dry_run",
+        help = "Preview only the first N files (with --dry-run)"
+    )]
+    pub limit: Option<usize>,
+    /// Journal the run so it can be rolled back
+    #[arg(
+        long,
+        help = "Record the run so `tooka rollback` can undo it; files deleted permanently are kept in the logs folder until then"
+    )]
+    pub journal: bool,
+    /// Skip the confirmation prompt
+    #[arg(
+        short,
+        long,
+        help = "Start sorting without asking for confirmation first"
+    )]
+    pub yes: bool,
+    /// Format of the command's output
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        help = "Output format; json prints the results, or the error with its error_code"
+    )]
+    pub output_format: SortOutputFormat,
+}
+
+/// Supported output formats for `tooka sort`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortOutputFormat {
+    /// Progress, statistics and a table of sorted files
+    Text,
+    /// A JSON document with the summary and results, or the error
+    Json,
 }
 
 pub fn run(args: SortArgs) -> Result<()> {
-    if args.dry_run {
+    let json = args.output_format == SortOutputFormat::Json;
+    let result = sort(args);
+    if json && let Err(e) = &result {
+        let (kind, code) = match e.downcast_ref::<TookaError>() {
+            Some(tooka_error) => (tooka_error.kind(), tooka_error.code()),
+            None => ("Other", error::ERROR_OTHER),
+        };
+        let output = serde_json::json!({
+            "error": { "type": kind, "message": e.to_string() },
+            "error_code": code,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    }
+    result
+}
+
+fn sort(args: SortArgs) -> Result<()> {
+    let text = args.output_format == SortOutputFormat::Text;
+    // In JSON mode only the JSON document goes to stdout
+    if text && args.dry_run {
         cli::warning("🔍 Running in dry-run mode - no files will be moved");
-    } else {
+    } else if text {
         cli::info("🚀 Starting file sorting...");
     }
 
@@ -55,89 +135,136 @@ pub fn run(args: SortArgs) -> Result<()> {
 
     // Load config and rules directly instead of using global context
     let config = Config::load()?;
-    let source_path = if let Some(source) = args.source {
-        if source == "<default>" {
-            config.source_folder.clone()
+    // Sorting the configured source folder needs it to exist, other commands don't
+    if args.source.is_empty() || args.source.iter().any(|source| source == "<default>") {
+        config.validate()?;
+    }
+    let mut source_paths: Vec<PathBuf> = Vec::new();
+    for source in &args.source {
+        let paths = if source == "<default>" {
+            config.all_source_folders()
         } else {
-            PathBuf::from(source)
+            vec![PathBuf::from(source)]
+        };
+        for path in paths {
+            if !source_paths.contains(&path) {
+                source_paths.push(path);
+            }
         }
-    } else {
-        config.source_folder.clone()
-    };
-
-    let rules_file = RulesFile::load()?;
+    }
+    if source_paths.is_empty() {
+        source_paths = config.all_source_folders();
+    }
 
-    // Parse rule filter
-    let rule_filter = args.rules.as_ref().and_then(|r| {
-        if r == "<all>" {
-            None
-        } else {
-            Some(
-                r.split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<_>>(),
-            )
-        }
-    });
+    let started = std::time::Instant::now();
 
-    let optimized_rules = rules_file.optimized_with_filter(rule_filter.as_deref())?;
+    // Collect files first to show the scope and a progress bar
+    let rules = args.rules.as_deref().unwrap_or("<all>");
+    let options = sorter::SortOptions {
+        journal_path: args
+            .journal
+            .then(|| config.logs_folder.join(journal::JOURNAL_FILE_NAME)),
+        tag: args.tag.clone(),
+        extra_rules: args.extra_rules.as_ref().map(PathBuf::from),
+        ..Default::default()
+    };
+    let rules_file = sorter::load_sort_rules(rules, &options)?;
+    let sources = sorter::prepare_sort_multi(&source_paths, &rules_file, &options)?;
+    let total = sources.iter().map(|prepared| prepared.files.len()).sum();
+    if text {
+        show_prepare_stats(&sources);
+    }
 
-    // Collect files first to show progress bar
-    let files = sorter::collect_files(&source_path)?;
+    // Scripts and dry runs can't or needn't answer, so only ask interactive users
+    let ask = text && !args.dry_run && !args.yes && total > 0 && std::io::stdin().is_terminal();
+    if ask && !cli::confirm("Proceed with sorting?")? {
+        cli::warning("Sorting cancelled.");
+        log::info!("Sorting cancelled by user");
+        return Ok(());
+    }
 
-    let pb = ProgressBar::new(files.len() as u64);
+    let pb = if text {
+        ProgressBar::new(args.limit.map_or(total, |limit| limit.min(total)) as u64)
+    } else {
+        ProgressBar::hidden()
+    };
     pb.set_style(cli::progress_style());
 
-    // Use the main sort_files function with optimized rules
-    let results = sorter::sort_files(
-        &files,
-        &source_path,
-        &optimized_rules,
-        args.dry_run,
-        Some(|| {
-            pb.inc(1);
-        }),
-    )?;
-
+    let (results, limited) = match args.limit {
+        Some(limit) => sorter::sort_files_multi_limited(
+            &sources,
+            args.dry_run,
+            limit,
+            sorter::DEFAULT_PREVIEW_TIME_BUDGET.saturating_sub(started.elapsed()),
+        )?,
+        None => {
+            let results = sorter::sort_files_multi(
+                &sources,
+                args.dry_run,
+                &options,
+                Some(|progress: sorter::SortProgress| {
+                    pb.set_position(progress.files_processed as u64);
+                    if let Some(eta) = progress.estimated_remaining {
+                        pb.set_message(format!("ETA {}", HumanDuration(eta)));
+                    }
+                }),
+            )?;
+            (results, false)
+        }
+    };
     pb.finish_with_message("✅ Sorting complete");
-
-    cli::success("Sorting completed successfully!");
     log::info!("Sorting completed, found {} matches", results.len());
 
-    if args.report.is_none() && !results.is_empty() {
-        cli::header("📁 Sorted Files");
+    let stats = sorter::SortStats::from_results(&results);
+    let mut summary = report::compute_summary(&results, &stats);
+    summary.duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    if !text {
+        write_report(&args, &results, &summary)?;
+        let output = serde_json::json!({ "summary": summary, "results": results });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-        println!(
-            "{} | {} | {} | {}",
-            "File".bright_cyan().bold(),
-            "Matched Rule".bright_cyan().bold(),
-            "Current Path".bright_cyan().bold(),
-            "New Path".bright_cyan().bold()
-        );
-        println!("{}", "─".repeat(120).bright_black());
-
-        for result in &results {
-            println!(
-                "{:<40} | {:<30} | {:<40} | {}",
-                result.file_name.bright_white(),
-                result.matched_rule_id.green(),
-                result.current_path.display().to_string().yellow(),
-                result.new_path.display().to_string().blue()
-            );
-        }
+    cli::success("Sorting completed successfully!");
+    if limited {
+        cli::warning(&format!(
+            "Preview stopped early (--limit or the {}s time budget), run without --limit to see every file",
+            sorter::DEFAULT_PREVIEW_TIME_BUDGET.as_secs()
+        ));
+    }
+    // Journaled deletes only move the files into the logs folder
+    let deleted = if args.journal && !args.dry_run {
+        format!(
+            "Deleted {} (kept in {})",
+            report::format_bytes(stats.bytes_deleted),
+            config
+                .logs_folder
+                .join(journal::TRASH_FOLDER_NAME)
+                .display()
+        )
+    } else {
+        format!("Freed {}", report::format_bytes(stats.bytes_deleted))
+    };
+    cli::info(&format!(
+        "{}Moved {}, Copied {}, {deleted}",
+        if args.dry_run { "Would have: " } else { "" },
+        report::format_bytes(stats.bytes_moved),
+        report::format_bytes(stats.bytes_copied),
+    ));
+
+    // Dry runs always show what would happen, even when writing a report
+    if (args.dry_run || args.report.is_none()) && !results.is_empty() {
+        cli::header(if args.dry_run {
+            "📁 Files That Would Be Sorted"
+        } else {
+            "📁 Sorted Files"
+        });
+        display::print_match_table(&results, &mut std::io::stdout().lock())?;
     } else if results.is_empty() {
         cli::info("No files matched the sorting rules.");
     }
 
-    // Handle report generation
-    if let Some(report_type) = &args.report {
-        log::info!("Generating report of type: {report_type}");
-        let output_dir = args.output.as_ref().map_or_else(
-            || std::env::current_dir().expect("Cannot get current working directory"),
-            PathBuf::from,
-        );
-
-        report::generate_report(report_type, &output_dir, &results)?;
+    if let Some(output_dir) = write_report(&args, &results, &summary)? {
         cli::success(&format!(
             "Report generated successfully in {}",
             output_dir.display()
@@ -146,3 +273,84 @@ pub fn run(args: SortArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Writes the report requested with `--report`, returning its directory
+fn write_report(
+    args: &SortArgs,
+    results: &[sorter::MatchResult],
+    summary: &report::ReportSummary,
+) -> Result<Option<PathBuf>> {
+    let Some(report_type) = &args.report else {
+        return Ok(None);
+    };
+    log::info!("Generating report of type: {report_type}");
+    let output_dir = args.output.as_ref().map_or_else(
+        || std::env::current_dir().expect("Cannot get current working directory"),
+        PathBuf::from,
+    );
+
+    if args.report_append {
+        let path = output_dir.join(format!("tooka_report.{}", report_type.to_lowercase()));
+        report::append_to_report(report_type, &path, results)?;
+    } else {
+        report::generate_report_with_summary(report_type, &output_dir, results, summary)?;
+    }
+    Ok(Some(output_dir))
+}
+
+/// Prints the number of files found and the rules that apply to them
+fn show_prepare_stats(sources: &[sorter::PreparedSort]) {
+    let mut total = 0;
+    let mut unmatched = 0;
+    let mut rule_matches: Vec<(&str, usize)> = Vec::new();
+    let mut extensions: BTreeMap<&str, usize> = BTreeMap::new();
+    for prepared in sources {
+        let stats = &prepared.stats;
+        total += stats.file_count;
+        unmatched += stats.unmatched;
+        for (id, count) in &stats.rule_matches {
+            match rule_matches.iter_mut().find(|(existing, _)| existing == id) {
+                Some((_, existing)) => *existing += count,
+                None => rule_matches.push((id, *count)),
+            }
+        }
+        for (extension, count) in &stats.extensions {
+            *extensions.entry(extension).or_insert(0) += count;
+        }
+    }
+    rule_matches.retain(|(_, count)| *count > 0);
+
+    cli::info(&format!(
+        "Found {} files, {} applicable rules",
+        cli::format_count(total),
+        rule_matches.len()
+    ));
+
+    let mut extensions: Vec<_> = extensions.into_iter().collect();
+    extensions.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    if !extensions.is_empty() {
+        let top: Vec<String> = extensions
+            .iter()
+            .take(5)
+            .map(|(extension, count)| {
+                let extension = if extension.is_empty() {
+                    "(none)"
+                } else {
+                    extension
+                };
+                format!("{extension} ({})", cli::format_count(*count))
+            })
+            .collect();
+        println!("  {} {}", "Extensions:".bright_black(), top.join(", "));
+    }
+    for (id, count) in &rule_matches {
+        println!("  {:<30} {} files", id.green(), cli::format_count(*count));
+    }
+    if unmatched > 0 {
+        println!(
+            "  {:<30} {} files",
+            "(no matching rule)".bright_black(),
+            cli::format_count(unmatched)
+        );
+    }
+}
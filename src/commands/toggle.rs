@@ -1,7 +1,7 @@
 use crate::cli;
-use crate::core::context;
 use anyhow::{Result, anyhow};
 use clap::Args;
+use tooka::core::context;
 
 #[derive(Args)]
 #[command(about = "🔄 Toggle the enabled/disabled state of a rule")]
@@ -0,0 +1,40 @@
+use crate::cli;
+use anyhow::{Result, anyhow};
+use clap::Args;
+use tooka::core::context;
+
+#[derive(Args)]
+#[command(about = "✏️ Update fields of an existing rule")]
+pub struct UpdateArgs {
+    /// ID of the rule to update
+    #[arg(
+        value_name = "ID",
+        help = "The unique identifier of the rule to update"
+    )]
+    pub rule_id: String,
+
+    /// YAML patches applied in order, e.g. `--set 'priority: 10'`
+    #[arg(
+        long = "set",
+        value_name = "YAML",
+        required = true,
+        help = "YAML with the fields to change, e.g. 'priority: 10' (can be repeated)"
+    )]
+    pub patches: Vec<String>,
+}
+
+pub fn run(args: &UpdateArgs) -> Result<()> {
+    cli::info(&format!("✏️ Updating rule with ID: {}", args.rule_id));
+    log::info!("Updating rule with ID: {}", args.rule_id);
+
+    let patch = args.patches.join("\n");
+    let mut rf = context::get_locked_rules_file()?;
+    rf.update_rule(&args.rule_id, &patch).map_err(|e| {
+        log::warn!("Failed to update rule '{}': {e}", args.rule_id);
+        anyhow!("Failed to update rule with ID '{}': {}", args.rule_id, e)
+    })?;
+
+    cli::success(&format!("Rule with ID '{}' updated.", args.rule_id));
+    log::info!("Rule with ID '{}' updated.", args.rule_id);
+    Ok(())
+}
@@ -1,6 +1,7 @@
-use crate::rules::rule::Rule;
 use anyhow::Result;
 use clap::Args;
+use tooka::rules::rule::Rule;
+use tooka::rules::rules_file::RulesFile;
 
 #[derive(Args)]
 #[command(about = "✅ Validate a rule YAML file against the schema")]
@@ -28,24 +29,29 @@ pub fn run(args: &ValidateArgs) -> Result<()> {
     log::info!("Loaded {} rules from file: {}", rules.len(), args.file);
     println!("Loaded {} rules from file: {}", rules.len(), args.file);
 
-    if !args.deep {
-        println!("✅ File is structurally valid (schema match)");
-        return Ok(());
+    let rules_file = RulesFile { rules };
+    let conflicts = rules_file.conflicting_rules();
+    for conflict in &conflicts {
+        log::warn!(
+            "Conflict between rules '{}' and '{}': {}",
+            conflict.rule_id_a,
+            conflict.rule_id_b,
+            conflict.reason
+        );
+        println!(
+            "⚠️  Rules '{}' and '{}' conflict: {}",
+            conflict.rule_id_a, conflict.rule_id_b, conflict.reason
+        );
     }
 
-    // Only validate rule content when deep is true
-    let mut err_count = 0;
-    for rule in rules {
-        if let Err(e) = rule.validate(true) {
-            log::error!("Rule '{}' is invalid: {}", rule.name, e);
-            println!("❌ Rule '{}' is invalid: {}", rule.name, e);
-            err_count += 1;
-        } else {
-            log::info!("Rule '{}' is valid", rule.name);
-            println!("✅ Rule '{}' is valid", rule.name);
-        }
+    // Rule content is only checked with --deep
+    let errors = rules_file.validate_all(args.deep);
+    for (id, e) in &errors {
+        log::error!("Rule '{id}' is invalid: {e}");
+        println!("❌ Rule '{id}' is invalid: {e}");
     }
 
+    let err_count = errors.len();
     if err_count > 0 {
         log::error!("Validation completed with {err_count} errors");
         println!("Validation completed with {err_count} errors");
@@ -55,6 +61,11 @@ pub fn run(args: &ValidateArgs) -> Result<()> {
         ));
     }
 
+    if !args.deep {
+        println!("✅ File is structurally valid (schema match)");
+        return Ok(());
+    }
+
     log::info!("All rules are valid");
     println!("✅ All rules are valid");
 
@@ -6,14 +6,18 @@
 //! It provides functionality to load, save, reset, and display configuration
 //! settings from a user-specific file (typically stored in `$HOME/.config/tooka/config.yml`).
 
-use super::environment::{get_dir_with_env, get_source_folder};
+use super::environment::{expand_tilde, get_dir_with_env, get_source_folder};
 use crate::{
     core::context::{CONFIG_FILE_NAME, CONFIG_VERSION, DEFAULT_LOGS_FOLDER, RULES_FILE_NAME},
-    core::error::TookaError,
+    core::error::{ConfigValidationError, TookaError},
 };
 use anyhow::Result;
+use comfy_table::Table;
 use serde::{Deserialize, Serialize};
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
 
 /// Represents the user configuration for Tooka.
 ///
@@ -25,12 +29,98 @@ use std::{env, fs, path::PathBuf};
 pub struct Config {
     /// Version of the configuration file
     pub version: usize,
-    /// Folder that Tooka will sort files in
+    /// Folder that Tooka will sort files in. The configuration file may also
+    /// list several folders here, the others are added to `source_folders`.
     pub source_folder: PathBuf,
+    /// Additional folders sorted alongside `source_folder`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub source_folders: Vec<PathBuf>,
     /// Path to the file containing all rules
     pub rules_file: PathBuf,
+    /// Folder whose `.yaml`/`.yml` files are loaded as rules instead of `rules_file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules_folder: Option<PathBuf>,
     /// Folder where Tooka will store logs
     pub logs_folder: PathBuf,
+    /// Where each value above comes from, filled in by [`Config::load`]
+    #[serde(skip)]
+    pub sources: ConfigSources,
+}
+
+/// Origin of a configuration value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    #[default]
+    Default,
+    /// Set in the configuration file
+    ConfigFile,
+    /// Derived from an environment variable such as `TOOKA_DATA_DIR`
+    EnvVar,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigSource::Default => "default",
+            ConfigSource::ConfigFile => "config file",
+            ConfigSource::EnvVar => "env var",
+        })
+    }
+}
+
+/// Origin of every value in a [`Config`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSources {
+    pub version: ConfigSource,
+    pub source_folder: ConfigSource,
+    pub source_folders: ConfigSource,
+    pub rules_file: ConfigSource,
+    pub rules_folder: ConfigSource,
+    pub logs_folder: ConfigSource,
+}
+
+impl ConfigSources {
+    /// Returns the sources of a configuration built from defaults, taking
+    /// `TOOKA_SOURCE_FOLDER` and `TOOKA_DATA_DIR` overrides into account.
+    pub fn from_env() -> Self {
+        let env_or_default = |var| {
+            if env::var_os(var).is_some() {
+                ConfigSource::EnvVar
+            } else {
+                ConfigSource::Default
+            }
+        };
+        let data_dir = env_or_default("TOOKA_DATA_DIR");
+        Self {
+            source_folder: env_or_default("TOOKA_SOURCE_FOLDER"),
+            rules_file: data_dir,
+            logs_folder: data_dir,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the sources of a configuration read from a YAML document,
+    /// where every key present in the document comes from the config file.
+    pub fn from_yaml(document: &serde_yaml::Value) -> Self {
+        let mut sources = Self::from_env();
+        let Some(mapping) = document.as_mapping() else {
+            return sources;
+        };
+        for (key, source) in [
+            ("version", &mut sources.version),
+            ("source_folder", &mut sources.source_folder),
+            ("source_folders", &mut sources.source_folders),
+            ("rules_file", &mut sources.rules_file),
+            ("rules_folder", &mut sources.rules_folder),
+            ("logs_folder", &mut sources.logs_folder),
+        ] {
+            if mapping.contains_key(key) {
+                *source = ConfigSource::ConfigFile;
+            }
+        }
+        sources
+    }
 }
 
 /// Default values for the configuration
@@ -66,8 +156,11 @@ impl Config {
         Self {
             version: CONFIG_VERSION,
             source_folder,
+            source_folders: Vec::new(),
             rules_file: data_dir.join(RULES_FILE_NAME),
+            rules_folder: None,
             logs_folder: data_dir.join(DEFAULT_LOGS_FOLDER),
+            sources: ConfigSources::from_env(),
         }
     }
 
@@ -84,10 +177,7 @@ impl Config {
         let config_path = Self::config_path();
 
         if config_path.exists() {
-            let file = fs::File::open(&config_path)?;
-            let reader = std::io::BufReader::new(file);
-            let config: Config = serde_yaml::from_reader(reader)?;
-            Ok(config)
+            Self::load_from(&config_path)
         } else {
             let config = Config::new_with_fallbacks();
             config.save()?;
@@ -95,6 +185,31 @@ impl Config {
         }
     }
 
+    /// Loads the Tooka configuration from the given file path.
+    ///
+    /// Unlike [`Config::load`], a missing file is an error rather than being
+    /// created with default values.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the file does not exist or cannot be read or parsed.
+    pub fn load_from(path: &Path) -> Result<Self, TookaError> {
+        log::debug!("Loading configuration from {}", path.display());
+        if !path.is_file() {
+            return Err(TookaError::ConfigError(format!(
+                "Config file not found: {}",
+                path.display()
+            )));
+        }
+
+        let mut document: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+        split_source_folder_list(&mut document)?;
+        let sources = ConfigSources::from_yaml(&document);
+        let mut config: Config = serde_yaml::from_value(document)?;
+        config.sources = sources;
+        config.expand_paths();
+        Ok(config)
+    }
+
     /// Saves the current configuration to the default path on disk.
     ///
     /// # Errors
@@ -129,8 +244,127 @@ impl Config {
     /// # Errors
     /// Returns a [`TookaError`] if the default configuration cannot be created or saved.
     pub fn reset_config(&mut self) -> Result<(), TookaError> {
-        *self = Config::new_with_fallbacks();
-        self.save()
+        *self = Self::reset(false)?;
+        Ok(())
+    }
+
+    /// Overwrites the configuration file with the default configuration,
+    /// without loading the current file first so that corrupted files can be reset.
+    ///
+    /// With `keep_rules_file`, the `rules_file` path of the current file is kept
+    /// if the file is still valid YAML.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the default configuration is invalid or cannot be saved.
+    pub fn reset(keep_rules_file: bool) -> Result<Self, TookaError> {
+        let mut config = Config::new_with_fallbacks();
+        if keep_rules_file {
+            match Self::stored_rules_file(&Self::config_path()) {
+                Some(rules_file) => {
+                    log::info!("Keeping rules file {}", rules_file.display());
+                    config.rules_file = rules_file;
+                    config.expand_paths();
+                }
+                None => {
+                    log::warn!("No readable rules_file in the current config, using the default")
+                }
+            }
+        }
+        config.validate_except_source_folder()?;
+        config.save()?;
+        Ok(config)
+    }
+
+    /// Reads just the `rules_file` entry of a configuration file, which works
+    /// as long as the file is valid YAML even if other fields are broken
+    pub(crate) fn stored_rules_file(path: &Path) -> Option<PathBuf> {
+        let document: serde_yaml::Value =
+            serde_yaml::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        document.get("rules_file")?.as_str().map(PathBuf::from)
+    }
+
+    /// Checks that the paths in the configuration are usable.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if `rules_folder` is set but is not a directory, or
+    /// [`ConfigValidationError::SourceFolderInvalid`] if `source_folder` is missing,
+    /// not a directory or not readable.
+    pub fn validate(&self) -> Result<(), TookaError> {
+        self.validate_except_source_folder()?;
+        self.validate_source_folder()?;
+        Ok(())
+    }
+
+    /// Like [`Config::validate`], but only logs a warning for an unusable
+    /// `source_folder`, which matters to sorting alone.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if `rules_folder` is set but is not a directory.
+    pub(crate) fn validate_except_source_folder(&self) -> Result<(), TookaError> {
+        if let Some(folder) = &self.rules_folder {
+            if !folder.is_dir() {
+                return Err(TookaError::ConfigError(format!(
+                    "Rules folder does not exist or is not a directory: {}",
+                    folder.display()
+                )));
+            }
+        }
+        if let Err(e) = self.validate_source_folder() {
+            log::warn!("{e}");
+        }
+        Ok(())
+    }
+
+    /// Checks that `source_folder` is an existing, readable directory,
+    /// suggesting a similarly named sibling directory if it does not exist
+    fn validate_source_folder(&self) -> Result<(), ConfigValidationError> {
+        let path = &self.source_folder;
+        let invalid = |reason: String| ConfigValidationError::SourceFolderInvalid {
+            path: path.clone(),
+            reason,
+        };
+
+        if !path.exists() {
+            let reason = match suggest_sibling(path) {
+                Some(suggestion) => {
+                    format!("does not exist. Did you mean '{}'?", suggestion.display())
+                }
+                None => "does not exist".to_string(),
+            };
+            return Err(invalid(reason));
+        }
+        if !path.is_dir() {
+            return Err(invalid("is not a directory".to_string()));
+        }
+        if let Err(e) = fs::read_dir(path) {
+            return Err(invalid(format!("is not readable: {e}")));
+        }
+        Ok(())
+    }
+
+    /// Expands a leading `~` in every path of the configuration to the home directory.
+    pub fn expand_paths(&mut self) {
+        self.source_folder = expand_tilde(&self.source_folder);
+        for folder in &mut self.source_folders {
+            *folder = expand_tilde(folder);
+        }
+        self.rules_file = expand_tilde(&self.rules_file);
+        if let Some(folder) = &mut self.rules_folder {
+            *folder = expand_tilde(folder);
+        }
+        self.logs_folder = expand_tilde(&self.logs_folder);
+    }
+
+    /// Returns `source_folder` followed by any additional `source_folders`,
+    /// without duplicates.
+    pub fn all_source_folders(&self) -> Vec<PathBuf> {
+        let mut folders = vec![self.source_folder.clone()];
+        for folder in &self.source_folders {
+            if !folders.contains(folder) {
+                folders.push(folder.clone());
+            }
+        }
+        folders
     }
 
     /// Returns the current configuration as a YAML-formatted string.
@@ -140,6 +374,58 @@ impl Config {
         serde_yaml::to_string(self).unwrap_or_else(|_| "Failed to serialize config".into())
     }
 
+    /// Returns the current configuration as a table of fields, values and
+    /// the source of each value.
+    pub fn print_table(&self) -> String {
+        let optional = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map_or_else(|| "-".to_string(), |p| p.display().to_string())
+        };
+        let source_folders = if self.source_folders.is_empty() {
+            "-".to_string()
+        } else {
+            self.source_folders
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut table = Table::new();
+        table.set_header(["Field", "Value", "Source"]);
+        for (field, value, source) in [
+            ("version", self.version.to_string(), self.sources.version),
+            (
+                "source_folder",
+                self.source_folder.display().to_string(),
+                self.sources.source_folder,
+            ),
+            (
+                "source_folders",
+                source_folders,
+                self.sources.source_folders,
+            ),
+            (
+                "rules_file",
+                self.rules_file.display().to_string(),
+                self.sources.rules_file,
+            ),
+            (
+                "rules_folder",
+                optional(&self.rules_folder),
+                self.sources.rules_folder,
+            ),
+            (
+                "logs_folder",
+                self.logs_folder.display().to_string(),
+                self.sources.logs_folder,
+            ),
+        ] {
+            table.add_row([field.to_string(), value, source.to_string()]);
+        }
+        table.to_string()
+    }
+
     /// Returns the path to the configuration file, creating it if necessary
     fn config_path() -> std::path::PathBuf {
         let home_dir = env::var("HOME").map_or_else(
@@ -156,3 +442,61 @@ impl Config {
         config_dir.join(CONFIG_FILE_NAME)
     }
 }
+
+/// Accepts `source_folder` as a list of folders: the first one becomes
+/// `source_folder` and the others are put in front of `source_folders`
+fn split_source_folder_list(document: &mut serde_yaml::Value) -> Result<(), TookaError> {
+    let Some(serde_yaml::Value::Sequence(folders)) = document.get_mut("source_folder") else {
+        return Ok(());
+    };
+    let mut folders = std::mem::take(folders);
+    if folders.is_empty() {
+        return Err(TookaError::ConfigError(
+            "source_folder must list at least one folder".into(),
+        ));
+    }
+    let first = folders.remove(0);
+    if let Some(serde_yaml::Value::Sequence(existing)) = document.get("source_folders") {
+        folders.extend(existing.iter().cloned());
+    }
+
+    document["source_folder"] = first;
+    if !folders.is_empty() {
+        document["source_folders"] = serde_yaml::Value::Sequence(folders);
+    }
+    Ok(())
+}
+
+/// Returns the sibling directory whose name is closest to the last component
+/// of `path`, if it is close enough to be a likely typo
+fn suggest_sibling(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let parent = path.parent()?;
+    fs::read_dir(parent)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let candidate = entry.file_name().to_str()?.to_lowercase();
+            let distance = levenshtein(&name, &candidate);
+            (distance <= 2 && distance < name.chars().count()).then(|| (distance, entry.path()))
+        })
+        .min()
+        .map(|(_, path)| path)
+}
+
+/// Number of single-character insertions, deletions and substitutions
+/// needed to turn `a` into `b`
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use crate::common::config::{Config, ConfigSource, ConfigSources, levenshtein};
+    use crate::core::error::{ConfigValidationError, TookaError};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_expand_paths_replaces_tilde() {
+        let home = PathBuf::from(std::env::var("HOME").unwrap());
+        let mut config = Config {
+            version: 1,
+            source_folder: PathBuf::from("~/Downloads"),
+            source_folders: vec![PathBuf::from("~"), PathBuf::from("~other/Desktop")],
+            rules_file: PathBuf::from("~/.config/tooka/rules.yaml"),
+            rules_folder: Some(PathBuf::from("~/rules")),
+            logs_folder: PathBuf::from("/var/log/tooka"),
+            sources: ConfigSources::default(),
+        };
+
+        config.expand_paths();
+
+        assert_eq!(config.source_folder, home.join("Downloads"));
+        assert_eq!(
+            config.source_folders,
+            [home.clone(), PathBuf::from("~other/Desktop")]
+        );
+        assert_eq!(config.rules_file, home.join(".config/tooka/rules.yaml"));
+        assert_eq!(config.rules_folder, Some(home.join("rules")));
+        assert_eq!(config.logs_folder, PathBuf::from("/var/log/tooka"));
+    }
+
+    #[test]
+    fn test_sources_from_yaml() {
+        let document = serde_yaml::from_str("version: 1\nrules_folder: /rules\n").unwrap();
+        let sources = ConfigSources::from_yaml(&document);
+
+        assert_eq!(sources.version, ConfigSource::ConfigFile);
+        assert_eq!(sources.rules_folder, ConfigSource::ConfigFile);
+        assert_ne!(sources.source_folders, ConfigSource::ConfigFile);
+    }
+
+    #[test]
+    fn test_print_table_shows_sources() {
+        let config = Config {
+            version: 1,
+            source_folder: PathBuf::from("/home/user/Downloads"),
+            source_folders: Vec::new(),
+            rules_file: PathBuf::from("/data/rules.yaml"),
+            rules_folder: None,
+            logs_folder: PathBuf::from("/data/logs"),
+            sources: ConfigSources {
+                version: ConfigSource::ConfigFile,
+                rules_file: ConfigSource::EnvVar,
+                ..Default::default()
+            },
+        };
+
+        let table = config.print_table();
+        let row = |field: &str| table.lines().find(|l| l.contains(field)).unwrap();
+        assert!(row("version").contains("config file"));
+        assert!(row("rules_file").contains("/data/rules.yaml"));
+        assert!(row("rules_file").contains("env var"));
+        assert!(row("logs_folder").contains("default"));
+    }
+
+    #[test]
+    fn test_stored_rules_file_survives_broken_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        std::fs::write(&path, "version: broken\nrules_file: /data/rules.yaml\n").unwrap();
+        assert_eq!(
+            Config::stored_rules_file(&path),
+            Some(PathBuf::from("/data/rules.yaml"))
+        );
+
+        std::fs::write(&path, "rules_file: [unclosed").unwrap();
+        assert_eq!(Config::stored_rules_file(&path), None);
+        assert_eq!(Config::stored_rules_file(&dir.path().join("missing")), None);
+    }
+
+    #[test]
+    fn test_load_from_accepts_source_folder_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        std::fs::write(
+            &path,
+            "source_folder:\n  - /data/Downloads\n  - /data/Desktop\nsource_folders: [/data/Scans]\n",
+        )
+        .unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.source_folder, PathBuf::from("/data/Downloads"));
+        assert_eq!(
+            config.all_source_folders(),
+            ["/data/Downloads", "/data/Desktop", "/data/Scans"].map(PathBuf::from)
+        );
+        assert_eq!(config.sources.source_folders, ConfigSource::ConfigFile);
+
+        std::fs::write(&path, "source_folder: [/data/Downloads]\n").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(
+            config.all_source_folders(),
+            [PathBuf::from("/data/Downloads")]
+        );
+
+        std::fs::write(&path, "source_folder: []\n").unwrap();
+        assert!(matches!(
+            Config::load_from(&path),
+            Err(TookaError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("downloads", "downloads"), 0);
+        assert_eq!(levenshtein("downlods", "downloads"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_validate_source_folder_suggests_typo_fix() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("Downloads")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a folder").unwrap();
+        let config_with = |source: &str| Config {
+            version: 1,
+            source_folder: dir.path().join(source),
+            source_folders: Vec::new(),
+            rules_file: dir.path().join("rules.yaml"),
+            rules_folder: None,
+            logs_folder: dir.path().join("logs"),
+            sources: ConfigSources::default(),
+        };
+
+        assert!(config_with("Downloads").validate().is_ok());
+
+        let error = config_with("Downlods").validate().unwrap_err();
+        assert!(matches!(
+            &error,
+            TookaError::ConfigValidation(ConfigValidationError::SourceFolderInvalid { path, .. })
+                if path.ends_with("Downlods")
+        ));
+        let expected = format!("Did you mean '{}'?", dir.path().join("Downloads").display());
+        assert!(error.to_string().contains(&expected), "{error}");
+
+        let error = config_with("Elsewhere").validate().unwrap_err();
+        assert!(error.to_string().ends_with("does not exist"), "{error}");
+        let error = config_with("notes.txt").validate().unwrap_err();
+        assert!(error.to_string().contains("is not a directory"), "{error}");
+
+        // Only sorting needs the source folder
+        assert!(
+            config_with("Downlods")
+                .validate_except_source_folder()
+                .is_ok()
+        );
+    }
+}
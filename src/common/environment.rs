@@ -44,6 +44,23 @@ where
     fallback
 }
 
+/// Expands a leading `~` in `path` to the user's home directory.
+///
+/// Only `~` on its own or followed by a separator is expanded; other paths,
+/// including `~user`, are returned unchanged, as is everything if `$HOME` is not set.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    let Ok(rest) = path.strip_prefix("~") else {
+        return path.to_path_buf();
+    };
+    match env::var("HOME") {
+        Ok(home) => PathBuf::from(home).join(rest),
+        Err(_) => {
+            log::warn!("$HOME not set; cannot expand '{}'", path.display());
+            path.to_path_buf()
+        }
+    }
+}
+
 /// Returns the source folder path, usually pointing to the Downloads directory.
 ///
 /// Uses `TOOKA_SOURCE_FOLDER` if set. Otherwise tries to locate the system's
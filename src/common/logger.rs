@@ -4,7 +4,7 @@
 //! It supports separate log files for general logs and file operation logs,
 //! with daily log rotation and a maximum number of retained log files.
 
-use crate::{core::context, core::error::TookaError};
+use crate::{common::config::Config, core::context, core::error::TookaError};
 use chrono::Local;
 use flexi_logger::writers::LogWriter;
 use flexi_logger::{LogSpecification, Logger, Record, WriteMode};
@@ -24,6 +24,56 @@ static LOGGER_HANDLE: OnceLock<flexi_logger::LoggerHandle> = OnceLock::new();
 /// Maximum number of log files to keep
 const MAX_LOG_FILES: usize = 10;
 
+/// Timestamp format shared by the formatter and the log parser
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Which set of log files to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogType {
+    /// General application log, `main.log`
+    Main,
+    /// File operation logs, the daily files in `ops/`
+    Ops,
+}
+
+/// Single log record parsed from a log file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Parses a line written by `custom_format`, e.g.
+    /// `2025-01-01 12:00:00 [INFO] tooka - Tooka CLI started`
+    fn parse(line: &str) -> Option<Self> {
+        let timestamp = line.get(..19)?;
+        chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+        let rest = line[19..].strip_prefix(" [")?;
+        let (level, rest) = rest.split_once("] ")?;
+        let (target, message) = rest.split_once(" - ")?;
+        Some(Self {
+            timestamp: timestamp.to_string(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: message.to_string(),
+        })
+    }
+
+    /// Whether the level, target or message contains `query`
+    pub(crate) fn contains(&self, query: &str, case_sensitive: bool) -> bool {
+        let fields = [&self.level, &self.target, &self.message];
+        if case_sensitive {
+            fields.iter().any(|f| f.contains(query))
+        } else {
+            let query = query.to_lowercase();
+            fields.iter().any(|f| f.to_lowercase().contains(&query))
+        }
+    }
+}
+
 /// Writer that routes logs based on target
 struct DualWriter {
     /// Directory for main logs
@@ -35,13 +85,14 @@ struct DualWriter {
 /// Initializes the Tooka logger.
 ///
 /// Sets up logging directories, configures log levels and targets,
-/// and ensures that the logger is only initialized once.
+/// and ensures that the logger is only initialized once. The logs folder is
+/// read from the config file rather than the global context, so the logger
+/// can be set up first and record how the context is loaded.
 ///
 /// # Errors
 /// Returns a [`TookaError`] if initialization fails or config cannot be loaded.
 pub fn init_logger() -> Result<(), TookaError> {
-    let config = context::get_locked_config()
-        .map_err(|e| TookaError::ConfigError(format!("Failed to get config: {e}")))?;
+    let config = Config::load()?;
     let logs_folder = &config.logs_folder;
 
     // Ensure folders exist
@@ -65,10 +116,96 @@ pub fn init_logger() -> Result<(), TookaError> {
 /// Logs a file operation message.
 ///
 /// Sends the message to the `file_ops` log target for separate logging.
+#[doc(hidden)]
 pub fn log_file_operation(msg: &str) {
     log::info!(target: "file_ops", "{msg}");
 }
 
+/// Reads the last `max_lines` entries of the given log type, oldest first.
+///
+/// # Errors
+/// Returns a [`TookaError`] if the config cannot be loaded or a log file
+/// cannot be read.
+pub fn read_recent_logs(max_lines: usize, log_type: LogType) -> Result<Vec<LogEntry>, TookaError> {
+    let mut entries = read_logs(&logs_folder()?, log_type)?;
+    let skip = entries.len().saturating_sub(max_lines);
+    entries.drain(..skip);
+    Ok(entries)
+}
+
+/// Returns all entries of the given log type whose level, target or message
+/// contains `query`, oldest first.
+///
+/// # Errors
+/// Returns a [`TookaError`] if the config cannot be loaded or a log file
+/// cannot be read.
+pub fn search_logs(
+    query: &str,
+    log_type: LogType,
+    case_sensitive: bool,
+) -> Result<Vec<LogEntry>, TookaError> {
+    let mut entries = read_logs(&logs_folder()?, log_type)?;
+    entries.retain(|entry| entry.contains(query, case_sensitive));
+    Ok(entries)
+}
+
+fn logs_folder() -> Result<PathBuf, TookaError> {
+    let config = context::get_locked_config()
+        .map_err(|e| TookaError::ConfigError(format!("Failed to get config: {e}")))?;
+    Ok(config.logs_folder.clone())
+}
+
+/// Parses every entry of the given log type under `logs_folder`
+pub(crate) fn read_logs(
+    logs_folder: &Path,
+    log_type: LogType,
+) -> Result<Vec<LogEntry>, TookaError> {
+    let files = match log_type {
+        LogType::Main => vec![logs_folder.join("main.log")],
+        LogType::Ops => {
+            let ops_dir = logs_folder.join("ops");
+            if !ops_dir.is_dir() {
+                return Ok(Vec::new());
+            }
+            let mut files: Vec<_> = std::fs::read_dir(ops_dir)?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("log"))
+                .collect();
+            // Daily files sort by name, but `-10` must come after `-9`
+            files.sort_by_key(|path| ops_file_order(path));
+            files
+        }
+    };
+
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for file in files.iter().filter(|f| f.is_file()) {
+        for line in std::fs::read_to_string(file)?.lines() {
+            match LogEntry::parse(line) {
+                Some(entry) => entries.push(entry),
+                // Messages spanning several lines belong to the previous entry
+                None => {
+                    if let Some(last) = entries.last_mut() {
+                        last.message.push('\n');
+                        last.message.push_str(line);
+                    }
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Sort key for ops log files named `YYYY-MM-DD.log` or `YYYY-MM-DD-N.log`
+fn ops_file_order(path: &Path) -> (String, usize) {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let date = stem.get(..10).unwrap_or(stem);
+    let index = stem
+        .get(11..)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_default();
+    (date.to_string(), index)
+}
+
 /// Custom formatter
 fn custom_format(
     w: &mut dyn Write,
@@ -78,7 +215,7 @@ fn custom_format(
     writeln!(
         w,
         "{} [{}] {} - {}",
-        now.format("%Y-%m-%d %H:%M:%S"),
+        now.format(TIMESTAMP_FORMAT),
         record.level(),
         record.target(),
         record.args()
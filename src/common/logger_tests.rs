@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use crate::common::logger::{LogType, read_logs};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const MAIN_LOG: &str = "\
+2025-01-01 12:00:00 [INFO] tooka - Tooka CLI started
+2025-01-01 12:00:01 [ERROR] tooka::core::sorter - Failed to sort file
+caused by: permission denied
+2025-01-01 12:00:02 [DEBUG] tooka::file::file_match - Matching rule 'pdfs'
+";
+
+    #[test]
+    fn test_read_main_log_entries() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.log"), MAIN_LOG).unwrap();
+
+        let entries = read_logs(dir.path(), LogType::Main).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].timestamp, "2025-01-01 12:00:00");
+        assert_eq!(entries[0].level, "INFO");
+        assert_eq!(entries[0].target, "tooka");
+        assert_eq!(entries[0].message, "Tooka CLI started");
+        assert_eq!(
+            entries[1].message,
+            "Failed to sort file\ncaused by: permission denied"
+        );
+    }
+
+    #[test]
+    fn test_read_missing_logs_is_empty() {
+        let dir = tempdir().unwrap();
+
+        assert!(read_logs(dir.path(), LogType::Main).unwrap().is_empty());
+        assert!(read_logs(dir.path(), LogType::Ops).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_ops_logs_in_rotation_order() {
+        let dir = tempdir().unwrap();
+        let ops = dir.path().join("ops");
+        fs::create_dir_all(&ops).unwrap();
+        for (name, message) in [
+            ("2025-01-02.log", "third"),
+            ("2025-01-01-10.log", "second"),
+            ("2025-01-01-9.log", "first"),
+        ] {
+            fs::write(
+                ops.join(name),
+                format!("2025-01-01 12:00:00 [INFO] file_ops - {message}\n"),
+            )
+            .unwrap();
+        }
+
+        let entries = read_logs(dir.path(), LogType::Ops).unwrap();
+        let messages: Vec<_> = entries.iter().map(|e| e.message.as_str()).collect();
+
+        assert_eq!(messages, ["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_log_entry_search() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.log"), MAIN_LOG).unwrap();
+        let entries = read_logs(dir.path(), LogType::Main).unwrap();
+
+        let matches = |query: &str, case_sensitive: bool| {
+            entries
+                .iter()
+                .filter(|e| e.contains(query, case_sensitive))
+                .count()
+        };
+
+        assert_eq!(matches("error", false), 1);
+        assert_eq!(matches("error", true), 0);
+        assert_eq!(matches("permission", true), 1);
+        assert_eq!(matches("tooka::", false), 2);
+    }
+}
@@ -1,3 +1,9 @@
 pub mod config;
+#[doc(hidden)]
 pub mod environment;
 pub mod logger;
+
+#[cfg(test)]
+mod config_tests;
+#[cfg(test)]
+mod logger_tests;
@@ -2,7 +2,13 @@ use anyhow::Result;
 use clap::Args;
 use clap::CommandFactory;
 use clap_complete::{generate, shells::Shell};
-use std::io;
+use std::io::{self, Write};
+
+/// Subcommands whose positional argument is a rule ID
+const RULE_ID_SUBCOMMANDS: [&str; 4] = ["toggle", "remove", "export", "update"];
+
+/// Command used at completion time to fetch the current rule IDs
+const RULE_IDS_COMMAND: &str = "tooka list --format=ids";
 
 #[derive(Args)]
 #[command(about = "🔧 Generate shell completions")]
@@ -16,10 +22,88 @@ pub fn run(args: &CompletionsArgs) -> Result<()> {
     log::info!("Generating completions for shell: {:?}", args.shell);
 
     let mut cmd = crate::Cli::command();
-    generate(args.shell, &mut cmd, "tooka", &mut io::stdout());
+    let mut buf = Vec::new();
+    generate(args.shell, &mut cmd, "tooka", &mut buf);
+
+    let script = add_rule_id_completions(args.shell, String::from_utf8(buf)?);
+    io::stdout().write_all(script.as_bytes())?;
+
     log::info!(
         "Completions generated successfully for shell: {:?}",
         args.shell
     );
     Ok(())
 }
+
+/// Extends a generated completion script so that rule ID arguments are
+/// completed dynamically from `tooka list --format=ids`.
+///
+/// Shells without a dedicated hook get the script back unchanged.
+fn add_rule_id_completions(shell: Shell, script: String) -> String {
+    let subcommands = RULE_ID_SUBCOMMANDS.join("|");
+
+    match shell {
+        Shell::Bash => format!(
+            r#"{script}
+_tooka_with_rule_ids() {{
+    if [[ ${{COMP_CWORD}} -eq 2 ]]; then
+        case "${{COMP_WORDS[1]}}" in
+            {subcommands})
+                COMPREPLY=( $(compgen -W "$({RULE_IDS_COMMAND} 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+                return 0
+                ;;
+        esac
+    fi
+    _tooka "$@"
+}}
+
+complete -F _tooka_with_rule_ids -o bashdefault -o default tooka
+"#
+        ),
+        Shell::Zsh => {
+            let helper = format!(
+                r#"(( $+functions[_tooka_rule_ids] )) ||
+_tooka_rule_ids() {{
+    local -a rule_ids
+    rule_ids=(${{(f)"$({RULE_IDS_COMMAND} 2>/dev/null)"}})
+    _describe -t rule-ids 'rule id' rule_ids
+}}
+
+"#
+            );
+
+            let mut patched: String = script
+                .lines()
+                .map(|line| {
+                    let is_rule_id_arg =
+                        line.starts_with("':id -- ") || line.starts_with("':rule_id -- ");
+                    if is_rule_id_arg {
+                        line.replacen(":_default'", ":_tooka_rule_ids'", 1)
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            patched.push('\n');
+
+            match patched.find("if [ \"$funcstack[1]\" = \"_tooka\" ]") {
+                Some(pos) => {
+                    patched.insert_str(pos, &helper);
+                    patched
+                }
+                None => format!("{patched}\n{helper}"),
+            }
+        }
+        Shell::Fish => {
+            let mut script = script;
+            for subcommand in RULE_ID_SUBCOMMANDS {
+                script.push_str(&format!(
+                    "complete -c tooka -n '__fish_seen_subcommand_from {subcommand}' -f -a \"({RULE_IDS_COMMAND})\"\n"
+                ));
+            }
+            script
+        }
+        _ => script,
+    }
+}
@@ -1,14 +1,29 @@
 //! Core application context for Tooka.
 //!
-//! This module defines global constants and manages the global state for
-//! configuration and rules file, providing thread-safe access via `Mutex`
-//! wrapped in `Arc` and initialized once with `OnceLock`.
+//! This module defines global constants and the [`Context`] struct, which
+//! bundles the configuration and rules file behind thread-safe `Mutex`es and
+//! exposes sorting and reporting as methods. Embedders can create as many
+//! contexts as they need; the CLI keeps a single global one initialized once
+//! with `OnceLock`.
 //!
-//! It includes functions to initialize, set, and safely access these globals.
+//! The global functions are thin wrappers over that global context. Tests
+//! that modify it can isolate themselves with [`snapshot`] and [`restore`].
 
-use crate::{common::config::Config, core::error::TookaError, rules::rules_file::RulesFile};
-use anyhow::{Context, Result};
-use std::sync::{Arc, Mutex, OnceLock};
+use crate::{
+    common::config::Config,
+    core::{
+        error::TookaError,
+        report,
+        sorter::{self, MatchResult, SortOptions, SortProgress},
+    },
+    rules::rules_file::RulesFile,
+};
+use anyhow::{Context as _, Result};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
 
 /// Configuration version number.
 pub const CONFIG_VERSION: usize = 0;
@@ -26,55 +41,280 @@ pub const APP_ORG: &str = "github.tooka-org";
 /// Application name.
 pub const APP_NAME: &str = "tooka";
 
-/// Global, thread-safe storage of the configuration.
-static CONFIG: OnceLock<Arc<Mutex<Config>>> = OnceLock::new();
-/// Global, thread-safe storage of the rules file.
-static RULES_FILE: OnceLock<Arc<Mutex<RulesFile>>> = OnceLock::new();
+/// Configuration and rules used to sort files, independent of global state.
+#[derive(Debug)]
+pub struct Context {
+    config: Mutex<Config>,
+    rules_file: Mutex<RulesFile>,
+}
+
+impl Context {
+    /// Creates a new context from a configuration and a rules file.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the rules file contains duplicate rule IDs.
+    pub fn new(config: Config, rules_file: RulesFile) -> Result<Self, TookaError> {
+        let mut seen = HashSet::new();
+        if let Some(rule) = rules_file
+            .rules
+            .iter()
+            .find(|r| !seen.insert(r.id.as_str()))
+        {
+            return Err(TookaError::InvalidRule(format!(
+                "Duplicate rule ID '{}'",
+                rule.id
+            )));
+        }
+
+        Ok(Self {
+            config: Mutex::new(config),
+            rules_file: Mutex::new(rules_file),
+        })
+    }
+
+    /// Creates a context from a config file and a rules file at the given
+    /// paths, without looking up the default config directory.
+    ///
+    /// `rules_path` replaces the `rules_file` and `rules_folder` of the config.
+    /// An empty rules file is created if it doesn't exist.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the config file is missing or invalid, or
+    /// the rules file can't be read or contains duplicate rule IDs.
+    pub fn new_from_path(config_path: &Path, rules_path: &Path) -> Result<Self, TookaError> {
+        let mut config = Config::load_from(config_path)?;
+        config.rules_file = rules_path.to_path_buf();
+        config.rules_folder = None;
+        let rules_file = RulesFile::load_from(rules_path)?;
+        Self::new(config, rules_file)
+    }
+
+    /// Locks and returns the configuration of this context.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the lock is poisoned.
+    pub fn config(&self) -> Result<MutexGuard<'_, Config>, TookaError> {
+        self.config
+            .lock()
+            .map_err(|e| TookaError::Other(format!("Failed to acquire lock on config: {e}")))
+    }
+
+    /// Locks and returns the rules file of this context.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the lock is poisoned.
+    pub fn rules_file(&self) -> Result<MutexGuard<'_, RulesFile>, TookaError> {
+        self.rules_file
+            .lock()
+            .map_err(|e| TookaError::Other(format!("Failed to acquire lock on rules file: {e}")))
+    }
+
+    /// Sorts `files` with the enabled rules of this context, in priority order,
+    /// the same way `tooka sort` does with [`sorter::sort_files_multi`].
+    ///
+    /// Each file is sorted relative to the first configured source folder that
+    /// contains it, see [`Config::all_source_folders`]; files outside all of
+    /// them are sorted relative to `source_folder`.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if no rules are enabled or a file operation fails.
+    pub fn sort_files<F>(
+        &self,
+        files: &[PathBuf],
+        dry_run: bool,
+        on_progress: Option<F>,
+    ) -> Result<Vec<MatchResult>, TookaError>
+    where
+        F: Fn(usize, usize) + Send + Sync,
+    {
+        let source_folders = self.config()?.all_source_folders();
+        let rules = self.rules_file()?.clone().optimized_with_filter(None)?;
+
+        let mut files_by_source = vec![Vec::new(); source_folders.len()];
+        for file in files {
+            let index = source_folders
+                .iter()
+                .position(|folder| file.starts_with(folder))
+                .unwrap_or(0);
+            files_by_source[index].push(file.clone());
+        }
+        let sources: Vec<_> = source_folders
+            .iter()
+            .zip(files_by_source)
+            .filter(|(_, files)| !files.is_empty())
+            .map(|(folder, files)| sorter::prepare_files(folder, rules.clone(), files))
+            .collect();
+
+        sorter::sort_files_multi(
+            &sources,
+            dry_run,
+            &SortOptions::default(),
+            on_progress.map(|cb| move |p: SortProgress| cb(p.files_processed, p.files_total)),
+        )
+    }
 
-/// Loads and initializes the global configuration.
+    /// Recursively collects all files in the configured source folders, each
+    /// file once.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if a source folder does not exist or cannot be read.
+    pub fn collect_files(&self) -> Result<Vec<PathBuf>, TookaError> {
+        let source_folders = self.config()?.all_source_folders();
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+        for folder in &source_folders {
+            files.extend(
+                sorter::collect_files(folder)?
+                    .into_iter()
+                    .filter(|file| seen.insert(file.clone())),
+            );
+        }
+        Ok(files)
+    }
+
+    /// Generates a report of `results` in the given format (`json`, `csv` or `pdf`).
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if the format is unsupported or writing the report fails.
+    pub fn generate_report(
+        &self,
+        format: &str,
+        output_dir: &Path,
+        results: &[MatchResult],
+    ) -> Result<(), TookaError> {
+        report::generate_report(format, output_dir, results)
+    }
+}
+
+/// Global context used by the CLI.
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+
+/// Loads the configuration and rules and initializes the global context.
 ///
 /// # Errors
-/// Returns an error if loading the configuration or initialization fails.
-pub fn init_config() -> Result<()> {
+/// Returns an error if loading either file fails or the context is already initialized.
+pub fn init_context() -> Result<()> {
     let config = Config::load().context("Failed to load configuration")?;
-    CONFIG
-        .set(Arc::new(Mutex::new(config)))
-        .map_err(|_| TookaError::ConfigAlreadyInitialized.into())
+    config.validate_except_source_folder()?;
+    let rules_file = RulesFile::load_for(&config).context("Failed to load rules")?;
+    init_context_with(Context::new(config, rules_file)?)
 }
 
-/// Loads and initializes the global rules file.
+/// Initializes the global context with an already constructed [`Context`].
 ///
 /// # Errors
-/// Returns an error if loading the rules file or initialization fails.
-pub fn init_rules_file() -> Result<()> {
-    let rules_file = RulesFile::load().context("Failed to load rules file")?;
-    RULES_FILE
-        .set(Arc::new(Mutex::new(rules_file)))
-        .map_err(|_| TookaError::RulesFileAlreadyInitialized.into())
+/// Returns an error if the context is already initialized.
+pub fn init_context_with(context: Context) -> Result<()> {
+    CONTEXT
+        .set(context)
+        .map_err(|_| TookaError::ContextAlreadyInitialized.into())
 }
 
-/// Locks and returns a reference to the global rules file.
+/// Returns the global context.
 ///
 /// # Errors
-/// Returns an error if the rules file is not initialized or lock acquisition fails.
-pub fn get_locked_rules_file() -> Result<std::sync::MutexGuard<'static, RulesFile>> {
-    let rules_file = RULES_FILE
+/// Returns an error if the context is not initialized.
+pub fn get_context() -> Result<&'static Context> {
+    CONTEXT
         .get()
-        .ok_or_else(|| anyhow::anyhow!("Rules file not initialized"))?;
-    rules_file
-        .lock()
-        .map_err(|e| anyhow::anyhow!("Failed to acquire lock on rules file: {}", e))
+        .ok_or_else(|| anyhow::anyhow!("Context not initialized"))
+}
+
+/// Locks and returns a reference to the global rules file.
+///
+/// # Errors
+/// Returns an error if the context is not initialized or lock acquisition fails.
+pub fn get_locked_rules_file() -> Result<MutexGuard<'static, RulesFile>> {
+    Ok(get_context()?.rules_file()?)
 }
 
 /// Locks and returns a reference to the global configuration.
 ///
 /// # Errors
-/// Returns an error if the config is not initialized or lock acquisition fails.
-pub fn get_locked_config() -> Result<std::sync::MutexGuard<'static, Config>> {
-    let config = CONFIG
-        .get()
-        .ok_or_else(|| anyhow::anyhow!("Config not initialized"))?;
-    config
-        .lock()
-        .map_err(|e| anyhow::anyhow!("Failed to acquire lock on config: {}", e))
+/// Returns an error if the context is not initialized or lock acquisition fails.
+pub fn get_locked_config() -> Result<MutexGuard<'static, Config>> {
+    Ok(get_context()?.config()?)
+}
+
+/// Copy of the global configuration and rules file, taken with [`snapshot`].
+///
+/// Fields are `None` if the global context was not initialized when the
+/// snapshot was taken.
+#[derive(Debug, Clone)]
+pub struct ContextSnapshot {
+    config: Option<Config>,
+    rules_file: Option<RulesFile>,
+}
+
+/// Captures the current global configuration and rules file.
+///
+/// Poisoned locks are recovered, so a panicking test does not prevent
+/// later tests from taking snapshots.
+#[must_use]
+pub fn snapshot() -> ContextSnapshot {
+    let Some(context) = CONTEXT.get() else {
+        return ContextSnapshot {
+            config: None,
+            rules_file: None,
+        };
+    };
+
+    ContextSnapshot {
+        config: Some(
+            context
+                .config
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        ),
+        rules_file: Some(
+            context
+                .rules_file
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        ),
+    }
+}
+
+/// Restores the global configuration and rules file from a [`snapshot`].
+///
+/// Does nothing for values that were not captured.
+pub fn restore(snapshot: ContextSnapshot) {
+    let Some(context) = CONTEXT.get() else {
+        return;
+    };
+
+    if let Some(config) = snapshot.config {
+        *context
+            .config
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = config;
+    }
+    if let Some(rules_file) = snapshot.rules_file {
+        *context
+            .rules_file
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = rules_file;
+    }
+}
+
+/// Creates an isolated [`Context`] whose folders and rules file live in a
+/// fresh temp directory. The directory is removed when the returned
+/// [`tempfile::TempDir`] is dropped.
+#[cfg(test)]
+pub(crate) fn test_context() -> (tempfile::TempDir, Context) {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let config = Config {
+        version: CONFIG_VERSION,
+        source_folder: dir.path().join("source"),
+        source_folders: Vec::new(),
+        rules_file: dir.path().join(RULES_FILE_NAME),
+        rules_folder: None,
+        logs_folder: dir.path().join(DEFAULT_LOGS_FOLDER),
+        sources: Default::default(),
+    };
+    std::fs::create_dir_all(&config.source_folder).expect("Failed to create source folder");
+    let context = Context::new(config, RulesFile::default()).expect("Empty rules are valid");
+    (dir, context)
 }
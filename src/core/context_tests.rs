@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use crate::common::config::Config;
+    use crate::core::context::{self, Context, test_context};
+    use crate::core::error::TookaError;
+    use crate::rules::rule::{Action, Conditions, ConflictStrategy, MoveAction, Rule};
+    use crate::rules::rules_file::RulesFile;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Helper function to create a rule moving files with the given extension
+    fn extension_rule(id: &str, ext: &str, dest: &Path, enabled: bool) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled,
+            description: None,
+            priority: 1,
+            when: Conditions {
+                any: None,
+                filename: None,
+                extensions: Some(vec![ext.to_string()]),
+                path: None,
+                path_any: None,
+                exclude_path: None,
+                min_depth: None,
+                max_depth: None,
+                size_kb: None,
+                is_empty_file: None,
+                mime_type: None,
+                created_date: None,
+                modified_date: None,
+                file_age_days: None,
+                is_symlink: None,
+                hardlink_count: None,
+                permissions: None,
+                case_sensitive: None,
+                content_matches: None,
+                content_max_bytes: None,
+                content_hash: None,
+                metadata: None,
+                all: None,
+                any_of: None,
+            },
+            then: vec![Action::Move(MoveAction {
+                to: dest.to_string_lossy().to_string(),
+                preserve_structure: false,
+                preserve_depth: None,
+                allow_directory: false,
+                verify_after_move: false,
+                if_newer_than: None,
+                if_older_than: None,
+                on_conflict: ConflictStrategy::Overwrite,
+            })],
+            after: None,
+            tags: None,
+        }
+    }
+
+    /// Helper function to create a config rooted in the given directory
+    fn test_config(root: &Path) -> Config {
+        Config {
+            version: 0,
+            source_folder: root.join("source"),
+            source_folders: Vec::new(),
+            rules_file: root.join("rules.yaml"),
+            rules_folder: None,
+            logs_folder: root.join("logs"),
+            sources: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_duplicate_rule_ids() {
+        let dir = tempdir().unwrap();
+        let rules_file = RulesFile {
+            rules: vec![
+                extension_rule("dup", "txt", dir.path(), true),
+                extension_rule("dup", "log", dir.path(), true),
+            ],
+        };
+
+        let result = Context::new(test_config(dir.path()), rules_file);
+
+        assert!(matches!(result, Err(TookaError::InvalidRule(msg)) if msg.contains("dup")));
+    }
+
+    #[test]
+    fn test_collect_and_sort_files_use_context_state() {
+        let (dir, context) = test_context();
+        let source = context.config().unwrap().source_folder.clone();
+        fs::write(source.join("notes.txt"), "notes").unwrap();
+        fs::write(source.join("app.log"), "log").unwrap();
+
+        let dest = dir.path().join("dest");
+        context.rules_file().unwrap().rules = vec![
+            extension_rule("txt_rule", "txt", &dest, true),
+            extension_rule("log_rule", "log", &dest, false),
+        ];
+
+        let files = context.collect_files().unwrap();
+        assert_eq!(files.len(), 2);
+
+        let results = context
+            .sort_files(&files, true, None::<fn(usize, usize)>)
+            .unwrap();
+        let txt = results.iter().find(|r| r.file_name == "notes.txt").unwrap();
+        let log = results.iter().find(|r| r.file_name == "app.log").unwrap();
+
+        assert_eq!(txt.matched_rule_id, "txt_rule");
+        assert_eq!(txt.new_path, dest.join("notes.txt"));
+        // Disabled rules are not applied
+        assert_eq!(log.matched_rule_id, "none");
+    }
+
+    #[test]
+    fn test_sort_files_uses_all_source_folders() {
+        let (dir, context) = test_context();
+        let source = context.config().unwrap().source_folder.clone();
+        let extra = dir.path().join("extra");
+        context.config().unwrap().source_folders = vec![extra.clone()];
+        fs::create_dir_all(source.join("a")).unwrap();
+        fs::create_dir_all(extra.join("b")).unwrap();
+        fs::write(source.join("a/notes.txt"), "notes").unwrap();
+        fs::write(extra.join("b/todo.txt"), "todo").unwrap();
+
+        let dest = dir.path().join("dest");
+        let mut rule = extension_rule("txt_rule", "txt", &dest, true);
+        if let Action::Move(action) = &mut rule.then[0] {
+            action.preserve_structure = true;
+        }
+        context.rules_file().unwrap().rules = vec![rule];
+
+        let files = context.collect_files().unwrap();
+        assert_eq!(files.len(), 2);
+
+        // Each file keeps its structure relative to its own source folder
+        let results = context
+            .sort_files(&files, true, None::<fn(usize, usize)>)
+            .unwrap();
+        let new_path = |name: &str| {
+            results
+                .iter()
+                .find(|r| r.file_name == name)
+                .unwrap()
+                .new_path
+                .clone()
+        };
+        assert_eq!(new_path("notes.txt"), dest.join("a/notes.txt"));
+        assert_eq!(new_path("todo.txt"), dest.join("b/todo.txt"));
+    }
+
+    #[test]
+    fn test_new_from_path_contexts_are_independent() {
+        let dir = tempdir().unwrap();
+        let contexts: Vec<Context> = ["a", "b"]
+            .iter()
+            .map(|name| {
+                let root = dir.path().join(name);
+                let config = test_config(&root);
+                fs::create_dir_all(&config.source_folder).unwrap();
+                fs::write(config.source_folder.join("notes.txt"), "notes").unwrap();
+                let config_path = root.join("tooka.yaml");
+                fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+
+                let rules = RulesFile {
+                    rules: vec![extension_rule(name, "txt", &root.join("dest"), true)],
+                };
+                let rules_path = root.join("my_rules.yaml");
+                fs::write(&rules_path, serde_yaml::to_string(&rules).unwrap()).unwrap();
+
+                Context::new_from_path(&config_path, &rules_path).unwrap()
+            })
+            .collect();
+
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = contexts
+                .iter()
+                .map(|context| {
+                    scope.spawn(|| {
+                        let files = context.collect_files().unwrap();
+                        context
+                            .sort_files(&files, true, None::<fn(usize, usize)>)
+                            .unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        for (name, results) in ["a", "b"].iter().zip(&results) {
+            let root = dir.path().join(name);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].matched_rule_id, *name);
+            assert_eq!(results[0].new_path, root.join("dest/notes.txt"));
+        }
+        assert_eq!(
+            contexts[0].config().unwrap().rules_file,
+            dir.path().join("a/my_rules.yaml")
+        );
+    }
+
+    #[test]
+    fn test_new_from_path_requires_config_file() {
+        let dir = tempdir().unwrap();
+
+        let result = Context::new_from_path(
+            &dir.path().join("missing.yaml"),
+            &dir.path().join("rules.yaml"),
+        );
+
+        assert!(matches!(result, Err(TookaError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_generate_report_writes_json() {
+        let (dir, context) = test_context();
+
+        context
+            .generate_report("json", &dir.path().join("reports"), &[])
+            .unwrap();
+
+        assert!(dir.path().join("reports/tooka_report.json").exists());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_global_context() {
+        let (dir, isolated) = test_context();
+        // Another test may have initialized the global context already
+        let _ = context::init_context_with(isolated);
+
+        let snapshot = context::snapshot();
+        let original_source = context::get_locked_config().unwrap().source_folder.clone();
+        let original_rules = context::get_locked_rules_file().unwrap().rules.len();
+
+        context::get_locked_config().unwrap().source_folder = dir.path().join("elsewhere");
+        context::get_locked_rules_file()
+            .unwrap()
+            .rules
+            .push(extension_rule("temp_rule", "tmp", dir.path(), true));
+
+        context::restore(snapshot);
+
+        assert_eq!(
+            context::get_locked_config().unwrap().source_folder,
+            original_source
+        );
+        assert_eq!(
+            context::get_locked_rules_file().unwrap().rules.len(),
+            original_rules
+        );
+    }
+}
@@ -7,15 +7,35 @@
 //! It also defines `RuleValidationError` for detailed validation error reporting
 //! related to rules processing.
 
+use crate::core::sorter::VerificationFailure;
 use glob::PatternError;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{io, path};
 use thiserror::Error;
 
+/// Stable code of [`TookaError::RuleNotFound`]
+pub const ERROR_RULE_NOT_FOUND: u32 = 1001;
+/// Stable code of failed file operations
+pub const ERROR_FILE_OPERATION: u32 = 1002;
+/// Stable code of configuration errors
+pub const ERROR_CONFIG: u32 = 2001;
+/// Stable code of errors loading or initializing the rules file
+pub const ERROR_RULES_FILE: u32 = 2002;
+/// Stable code of invalid rules
+pub const ERROR_INVALID_RULE: u32 = 3001;
+/// Stable code of JSON, CSV and YAML parse errors
+pub const ERROR_PARSE: u32 = 3002;
+/// Code of errors without a more specific code
+pub const ERROR_OTHER: u32 = 9999;
+
 /// Represents all errors that can occur within Tooka.
 ///
 /// Wraps errors from standard IO, parsing libraries, configuration,
 /// logging, rules processing, and other application-specific failures.
+///
+/// New variants may be added in minor releases.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum TookaError {
     // === General ===
     #[error("I/O error: {0}")]
@@ -30,13 +50,41 @@ pub enum TookaError {
     #[error("YAML parse error: {0}")]
     Yaml(#[from] serde_yaml::Error),
 
+    #[error("TOML error: {0}")]
+    Toml(String),
+
     #[error("File operation error: {0}")]
     FileOperationError(String),
 
+    #[error("Checksum mismatch for '{}': expected {expected}, got {actual}", .path.display())]
+    ChecksumMismatch {
+        path: path::PathBuf,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Command '{command}' timed out after {timeout_secs} second(s)")]
+    ExecuteTimeout { command: String, timeout_secs: u64 },
+
+    #[error("Processing '{}' timed out after {timeout_secs} second(s)", .path.display())]
+    OperationTimeout {
+        path: path::PathBuf,
+        timeout_secs: u64,
+    },
+
+    #[error("Gave up after {attempts} attempts: {last_error}")]
+    ExhaustedRetries {
+        attempts: u32,
+        last_error: Box<TookaError>,
+    },
+
     // === Config ===
     #[error("Config error: {0}")]
     ConfigError(String),
 
+    #[error("Invalid config: {0}")]
+    ConfigValidation(#[from] ConfigValidationError),
+
     #[error("Logger error: {0}")]
     LoggerError(#[from] flexi_logger::FlexiLoggerError),
 
@@ -46,6 +94,9 @@ pub enum TookaError {
     #[error("Rules file already initialized")]
     RulesFileAlreadyInitialized,
 
+    #[error("Context already initialized")]
+    ContextAlreadyInitialized,
+
     // === Matching ===
     #[error("Invalid glob pattern: {0}")]
     InvalidGlobPattern(#[from] PatternError),
@@ -66,7 +117,19 @@ pub enum TookaError {
     #[error("Invalid rule: {0}")]
     InvalidRule(String),
 
+    #[error("Circular dependency between rules: {}", .0.join(", "))]
+    CircularDependency(Vec<String>),
+
+    #[error(
+        "Dry run and real run disagree: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    VerificationFailed(Vec<VerificationFailure>),
+
     // === Others ===
+    #[error("Unsupported report format: {0}")]
+    UnsupportedFormat(String),
+
     #[error("Failed to generate PDF: {0}")]
     PdfGenerationError(String),
 
@@ -74,8 +137,103 @@ pub enum TookaError {
     Other(String),
 }
 
+impl TookaError {
+    /// Returns the name of the error variant, e.g. `"FileOperationError"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TookaError::Io(_) => "Io",
+            TookaError::Json(_) => "Json",
+            TookaError::Csv(_) => "Csv",
+            TookaError::Yaml(_) => "Yaml",
+            TookaError::Toml(_) => "Toml",
+            TookaError::FileOperationError(_) => "FileOperationError",
+            TookaError::ChecksumMismatch { .. } => "ChecksumMismatch",
+            TookaError::ExecuteTimeout { .. } => "ExecuteTimeout",
+            TookaError::OperationTimeout { .. } => "OperationTimeout",
+            TookaError::ExhaustedRetries { .. } => "ExhaustedRetries",
+            TookaError::ConfigError(_) => "ConfigError",
+            TookaError::ConfigValidation(_) => "ConfigValidation",
+            TookaError::LoggerError(_) => "LoggerError",
+            TookaError::ConfigAlreadyInitialized => "ConfigAlreadyInitialized",
+            TookaError::RulesFileAlreadyInitialized => "RulesFileAlreadyInitialized",
+            TookaError::ContextAlreadyInitialized => "ContextAlreadyInitialized",
+            TookaError::InvalidGlobPattern(_) => "InvalidGlobPattern",
+            TookaError::InvalidRegexPattern(_) => "InvalidRegexPattern",
+            TookaError::FailedPrefix(_) => "FailedPrefix",
+            TookaError::RuleNotFound(_) => "RuleNotFound",
+            TookaError::RuleValidationError(_) => "RuleValidationError",
+            TookaError::InvalidRule(_) => "InvalidRule",
+            TookaError::CircularDependency(_) => "CircularDependency",
+            TookaError::VerificationFailed(_) => "VerificationFailed",
+            TookaError::UnsupportedFormat(_) => "UnsupportedFormat",
+            TookaError::PdfGenerationError(_) => "PdfGenerationError",
+            TookaError::Other(_) => "Other",
+        }
+    }
+
+    /// Returns the stable numeric code of the error, e.g. [`ERROR_RULE_NOT_FOUND`],
+    /// for scripts that branch on the kind of failure.
+    ///
+    /// Codes never change once assigned; variants without a code of their own
+    /// return [`ERROR_OTHER`].
+    pub fn code(&self) -> u32 {
+        match self {
+            TookaError::RuleNotFound(_) => ERROR_RULE_NOT_FOUND,
+            TookaError::FileOperationError(_) | TookaError::ChecksumMismatch { .. } => {
+                ERROR_FILE_OPERATION
+            }
+            TookaError::ConfigError(_)
+            | TookaError::ConfigValidation(_)
+            | TookaError::ConfigAlreadyInitialized
+            | TookaError::ContextAlreadyInitialized => ERROR_CONFIG,
+            TookaError::RulesFileAlreadyInitialized => ERROR_RULES_FILE,
+            TookaError::RuleValidationError(_)
+            | TookaError::InvalidRule(_)
+            | TookaError::CircularDependency(_) => ERROR_INVALID_RULE,
+            TookaError::Json(_)
+            | TookaError::Csv(_)
+            | TookaError::Yaml(_)
+            | TookaError::Toml(_) => ERROR_PARSE,
+            TookaError::ExhaustedRetries { last_error, .. } => last_error.code(),
+            _ => ERROR_OTHER,
+        }
+    }
+}
+
+/// Serializable form of a [`TookaError`], as written to JSON reports:
+/// `{"type": "FileOperationError", "message": "..."}`.
+///
+/// Errors wrapping IO or parser errors cannot be rebuilt from JSON, so reports
+/// are read back into this record rather than into `TookaError` itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorRecord {
+    /// Name of the error variant
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Human-readable error message
+    pub message: String,
+}
+
+impl From<&TookaError> for ErrorRecord {
+    fn from(error: &TookaError) -> Self {
+        ErrorRecord {
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Serialize for TookaError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorRecord::from(self).serialize(serializer)
+    }
+}
+
 /// Errors specific to rule validation failures.
+///
+/// New variants may be added in minor releases.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum RuleValidationError {
     #[error("rule id is required")]
     MissingId,
@@ -95,3 +253,13 @@ pub enum RuleValidationError {
     #[error("invalid format: {0}")]
     InvalidFormat(String),
 }
+
+/// Errors specific to invalid configuration values.
+///
+/// New variants may be added in minor releases.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ConfigValidationError {
+    #[error("Source folder '{}' {reason}", .path.display())]
+    SourceFolderInvalid { path: path::PathBuf, reason: String },
+}
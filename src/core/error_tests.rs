@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::error::{self, ErrorRecord, TookaError};
+
+    #[test]
+    fn test_error_serializes_with_type_and_message() {
+        let error = TookaError::FileOperationError("disk full".to_string());
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "FileOperationError",
+                "message": "File operation error: disk full"
+            })
+        );
+
+        let record: ErrorRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(record, ErrorRecord::from(&error));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(
+            TookaError::RuleNotFound("x".to_string()).code(),
+            error::ERROR_RULE_NOT_FOUND
+        );
+        assert_eq!(
+            TookaError::ConfigError("x".to_string()).code(),
+            error::ERROR_CONFIG
+        );
+        let yaml = serde_yaml::from_str::<u32>("[").unwrap_err();
+        assert_eq!(TookaError::from(yaml).code(), error::ERROR_PARSE);
+        let retried = TookaError::ExhaustedRetries {
+            attempts: 3,
+            last_error: Box::new(TookaError::FileOperationError("busy".to_string())),
+        };
+        assert_eq!(retried.code(), error::ERROR_FILE_OPERATION);
+        assert_eq!(
+            TookaError::Other("x".to_string()).code(),
+            error::ERROR_OTHER
+        );
+    }
+}
@@ -5,3 +5,12 @@ pub mod sorter;
 
 #[cfg(test)]
 mod sorter_tests;
+
+#[cfg(test)]
+mod context_tests;
+
+#[cfg(test)]
+mod report_tests;
+
+#[cfg(test)]
+mod error_tests;
@@ -1,18 +1,113 @@
 //! Report generation module for Tooka.
 //!
-//! Supports creating reports in JSON, CSV, and PDF formats from sorting results.
+//! Supports creating reports in JSON, CSV, HTML, and PDF formats from sorting results.
+//! PDF reports require the `pdf` feature, which is enabled by default.
+//! Every report starts with a [`ReportSummary`] of the run.
 
-use crate::{core::error::TookaError, core::sorter::MatchResult, utils::gen_pdf::generate_pdf};
+#[cfg(feature = "pdf")]
+use crate::utils::gen_pdf::generate_pdf;
+use crate::{
+    core::error::TookaError,
+    core::sorter::{MatchResult, SortStats},
+    utils::{gen_csv, gen_html::generate_html},
+};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::{
-    fs::{File, create_dir_all},
-    path::Path,
+    collections::HashMap,
+    fs::{self, File, create_dir_all},
+    io::Write,
+    path::{Path, PathBuf},
 };
 
+/// Overview of a sort run shown at the top of every report.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportSummary {
+    /// Number of results in the report
+    pub total: usize,
+    /// Number of results per action, e.g. `"move" -> 3`
+    pub by_action: HashMap<String, usize>,
+    /// Number of results per matched rule ID
+    pub by_rule: HashMap<String, usize>,
+    /// Bytes moved to a new location
+    pub bytes_moved: u64,
+    /// Bytes copied to a new location
+    pub bytes_copied: u64,
+    /// Bytes freed by deleting files
+    pub bytes_deleted: u64,
+    /// Duration of the sort run in milliseconds, 0 if unknown
+    pub duration_ms: u64,
+}
+
+impl ReportSummary {
+    /// Returns the summary as `(label, value)` lines in display order, with
+    /// actions and rules sorted by name.
+    pub fn lines(&self) -> Vec<(String, String)> {
+        let sorted = |counts: &HashMap<String, usize>| {
+            let mut counts: Vec<_> = counts.iter().collect();
+            counts.sort();
+            counts
+                .into_iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        vec![
+            ("Total changes".to_string(), self.total.to_string()),
+            ("Actions".to_string(), sorted(&self.by_action)),
+            ("Rules".to_string(), sorted(&self.by_rule)),
+            ("Moved".to_string(), format_bytes(self.bytes_moved)),
+            ("Copied".to_string(), format_bytes(self.bytes_copied)),
+            ("Freed".to_string(), format_bytes(self.bytes_deleted)),
+            ("Duration".to_string(), format!("{} ms", self.duration_ms)),
+        ]
+    }
+}
+
+/// Computes the summary of `results`, taking byte counts from `stats`.
+///
+/// `duration_ms` is left at 0 for the caller to fill in.
+pub fn compute_summary(results: &[MatchResult], stats: &SortStats) -> ReportSummary {
+    let mut summary = ReportSummary {
+        total: results.len(),
+        bytes_moved: stats.bytes_moved,
+        bytes_copied: stats.bytes_copied,
+        bytes_deleted: stats.bytes_deleted,
+        ..Default::default()
+    };
+    for result in results {
+        *summary.by_action.entry(result.action.clone()).or_insert(0) += 1;
+        *summary
+            .by_rule
+            .entry(result.matched_rule_id.clone())
+            .or_insert(0) += 1;
+    }
+    summary
+}
+
+/// Formats a byte count with a binary unit, e.g. `15.3 GB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
 /// Generates a report from sorting results in the specified format.
 ///
-/// Supported formats are `"json"`, `"csv"`, and `"pdf"`. The generated report
-/// is saved in the provided output directory.
+/// Supported formats are `"json"`, `"csv"`, `"html"`, and `"pdf"`. The generated report
+/// is saved in the provided output directory. The summary at the top of the
+/// report is computed from `results`, without a duration.
 ///
 /// # Arguments
 /// * `report_type` - A string slice indicating the desired report format.
@@ -20,54 +115,354 @@ use std::{
 /// * `results` - Slice of [`MatchResult`] structs containing sorting results.
 ///
 /// # Errors
+/// Returns a [`TookaError`] if directory creation, file writing, or PDF generation fails.
+/// Returns [`TookaError::UnsupportedFormat`] for unknown formats, and for `"pdf"`
+/// when the `pdf` feature is disabled.
+pub fn generate_report(
+    report_type: &str,
+    output_dir: &Path,
+    results: &[MatchResult],
+) -> Result<(), TookaError> {
+    ReportBuilder::new(results)
+        .format(report_type)
+        .output_dir(output_dir)
+        .build()
+        .map(|_| ())
+}
+
+/// Same as [`generate_report`], with an explicit summary for the top of the report.
+///
+/// # Errors
 /// Returns a [`TookaError`] if directory creation, file writing, or PDF generation fails,
 /// or if an unsupported report format is requested.
-pub fn generate_report(
+pub fn generate_report_with_summary(
     report_type: &str,
     output_dir: &Path,
     results: &[MatchResult],
+    summary: &ReportSummary,
 ) -> Result<(), TookaError> {
-    create_dir_all(output_dir)?;
+    ReportBuilder::new(results)
+        .format(report_type)
+        .output_dir(output_dir)
+        .summary(summary.clone())
+        .build()
+        .map(|_| ())
+}
+
+/// Configures and writes a report of sorting results.
+///
+/// ```no_run
+/// # use tooka::core::report::ReportBuilder;
+/// # let results: Vec<tooka::core::sorter::MatchResult> = Vec::new();
+/// let path = ReportBuilder::new(&results)
+///     .format("pdf")
+///     .output_dir("/tmp")
+///     .exclude_skipped(true)
+///     .filter_action("delete")
+///     .build()?;
+/// # Ok::<(), tooka::core::error::TookaError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReportBuilder<'a> {
+    results: &'a [MatchResult],
+    format: String,
+    output_dir: PathBuf,
+    dry_run: bool,
+    exclude_skipped: bool,
+    filter_actions: Vec<String>,
+    filter_rules: Vec<String>,
+    summary: Option<ReportSummary>,
+}
 
-    match report_type.to_lowercase().as_str() {
-        "json" => {
-            let path = output_dir.join("tooka_report.json");
-            let file = File::create(&path)?;
-            serde_json::to_writer_pretty(file, results)?;
+impl<'a> ReportBuilder<'a> {
+    /// Starts a JSON report of `results` in the current directory.
+    pub fn new(results: &'a [MatchResult]) -> Self {
+        Self {
+            results,
+            format: "json".to_string(),
+            output_dir: PathBuf::from("."),
+            dry_run: false,
+            exclude_skipped: false,
+            filter_actions: Vec::new(),
+            filter_rules: Vec::new(),
+            summary: None,
         }
-        "csv" => {
-            let path = output_dir.join("tooka_report.csv");
-            let mut wtr = csv::Writer::from_path(&path)?;
-            // Write header
-            wtr.write_record([
-                "file_name",
-                "action",
-                "matched_rule_id",
-                "current_path",
-                "new_path",
-            ])?;
-            for r in results {
-                wtr.serialize((
-                    &r.file_name,
-                    &r.action,
-                    &r.matched_rule_id,
-                    r.current_path.display().to_string(),
-                    r.new_path.display().to_string(),
-                ))?;
-            }
-            wtr.flush()?;
+    }
+
+    /// Sets the report format: `json`, `csv`, `html` or `pdf`, case-insensitive.
+    #[must_use]
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = format.to_lowercase();
+        self
+    }
+
+    /// Sets the directory the report is written to; it is created if needed.
+    #[must_use]
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    /// If true, [`build`](Self::build) only returns the report path without
+    /// writing anything.
+    #[must_use]
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// If true, leaves out files that were not changed: `skip` results and
+    /// actions ending in `_skipped`, such as `delete_skipped`.
+    #[must_use]
+    pub fn exclude_skipped(mut self, exclude_skipped: bool) -> Self {
+        self.exclude_skipped = exclude_skipped;
+        self
+    }
+
+    /// Only includes results of this action. Can be called several times to
+    /// include several actions.
+    #[must_use]
+    pub fn filter_action(mut self, action: &str) -> Self {
+        self.filter_actions.push(action.to_string());
+        self
+    }
+
+    /// Only includes results of this rule ID. Can be called several times to
+    /// include several rules.
+    #[must_use]
+    pub fn filter_rule(mut self, rule_id: &str) -> Self {
+        self.filter_rules.push(rule_id.to_string());
+        self
+    }
+
+    /// Uses `summary` at the top of the report instead of computing one from
+    /// the included results.
+    #[must_use]
+    pub fn summary(mut self, summary: ReportSummary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// Writes the report and returns its path.
+    ///
+    /// # Errors
+    /// Returns a [`TookaError`] if directory creation, file writing, or PDF generation fails.
+    /// Returns [`TookaError::UnsupportedFormat`] for unknown formats, and for `"pdf"`
+    /// when the `pdf` feature is disabled.
+    pub fn build(self) -> Result<PathBuf, TookaError> {
+        let path = self
+            .output_dir
+            .join(format!("tooka_report.{}", self.format));
+        if self.dry_run {
+            return match self.format.as_str() {
+                "json" | "csv" | "html" => Ok(path),
+                "pdf" if cfg!(feature = "pdf") => Ok(path),
+                other => Err(TookaError::UnsupportedFormat(other.to_string())),
+            };
         }
-        "pdf" => {
-            let path = output_dir.join("tooka_report.pdf");
-            generate_pdf(&path, results)
-                .map_err(|e| TookaError::PdfGenerationError(e.to_string()))?;
+
+        let results: Vec<MatchResult> = self
+            .results
+            .iter()
+            .filter(|r| self.includes(r))
+            .cloned()
+            .collect();
+        let summary = self
+            .summary
+            .clone()
+            .unwrap_or_else(|| compute_summary(&results, &SortStats::from_results(&results)));
+
+        create_dir_all(&self.output_dir)?;
+        write_report(&self.format, &path, &results, &summary)?;
+        Ok(path)
+    }
+
+    fn includes(&self, result: &MatchResult) -> bool {
+        let skipped = result.action == "skip" || result.action.ends_with("_skipped");
+        !(self.exclude_skipped && skipped)
+            && (self.filter_actions.is_empty() || self.filter_actions.contains(&result.action))
+            && (self.filter_rules.is_empty() || self.filter_rules.contains(&result.matched_rule_id))
+    }
+}
+
+/// Appends results to an existing report, creating it if it does not exist yet.
+///
+/// The report is read back, extended with `new_results` and rewritten with an
+/// updated summary. HTML and PDF reports cannot be read back, so all results
+/// are kept in a companion `<report>.json` file next to them.
+///
+/// # Arguments
+/// * `format` - Report format: `"json"`, `"csv"`, `"html"` or `"pdf"`.
+/// * `output_path` - Path of the report file.
+/// * `new_results` - Results to add to the report.
+///
+/// # Errors
+/// Returns a [`TookaError`] if the existing report cannot be read, writing fails,
+/// or an unsupported report format is requested.
+pub fn append_to_report(
+    format: &str,
+    output_path: &Path,
+    new_results: &[MatchResult],
+) -> Result<(), TookaError> {
+    if let Some(parent) = output_path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let format = format.to_lowercase();
+    let (mut results, previous) = match format.as_str() {
+        "json" => read_json_report(output_path)?,
+        "csv" => read_csv_report(output_path)?,
+        "html" => read_json_report(&output_path.with_extension("html.json"))?,
+        "pdf" if cfg!(feature = "pdf") => {
+            read_json_report(&output_path.with_extension("pdf.json"))?
         }
-        other => {
-            return Err(TookaError::Other(format!(
-                "Unsupported report format: {other}"
-            )));
+        other => return Err(TookaError::UnsupportedFormat(other.to_string())),
+    };
+    results.extend_from_slice(new_results);
+
+    let mut stats = SortStats::from_results(new_results);
+    stats.bytes_moved += previous.bytes_moved;
+    stats.bytes_copied += previous.bytes_copied;
+    stats.bytes_deleted += previous.bytes_deleted;
+    let mut summary = compute_summary(&results, &stats);
+    summary.duration_ms = previous.duration_ms;
+
+    if matches!(format.as_str(), "html" | "pdf") {
+        write_json(
+            &output_path.with_extension(format!("{format}.json")),
+            &results,
+            &summary,
+        )?;
+    }
+    write_report(&format, output_path, &results, &summary)
+}
+
+/// Writes a complete report in the given (lowercase) format.
+fn write_report(
+    format: &str,
+    path: &Path,
+    results: &[MatchResult],
+    summary: &ReportSummary,
+) -> Result<(), TookaError> {
+    match format {
+        "json" => write_json(path, results, summary),
+        "csv" => write_csv_report(path, results, summary),
+        "html" => Ok(generate_html(path, results, summary)?),
+        #[cfg(feature = "pdf")]
+        "pdf" => generate_pdf(path, results, summary)
+            .map_err(|e| TookaError::PdfGenerationError(e.to_string())),
+        other => Err(TookaError::UnsupportedFormat(other.to_string())),
+    }
+}
+
+/// Layout of a JSON report
+#[derive(Serialize, Deserialize)]
+struct JsonReport {
+    summary: ReportSummary,
+    results: Vec<MatchResult>,
+}
+
+/// JSON reports written before summaries were added are plain result arrays
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredJsonReport {
+    WithSummary(JsonReport),
+    ResultsOnly(Vec<MatchResult>),
+}
+
+fn write_json(
+    path: &Path,
+    results: &[MatchResult],
+    summary: &ReportSummary,
+) -> Result<(), TookaError> {
+    let report = JsonReport {
+        summary: summary.clone(),
+        results: results.to_vec(),
+    };
+    serde_json::to_writer_pretty(File::create(path)?, &report)?;
+    Ok(())
+}
+
+/// Reads the results and summary of a JSON report, or nothing if it does not exist yet.
+fn read_json_report(path: &Path) -> Result<(Vec<MatchResult>, ReportSummary), TookaError> {
+    if !path.exists() {
+        return Ok((Vec::new(), ReportSummary::default()));
+    }
+    Ok(match serde_json::from_reader(File::open(path)?)? {
+        StoredJsonReport::WithSummary(report) => (report.results, report.summary),
+        StoredJsonReport::ResultsOnly(results) => {
+            let summary = compute_summary(&results, &SortStats::from_results(&results));
+            (results, summary)
         }
+    })
+}
+
+/// Writes `results` as CSV to `writer`: a header row followed by one row per
+/// result, with the columns of [`MatchResult::CSV_HEADER`]. Unlike CSV reports,
+/// the output has no summary lines.
+///
+/// # Errors
+/// Returns `TookaError` if writing fails.
+pub fn write_csv(writer: impl Write, results: &[MatchResult]) -> Result<(), TookaError> {
+    gen_csv::write_results(writer, results)?.flush()?;
+    Ok(())
+}
+
+/// Writes the summary as `# label,value` comment lines, followed by the results.
+fn write_csv_report(
+    path: &Path,
+    results: &[MatchResult],
+    summary: &ReportSummary,
+) -> Result<(), TookaError> {
+    let mut content = String::new();
+    for (label, value) in summary.lines() {
+        content.push_str(&format!("# {label}: {value}\n"));
+    }
+    for (key, value) in [
+        ("bytes_moved", summary.bytes_moved),
+        ("bytes_copied", summary.bytes_copied),
+        ("bytes_deleted", summary.bytes_deleted),
+        ("duration_ms", summary.duration_ms),
+    ] {
+        content.push_str(&format!("# {key}={value}\n"));
     }
 
+    let bytes = gen_csv::write_results(content.into_bytes(), results)?;
+    fs::write(path, bytes)?;
     Ok(())
 }
+
+/// Reads the results of a CSV report and the byte counts and duration recorded
+/// in its summary, or nothing if it does not exist yet.
+fn read_csv_report(path: &Path) -> Result<(Vec<MatchResult>, ReportSummary), TookaError> {
+    let mut summary = ReportSummary::default();
+    if !path.exists() {
+        return Ok((Vec::new(), summary));
+    }
+
+    let content = fs::read_to_string(path)?;
+    for line in content.lines().filter_map(|l| l.strip_prefix("# ")) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().parse().unwrap_or(0);
+        match key {
+            "bytes_moved" => summary.bytes_moved = value,
+            "bytes_copied" => summary.bytes_copied = value,
+            "bytes_deleted" => summary.bytes_deleted = value,
+            "duration_ms" => summary.duration_ms = value,
+            _ => {}
+        }
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .from_reader(content.as_bytes());
+    let mut results = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row: Vec<&str> = record.iter().collect();
+        results.push(MatchResult::from_csv_row(&row)?);
+    }
+    Ok((results, summary))
+}
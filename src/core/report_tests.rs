@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::error::TookaError;
+    use crate::core::report::{
+        ReportBuilder, append_to_report, compute_summary, generate_report, write_csv,
+    };
+    use crate::core::sorter::{MatchResult, SortStats, test_result};
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// Helper function to read the results and summary of a JSON report
+    fn read_json(path: &std::path::Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_compute_summary() {
+        let results = vec![
+            test_result("a.txt", "rule", "move"),
+            test_result("b.txt", "backup", "copy"),
+        ];
+
+        let summary = compute_summary(&results, &SortStats::from_results(&results));
+        assert_eq!(summary.total, 2);
+        assert_eq!(summary.by_action.get("move"), Some(&1));
+        assert_eq!(summary.by_action.get("copy"), Some(&1));
+        assert_eq!(summary.by_rule.get("backup"), Some(&1));
+        assert_eq!(summary.bytes_moved, 100);
+        assert_eq!(summary.bytes_copied, 100);
+        assert_eq!(summary.duration_ms, 0);
+    }
+
+    #[test]
+    fn test_reports_start_with_summary() {
+        let dir = tempdir().unwrap();
+        let results = vec![test_result("a.txt", "rule", "move")];
+
+        generate_report("json", dir.path(), &results).unwrap();
+        let json = read_json(&dir.path().join("tooka_report.json"));
+        assert_eq!(json["summary"]["total"], 1);
+        assert_eq!(json["results"][0]["file_name"], "a.txt");
+
+        generate_report("csv", dir.path(), &results).unwrap();
+        let csv = fs::read_to_string(dir.path().join("tooka_report.csv")).unwrap();
+        assert!(csv.starts_with("# Total changes: 1\n"));
+    }
+
+    #[test]
+    fn test_write_csv_to_writer() {
+        let mut output = Vec::new();
+        write_csv(&mut output, &[test_result("a,b.txt", "rule", "move")]).unwrap();
+
+        let csv = String::from_utf8(output).unwrap();
+        assert!(csv.starts_with("file_name,action,matched_rule_id,current_path,new_path\n"));
+        assert!(csv.contains("\"a,b.txt\",move,rule,\"/source/a,b.txt\",\"/dest/rule/a,b.txt\"\n"));
+        assert!(!csv.contains('#'));
+    }
+
+    #[test]
+    fn test_generate_html_report() {
+        let dir = tempdir().unwrap();
+        let results = vec![
+            test_result("a.txt", "rule", "move"),
+            test_result("old.tmp", "cleanup", "delete"),
+        ];
+
+        generate_report("html", dir.path(), &results).unwrap();
+        let html = fs::read_to_string(dir.path().join("tooka_report.html")).unwrap();
+        for text in [
+            "Rule: rule",
+            "Rule: cleanup",
+            "a.txt",
+            "old.tmp",
+            ">move<",
+            ">delete<",
+        ] {
+            assert!(html.contains(text), "missing {text}");
+        }
+    }
+
+    #[test]
+    fn test_append_to_html_report_keeps_companion_json() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tooka_report.html");
+
+        append_to_report("html", &path, &[test_result("a.txt", "rule", "move")]).unwrap();
+        append_to_report("html", &path, &[test_result("b.txt", "rule", "move")]).unwrap();
+
+        let html = fs::read_to_string(&path).unwrap();
+        assert!(html.contains("a.txt") && html.contains("b.txt"));
+        let companion = read_json(&dir.path().join("tooka_report.html.json"));
+        assert_eq!(companion["results"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_append_to_json_report() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tooka_report.json");
+
+        append_to_report("json", &path, &[test_result("a.txt", "rule", "move")]).unwrap();
+        append_to_report("json", &path, &[test_result("b.txt", "rule", "move")]).unwrap();
+
+        let json = read_json(&path);
+        let results: Vec<MatchResult> = serde_json::from_value(json["results"].clone()).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                test_result("a.txt", "rule", "move"),
+                test_result("b.txt", "rule", "move")
+            ]
+        );
+        assert_eq!(json["summary"]["total"], 2);
+        assert_eq!(json["summary"]["bytes_moved"], 200);
+    }
+
+    #[test]
+    fn test_append_to_legacy_json_report() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tooka_report.json");
+        fs::write(
+            &path,
+            serde_json::to_string(&[test_result("a.txt", "rule", "move")]).unwrap(),
+        )
+        .unwrap();
+
+        append_to_report("json", &path, &[test_result("b.txt", "rule", "move")]).unwrap();
+
+        assert_eq!(read_json(&path)["summary"]["total"], 2);
+    }
+
+    #[test]
+    fn test_append_to_csv_report_updates_summary() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tooka_report.csv");
+
+        append_to_report("csv", &path, &[test_result("a.txt", "rule", "move")]).unwrap();
+        append_to_report("csv", &path, &[test_result("b.txt", "rule", "move")]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# Total changes: 2\n"));
+        assert!(content.contains("# bytes_moved=200\n"));
+
+        let rows: Vec<_> = content.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("file_name,"));
+        assert!(rows[2].starts_with("b.txt,"));
+    }
+
+    #[test]
+    #[cfg(feature = "pdf")]
+    fn test_append_to_pdf_report_keeps_companion_json() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tooka_report.pdf");
+
+        append_to_report("pdf", &path, &[test_result("a.txt", "rule", "move")]).unwrap();
+        append_to_report("pdf", &path, &[test_result("b.txt", "rule", "move")]).unwrap();
+
+        assert!(path.exists());
+        let companion = read_json(&dir.path().join("tooka_report.pdf.json"));
+        assert_eq!(companion["results"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_report_builder_filters_results() {
+        let dir = tempdir().unwrap();
+        let results = vec![
+            test_result("a.txt", "rule", "move"),
+            test_result("skipped.txt", "none", "skip"),
+            test_result("old.tmp", "cleanup", "delete"),
+        ];
+
+        let path = ReportBuilder::new(&results)
+            .format("JSON")
+            .output_dir(dir.path().join("reports"))
+            .exclude_skipped(true)
+            .build()
+            .unwrap();
+        assert_eq!(path, dir.path().join("reports/tooka_report.json"));
+        let report = read_json(&path);
+        assert_eq!(report["summary"]["total"], 2);
+        assert_eq!(report["results"].as_array().unwrap().len(), 2);
+
+        let path = ReportBuilder::new(&results)
+            .format("csv")
+            .output_dir(dir.path())
+            .filter_action("delete")
+            .filter_action("move")
+            .filter_rule("cleanup")
+            .build()
+            .unwrap();
+        let csv = fs::read_to_string(path).unwrap();
+        assert!(csv.contains("old.tmp"));
+        assert!(!csv.contains("a.txt"));
+        assert!(!csv.contains("skipped.txt"));
+    }
+
+    #[test]
+    fn test_report_builder_dry_run_writes_nothing() {
+        let dir = tempdir().unwrap();
+        let results = vec![test_result("a.txt", "rule", "move")];
+
+        let path = ReportBuilder::new(&results)
+            .format("csv")
+            .output_dir(dir.path().join("reports"))
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(path, dir.path().join("reports/tooka_report.csv"));
+        assert!(!dir.path().join("reports").exists());
+        assert!(matches!(
+            ReportBuilder::new(&results)
+                .format("xml")
+                .dry_run(true)
+                .build(),
+            Err(TookaError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_format() {
+        let dir = tempdir().unwrap();
+        let results = vec![test_result("a.txt", "rule", "move")];
+
+        assert!(matches!(
+            generate_report("xml", dir.path(), &results),
+            Err(TookaError::UnsupportedFormat(format)) if format == "xml"
+        ));
+        #[cfg(not(feature = "pdf"))]
+        assert!(matches!(
+            generate_report("pdf", dir.path(), &results),
+            Err(TookaError::UnsupportedFormat(format)) if format == "pdf"
+        ));
+    }
+}
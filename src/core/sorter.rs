@@ -7,17 +7,35 @@
 
 use super::error::TookaError;
 use crate::{
-    common::logger::log_file_operation,
-    file::{file_match, file_ops},
-    rules::rules_file::RulesFile,
+    common::{config::Config, logger::log_file_operation},
+    file::{
+        archive::{self, ArchiveEntry},
+        file_match,
+        file_ops::{self, DryRunDestinations, FileOperationOptions, FileOperationResult},
+        ignore_file::IgnoreMatcher,
+        journal::{self, Journal, JournalAction},
+    },
+    rules::{
+        rule::{Action, ArchiveFormat},
+        rules_file::{MergePrecedence, MergeStrategy, RulesFile},
+    },
 };
+use dashmap::DashMap;
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use walkdir::WalkDir;
 
 /// Result of matching a file against a rule and executing an action.
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct MatchResult {
     /// File name matched by the rule.
     pub file_name: String,
@@ -29,6 +47,284 @@ pub struct MatchResult {
     pub current_path: PathBuf,
     /// Destination path after action.
     pub new_path: PathBuf,
+    /// Number of bytes moved, copied or deleted by the action.
+    #[serde(default)]
+    pub bytes_affected: u64,
+}
+
+/// Bytes affected by a sort run, per action type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortStats {
+    /// Bytes moved to a new location
+    pub bytes_moved: u64,
+    /// Bytes copied to a new location
+    pub bytes_copied: u64,
+    /// Bytes freed by deleting files
+    pub bytes_deleted: u64,
+}
+
+impl SortStats {
+    /// Sums `bytes_affected` of the results per action type.
+    pub fn from_results(results: &[MatchResult]) -> Self {
+        let mut stats = Self::default();
+        for result in results {
+            match result.action.as_str() {
+                "move" | "move_and_hardlink" => stats.bytes_moved += result.bytes_affected,
+                "copy" | "archive" | "compress" => stats.bytes_copied += result.bytes_affected,
+                "delete" => stats.bytes_deleted += result.bytes_affected,
+                _ => {}
+            }
+        }
+        stats
+    }
+}
+
+impl MatchResult {
+    /// Column names of a CSV row, in the order used by [`MatchResult::to_csv_row`].
+    pub const CSV_HEADER: [&'static str; 5] = [
+        "file_name",
+        "action",
+        "matched_rule_id",
+        "current_path",
+        "new_path",
+    ];
+
+    /// Converts the result into a CSV row, in the order of [`MatchResult::CSV_HEADER`].
+    pub fn to_csv_row(&self) -> Vec<String> {
+        vec![
+            self.file_name.clone(),
+            self.action.clone(),
+            self.matched_rule_id.clone(),
+            self.current_path.display().to_string(),
+            self.new_path.display().to_string(),
+        ]
+    }
+
+    /// Parses a CSV row written by [`MatchResult::to_csv_row`].
+    ///
+    /// CSV reports do not record `bytes_affected`, so it is always 0.
+    ///
+    /// # Errors
+    /// Returns `TookaError::InvalidRule` if the row does not have exactly five columns.
+    pub fn from_csv_row(row: &[&str]) -> Result<Self, TookaError> {
+        let [file_name, action, matched_rule_id, current_path, new_path] = row else {
+            return Err(TookaError::InvalidRule(format!(
+                "Expected {} columns in match result row, found {}",
+                Self::CSV_HEADER.len(),
+                row.len()
+            )));
+        };
+
+        Ok(Self {
+            file_name: (*file_name).to_string(),
+            action: (*action).to_string(),
+            matched_rule_id: (*matched_rule_id).to_string(),
+            current_path: PathBuf::from(current_path),
+            new_path: PathBuf::from(new_path),
+            bytes_affected: 0,
+        })
+    }
+}
+
+/// Creates a result for `file_name` matched by `rule`, going from `/source`
+/// to `/dest/<rule>` with 100 bytes affected, for tests of reports.
+#[cfg(test)]
+pub(crate) fn test_result(file_name: &str, rule: &str, action: &str) -> MatchResult {
+    MatchResult {
+        file_name: file_name.to_string(),
+        action: action.to_string(),
+        matched_rule_id: rule.to_string(),
+        current_path: format!("/source/{file_name}").into(),
+        new_path: format!("/dest/{rule}/{file_name}").into(),
+        bytes_affected: 100,
+    }
+}
+
+/// Environment variable used as the thread count when [`SortOptions::num_threads`] is unset
+pub const THREADS_ENV_VAR: &str = "TOOKA_THREADS";
+
+/// How sorting reacts to a file whose actions fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorMode {
+    /// Stop sorting and return the first error
+    #[default]
+    FailFast,
+    /// Log the error, skip the file and keep sorting the rest
+    Continue,
+}
+
+/// Order in which files are processed and results are returned.
+///
+/// Any order but [`SortOrder::Unstable`] sorts the files first and processes
+/// them in consecutive batches, the files of a batch in parallel. Each batch is
+/// done before the next one starts, so e.g. the first file of the order to
+/// reach a destination is the one that keeps its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// By full path
+    Alphabetical,
+    /// By file size, smallest first
+    BySize,
+    /// By modification time, oldest first
+    ByModified,
+    /// By creation time, oldest first
+    ByCreated,
+    /// In the order the files were given
+    #[default]
+    Unstable,
+}
+
+/// Tuning options for collecting and sorting files.
+#[derive(Debug, Clone, Default)]
+pub struct SortOptions {
+    /// Number of worker threads; falls back to `TOOKA_THREADS`, then to rayon's default
+    pub num_threads: Option<usize>,
+    /// Number of files handed to a worker at once; `None` lets rayon split the work.
+    /// With any `sort_order` but [`SortOrder::Unstable`], the number of files in
+    /// a batch instead, one per thread if `None`.
+    pub chunk_size: Option<usize>,
+    /// Follow symbolic links while collecting files
+    pub follow_symlinks: bool,
+    /// Maximum directory depth to descend into while collecting files
+    pub max_depth: Option<usize>,
+    /// What to do when a file fails to sort
+    pub error_mode: ErrorMode,
+    /// Order in which files are processed and results are returned, see
+    /// [`SortOrder`]
+    pub sort_order: SortOrder,
+    /// Run in dry-run mode first and fail if the real run ends up elsewhere,
+    /// see [`sort_files_verified`]. Ignored when sorting in dry-run mode.
+    pub dry_run_verify: bool,
+    /// Gives up on a file after this many seconds and reports it as `timed_out`.
+    /// An action that already started is always waited for, and no further
+    /// action is started on the file once it has timed out. Each file is then
+    /// sorted on a thread of its own.
+    pub file_timeout_seconds: Option<u64>,
+    /// Journal every move, copy, rename and delete to this file so the run can
+    /// be undone with [`rollback_last_run`]. Files deleted without `trash` are
+    /// then kept in a `.tooka_trash` folder next to the journal. Ignored when
+    /// sorting in dry-run mode.
+    pub journal_path: Option<PathBuf>,
+    /// Only apply rules carrying this tag, see [`RulesFile::filter_by_tag`].
+    /// Used when loading the rules in [`prepare_sort_full`].
+    pub tag: Option<String>,
+    /// Rules file merged into the configured rules by [`prepare_sort_full`],
+    /// see [`RulesFile::load_any`]. Its rules replace configured rules with
+    /// the same ID and come before the other configured rules.
+    pub extra_rules: Option<PathBuf>,
+}
+
+/// A result of a real run that differs from what the dry run predicted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationFailure {
+    /// Path the action was applied to
+    pub file: PathBuf,
+    /// Action that was predicted or performed
+    pub action: String,
+    /// Destination predicted by the dry run, `None` if it predicted no such action
+    pub expected: Option<PathBuf>,
+    /// Destination of the real run, `None` if it performed no such action
+    pub actual: Option<PathBuf>,
+}
+
+impl fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map_or_else(|| "nothing".to_string(), |p| format!("'{}'", p.display()))
+        };
+        write!(
+            f,
+            "{} of '{}': expected {}, got {}",
+            self.action,
+            self.file.display(),
+            show(&self.expected),
+            show(&self.actual)
+        )
+    }
+}
+
+impl SortOptions {
+    /// Returns the thread count to use, reading `TOOKA_THREADS` if `num_threads` is unset.
+    fn thread_count(&self) -> Option<usize> {
+        self.num_threads.or_else(|| {
+            let value = std::env::var(THREADS_ENV_VAR).ok()?;
+            match value.trim().parse::<usize>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => {
+                    log::warn!("Ignoring invalid {THREADS_ENV_VAR} value '{value}'");
+                    None
+                }
+            }
+        })
+    }
+}
+
+/// Number of recent files the estimated remaining time of [`SortProgress`] is based on
+const PROGRESS_WINDOW: usize = 50;
+
+/// Progress of a sort run, passed to the callback of [`sort_files_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortProgress {
+    /// Number of files processed so far
+    pub files_processed: usize,
+    /// Number of files in the run
+    pub files_total: usize,
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// Estimated time until all files are processed, from the average time
+    /// per file over the last 50 files. `None` until a file is processed.
+    pub estimated_remaining: Option<Duration>,
+}
+
+/// Tracks when files finish to compute [`SortProgress`]
+struct ProgressTracker {
+    started: Instant,
+    total: usize,
+    /// Times the last files finished, starting with the start of the run
+    recent: Mutex<VecDeque<Instant>>,
+}
+
+impl ProgressTracker {
+    fn new(total: usize) -> Self {
+        let started = Instant::now();
+        Self {
+            started,
+            total,
+            recent: Mutex::new(VecDeque::from([started])),
+        }
+    }
+
+    /// Records that a file finished, `files_processed` files in total
+    fn record(&self, files_processed: usize) -> SortProgress {
+        let now = Instant::now();
+        let mut recent = self
+            .recent
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        recent.push_back(now);
+        if recent.len() > PROGRESS_WINDOW + 1 {
+            recent.pop_front();
+        }
+        let per_file = now.duration_since(recent[0]) / (recent.len() as u32 - 1);
+        let remaining = self.total.saturating_sub(files_processed);
+        SortProgress {
+            files_processed,
+            files_total: self.total,
+            elapsed: now.duration_since(self.started),
+            estimated_remaining: Some(per_file * u32::try_from(remaining).unwrap_or(u32::MAX)),
+        }
+    }
+
+    /// Progress once every file is processed
+    fn finished(&self) -> SortProgress {
+        SortProgress {
+            files_processed: self.total,
+            files_total: self.total,
+            elapsed: self.started.elapsed(),
+            estimated_remaining: Some(Duration::ZERO),
+        }
+    }
 }
 
 /// Sorts a batch of files using optimized rules processing.
@@ -38,7 +334,8 @@ pub struct MatchResult {
 /// * `source_path` - Base directory of source files.
 /// * `rules_file` - Rules file with pre-sorted rules to apply.
 /// * `dry_run` - If true, actions are logged but not performed.
-/// * `on_progress` - Optional callback invoked after each file processed.
+/// * `on_progress` - Optional callback invoked after each file processed with
+///   `(files_done, files_total)`, and once more with `(total, total)` when done.
 ///
 /// # Returns
 /// List of matching results for files that matched any rule.
@@ -53,31 +350,943 @@ pub fn sort_files<F>(
     on_progress: Option<F>,
 ) -> Result<Vec<MatchResult>, TookaError>
 where
-    F: Fn() + Send + Sync,
+    F: Fn(usize, usize) + Send + Sync,
 {
-    let progress = Arc::new(on_progress.map(|f| Arc::new(f)));
+    sort_files_with_options(
+        files,
+        source_path,
+        rules_file,
+        dry_run,
+        &SortOptions::default(),
+        on_progress,
+    )
+}
 
-    let results: Result<Vec<_>, TookaError> = files
-        .par_iter()
-        .map(|file_path| {
-            let res = sort_file(file_path, rules_file, dry_run, source_path);
-            if let Some(ref cb) = *progress {
-                cb();
+/// Same as [`sort_files`], with explicit [`SortOptions`] for thread count,
+/// chunking and error handling.
+///
+/// # Errors
+/// Returns `TookaError` if the thread pool cannot be built, or if file
+/// operations fail and `error_mode` is [`ErrorMode::FailFast`].
+pub fn sort_files_with_options<F>(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    options: &SortOptions,
+    on_progress: Option<F>,
+) -> Result<Vec<MatchResult>, TookaError>
+where
+    F: Fn(usize, usize) + Send + Sync,
+{
+    sort_files_with_progress(
+        files,
+        source_path,
+        rules_file,
+        dry_run,
+        options,
+        on_progress.map(|cb| move |p: SortProgress| cb(p.files_processed, p.files_total)),
+    )
+}
+
+/// Same as [`sort_files_with_options`], with a callback that also receives
+/// the elapsed and estimated remaining time, see [`SortProgress`].
+///
+/// # Errors
+/// Returns `TookaError` if the thread pool cannot be built, or if file
+/// operations fail and `error_mode` is [`ErrorMode::FailFast`].
+pub fn sort_files_with_progress<F>(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    options: &SortOptions,
+    on_progress: Option<F>,
+) -> Result<Vec<MatchResult>, TookaError>
+where
+    F: Fn(SortProgress) + Send + Sync,
+{
+    sort_files_planned(
+        files,
+        source_path,
+        rules_file,
+        dry_run,
+        options,
+        on_progress,
+        &DryRunDestinations::default(),
+    )
+}
+
+/// [`sort_files_with_progress`] as part of a larger run, which resolves
+/// conflicts in a dry run against the destinations picked earlier in the run
+fn sort_files_planned<F>(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    options: &SortOptions,
+    on_progress: Option<F>,
+    destinations: &DryRunDestinations,
+) -> Result<Vec<MatchResult>, TookaError>
+where
+    F: Fn(SortProgress) + Send + Sync,
+{
+    if !rules_file.is_ready() {
+        log::warn!("Rules are not optimized, call RulesFile::optimized_with_filter before sorting");
+    }
+    if options.dry_run_verify && !dry_run {
+        let (results, failures) =
+            verify_sort(files, source_path, rules_file, options, on_progress)?;
+        if !failures.is_empty() {
+            return Err(TookaError::VerificationFailed(failures));
+        }
+        return Ok(results);
+    }
+
+    let files = order_files(files, options.sort_order);
+    let files = files.as_ref();
+    let files_processed = Arc::new(AtomicUsize::new(0));
+    let tracker = ProgressTracker::new(files.len());
+    // Timed workers may outlive this call, so they need their own copy of the rules
+    let shared_rules = options
+        .file_timeout_seconds
+        .map(|_| Arc::new(rules_file.clone()));
+    let journal = match &options.journal_path {
+        Some(path) if !dry_run => Some(Arc::new(Journal::open(path)?)),
+        _ => None,
+    };
+
+    let process = |file_path: &PathBuf| {
+        let res = match (&shared_rules, options.file_timeout_seconds) {
+            (Some(rules), Some(timeout_secs)) => sort_file_with_timeout(
+                file_path,
+                rules,
+                dry_run,
+                source_path,
+                journal.clone(),
+                timeout_secs,
+                destinations,
+            ),
+            _ => sort_file(
+                file_path,
+                rules_file,
+                dry_run,
+                source_path,
+                journal.as_deref(),
+                None,
+                destinations,
+            ),
+        };
+        let res = match res {
+            Err(TookaError::OperationTimeout { path, timeout_secs }) => {
+                log::warn!(
+                    "Gave up on '{}' after {timeout_secs} second(s)",
+                    path.display()
+                );
+                Ok(vec![timed_out_result(path)])
+            }
+            Err(e) if options.error_mode == ErrorMode::Continue => {
+                log::error!("Failed to sort '{}': {e}", file_path.display());
+                Ok(Vec::new())
+            }
+            res => res,
+        };
+        let done = files_processed.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(cb) = &on_progress {
+            cb(tracker.record(done));
+        }
+        res
+    };
+
+    let run = || -> Result<Vec<MatchResult>, TookaError> {
+        let results: Vec<Vec<MatchResult>> = match (options.sort_order, options.chunk_size) {
+            (SortOrder::Unstable, Some(size)) if size > 0 => files
+                .par_chunks(size)
+                .map(|chunk| chunk.iter().map(process).collect::<Result<Vec<_>, _>>())
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+            (SortOrder::Unstable, _) => files.par_iter().map(process).collect::<Result<_, _>>()?,
+            // A batch is done before the next one starts, so later files in the
+            // order never get ahead of earlier batches
+            (_, size) => {
+                let batch_size = size
+                    .filter(|&size| size > 0)
+                    .unwrap_or_else(rayon::current_num_threads);
+                let mut results = Vec::with_capacity(files.len());
+                for batch in files.chunks(batch_size) {
+                    results.extend(
+                        batch
+                            .par_iter()
+                            .map(process)
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                results
+            }
+        };
+        Ok(results.into_iter().flatten().collect())
+    };
+
+    let results = match options.thread_count() {
+        Some(threads) => {
+            log::debug!("Sorting with {threads} threads");
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| TookaError::Other(format!("Failed to build thread pool: {e}")))?
+                .install(run)?
+        }
+        None => run()?,
+    };
+
+    if dry_run {
+        log::debug!("Dry run: not writing archives");
+    } else {
+        write_archives(&results, rules_file, source_path, options.error_mode)?;
+    }
+
+    if let Some(cb) = &on_progress {
+        cb(tracker.finished());
+    }
+    Ok(results)
+}
+
+/// Default time budget of `tooka sort --limit`, see [`sort_files_limited`]
+pub const DEFAULT_PREVIEW_TIME_BUDGET: Duration = Duration::from_secs(30);
+
+/// Sorts `files` one at a time, in order, and stops early once `max_files`
+/// files are processed or `time_budget` has elapsed, e.g. for a quick dry-run
+/// preview of new rules. A file that has started is always finished.
+///
+/// Returns the results so far, and true if files were left unprocessed.
+///
+/// # Errors
+/// Returns `TookaError` if file operations fail.
+pub fn sort_files_limited(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    max_files: usize,
+    time_budget: Duration,
+) -> Result<(Vec<MatchResult>, bool), TookaError> {
+    sort_files_limited_planned(
+        files,
+        source_path,
+        rules_file,
+        dry_run,
+        max_files,
+        time_budget,
+        &DryRunDestinations::default(),
+    )
+}
+
+/// [`sort_files_limited`] as part of a larger run, see [`sort_files_planned`]
+fn sort_files_limited_planned(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    max_files: usize,
+    time_budget: Duration,
+    destinations: &DryRunDestinations,
+) -> Result<(Vec<MatchResult>, bool), TookaError> {
+    let started = Instant::now();
+    let mut results = Vec::new();
+    let mut processed = 0;
+    for file_path in files {
+        if processed >= max_files || started.elapsed() >= time_budget {
+            break;
+        }
+        results.extend(sort_file(
+            file_path,
+            rules_file,
+            dry_run,
+            source_path,
+            None,
+            None,
+            destinations,
+        )?);
+        processed += 1;
+    }
+    let exhausted = processed < files.len();
+    if exhausted {
+        log::info!(
+            "Stopped after {processed} of {} file(s) in {:?}",
+            files.len(),
+            started.elapsed()
+        );
+    }
+
+    if !dry_run {
+        write_archives(&results, rules_file, source_path, ErrorMode::FailFast)?;
+    }
+    Ok((results, exhausted))
+}
+
+/// Writes one archive per distinct archive path among the `archive` results,
+/// in parallel. Files are stored under their name, or their path relative to
+/// `source_path` with `preserve_structure`; clashing names get a `_2`, `_3`...
+/// suffix.
+fn write_archives(
+    results: &[MatchResult],
+    rules_file: &RulesFile,
+    source_path: &Path,
+    error_mode: ErrorMode,
+) -> Result<(), TookaError> {
+    let mut archives: BTreeMap<&Path, (ArchiveFormat, Vec<ArchiveEntry>)> = BTreeMap::new();
+    let mut names: HashMap<&Path, HashSet<String>> = HashMap::new();
+    for result in results.iter().filter(|r| r.action == "archive") {
+        let Some(action) = rules_file
+            .rules
+            .iter()
+            .filter(|rule| rule.id == result.matched_rule_id)
+            .flat_map(|rule| &rule.then)
+            .find_map(|action| match action {
+                Action::Archive(inner) => Some(inner),
+                _ => None,
+            })
+        else {
+            continue;
+        };
+
+        let relative = result
+            .current_path
+            .strip_prefix(source_path)
+            .ok()
+            .filter(|_| action.preserve_structure);
+        let name = match relative {
+            Some(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => result.file_name.clone(),
+        };
+
+        let archive = result.new_path.as_path();
+        let (format, entries) = archives
+            .entry(archive)
+            .or_insert_with(|| (action.format, Vec::new()));
+        if *format != action.format {
+            return Err(TookaError::FileOperationError(format!(
+                "Rules archive into '{}' with different formats",
+                archive.display()
+            )));
+        }
+        entries.push(ArchiveEntry {
+            path: result.current_path.clone(),
+            name: unique_entry_name(&name, names.entry(archive).or_default()),
+        });
+    }
+
+    archives
+        .into_par_iter()
+        .try_for_each(|(archive, (format, entries))| {
+            match archive::write_archive(archive, format, &entries) {
+                Ok(bytes) => {
+                    log_file_operation(&format!(
+                        "[Archive] {} file(s), {bytes} bytes to '{}'",
+                        entries.len(),
+                        archive.display()
+                    ));
+                    Ok(())
+                }
+                Err(e) if error_mode == ErrorMode::Continue => {
+                    log::error!("Failed to write archive '{}': {e}", archive.display());
+                    Ok(())
+                }
+                Err(e) => Err(e),
             }
-            res
         })
+}
+
+/// Returns `name`, or `name` with a `_2`, `_3`... suffix before its extension
+/// if it is already in `taken`, and records the result in `taken`
+fn unique_entry_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > name.rfind('/').map_or(0, |slash| slash + 1) => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut candidate = name.to_string();
+    let mut n = 1;
+    while !taken.insert(candidate.clone()) {
+        n += 1;
+        candidate = format!("{stem}_{n}{extension}");
+    }
+    candidate
+}
+
+/// Sorts `files` for real after a dry run and compares the destinations.
+///
+/// Returns the results of the real run together with every action whose
+/// destination differs from the dry run's prediction, e.g. because of race
+/// conditions, case-insensitive filesystems or action chaining bugs. Progress
+/// is only reported for the real run.
+///
+/// # Errors
+/// Returns `TookaError` if either run fails as in [`sort_files_with_options`].
+pub fn sort_files_verified<F>(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    options: &SortOptions,
+    on_progress: Option<F>,
+) -> Result<(Vec<MatchResult>, Vec<VerificationFailure>), TookaError>
+where
+    F: Fn(usize, usize) + Send + Sync,
+{
+    verify_sort(
+        files,
+        source_path,
+        rules_file,
+        options,
+        on_progress.map(|cb| move |p: SortProgress| cb(p.files_processed, p.files_total)),
+    )
+}
+
+/// [`sort_files_verified`] with a [`SortProgress`] callback
+fn verify_sort<F>(
+    files: &[PathBuf],
+    source_path: &Path,
+    rules_file: &RulesFile,
+    options: &SortOptions,
+    on_progress: Option<F>,
+) -> Result<(Vec<MatchResult>, Vec<VerificationFailure>), TookaError>
+where
+    F: Fn(SortProgress) + Send + Sync,
+{
+    let options = SortOptions {
+        dry_run_verify: false,
+        ..options.clone()
+    };
+    let expected =
+        sort_files_with_progress(files, source_path, rules_file, true, &options, None::<F>)?;
+    let actual =
+        sort_files_with_progress(files, source_path, rules_file, false, &options, on_progress)?;
+    let failures = compare_results(&expected, &actual);
+    Ok((actual, failures))
+}
+
+/// Returns the actions whose destinations differ between `expected` and `actual`
+pub(crate) fn compare_results(
+    expected: &[MatchResult],
+    actual: &[MatchResult],
+) -> Vec<VerificationFailure> {
+    let key = |r: &MatchResult| (r.current_path.clone(), r.action.clone());
+    let mut predicted: HashMap<_, PathBuf> = expected
+        .iter()
+        .map(|r| (key(r), r.new_path.clone()))
         .collect();
 
-    results.map(|v| v.into_iter().flatten().collect())
+    let mut failures = Vec::new();
+    for result in actual {
+        let expected = predicted.remove(&key(result));
+        if expected.as_ref() != Some(&result.new_path) {
+            failures.push(VerificationFailure {
+                file: result.current_path.clone(),
+                action: result.action.clone(),
+                expected,
+                actual: Some(result.new_path.clone()),
+            });
+        }
+    }
+    failures.extend(
+        predicted
+            .into_iter()
+            .map(|((file, action), expected)| VerificationFailure {
+                file,
+                action,
+                expected: Some(expected),
+                actual: None,
+            }),
+    );
+    failures
+}
+
+/// Returns `files` in the given order; [`SortOrder::Unstable`] leaves them untouched.
+fn order_files(files: &[PathBuf], order: SortOrder) -> Cow<'_, [PathBuf]> {
+    let time_key = |path: &PathBuf, created: bool| {
+        fs::metadata(path)
+            .and_then(|m| if created { m.created() } else { m.modified() })
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    };
+
+    let mut sorted = files.to_vec();
+    match order {
+        SortOrder::Unstable => return Cow::Borrowed(files),
+        SortOrder::Alphabetical => sorted.sort(),
+        SortOrder::BySize => {
+            sorted.sort_by_cached_key(|path| fs::metadata(path).map_or(0, |m| m.len()));
+        }
+        SortOrder::ByModified => sorted.sort_by_cached_key(|path| time_key(path, false)),
+        SortOrder::ByCreated => sorted.sort_by_cached_key(|path| time_key(path, true)),
+    }
+    Cow::Owned(sorted)
+}
+
+/// Sorts the files of several source directories prepared with
+/// [`prepare_sort_multi`] or [`prepare_sort`], one source after the other.
+///
+/// Each source is used as the base path for its own files so
+/// `preserve_structure` stays relative to the directory the file came from.
+///
+/// # Arguments
+/// * `sources` - Prepared sources, each with its files and rules.
+/// * `dry_run` - If true, actions are logged but not performed.
+/// * `options` - Options applied to every source, see [`SortOptions`].
+/// * `on_progress` - Optional callback invoked after each file processed, with
+///   the progress counted across all sources.
+///
+/// # Errors
+/// Returns `TookaError` if the thread pool cannot be built, or if file
+/// operations fail and `error_mode` is [`ErrorMode::FailFast`].
+pub fn sort_files_multi<F>(
+    sources: &[PreparedSort],
+    dry_run: bool,
+    options: &SortOptions,
+    on_progress: Option<F>,
+) -> Result<Vec<MatchResult>, TookaError>
+where
+    F: Fn(SortProgress) + Send + Sync,
+{
+    let total = sources.iter().map(|prepared| prepared.files.len()).sum();
+    let destinations = DryRunDestinations::default();
+    let mut results = Vec::with_capacity(total);
+    let mut offset = 0;
+    for prepared in sources {
+        log::debug!(
+            "Sorting source directory '{}'",
+            prepared.source_path.display()
+        );
+        results.extend(sort_files_planned(
+            &prepared.files,
+            &prepared.source_path,
+            &prepared.rules_file,
+            dry_run,
+            options,
+            on_progress.as_ref().map(|cb| {
+                move |progress: SortProgress| cb(overall_progress(progress, offset, total))
+            }),
+            &destinations,
+        )?);
+        offset += prepared.files.len();
+    }
+    Ok(results)
+}
+
+/// Turns the progress of one source into the progress of a multi-source run
+/// of `total` files, `offset` of which were in earlier sources. The estimate
+/// is scaled to the files of the sources not sorted yet, and is `None` once
+/// the current source is done.
+fn overall_progress(progress: SortProgress, offset: usize, total: usize) -> SortProgress {
+    let files_processed = offset + progress.files_processed;
+    let left_here = progress
+        .files_total
+        .saturating_sub(progress.files_processed);
+    let left_overall = total.saturating_sub(files_processed);
+    // Nothing is left to scale once the source is done
+    let estimated_remaining = progress
+        .estimated_remaining
+        .filter(|_| left_here > 0)
+        .map(|eta| eta.mul_f64(left_overall as f64 / left_here as f64));
+    SortProgress {
+        files_processed,
+        files_total: total,
+        estimated_remaining,
+        ..progress
+    }
+}
+
+/// Same as [`sort_files_multi`], but stops once `max_files` files were
+/// processed across all sources or `time_budget` has elapsed, see
+/// [`sort_files_limited`]. The returned bool is true if some files were left
+/// unprocessed.
+///
+/// # Errors
+/// Returns `TookaError` if file operations fail.
+pub fn sort_files_multi_limited(
+    sources: &[PreparedSort],
+    dry_run: bool,
+    max_files: usize,
+    time_budget: Duration,
+) -> Result<(Vec<MatchResult>, bool), TookaError> {
+    let started = Instant::now();
+    let destinations = DryRunDestinations::default();
+    let mut results = Vec::new();
+    let mut processed = 0;
+    for prepared in sources {
+        let (batch, exhausted) = sort_files_limited_planned(
+            &prepared.files,
+            &prepared.source_path,
+            &prepared.rules_file,
+            dry_run,
+            max_files.saturating_sub(processed),
+            time_budget.saturating_sub(started.elapsed()),
+            &destinations,
+        )?;
+        results.extend(batch);
+        if exhausted {
+            return Ok((results, true));
+        }
+        processed += prepared.files.len();
+    }
+    Ok((results, false))
+}
+
+/// Default time a file has to stay unchanged before watch mode sorts it
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Default time between two scans of the watched directory. Every scan walks
+/// the whole directory, so this is kept coarse.
+pub const DEFAULT_WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest time watch mode sleeps before checking its `stop` flag
+const WATCH_STOP_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Options for [`watch_sort_with_options`].
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Time a new or changed file has to stay unchanged before it is sorted,
+    /// so files that are still being written are left alone
+    pub debounce: Duration,
+    /// Time between two scans of the source directory. Each scan walks the
+    /// whole directory, so short intervals are expensive on large folders.
+    pub poll_interval: Duration,
+    /// Watching stops once this is set
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_WATCH_DEBOUNCE,
+            poll_interval: DEFAULT_WATCH_POLL_INTERVAL,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+/// Watches `source` and sorts files as they are created in or moved into it.
+/// Files already in the directory are left alone. Runs until the process
+/// exits; use [`watch_sort_with_options`] to stop it.
+///
+/// The directory is polled every [`DEFAULT_WATCH_POLL_INTERVAL`], there are
+/// no filesystem events.
+///
+/// # Errors
+/// Returns `TookaError` if `source` is not a directory when watching starts.
+pub fn watch_sort<F>(
+    source: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    on_match: F,
+) -> Result<(), TookaError>
+where
+    F: FnMut(MatchResult),
+{
+    watch_sort_with_options(
+        source,
+        rules_file,
+        dry_run,
+        &WatchOptions::default(),
+        on_match,
+    )
+}
+
+/// Same as [`watch_sort`], with explicit [`WatchOptions`]. Returns once the
+/// `stop` flag is set.
+///
+/// Changes are found by polling: the whole directory is scanned every
+/// `poll_interval`, and a file is sorted once its size and modification time
+/// haven't changed for `debounce`, so after at least two scans. Every
+/// [`MatchResult`] is passed to `on_match` as soon as its file is sorted. A
+/// file that fails to sort is logged and skipped, and so is a scan that fails,
+/// e.g. while the directory is briefly unavailable. Archive actions are not
+/// applied, since no run ever ends to write the archive.
+///
+/// # Errors
+/// Returns `TookaError` if `source` is not a directory when watching starts.
+pub fn watch_sort_with_options<F>(
+    source: &Path,
+    rules_file: &RulesFile,
+    dry_run: bool,
+    options: &WatchOptions,
+    mut on_match: F,
+) -> Result<(), TookaError>
+where
+    F: FnMut(MatchResult),
+{
+    if rules_file
+        .enabled_rules()
+        .any(|rule| rule.then.iter().any(|a| matches!(a, Action::Archive(_))))
+    {
+        log::warn!("Archive actions are not applied in watch mode");
+    }
+
+    // Files seen in a settled state, with their signature
+    let mut known: HashMap<PathBuf, FileSignature> = collect_files(source)?
+        .into_iter()
+        .filter_map(|path| FileSignature::of(&path).map(|sig| (path, sig)))
+        .collect();
+    // New or changed files waiting to settle, with the time they last changed
+    let mut pending: HashMap<PathBuf, (FileSignature, Instant)> = HashMap::new();
+    let destinations = DryRunDestinations::default();
+    log::info!(
+        "Watching '{}' ({} existing file(s) left alone)",
+        source.display(),
+        known.len()
+    );
+
+    while !options.stop.load(Ordering::Relaxed) {
+        sleep_unless_stopped(options.poll_interval, &options.stop);
+        if options.stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let files: HashSet<PathBuf> = match collect_files(source) {
+            Ok(files) => files.into_iter().collect(),
+            Err(e) => {
+                log::warn!("Failed to scan '{}', retrying: {e}", source.display());
+                continue;
+            }
+        };
+        known.retain(|path, _| files.contains(path));
+        pending.retain(|path, _| files.contains(path));
+
+        let now = Instant::now();
+        for path in files {
+            let Some(sig) = FileSignature::of(&path) else {
+                continue;
+            };
+            if known.get(&path) == Some(&sig) {
+                continue;
+            }
+            match pending.get_mut(&path) {
+                Some((pending_sig, _)) if *pending_sig == sig => {}
+                Some(entry) => *entry = (sig, now),
+                None => {
+                    log::debug!("Noticed '{}'", path.display());
+                    pending.insert(path, (sig, now));
+                }
+            }
+        }
+
+        let mut settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, (_, changed))| now.duration_since(*changed) >= options.debounce)
+            .map(|(path, _)| path.clone())
+            .collect();
+        settled.sort();
+
+        for path in settled {
+            if options.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let Some((sig, _)) = pending.remove(&path) else {
+                continue;
+            };
+            known.insert(path.clone(), sig);
+            match sort_file(
+                &path,
+                rules_file,
+                dry_run,
+                source,
+                None,
+                None,
+                &destinations,
+            ) {
+                Ok(results) => {
+                    for result in results {
+                        // Don't sort files again that were sorted into the source
+                        if let Some(sig) = FileSignature::of(&result.new_path) {
+                            known.insert(result.new_path.clone(), sig);
+                        }
+                        on_match(result);
+                    }
+                }
+                Err(e) => log::error!("Failed to sort '{}': {e}", path.display()),
+            }
+        }
+    }
+
+    log::info!("Stopped watching '{}'", source.display());
+    Ok(())
+}
+
+/// Sleeps for `duration`, waking up early once `stop` is set
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        thread::sleep(left.min(WATCH_STOP_CHECK_INTERVAL));
+    }
+}
+
+/// Size and modification time of a file, used by watch mode to notice changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileSignature {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileSignature {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// How often a timed-out file is checked again while one of its actions runs
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Shared between [`sort_file_with_timeout`] and its worker, so the worker
+/// only starts actions while the caller is still waiting for it
+struct ActionGate {
+    /// No action is started after this point
+    deadline: Instant,
+    /// Set once the file timed out; no action is started after that
+    cancelled: bool,
+    /// Whether an action is running right now
+    in_action: bool,
+    /// Number of actions started so far
+    started: usize,
+}
+
+impl ActionGate {
+    fn new(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            cancelled: false,
+            in_action: false,
+            started: 0,
+        }
+    }
+
+    /// Marks an action as running, unless the file timed out
+    fn begin(gate: &Mutex<Self>) -> bool {
+        let mut gate = gate.lock().unwrap_or_else(PoisonError::into_inner);
+        if gate.cancelled || Instant::now() >= gate.deadline {
+            gate.cancelled = true;
+            return false;
+        }
+        gate.in_action = true;
+        gate.started += 1;
+        true
+    }
+
+    fn end(gate: &Mutex<Self>) {
+        gate.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .in_action = false;
+    }
+
+    /// Gives up on the worker and returns the number of actions it started,
+    /// or `None` if an action is running and has to be waited for
+    fn cancel(gate: &Mutex<Self>) -> Option<usize> {
+        let mut gate = gate.lock().unwrap_or_else(PoisonError::into_inner);
+        if gate.in_action {
+            return None;
+        }
+        gate.cancelled = true;
+        Some(gate.started)
+    }
+}
+
+/// Result reported for a file that was given up on
+fn timed_out_result(path: PathBuf) -> MatchResult {
+    MatchResult {
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        action: "timed_out".to_string(),
+        matched_rule_id: String::new(),
+        current_path: path.clone(),
+        new_path: path,
+        bytes_affected: 0,
+    }
+}
+
+/// Runs [`sort_file`] on a separate thread and waits at most `timeout_secs`
+/// for it, returning `TookaError::OperationTimeout` if it takes longer.
+///
+/// The timeout never interrupts an action: one that is running is waited for,
+/// and the worker doesn't start another one after the timeout. If some
+/// actions already ran, their results are returned followed by a `timed_out`
+/// result. A worker stuck before its first action, e.g. reading the file's
+/// content, is left to finish on its own without touching the file.
+fn sort_file_with_timeout(
+    file_path: &Path,
+    rules_file: &Arc<RulesFile>,
+    dry_run: bool,
+    source_path: &Path,
+    journal: Option<Arc<Journal>>,
+    timeout_secs: u64,
+    destinations: &DryRunDestinations,
+) -> Result<Vec<MatchResult>, TookaError> {
+    let (tx, rx) = mpsc::channel();
+    let timeout = Duration::from_secs(timeout_secs);
+    let gate = Arc::new(Mutex::new(ActionGate::new(Instant::now() + timeout)));
+    let rules_file = Arc::clone(rules_file);
+    let (path, source) = (file_path.to_path_buf(), source_path.to_path_buf());
+    let worker_gate = Arc::clone(&gate);
+    let destinations = destinations.clone();
+    thread::spawn(move || {
+        let _ = tx.send(sort_file(
+            &path,
+            &rules_file,
+            dry_run,
+            &source,
+            journal.as_deref(),
+            Some(&worker_gate),
+            &destinations,
+        ));
+    });
+
+    let panicked =
+        || TookaError::Other(format!("Worker sorting '{}' panicked", file_path.display()));
+    let mut wait = timeout;
+    loop {
+        match rx.recv_timeout(wait) {
+            Ok(res) => return res,
+            Err(RecvTimeoutError::Disconnected) => return Err(panicked()),
+            Err(RecvTimeoutError::Timeout) => match ActionGate::cancel(&gate) {
+                None => wait = TIMEOUT_POLL_INTERVAL,
+                Some(0) => {
+                    return Err(TookaError::OperationTimeout {
+                        path: file_path.to_path_buf(),
+                        timeout_secs,
+                    });
+                }
+                // The worker is between actions and returns right away,
+                // ending its results with a `timed_out` one
+                Some(_) => return rx.recv().map_err(|_| panicked())?,
+            },
+        }
+    }
 }
 
 /// Processes a single file against rules and returns the match results.
 /// Uses pre-sorted rules for better performance with early termination.
+/// With a `gate`, stops before the next action once the file timed out and
+/// ends the results with a `timed_out` one.
 fn sort_file(
     file_path: &Path,
     rules_file: &RulesFile,
     dry_run: bool,
     source_path: &Path,
+    journal: Option<&Journal>,
+    gate: Option<&Mutex<ActionGate>>,
+    destinations: &DryRunDestinations,
 ) -> Result<Vec<MatchResult>, TookaError> {
     log::debug!("Processing file: '{}'", file_path.display());
 
@@ -91,12 +1300,12 @@ fn sort_file(
             ))
         })?;
 
-    // Since rules are pre-sorted by priority, we can take the first match
-    let Some(rule) = rules_file
-        .rules
-        .iter()
-        .find(|rule| file_match::match_rule_matcher(file_path, &rule.when))
-    else {
+    // Since rules are pre-sorted by priority, we can take the first match.
+    // The extension check skips most rules without touching the file.
+    let Some(rule) = rules_file.enabled_rules().find(|rule| {
+        rule.applicable_to(file_path)
+            && file_match::match_rule_matcher_in(file_path, source_path, &rule.when)
+    }) else {
         log::debug!("No matching rules found for file '{file_name}'");
         return Ok(vec![MatchResult {
             file_name: file_name.to_string(),
@@ -104,6 +1313,7 @@ fn sort_file(
             matched_rule_id: "none".to_string(),
             current_path: file_path.to_path_buf(),
             new_path: file_path.to_path_buf(),
+            bytes_affected: 0,
         }]);
     };
 
@@ -116,12 +1326,61 @@ fn sort_file(
 
     let mut results = Vec::with_capacity(rule.then.len());
     let mut current_path = file_path.to_path_buf();
+    // Rename templates can use the capture groups of the filename regex as {{1}}, {{2}}...
+    let template_values = if rule.then.iter().any(|a| matches!(a, Action::Rename(_))) {
+        file_match::filename_captures(file_path, &rule.when)
+    } else {
+        HashMap::new()
+    };
 
     for (i, action) in rule.then.iter().enumerate() {
-        let op_result = file_ops::execute_action(&current_path, action, dry_run, source_path)
-            .map_err(|e| {
-                TookaError::FileOperationError(format!("Failed to execute action: {e}"))
-            })?;
+        if gate.is_some_and(|gate| !ActionGate::begin(gate)) {
+            log::warn!(
+                "Gave up on '{}' before action {i} of rule '{}'",
+                file_path.display(),
+                rule.id
+            );
+            results.push(timed_out_result(file_path.to_path_buf()));
+            break;
+        }
+        // Journaled deletes keep the file so the run can be rolled back
+        let trash_path = match (journal, action) {
+            (Some(journal), Action::Delete(inner)) if !inner.trash => {
+                Some(journal.trash_path(&current_path))
+            }
+            _ => None,
+        };
+        let options = FileOperationOptions {
+            delete_to: trash_path.clone(),
+            template_values: template_values.clone(),
+            dry_run_destinations: destinations.clone(),
+            ..Default::default()
+        };
+        let res = file_ops::execute_action_with_options(
+            &current_path,
+            action,
+            dry_run,
+            source_path,
+            &options,
+        )
+        .map_err(|e| TookaError::FileOperationError(format!("Failed to execute action: {e}")))
+        .and_then(|op_result| {
+            if let Some(journal) = journal {
+                record_operation(
+                    journal,
+                    &rule.id,
+                    &current_path,
+                    &op_result,
+                    trash_path.as_deref(),
+                )?;
+            }
+            Ok(op_result)
+        });
+        // The journal entry is written before the caller can give up on the file
+        if let Some(gate) = gate {
+            ActionGate::end(gate);
+        }
+        let op_result = res?;
 
         let log_prefix = if dry_run { "DRY" } else { "" };
         log_file_operation(&format!(
@@ -136,9 +1395,10 @@ fn sort_file(
             matched_rule_id: rule.id.clone(),
             current_path: current_path.clone(),
             new_path: op_result.new_path.clone(),
+            bytes_affected: op_result.bytes_affected,
         });
 
-        if op_result.action == "delete" {
+        if matches!(op_result.action.as_str(), "delete" | "delete_skipped") {
             if i + 1 < rule.then.len() {
                 log::warn!(
                     "File was deleted, skipping {} remaining action(s).",
@@ -148,14 +1408,347 @@ fn sort_file(
             break;
         }
 
-        current_path.clone_from(&op_result.new_path);
+        // Compressing leaves the file in place for the following actions
+        if !matches!(action, Action::Compress(_)) {
+            current_path.clone_from(&op_result.new_path);
+        }
     }
 
     Ok(results)
 }
 
-/// Recursively collects all files in the given directory using optimized traversal
+/// Appends a performed action to the journal if it can be undone
+fn record_operation(
+    journal: &Journal,
+    rule_id: &str,
+    original_path: &Path,
+    op_result: &FileOperationResult,
+    trash_path: Option<&Path>,
+) -> Result<(), TookaError> {
+    let action = match op_result.action.as_str() {
+        "move" => JournalAction::Move,
+        // With `link_back` the original stays in place, so the link is undone like a copy
+        "move_and_hardlink" if original_path.exists() => JournalAction::Copy,
+        "move_and_hardlink" => JournalAction::Move,
+        // Links leave the file in place, so they are undone like a copy
+        "copy" | "symlink" | "hardlink" | "compress" => JournalAction::Copy,
+        "rename" => JournalAction::Rename,
+        "delete" => JournalAction::Delete,
+        _ => return Ok(()),
+    };
+    journal.record(
+        action,
+        rule_id,
+        original_path,
+        &op_result.new_path,
+        trash_path,
+    )
+}
+
+/// Undoes the operations of the last run in the journal at `journal_path`,
+/// newest first: moved and renamed files are moved back, copies are deleted,
+/// and deleted files are restored from `.tooka_trash`.
+///
+/// Undone operations are removed from the journal; operations that fail stay
+/// in it, so calling this again retries them. Returns one result per undone
+/// operation, with `current_path` the file's location before the rollback and
+/// `new_path` its restored path (`[deleted]` for copies). Nothing is changed
+/// in dry-run mode.
+///
+/// # Errors
+/// Returns `TookaError` if the journal can't be read or written, or if any
+/// operation couldn't be undone.
+pub fn rollback_last_run(
+    journal_path: &Path,
+    dry_run: bool,
+) -> Result<Vec<MatchResult>, TookaError> {
+    let entries = journal::read_journal(journal_path)?;
+    let Some(run_id) = entries.last().map(|entry| entry.run_id) else {
+        log::info!("Nothing to roll back in {}", journal_path.display());
+        return Ok(Vec::new());
+    };
+
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+    for entry in entries.iter().rev().filter(|entry| entry.run_id == run_id) {
+        let current_path = match entry.action {
+            JournalAction::Delete => entry
+                .trash_path
+                .clone()
+                .unwrap_or_else(|| entry.destination_path.clone()),
+            _ => entry.destination_path.clone(),
+        };
+        let new_path = match entry.action {
+            JournalAction::Copy => PathBuf::from("[deleted]"),
+            _ => entry.original_path.clone(),
+        };
+        let bytes_affected = fs::symlink_metadata(&current_path).map_or(0, |m| m.len());
+
+        if !dry_run {
+            if let Err(e) = journal::undo(entry) {
+                log::error!(
+                    "Failed to undo {} of '{}': {e}",
+                    entry.action.name(),
+                    entry.original_path.display()
+                );
+                failed.push(entry);
+                continue;
+            }
+        }
+        let log_prefix = if dry_run { "DRY" } else { "" };
+        log_file_operation(&format!(
+            "{log_prefix}[Undo {}] '{}' to '{}'",
+            entry.action.name(),
+            current_path.display(),
+            new_path.display()
+        ));
+
+        results.push(MatchResult {
+            file_name: entry
+                .original_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            action: format!("undo_{}", entry.action.name()),
+            matched_rule_id: entry.rule_id.clone(),
+            current_path,
+            new_path,
+            bytes_affected,
+        });
+    }
+
+    if dry_run {
+        return Ok(results);
+    }
+
+    let remaining: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.run_id != run_id || failed.contains(entry))
+        .cloned()
+        .collect();
+    journal::write_journal(journal_path, &remaining)?;
+    if let Some(parent) = journal_path.parent() {
+        // Only succeeds once every deleted file of the run was restored
+        let _ = fs::remove_dir(
+            parent
+                .join(journal::TRASH_FOLDER_NAME)
+                .join(run_id.to_string()),
+        );
+    }
+
+    if failed.is_empty() {
+        Ok(results)
+    } else {
+        Err(TookaError::FileOperationError(format!(
+            "{} of {} operation(s) couldn't be undone and remain in the journal",
+            failed.len(),
+            failed.len() + results.len()
+        )))
+    }
+}
+
+/// Scope of a prepared sort, shown to the user before any file is touched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrepareStats {
+    /// Number of files found in the source folder
+    pub file_count: usize,
+    /// Number of files per lowercase extension, `""` for files without one
+    pub extensions: HashMap<String, usize>,
+    /// Number of files each enabled rule would handle, in rule order. Like in a real
+    /// run, a file only counts toward the first rule it matches.
+    pub rule_matches: Vec<(String, usize)>,
+    /// Number of files no rule matches
+    pub unmatched: usize,
+}
+
+impl PrepareStats {
+    /// Number of rules that would handle at least one file
+    pub fn applicable_rules(&self) -> usize {
+        self.rule_matches
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .count()
+    }
+}
+
+/// Files and rules collected for a sort, before any action has run.
+///
+/// Created by [`prepare_sort_full`] or [`prepare_sort_multi`]; pass it to
+/// [`sort_files_multi`], or pass `files`, `source_path` and `rules_file` to
+/// [`sort_files_with_options`] to carry out the sort.
+#[derive(Debug, Clone)]
+pub struct PreparedSort {
+    /// Folder the files were collected from
+    pub source_path: PathBuf,
+    /// Enabled rules to apply, sorted by priority
+    pub rules_file: RulesFile,
+    /// Files found in the source folder
+    pub files: Vec<PathBuf>,
+    /// Summary of the files and the rules that apply to them
+    pub stats: PrepareStats,
+}
+
+/// Loads the config and rules and collects the files of `source`, without
+/// running any action.
+///
+/// `rules` is a comma-separated list of rule IDs, or `<all>` for every rule;
+/// only enabled rules are kept, and only those with `options.tag` if it is set.
+///
+/// # Errors
+/// Returns `TookaError` if the config or rules can't be loaded, a listed rule
+/// doesn't exist, no enabled rule is left, or `source` is not a directory.
+pub fn prepare_sort_full(
+    source: &str,
+    rules: &str,
+    options: &SortOptions,
+) -> Result<PreparedSort, TookaError> {
+    prepare_sort(Path::new(source), load_sort_rules(rules, options)?, options)
+}
+
+/// Loads the config and the rules to sort with, see [`prepare_sort_full`].
+///
+/// # Errors
+/// Returns `TookaError` if the config or rules can't be loaded, a listed rule
+/// doesn't exist, or no enabled rule is left.
+pub fn load_sort_rules(rules: &str, options: &SortOptions) -> Result<RulesFile, TookaError> {
+    let config = Config::load()?;
+    let rule_filter: Option<Vec<String>> =
+        (rules != "<all>").then(|| rules.split(',').map(|id| id.trim().to_string()).collect());
+    let mut rules_file = RulesFile::load_for(&config)?;
+    if let Some(path) = &options.extra_rules {
+        log::debug!("Merging extra rules from '{}'", path.display());
+        rules_file.merge_with_precedence(
+            RulesFile::load_any(path)?,
+            MergeStrategy::Overwrite,
+            MergePrecedence::Other,
+        )?;
+    }
+    let mut rules_file = rules_file.optimized_with_filter(rule_filter.as_deref())?;
+    if let Some(tag) = &options.tag {
+        rules_file = rules_file.filter_by_tag(tag);
+        if rules_file.rules.is_empty() {
+            return Err(TookaError::RuleNotFound(format!(
+                "No enabled rules tagged '{tag}' found to apply."
+            )));
+        }
+    }
+    Ok(rules_file)
+}
+
+/// Collects the files of `source_path` and computes [`PrepareStats`] for
+/// `rules_file`, see [`prepare_sort_full`].
+///
+/// # Errors
+/// Returns `TookaError` if `source_path` is not a directory.
+pub fn prepare_sort(
+    source_path: &Path,
+    rules_file: RulesFile,
+    options: &SortOptions,
+) -> Result<PreparedSort, TookaError> {
+    log::debug!("Preparing sort of '{}'", source_path.display());
+    let files = collect_files_with_options(source_path, options)?;
+    Ok(prepare_files(source_path, rules_file, files))
+}
+
+/// Prepares the sort of several source directories with the same rules, see
+/// [`prepare_sort`]. Pass the result to [`sort_files_multi`].
+///
+/// A file reachable from several sources, e.g. when one source contains
+/// another, is only kept for the first of them.
+///
+/// # Errors
+/// Returns `TookaError` if a source is not a directory.
+pub fn prepare_sort_multi(
+    sources: &[PathBuf],
+    rules_file: &RulesFile,
+    options: &SortOptions,
+) -> Result<Vec<PreparedSort>, TookaError> {
+    let mut seen = HashSet::new();
+    sources
+        .iter()
+        .map(|source_path| {
+            log::debug!("Preparing sort of '{}'", source_path.display());
+            let mut files = collect_files_with_options(source_path, options)?;
+            files.sort();
+            files.retain(|file| seen.insert(file.clone()));
+            Ok(prepare_files(source_path, rules_file.clone(), files))
+        })
+        .collect()
+}
+
+/// Computes the [`PrepareStats`] of files collected from `source_path`
+pub(crate) fn prepare_files(
+    source_path: &Path,
+    rules_file: RulesFile,
+    files: Vec<PathBuf>,
+) -> PreparedSort {
+    let mut extensions = HashMap::new();
+    for file in &files {
+        let extension = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        *extensions.entry(extension).or_insert(0) += 1;
+    }
+
+    let enabled: Vec<_> = rules_file.enabled_rules().collect();
+    let matched: Vec<Option<usize>> = files
+        .par_iter()
+        .map(|file| {
+            enabled.iter().position(|rule| {
+                rule.applicable_to(file)
+                    && file_match::match_rule_matcher_in(file, source_path, &rule.when)
+            })
+        })
+        .collect();
+    let mut rule_matches: Vec<(String, usize)> =
+        enabled.iter().map(|rule| (rule.id.clone(), 0)).collect();
+    let mut unmatched = 0;
+    for index in matched {
+        match index {
+            Some(index) => rule_matches[index].1 += 1,
+            None => unmatched += 1,
+        }
+    }
+
+    let stats = PrepareStats {
+        file_count: files.len(),
+        extensions,
+        rule_matches,
+        unmatched,
+    };
+    log::debug!(
+        "Prepared sort of {} files, {} applicable rules",
+        stats.file_count,
+        stats.applicable_rules()
+    );
+
+    PreparedSort {
+        source_path: source_path.to_path_buf(),
+        rules_file,
+        files,
+        stats,
+    }
+}
+
+/// Recursively collects all files in the given directory using optimized traversal,
+/// skipping those excluded by `.tookaignore` files
 pub fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, TookaError> {
+    collect_files_with_options(dir, &SortOptions::default())
+}
+
+/// Same as [`collect_files`], honoring `follow_symlinks` and `max_depth` from [`SortOptions`].
+///
+/// Files and directories excluded by `.tookaignore` files are skipped, see
+/// [`crate::file::ignore_file`].
+///
+/// # Errors
+/// Returns `TookaError` if the directory does not exist.
+pub fn collect_files_with_options(
+    dir: &Path,
+    options: &SortOptions,
+) -> Result<Vec<PathBuf>, TookaError> {
     if !dir.exists() || !dir.is_dir() {
         return Err(TookaError::ConfigError(format!(
             "Path '{}' does not exist or is not a directory.",
@@ -163,9 +1756,17 @@ pub fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, TookaError> {
         )));
     }
 
-    let files: Result<Vec<PathBuf>, std::io::Error> = WalkDir::new(dir)
-        .follow_links(false)
+    let mut walker = WalkDir::new(dir).follow_links(options.follow_symlinks);
+    if let Some(depth) = options.max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    let mut ignore = IgnoreMatcher::default();
+    let files: Result<Vec<PathBuf>, std::io::Error> = walker
         .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !ignore.is_ignored(dir, entry.path(), entry.file_type().is_dir())
+        })
         .par_bridge()
         .filter_map(|entry| match entry {
             Ok(e) if e.file_type().is_file() => Some(Ok(e.path().to_path_buf())),
@@ -179,3 +1780,53 @@ pub fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, TookaError> {
 
     files.map_err(|e| TookaError::FileOperationError(format!("Failed to collect files: {e}")))
 }
+
+/// A file with the same content as an earlier one, as `(original, duplicate)`.
+pub type DuplicatePair = (PathBuf, PathBuf);
+
+/// Collects all files in the given directory and groups them by content.
+///
+/// Returns one representative file per unique SHA-256 hash, and a list of
+/// `(original, duplicate)` pairs for every other file with the same content.
+/// The representative is the lexicographically smallest path, so results are
+/// stable between runs. Files that cannot be read are skipped with a warning.
+pub fn collect_files_deduplicated(
+    dir: &Path,
+) -> Result<(Vec<PathBuf>, Vec<DuplicatePair>), TookaError> {
+    let mut files = collect_files(dir)?;
+    files.sort();
+
+    let first_seen: DashMap<String, PathBuf> = DashMap::new();
+    let hashed: Vec<(PathBuf, String)> = files
+        .into_par_iter()
+        .filter_map(|path| match file_ops::hash_file(&path) {
+            Ok(hash) => {
+                first_seen
+                    .entry(hash.clone())
+                    .and_modify(|existing| {
+                        if path < *existing {
+                            *existing = path.clone();
+                        }
+                    })
+                    .or_insert_with(|| path.clone());
+                Some((path, hash))
+            }
+            Err(err) => {
+                log::warn!("Failed to hash {}: {err}", path.display());
+                None
+            }
+        })
+        .collect();
+
+    let mut unique = Vec::new();
+    let mut duplicates = Vec::new();
+    for (path, hash) in hashed {
+        let original = first_seen.get(&hash).map(|entry| entry.value().clone());
+        match original {
+            Some(original) if original != path => duplicates.push((original, path)),
+            _ => unique.push(path),
+        }
+    }
+
+    Ok((unique, duplicates))
+}
@@ -1,9 +1,19 @@
 #[cfg(test)]
 mod tests {
     use crate::core::error::TookaError;
-    use crate::core::sorter::{MatchResult, collect_files, sort_files};
-    use crate::rules::rule::{Action, Conditions, CopyAction, MoveAction, Rule};
+    #[cfg(feature = "pdf")]
+    use crate::core::report::compute_summary;
+    use crate::core::sorter::{
+        ErrorMode, MatchResult, SortOptions, SortOrder, SortProgress, SortStats,
+        VerificationFailure, WatchOptions, collect_files, collect_files_deduplicated,
+        collect_files_with_options, compare_results, prepare_sort, prepare_sort_multi,
+        rollback_last_run, sort_files, sort_files_limited, sort_files_multi,
+        sort_files_multi_limited, sort_files_verified, sort_files_with_options,
+        sort_files_with_progress, watch_sort_with_options,
+    };
+    use crate::rules::rule::{Action, Conditions, ConflictStrategy, CopyAction, MoveAction, Rule};
     use crate::rules::rules_file::RulesFile;
+    #[cfg(feature = "pdf")]
     use crate::utils::gen_pdf::generate_pdf;
     use std::fs::{File, create_dir_all};
     use std::io::Write;
@@ -58,17 +68,39 @@ mod tests {
                     filename: Some(r".*\.txt$".to_string()),
                     extensions: Some(vec!["txt".to_string()]),
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: txt_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
             Rule {
                 id: "log_rule".to_string(),
@@ -81,17 +113,37 @@ mod tests {
                     filename: Some(r".*\.log$".to_string()),
                     extensions: Some(vec!["log".to_string()]),
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Copy(CopyAction {
                     to: log_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
             Rule {
                 id: "data_rule".to_string(),
@@ -104,17 +156,39 @@ mod tests {
                     filename: Some(r".*\.data$".to_string()),
                     extensions: Some(vec!["data".to_string()]),
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: data_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
         ];
 
@@ -133,8 +207,14 @@ mod tests {
         let rules_file = create_test_rules(&source_path);
 
         // Sort files in dry run mode
-        let results = sort_files(&files, &source_path, &rules_file, true, None::<fn()>)
-            .expect("sort_files should succeed");
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files should succeed");
 
         // Check that we got results for all files
         assert_eq!(results.len(), files.len());
@@ -178,8 +258,14 @@ mod tests {
         let rules_file = create_test_rules(&source_path);
 
         // Sort files with actual execution (not dry run)
-        let results = sort_files(&files, &source_path, &rules_file, false, None::<fn()>)
-            .expect("sort_files should succeed");
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files should succeed");
 
         // Check that txt file was moved
         let txt_result = results.iter().find(|r| r.file_name == "test1.txt").unwrap();
@@ -245,17 +331,39 @@ mod tests {
                     filename: Some(r".*\.txt$".to_string()),
                     extensions: None,
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: low_priority_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
             Rule {
                 id: "high_priority_rule".to_string(),
@@ -268,17 +376,39 @@ mod tests {
                     filename: Some(r".*\.txt$".to_string()),
                     extensions: None,
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: high_priority_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
         ];
 
@@ -291,7 +421,7 @@ mod tests {
             &source_path,
             &optimized_rules,
             true,
-            None::<fn()>,
+            None::<fn(usize, usize)>,
         )
         .expect("sort_files should succeed");
 
@@ -310,11 +440,9 @@ mod tests {
         let rules_file = create_test_rules(&source_path);
 
         // Track progress
-        let progress_count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
-        let progress_count_clone = progress_count.clone();
-
-        let progress_callback = move || {
-            progress_count_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let progress_calls = std::sync::Mutex::new(Vec::new());
+        let progress_callback = |done: usize, total: usize| {
+            progress_calls.lock().unwrap().push((done, total));
         };
 
         // Sort files with progress callback
@@ -327,14 +455,88 @@ mod tests {
         )
         .expect("sort_files should succeed");
 
-        // Check that progress callback was called for each file
-        assert_eq!(
-            progress_count.load(std::sync::atomic::Ordering::SeqCst),
-            files.len()
-        );
+        // Called once per file, then once more to signal completion
+        let mut calls = progress_calls.into_inner().unwrap();
+        assert_eq!(calls.len(), files.len() + 1);
+        assert_eq!(calls.pop(), Some((files.len(), files.len())));
+        calls.sort_unstable();
+        let expected: Vec<_> = (1..=files.len()).map(|done| (done, files.len())).collect();
+        assert_eq!(calls, expected);
         assert_eq!(results.len(), files.len());
     }
 
+    #[test]
+    fn test_sort_files_with_progress_estimates_remaining_time() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+        let options = SortOptions {
+            num_threads: Some(1),
+            ..Default::default()
+        };
+
+        let progress_calls = std::sync::Mutex::new(Vec::new());
+        sort_files_with_progress(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            &options,
+            Some(|progress: SortProgress| progress_calls.lock().unwrap().push(progress)),
+        )
+        .unwrap();
+
+        let calls = progress_calls.into_inner().unwrap();
+        assert_eq!(calls.len(), files.len() + 1);
+        for (i, progress) in calls.iter().take(files.len()).enumerate() {
+            assert_eq!(progress.files_processed, i + 1);
+            assert_eq!(progress.files_total, files.len());
+            assert!(progress.estimated_remaining.is_some());
+        }
+        assert!(calls.windows(2).all(|w| w[0].elapsed <= w[1].elapsed));
+        let last = calls.last().unwrap();
+        assert_eq!(last.files_processed, files.len());
+        assert_eq!(last.estimated_remaining, Some(std::time::Duration::ZERO));
+    }
+
+    #[test]
+    fn test_sort_files_limited() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+        let budget = std::time::Duration::from_secs(60);
+        let sorted_files = |results: &[MatchResult]| {
+            let mut paths: Vec<_> = results.iter().map(|r| r.current_path.clone()).collect();
+            paths.dedup();
+            paths
+        };
+
+        let (results, exhausted) =
+            sort_files_limited(&files, &source_path, &rules_file, true, 2, budget).unwrap();
+        assert!(exhausted);
+        assert_eq!(sorted_files(&results), files[..2]);
+
+        let (results, exhausted) =
+            sort_files_limited(&files, &source_path, &rules_file, true, files.len(), budget)
+                .unwrap();
+        assert!(!exhausted);
+        assert_eq!(sorted_files(&results), files);
+
+        let (results, exhausted) = sort_files_limited(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            usize::MAX,
+            std::time::Duration::ZERO,
+        )
+        .unwrap();
+        assert!(exhausted);
+        assert!(results.is_empty());
+    }
+
     #[test]
     fn test_sort_files_multiple_actions() {
         let temp_dir = tempdir().unwrap();
@@ -362,30 +564,62 @@ mod tests {
                 filename: Some(r".*\.txt$".to_string()),
                 extensions: None,
                 path: None,
+                path_any: None,
+                exclude_path: None,
+                min_depth: None,
+                max_depth: None,
                 size_kb: None,
+                is_empty_file: None,
                 mime_type: None,
                 created_date: None,
                 modified_date: None,
+                file_age_days: None,
                 is_symlink: None,
+                hardlink_count: None,
+                permissions: None,
+                case_sensitive: None,
+                content_matches: None,
+                content_max_bytes: None,
+                content_hash: None,
                 metadata: None,
+                all: None,
+                any_of: None,
             },
             then: vec![
                 Action::Copy(CopyAction {
                     to: copy_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 }),
                 Action::Move(MoveAction {
                     to: move_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 }),
             ],
+            after: None,
+            tags: None,
         }];
 
         let rules_file = RulesFile { rules };
 
         // Sort the file
-        let results = sort_files(&[test_file], &source_path, &rules_file, true, None::<fn()>)
-            .expect("sort_files should succeed");
+        let results = sort_files(
+            &[test_file],
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files should succeed");
 
         // Should have two results for the two actions
         assert_eq!(results.len(), 2);
@@ -446,6 +680,919 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sort_files_with_threads_and_chunks() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+
+        let options = SortOptions {
+            num_threads: Some(2),
+            chunk_size: Some(2),
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files_with_options should succeed");
+
+        assert_eq!(results.len(), files.len());
+        let txt_result = results.iter().find(|r| r.file_name == "test1.txt").unwrap();
+        assert_eq!(txt_result.matched_rule_id, "txt_rule");
+    }
+
+    #[test]
+    fn test_sort_files_with_sort_order() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let rules_file = create_test_rules(&source_path);
+        let files = vec![
+            source_path.join("b.txt"),
+            source_path.join("c.txt"),
+            source_path.join("a.txt"),
+        ];
+        create_test_file(&files[0], "medium size").unwrap();
+        create_test_file(&files[1], "s").unwrap();
+        create_test_file(&files[2], "the largest file of all").unwrap();
+
+        let names = |order: SortOrder| {
+            let options = SortOptions {
+                sort_order: order,
+                ..Default::default()
+            };
+            sort_files_with_options(
+                &files,
+                &source_path,
+                &rules_file,
+                true,
+                &options,
+                None::<fn(usize, usize)>,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_name)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(SortOrder::Alphabetical), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(names(SortOrder::BySize), ["c.txt", "b.txt", "a.txt"]);
+        assert_eq!(names(SortOrder::Unstable), ["b.txt", "c.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_sort_files_processes_in_sort_order() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let dest_dir = temp_dir.path().join("dest");
+        // Same-named files: the first one processed keeps its name
+        for (dir, content) in [("a", "aaa"), ("b", "b"), ("c", "cc")] {
+            create_dir_all(source_path.join(dir)).unwrap();
+            create_test_file(&source_path.join(dir).join("notes.txt"), content).unwrap();
+        }
+        let files = collect_files(&source_path).unwrap();
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: flatten\n    name: flatten\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: move\n        to: {}\n        on_conflict: rename\n",
+            dest_dir.display()
+        ))
+        .unwrap();
+
+        // Batches of one file, processed strictly in order
+        let options = SortOptions {
+            num_threads: Some(4),
+            chunk_size: Some(1),
+            sort_order: SortOrder::BySize,
+            ..Default::default()
+        };
+        sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dest_dir.join(name)).unwrap();
+        assert_eq!(read("notes.txt"), "b");
+        assert_eq!(read("notes_1.txt"), "cc");
+        assert_eq!(read("notes_2.txt"), "aaa");
+    }
+
+    #[test]
+    fn test_sort_files_error_mode() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+
+        // A regular file where the txt rule expects its destination directory
+        let txt_dir = source_path.join("txt_files");
+        std::fs::remove_dir(&txt_dir).unwrap();
+        create_test_file(&txt_dir, "in the way").unwrap();
+
+        let fail_fast = sort_files(
+            &files[..1],
+            &source_path,
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        );
+        assert!(matches!(fail_fast, Err(TookaError::FileOperationError(_))));
+
+        let options = SortOptions {
+            error_mode: ErrorMode::Continue,
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .expect("errors should be skipped in continue mode");
+        assert!(results.iter().all(|r| r.file_name != "test1.txt"));
+        assert!(results.iter().any(|r| r.matched_rule_id == "data_rule"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sort_files_file_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        // Reading a FIFO without a writer blocks, so matching never finishes
+        let fifo = source_path.join("slow.txt");
+        assert!(
+            std::process::Command::new("mkfifo")
+                .arg(&fifo)
+                .status()
+                .unwrap()
+                .success()
+        );
+        let dest_dir = source_path.join("dest");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: slow\n    name: slow\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n      content_matches: slow\n    then:\n      - action: move\n        to: {}\n",
+            dest_dir.display()
+        ))
+        .unwrap();
+
+        let options = SortOptions {
+            file_timeout_seconds: Some(1),
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            std::slice::from_ref(&fifo),
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].action, "timed_out");
+        assert_eq!(results[0].file_name, "slow.txt");
+
+        // Unblock the worker: the timed-out file must not be moved afterwards
+        drop(std::fs::OpenOptions::new().write(true).open(&fifo));
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(fifo.exists());
+        assert!(!dest_dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sort_files_file_timeout_waits_for_running_action() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let file = source_path.join("slow.txt");
+        create_test_file(&file, "slow").unwrap();
+        let dest_dir = source_path.join("dest");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: slow\n    name: slow\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: execute\n        command: sh\n        args: ['-c', 'sleep 2']\n      - action: move\n        to: {}\n",
+            dest_dir.display()
+        ))
+        .unwrap();
+
+        let options = SortOptions {
+            file_timeout_seconds: Some(1),
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            std::slice::from_ref(&file),
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        // The running command finishes, but the move after it never starts
+        let actions: Vec<_> = results.iter().map(|r| r.action.as_str()).collect();
+        assert_eq!(actions, ["execute", "timed_out"]);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(file.exists());
+        assert!(!dest_dir.exists());
+    }
+
+    #[test]
+    fn test_watch_sort_sorts_new_files() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        create_test_file(&source_path.join("old.txt"), "existing").unwrap();
+        // Sorted files land in the watched directory and must not be sorted again
+        let sorted_dir = source_path.join("sorted");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: txt\n    name: txt\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: move\n        to: {}\n",
+            sorted_dir.display()
+        ))
+        .unwrap();
+        let options = WatchOptions {
+            debounce: std::time::Duration::from_millis(50),
+            poll_interval: std::time::Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mut results = Vec::new();
+
+        std::thread::scope(|scope| {
+            // Adds a file once the watcher is running, and stops it once the file is sorted
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                create_test_file(&source_path.join("new.txt"), "new").unwrap();
+                let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+                while !sorted_dir.join("new.txt").exists() && std::time::Instant::now() < deadline {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                options
+                    .stop
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+
+            watch_sort_with_options(&source_path, &rules_file, false, &options, |result| {
+                results.push(result);
+            })
+            .unwrap();
+        });
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].action, "move");
+        assert_eq!(results[0].new_path, sorted_dir.join("new.txt"));
+        assert!(source_path.join("old.txt").exists());
+        assert!(sorted_dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_watch_sort_survives_failed_scan() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("watched");
+        let moved_away = temp_dir.path().join("away");
+        create_dir_all(&source_path).unwrap();
+        let sorted_dir = temp_dir.path().join("sorted");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: txt\n    name: txt\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: move\n        to: {}\n",
+            sorted_dir.display()
+        ))
+        .unwrap();
+        let options = WatchOptions {
+            debounce: std::time::Duration::from_millis(50),
+            poll_interval: std::time::Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mut results = Vec::new();
+
+        std::thread::scope(|scope| {
+            // Takes the directory away for a few scans, then adds a file to it
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                std::fs::rename(&source_path, &moved_away).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                std::fs::rename(&moved_away, &source_path).unwrap();
+                create_test_file(&source_path.join("new.txt"), "new").unwrap();
+                let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+                while !sorted_dir.join("new.txt").exists() && std::time::Instant::now() < deadline {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                options
+                    .stop
+                    .store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+
+            watch_sort_with_options(&source_path, &rules_file, false, &options, |result| {
+                results.push(result);
+            })
+            .unwrap();
+        });
+
+        assert_eq!(results.len(), 1);
+        assert!(sorted_dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_watch_sort_missing_source() {
+        let temp_dir = tempdir().unwrap();
+        let result = watch_sort_with_options(
+            &temp_dir.path().join("missing"),
+            &RulesFile { rules: vec![] },
+            true,
+            &WatchOptions::default(),
+            |_| {},
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_sort_files_rename_with_filename_captures() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("IMG_20240315_party.JPG");
+        create_test_file(&file, "photo").unwrap();
+        let rules_file = RulesFile::from_yaml_str(
+            "rules:\n  - id: photos\n    name: photos\n    enabled: true\n    priority: 1\n    when:\n      filename: '^img_(\\d{4})(\\d{2})\\d{2}_(.+)\\.jpg$'\n      case_sensitive: false\n    then:\n      - action: rename\n        to: '{{1}}-{{2}}_{{3}}.{{ext}}'\n",
+        )
+        .unwrap();
+
+        let results = sort_files(
+            std::slice::from_ref(&file),
+            temp_dir.path(),
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert_eq!(results[0].action, "rename");
+        assert_eq!(
+            results[0].new_path,
+            temp_dir.path().join("2024-03_party.JPG")
+        );
+        assert!(results[0].new_path.exists());
+    }
+
+    #[test]
+    fn test_sort_files_max_depth() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_dir_all(source_path.join("nested/deeper")).unwrap();
+        for file in ["top.txt", "nested/mid.txt", "nested/deeper/low.txt"] {
+            create_test_file(&source_path.join(file), "text").unwrap();
+        }
+        let files = collect_files(&source_path).unwrap();
+        let rules_file = RulesFile::from_yaml_str(
+            "rules:\n  - id: top\n    name: top\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n      max_depth: 0\n    then:\n      - action: skip\n  - id: nested\n    name: nested\n    enabled: true\n    priority: 1\n    when:\n      min_depth: 1\n      max_depth: 1\n    then:\n      - action: skip\n",
+        )
+        .unwrap();
+
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        let mut matched: Vec<_> = results
+            .iter()
+            .map(|r| (r.file_name.as_str(), r.matched_rule_id.as_str()))
+            .collect();
+        matched.sort_unstable();
+        assert_eq!(
+            matched,
+            [
+                ("low.txt", "none"),
+                ("mid.txt", "nested"),
+                ("top.txt", "top")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_files_rename_on_conflict_keeps_same_named_files() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let dest_dir = temp_dir.path().join("dest");
+        // Enough same-named files that several are sorted at the same time
+        let mut names: Vec<_> = (0..64).map(|n| format!("{n:02}")).collect();
+        for name in &names {
+            create_dir_all(source_path.join(name)).unwrap();
+            create_test_file(&source_path.join(name).join("notes.txt"), name).unwrap();
+        }
+        let files = collect_files(&source_path).unwrap();
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: flatten\n    name: flatten\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: move\n        to: {}\n        on_conflict: rename\n",
+            dest_dir.display()
+        ))
+        .unwrap();
+
+        let options = SortOptions {
+            num_threads: Some(8),
+            ..Default::default()
+        };
+        sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        let mut contents: Vec<_> = std::fs::read_dir(&dest_dir)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        contents.sort_unstable();
+        names.sort_unstable();
+        assert_eq!(contents, names);
+    }
+
+    #[test]
+    fn test_sort_files_multi_dry_run_resolves_conflicts_like_real_run() {
+        let temp_dir = tempdir().unwrap();
+        let downloads = temp_dir.path().join("downloads");
+        let desktop = temp_dir.path().join("desktop");
+        let dest_dir = temp_dir.path().join("dest");
+        for source in [&downloads, &desktop] {
+            create_dir_all(source).unwrap();
+            create_test_file(&source.join("notes.txt"), "notes").unwrap();
+        }
+
+        for (strategy, expected) in [
+            ("rename", vec!["copy", "copy"]),
+            ("skip", vec!["copy", "conflict_skipped"]),
+        ] {
+            let rules_file = RulesFile::from_yaml_str(&format!(
+                "rules:\n  - id: notes\n    name: notes\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: copy\n        to: {}\n        on_conflict: {strategy}\n",
+                dest_dir.display()
+            ))
+            .unwrap();
+            let options = SortOptions::default();
+            let sources =
+                prepare_sort_multi(&[downloads.clone(), desktop.clone()], &rules_file, &options)
+                    .unwrap();
+
+            let dry = sort_files_multi(&sources, true, &options, None::<fn(SortProgress)>).unwrap();
+            let real =
+                sort_files_multi(&sources, false, &options, None::<fn(SortProgress)>).unwrap();
+
+            let actions: Vec<_> = dry.iter().map(|r| r.action.as_str()).collect();
+            assert_eq!(actions, expected, "on_conflict: {strategy}");
+            if strategy == "rename" {
+                assert_eq!(dry[1].new_path, dest_dir.join("notes_1.txt"));
+            }
+            assert_eq!(
+                compare_results(&dry, &real),
+                vec![],
+                "on_conflict: {strategy}"
+            );
+            std::fs::remove_dir_all(&dest_dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_sort_files_dry_run_verify_with_conflicts() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let dest_dir = temp_dir.path().join("dest");
+        for name in ["a", "b", "c"] {
+            create_dir_all(source_path.join(name)).unwrap();
+            create_test_file(&source_path.join(name).join("notes.txt"), name).unwrap();
+        }
+        let files = collect_files(&source_path).unwrap();
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: flatten\n    name: flatten\n    enabled: true\n    priority: 1\n    when:\n      extensions: [txt]\n    then:\n      - action: move\n        to: {}\n        on_conflict: rename\n",
+            dest_dir.display()
+        ))
+        .unwrap();
+
+        // One thread, so both runs handle the files in the same order
+        let options = SortOptions {
+            num_threads: Some(1),
+            dry_run_verify: true,
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .expect("dry run and real run should agree");
+
+        let mut destinations: Vec<_> = results.iter().map(|r| r.new_path.clone()).collect();
+        destinations.sort();
+        assert_eq!(
+            destinations,
+            ["notes.txt", "notes_1.txt", "notes_2.txt"].map(|name| dest_dir.join(name))
+        );
+    }
+
+    #[test]
+    fn test_sort_files_compress_then_delete() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("app.log");
+        create_test_file(&file, "log line").unwrap();
+        let archive_dir = temp_dir.path().join("archive");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: logs\n    name: logs\n    enabled: true\n    priority: 1\n    when:\n      extensions: [log]\n    then:\n      - action: compress\n        to: {}\n      - action: delete\n",
+            archive_dir.display()
+        ))
+        .unwrap();
+
+        sort_files(
+            std::slice::from_ref(&file),
+            temp_dir.path(),
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        // The delete applies to the original, not to the compressed copy
+        assert!(!file.exists());
+        assert!(archive_dir.join("app.log.gz").exists());
+    }
+
+    #[test]
+    fn test_sort_files_archive() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_dir_all(source_path.join("nested")).unwrap();
+        create_test_file(&source_path.join("a.log"), "first").unwrap();
+        create_test_file(&source_path.join("nested/a.log"), "second").unwrap();
+        create_test_file(&source_path.join("b.txt"), "text").unwrap();
+        let files = collect_files(&source_path).unwrap();
+        let archive = temp_dir.path().join("archives/logs.zip");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:\n  - id: logs\n    name: logs\n    enabled: true\n    priority: 1\n    when:\n      extensions: [log]\n    then:\n      - action: archive\n        to: {}\n",
+            archive.display()
+        ))
+        .unwrap();
+
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert_eq!(results.iter().filter(|r| r.action == "archive").count(), 2);
+        assert!(!archive.exists());
+
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert!(
+            results
+                .iter()
+                .filter(|r| r.action == "archive")
+                .all(|r| r.new_path == archive)
+        );
+        let zip = std::fs::read(&archive).unwrap();
+        let contains = |name: &[u8]| zip.windows(name.len()).any(|w| w == name);
+        // Both files are stored flat, the second under a unique name
+        assert!(contains(b"a.log"));
+        assert!(contains(b"a_2.log"));
+        assert!(!contains(b"b.txt"));
+        assert!(source_path.join("a.log").exists());
+    }
+
+    #[test]
+    fn test_sort_files_journal_and_rollback() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_dir_all(&source_path).unwrap();
+        for (name, content) in [
+            ("a.txt", "moved"),
+            ("b.log", "copied"),
+            ("c.tmp", "deleted"),
+            ("d.md", "renamed"),
+        ] {
+            create_test_file(&source_path.join(name), content).unwrap();
+        }
+        let files = collect_files(&source_path).unwrap();
+        let dest = temp_dir.path().join("dest");
+        let rules_file = RulesFile::from_yaml_str(&format!(
+            "rules:
+  - {{id: move, name: move, enabled: true, priority: 1, when: {{extensions: [txt]}}, then: [{{action: move, to: '{dest}'}}]}}
+  - {{id: copy, name: copy, enabled: true, priority: 1, when: {{extensions: [log]}}, then: [{{action: copy, to: '{dest}'}}]}}
+  - {{id: delete, name: delete, enabled: true, priority: 1, when: {{extensions: [tmp]}}, then: [{{action: delete}}]}}
+  - {{id: rename, name: rename, enabled: true, priority: 1, when: {{extensions: [md]}}, then: [{{action: rename, to: 'renamed.md'}}]}}
+",
+            dest = dest.display()
+        ))
+        .unwrap();
+        let journal_path = temp_dir.path().join("logs/journal.json");
+        let options = SortOptions {
+            journal_path: Some(journal_path.clone()),
+            ..Default::default()
+        };
+
+        sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert!(!journal_path.exists());
+
+        sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+        assert!(dest.join("a.txt").exists());
+        assert!(dest.join("b.log").exists());
+        assert!(!source_path.join("c.tmp").exists());
+        assert!(source_path.join("renamed.md").exists());
+
+        let preview = rollback_last_run(&journal_path, true).unwrap();
+        assert_eq!(preview.len(), 4);
+        assert!(dest.join("a.txt").exists());
+
+        let mut undone: Vec<_> = rollback_last_run(&journal_path, false)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.action, r.matched_rule_id))
+            .collect();
+        undone.sort();
+        assert_eq!(
+            undone,
+            [
+                ("undo_copy".to_string(), "copy".to_string()),
+                ("undo_delete".to_string(), "delete".to_string()),
+                ("undo_move".to_string(), "move".to_string()),
+                ("undo_rename".to_string(), "rename".to_string()),
+            ]
+        );
+        for (name, content) in [
+            ("a.txt", "moved"),
+            ("b.log", "copied"),
+            ("c.tmp", "deleted"),
+            ("d.md", "renamed"),
+        ] {
+            assert_eq!(
+                std::fs::read_to_string(source_path.join(name)).unwrap(),
+                content
+            );
+        }
+        assert!(!dest.join("a.txt").exists());
+        assert!(!dest.join("b.log").exists());
+        assert!(!source_path.join("renamed.md").exists());
+        assert!(rollback_last_run(&journal_path, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sort_files_delete_without_journal_removes_file() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("c.tmp");
+        create_test_file(&file, "deleted").unwrap();
+        let rules_file = RulesFile::from_yaml_str(
+            "rules:\n  - {id: delete, name: delete, enabled: true, priority: 1, when: {extensions: [tmp]}, then: [{action: delete}]}\n",
+        )
+        .unwrap();
+
+        let results = sort_files(
+            std::slice::from_ref(&file),
+            temp_dir.path(),
+            &rules_file,
+            false,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+
+        assert_eq!(results[0].action, "delete");
+        assert!(!file.exists());
+        // Nothing is kept anywhere without a journal
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_sort_files_dry_run_verify() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+
+        let options = SortOptions {
+            dry_run_verify: true,
+            ..Default::default()
+        };
+        let results = sort_files_with_options(
+            &files,
+            &source_path,
+            &rules_file,
+            false,
+            &options,
+            None::<fn(usize, usize)>,
+        )
+        .expect("dry run and real run should agree");
+        assert!(results.iter().any(|r| r.action == "move"));
+        assert!(
+            results
+                .iter()
+                .all(|r| r.action != "move" || r.new_path.exists())
+        );
+    }
+
+    #[test]
+    fn test_sort_files_verified() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+
+        let (results, failures) = sort_files_verified(
+            &files,
+            &source_path,
+            &rules_file,
+            &SortOptions::default(),
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files_verified should succeed");
+
+        assert_eq!(results.len(), files.len());
+        assert!(failures.is_empty());
+    }
+
+    #[test]
+    fn test_compare_results() {
+        let result = |path: &str, action: &str, new_path: &str| MatchResult {
+            file_name: "a.txt".to_string(),
+            action: action.to_string(),
+            matched_rule_id: "rule".to_string(),
+            current_path: path.into(),
+            new_path: new_path.into(),
+            bytes_affected: 0,
+        };
+        let expected = [
+            result("/src/a.txt", "move", "/dest/a.txt"),
+            result("/src/b.txt", "move", "/dest/b.txt"),
+            result("/src/c.txt", "copy", "/backup/c.txt"),
+        ];
+        let actual = [
+            result("/src/a.txt", "move", "/dest/a.txt"),
+            result("/src/b.txt", "move", "/dest/B.txt"),
+        ];
+
+        let mut failures = compare_results(&expected, &actual);
+        failures.sort_by(|a, b| a.file.cmp(&b.file));
+        assert_eq!(
+            failures,
+            [
+                VerificationFailure {
+                    file: "/src/b.txt".into(),
+                    action: "move".to_string(),
+                    expected: Some("/dest/b.txt".into()),
+                    actual: Some("/dest/B.txt".into()),
+                },
+                VerificationFailure {
+                    file: "/src/c.txt".into(),
+                    action: "copy".to_string(),
+                    expected: Some("/backup/c.txt".into()),
+                    actual: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prepare_sort_stats() {
+        let temp_dir = tempdir().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        create_dir_all(&source_dir).unwrap();
+        create_test_files(&source_dir);
+        create_test_file(&source_dir.join("notes.TXT"), "more text").unwrap();
+        let mut rules_file = create_test_rules(&temp_dir.path().join("dest"));
+        rules_file.rules[2].enabled = false;
+
+        let prepared = prepare_sort(&source_dir, rules_file, &SortOptions::default()).unwrap();
+        let stats = &prepared.stats;
+
+        assert_eq!(prepared.files.len(), 6);
+        assert_eq!(stats.file_count, 6);
+        assert_eq!(stats.extensions["txt"], 2);
+        assert_eq!(stats.extensions["log"], 1);
+        // notes.TXT fails the case-sensitive filename regex of txt_rule
+        assert_eq!(
+            stats.rule_matches,
+            [("txt_rule".to_string(), 1), ("log_rule".to_string(), 1)]
+        );
+        assert_eq!(stats.unmatched, 4);
+        assert_eq!(stats.applicable_rules(), 2);
+        assert!(source_dir.join("test1.txt").exists());
+    }
+
+    #[test]
+    fn test_collect_files_with_max_depth() {
+        let temp_dir = tempdir().unwrap();
+        let base = temp_dir.path();
+        create_dir_all(base.join("nested")).unwrap();
+        create_test_file(&base.join("top.txt"), "top").unwrap();
+        create_test_file(&base.join("nested/deep.txt"), "deep").unwrap();
+
+        let options = SortOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let files = collect_files_with_options(base, &options).unwrap();
+        assert_eq!(files, vec![base.join("top.txt")]);
+    }
+
+    #[test]
+    fn test_sort_stats_from_results() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().to_path_buf();
+        let files = create_test_files(&source_path);
+        let rules_file = create_test_rules(&source_path);
+
+        let results = sort_files(
+            &files,
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files should succeed");
+        let stats = SortStats::from_results(&results);
+
+        // test1.txt and test3.data are moved, test2.log is copied
+        let size = |name: &str| std::fs::metadata(source_path.join(name)).unwrap().len();
+        assert_eq!(stats.bytes_moved, size("test1.txt") + size("test3.data"));
+        assert_eq!(stats.bytes_copied, size("test2.log"));
+        assert_eq!(stats.bytes_deleted, 0);
+    }
+
+    #[test]
+    fn test_match_result_csv_round_trip() {
+        let result = MatchResult {
+            file_name: "report.pdf".to_string(),
+            action: "move".to_string(),
+            matched_rule_id: "pdf_rule".to_string(),
+            current_path: "/source/report.pdf".into(),
+            new_path: "/dest/report.pdf".into(),
+            bytes_affected: 0,
+        };
+
+        let row = result.to_csv_row();
+        assert_eq!(row[0], "report.pdf");
+        assert_eq!(row[4], "/dest/report.pdf");
+
+        let fields: Vec<&str> = row.iter().map(String::as_str).collect();
+        assert_eq!(MatchResult::from_csv_row(&fields).unwrap(), result);
+
+        assert!(matches!(
+            MatchResult::from_csv_row(&fields[..4]),
+            Err(TookaError::InvalidRule(_))
+        ));
+    }
+
+    #[test]
+    fn test_collect_files_deduplicated() {
+        let temp_dir = tempdir().unwrap();
+        let base = temp_dir.path();
+        create_dir_all(base.join("nested")).unwrap();
+        create_test_file(&base.join("a.txt"), "same content").unwrap();
+        create_test_file(&base.join("nested/b.txt"), "same content").unwrap();
+        create_test_file(&base.join("nested/c.txt"), "same content").unwrap();
+        create_test_file(&base.join("unique.txt"), "other content").unwrap();
+
+        let (unique, duplicates) = collect_files_deduplicated(base).unwrap();
+
+        assert_eq!(unique, vec![base.join("a.txt"), base.join("unique.txt")]);
+        assert_eq!(
+            duplicates,
+            vec![
+                (base.join("a.txt"), base.join("nested/b.txt")),
+                (base.join("a.txt"), base.join("nested/c.txt")),
+            ]
+        );
+    }
+
     #[test]
     fn test_sort_files_empty_file_list() {
         let temp_dir = tempdir().unwrap();
@@ -453,12 +1600,152 @@ mod tests {
         let rules_file = create_test_rules(&source_path);
 
         // Sort empty file list
-        let results = sort_files(&[], &source_path, &rules_file, true, None::<fn()>)
-            .expect("sort_files should succeed with empty list");
+        let results = sort_files(
+            &[],
+            &source_path,
+            &rules_file,
+            true,
+            None::<fn(usize, usize)>,
+        )
+        .expect("sort_files should succeed with empty list");
 
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_sort_files_multi_preserves_structure_per_source() {
+        let temp_dir = tempdir().unwrap();
+        let downloads = temp_dir.path().join("Downloads");
+        let desktop = temp_dir.path().join("Desktop");
+        let dest = temp_dir.path().join("sorted");
+        create_dir_all(downloads.join("nested")).unwrap();
+        create_dir_all(&desktop).unwrap();
+        create_test_file(&downloads.join("nested/a.txt"), "a").unwrap();
+        create_test_file(&desktop.join("b.txt"), "b").unwrap();
+
+        let mut rules_file = create_test_rules(&dest);
+        rules_file.rules.truncate(1);
+        rules_file.rules[0].then = vec![Action::Move(MoveAction {
+            to: dest.to_string_lossy().to_string(),
+            preserve_structure: true,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict: ConflictStrategy::Overwrite,
+        })];
+
+        let options = SortOptions::default();
+        let sources = prepare_sort_multi(&[downloads, desktop], &rules_file, &options).unwrap();
+        let results = sort_files_multi(&sources, true, &options, None::<fn(SortProgress)>)
+            .expect("sort_files_multi should succeed");
+
+        assert_eq!(results.len(), 2);
+        let a = results.iter().find(|r| r.file_name == "a.txt").unwrap();
+        let b = results.iter().find(|r| r.file_name == "b.txt").unwrap();
+        assert_eq!(a.new_path, dest.join("nested/a.txt"));
+        assert_eq!(b.new_path, dest.join("b.txt"));
+
+        let (results, exhausted) =
+            sort_files_multi_limited(&sources, true, 1, std::time::Duration::from_secs(60))
+                .unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(exhausted);
+    }
+
+    #[test]
+    fn test_prepare_sort_multi() {
+        let temp_dir = tempdir().unwrap();
+        let downloads = temp_dir.path().join("Downloads");
+        let nested = downloads.join("nested");
+        create_dir_all(&nested).unwrap();
+        create_test_file(&downloads.join("a.txt"), "a").unwrap();
+        create_test_file(&nested.join("b.txt"), "b").unwrap();
+        let rules_file = create_test_rules(&temp_dir.path().join("sorted"));
+        let options = SortOptions::default();
+
+        // `nested` is inside `downloads`, so its file keeps the first source
+        let sources =
+            prepare_sort_multi(&[downloads.clone(), nested.clone()], &rules_file, &options)
+                .unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source_path, downloads);
+        assert_eq!(
+            sources[0].files,
+            [downloads.join("a.txt"), nested.join("b.txt")]
+        );
+        assert_eq!(sources[0].stats.file_count, 2);
+        assert!(sources[1].files.is_empty());
+        assert_eq!(sources[1].stats.file_count, 0);
+
+        assert!(
+            prepare_sort_multi(
+                &[downloads, temp_dir.path().join("missing")],
+                &rules_file,
+                &options
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sort_files_multi_from_two_sources() {
+        let downloads_dir = tempdir().unwrap();
+        let desktop_dir = tempdir().unwrap();
+        let dest_dir = tempdir().unwrap();
+        let (downloads, desktop, dest) =
+            (downloads_dir.path(), desktop_dir.path(), dest_dir.path());
+        create_dir_all(downloads.join("2024/invoices")).unwrap();
+        create_dir_all(desktop.join("notes")).unwrap();
+        create_test_file(&downloads.join("2024/invoices/a.txt"), "a").unwrap();
+        create_test_file(&desktop.join("notes/b.txt"), "b").unwrap();
+        create_test_file(&desktop.join("c.txt"), "c").unwrap();
+
+        let mut rules_file = create_test_rules(dest);
+        rules_file.rules.truncate(1);
+        rules_file.rules[0].then = vec![Action::Move(MoveAction {
+            to: dest.to_string_lossy().to_string(),
+            preserve_structure: true,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict: ConflictStrategy::Overwrite,
+        })];
+
+        let options = SortOptions::default();
+        let sources = prepare_sort_multi(
+            &[downloads.to_path_buf(), desktop.to_path_buf()],
+            &rules_file,
+            &options,
+        )
+        .unwrap();
+        let progress = std::sync::Mutex::new(Vec::new());
+        let results = sort_files_multi(
+            &sources,
+            false,
+            &options,
+            Some(|p: SortProgress| {
+                progress
+                    .lock()
+                    .unwrap()
+                    .push((p.files_processed, p.files_total));
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        // Each file keeps its structure relative to its own source
+        assert!(dest.join("2024/invoices/a.txt").exists());
+        assert!(dest.join("notes/b.txt").exists());
+        assert!(dest.join("c.txt").exists());
+        assert!(!downloads.join("2024/invoices/a.txt").exists());
+        assert!(!desktop.join("c.txt").exists());
+        assert_eq!(progress.lock().unwrap().last(), Some(&(3, 3)));
+    }
+
     #[test]
     fn test_sort_files_disabled_rules() {
         let temp_dir = tempdir().unwrap();
@@ -480,17 +1767,39 @@ mod tests {
                 filename: Some(r".*\.txt$".to_string()),
                 extensions: None,
                 path: None,
+                path_any: None,
+                exclude_path: None,
+                min_depth: None,
+                max_depth: None,
                 size_kb: None,
+                is_empty_file: None,
                 mime_type: None,
                 created_date: None,
                 modified_date: None,
+                file_age_days: None,
                 is_symlink: None,
+                hardlink_count: None,
+                permissions: None,
+                case_sensitive: None,
+                content_matches: None,
+                content_max_bytes: None,
+                content_hash: None,
                 metadata: None,
+                all: None,
+                any_of: None,
             },
             then: vec![Action::Move(MoveAction {
                 to: source_path.join("dest").to_string_lossy().to_string(),
                 preserve_structure: false,
+                preserve_depth: None,
+                allow_directory: false,
+                verify_after_move: false,
+                if_newer_than: None,
+                if_older_than: None,
+                on_conflict: ConflictStrategy::Overwrite,
             })],
+            after: None,
+            tags: None,
         }];
 
         let rules_file = RulesFile { rules };
@@ -534,17 +1843,39 @@ mod tests {
                     filename: Some(r".*\.txt$".to_string()),
                     extensions: None,
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: disabled_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
             Rule {
                 id: "enabled_rule".to_string(),
@@ -557,17 +1888,39 @@ mod tests {
                     filename: Some(r".*\.txt$".to_string()),
                     extensions: None,
                     path: None,
+                    path_any: None,
+                    exclude_path: None,
+                    min_depth: None,
+                    max_depth: None,
                     size_kb: None,
+                    is_empty_file: None,
                     mime_type: None,
                     created_date: None,
                     modified_date: None,
+                    file_age_days: None,
                     is_symlink: None,
+                    hardlink_count: None,
+                    permissions: None,
+                    case_sensitive: None,
+                    content_matches: None,
+                    content_max_bytes: None,
+                    content_hash: None,
                     metadata: None,
+                    all: None,
+                    any_of: None,
                 },
                 then: vec![Action::Move(MoveAction {
                     to: enabled_dir.to_string_lossy().to_string(),
                     preserve_structure: false,
+                    preserve_depth: None,
+                    allow_directory: false,
+                    verify_after_move: false,
+                    if_newer_than: None,
+                    if_older_than: None,
+                    on_conflict: ConflictStrategy::Overwrite,
                 })],
+                after: None,
+                tags: None,
             },
         ];
 
@@ -580,7 +1933,7 @@ mod tests {
             &source_path,
             &optimized_rules,
             true,
-            None::<fn()>,
+            None::<fn(usize, usize)>,
         )
         .expect("sort_files should succeed");
 
@@ -597,6 +1950,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "pdf")]
     fn test_pdf_generation() {
         let temp_dir = tempdir().unwrap();
         let source_path = temp_dir.path().to_path_buf();
@@ -611,7 +1965,7 @@ mod tests {
             &source_path,
             &rules_file,
             true, // dry run
-            None::<fn()>,
+            None::<fn(usize, usize)>,
         )
         .expect("sort_files should succeed");
 
@@ -620,7 +1974,12 @@ mod tests {
 
         // Generate PDF
         let pdf_path = temp_dir.path().join("test_report.pdf");
-        generate_pdf(&pdf_path, &results).expect("PDF generation should succeed");
+        generate_pdf(
+            &pdf_path,
+            &results,
+            &compute_summary(&results, &SortStats::from_results(&results)),
+        )
+        .expect("PDF generation should succeed");
 
         // Verify PDF file was created
         assert!(pdf_path.exists(), "PDF file should be created");
@@ -671,6 +2030,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "pdf")]
     fn test_pdf_generation_with_large_dataset() {
         let temp_dir = tempdir().unwrap();
         let source_path = temp_dir.path().to_path_buf();
@@ -686,6 +2046,7 @@ mod tests {
                 new_path: source_path.join("txt_files").join(format!("file{i}.txt")),
                 matched_rule_id: "txt_rule".to_string(),
                 action: "move".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -696,6 +2057,7 @@ mod tests {
                 new_path: source_path.join("log_files").join(format!("log{i}.log")),
                 matched_rule_id: "log_rule".to_string(),
                 action: "copy".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -706,6 +2068,7 @@ mod tests {
                 new_path: source_path.join("data_files").join(format!("data{i}.data")),
                 matched_rule_id: "data_rule".to_string(),
                 action: "move".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -718,6 +2081,7 @@ mod tests {
                     .join(format!("executed_{i}.exe")),
                 matched_rule_id: "execute_rule".to_string(),
                 action: "execute".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -728,13 +2092,18 @@ mod tests {
                 new_path: source_path.join(format!("unknown{i}.unknown")), // Same path for skip
                 matched_rule_id: "none".to_string(),
                 action: "skip".to_string(),
+                bytes_affected: 0,
             });
         }
 
         // Generate PDF with large dataset
         let pdf_path = temp_dir.path().join("large_test_report.pdf");
-        generate_pdf(&pdf_path, &mock_results)
-            .expect("PDF generation should succeed with large dataset");
+        generate_pdf(
+            &pdf_path,
+            &mock_results,
+            &compute_summary(&mock_results, &SortStats::from_results(&mock_results)),
+        )
+        .expect("PDF generation should succeed with large dataset");
 
         // Verify PDF file was created
         assert!(pdf_path.exists(), "Large PDF file should be created");
@@ -771,6 +2140,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "pdf")]
     fn test_pdf_generation_for_inspection() {
         // Create PDF in the project directory for easy inspection
         let pdf_path = std::path::Path::new("test_report_refactored.pdf");
@@ -789,6 +2159,7 @@ mod tests {
                     .join(format!("document_{i}.txt")),
                 matched_rule_id: "document_organization_rule".to_string(),
                 action: "move".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -801,6 +2172,7 @@ mod tests {
                     .join(format!("backup_{i}.log")),
                 matched_rule_id: "log_backup_rule".to_string(),
                 action: "copy".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -811,6 +2183,7 @@ mod tests {
                 new_path: base_path.join("temp").join(format!("temp_{i}.tmp")), // Same path for delete
                 matched_rule_id: "cleanup_rule".to_string(),
                 action: "delete".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -821,6 +2194,7 @@ mod tests {
                 new_path: base_path.join("data").join(format!("new_file_{i}.dat")),
                 matched_rule_id: "rename_rule".to_string(),
                 action: "rename".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -833,6 +2207,7 @@ mod tests {
                     .join(format!("executed_script_{i}.result")),
                 matched_rule_id: "script_execution_rule".to_string(),
                 action: "execute".to_string(),
+                bytes_affected: 0,
             });
         }
 
@@ -843,11 +2218,17 @@ mod tests {
                 new_path: base_path.join("misc").join(format!("unknown_{i}.xyz")), // Same path for skip
                 matched_rule_id: "none".to_string(),
                 action: "skip".to_string(),
+                bytes_affected: 0,
             });
         }
 
         // Generate PDF
-        generate_pdf(pdf_path, &mock_results).expect("PDF generation should succeed");
+        generate_pdf(
+            pdf_path,
+            &mock_results,
+            &compute_summary(&mock_results, &SortStats::from_results(&mock_results)),
+        )
+        .expect("PDF generation should succeed");
 
         // Verify PDF file was created
         assert!(pdf_path.exists(), "PDF file should be created");
@@ -885,6 +2266,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "pdf")]
     fn test_pdf_generation_with_long_paths() {
         // Create PDF with extremely long paths to test wrapping
         let pdf_path = std::path::Path::new("test_report_long_paths.pdf");
@@ -902,6 +2284,7 @@ mod tests {
                 ),
                 matched_rule_id: "document_organization_with_very_long_rule_name".to_string(),
                 action: "move".to_string(),
+                bytes_affected: 0,
             },
             MatchResult {
                 file_name: "short.log".to_string(),
@@ -911,6 +2294,7 @@ mod tests {
                 new_path: std::path::PathBuf::from("/backup/logs/short.log"),
                 matched_rule_id: "log_backup".to_string(),
                 action: "copy".to_string(),
+                bytes_affected: 0,
             },
             MatchResult {
                 file_name: "file_in_normal_path.dat".to_string(),
@@ -918,11 +2302,17 @@ mod tests {
                 new_path: std::path::PathBuf::from("/home/user/archived/file_in_normal_path.dat"),
                 matched_rule_id: "normal_rule".to_string(),
                 action: "move".to_string(),
+                bytes_affected: 0,
             },
         ];
 
         // Generate PDF
-        generate_pdf(pdf_path, &mock_results).expect("PDF generation should succeed");
+        generate_pdf(
+            pdf_path,
+            &mock_results,
+            &compute_summary(&mock_results, &SortStats::from_results(&mock_results)),
+        )
+        .expect("PDF generation should succeed");
 
         // Verify PDF file was created
         assert!(pdf_path.exists(), "PDF file should be created");
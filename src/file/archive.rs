@@ -0,0 +1,403 @@
+//! Writing of zip and tar.gz archives for the `archive` action.
+//!
+//! Files are stored without compression: zip entries use the `stored` method
+//! and the gzip stream of tar.gz archives is made of uncompressed deflate
+//! blocks. Both formats remain readable by standard tools. Archives are
+//! written to a temporary file next to the destination and renamed into place.
+
+use crate::{core::error::TookaError, rules::rule::ArchiveFormat};
+use chrono::{DateTime, Datelike, Local, Timelike};
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// A file to add to an archive and the name it is stored under
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveEntry {
+    /// Path of the file on disk
+    pub(crate) path: PathBuf,
+    /// `/`-separated name inside the archive
+    pub(crate) name: String,
+}
+
+/// Writes `entries` to a new archive at `destination`, replacing any existing
+/// file, and returns the number of bytes of file content archived.
+///
+/// # Errors
+/// Returns a `TookaError` if a file can't be read, the archive can't be written,
+/// or an entry exceeds the limits of the format (4 GiB per file and 65,535 files
+/// for zip, 100 + 155 bytes of name and 8 GiB per file for tar).
+pub(crate) fn write_archive(
+    destination: &Path,
+    format: ArchiveFormat,
+    entries: &[ArchiveEntry],
+) -> Result<u64, TookaError> {
+    let parent = destination.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+
+    let bytes = match format {
+        ArchiveFormat::Zip => {
+            let mut out = io::BufWriter::new(temp.as_file_mut());
+            let bytes = write_zip(&mut out, entries)?;
+            out.flush()?;
+            bytes
+        }
+        ArchiveFormat::TarGz => {
+            let mut gzip = GzipWriter::new(io::BufWriter::new(temp.as_file_mut()))?;
+            let bytes = write_tar(&mut gzip, entries)?;
+            gzip.finish()?.flush()?;
+            bytes
+        }
+    };
+
+    temp.as_file().sync_all()?;
+    temp.persist(destination)
+        .map_err(|e| TookaError::Io(e.error))?;
+    Ok(bytes)
+}
+
+fn too_large(what: &str) -> TookaError {
+    TookaError::FileOperationError(format!("Archive limit exceeded: {what}"))
+}
+
+// === Zip ===
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+/// Version 2.0, the minimum for stored files with directories in their names
+const ZIP_VERSION: u16 = 20;
+/// General purpose flag marking names as UTF-8
+const ZIP_UTF8_FLAG: u16 = 1 << 11;
+
+/// Central directory record of an entry written to a zip archive
+struct ZipRecord {
+    name: String,
+    crc: u32,
+    size: u32,
+    time: u16,
+    date: u16,
+    mode: u32,
+    offset: u32,
+}
+
+fn write_zip<W: Write + Seek>(out: &mut W, entries: &[ArchiveEntry]) -> Result<u64, TookaError> {
+    let count = u16::try_from(entries.len()).map_err(|_| too_large("more than 65,535 files"))?;
+    let mut records = Vec::with_capacity(entries.len());
+    let mut total = 0;
+
+    for entry in entries {
+        let mut file = fs::File::open(&entry.path)?;
+        let metadata = file.metadata()?;
+        let (time, date) = dos_date_time(metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+        let offset = u32::try_from(out.stream_position()?)
+            .map_err(|_| too_large("zip archive larger than 4 GiB"))?;
+        let name_len = u16::try_from(entry.name.len())
+            .map_err(|_| too_large(&format!("name of '{}'", entry.name)))?;
+
+        // CRC and sizes are patched in once the data has been written
+        out.write_all(&ZIP_LOCAL_HEADER.to_le_bytes())?;
+        out.write_all(&ZIP_VERSION.to_le_bytes())?;
+        out.write_all(&ZIP_UTF8_FLAG.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?; // stored
+        out.write_all(&time.to_le_bytes())?;
+        out.write_all(&date.to_le_bytes())?;
+        let sizes_at = out.stream_position()?;
+        out.write_all(&[0; 12])?;
+        out.write_all(&name_len.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(entry.name.as_bytes())?;
+
+        let (crc, size) = copy_with_crc(&mut file, out)?;
+        let size = u32::try_from(size)
+            .map_err(|_| too_large(&format!("'{}' is larger than 4 GiB", entry.name)))?;
+        let end = out.stream_position()?;
+        out.seek(SeekFrom::Start(sizes_at))?;
+        out.write_all(&crc.to_le_bytes())?;
+        out.write_all(&size.to_le_bytes())?;
+        out.write_all(&size.to_le_bytes())?;
+        out.seek(SeekFrom::Start(end))?;
+
+        total += u64::from(size);
+        records.push(ZipRecord {
+            name: entry.name.clone(),
+            crc,
+            size,
+            time,
+            date,
+            mode: file_mode(&metadata),
+            offset,
+        });
+    }
+
+    let directory_start = out.stream_position()?;
+    for record in &records {
+        out.write_all(&ZIP_CENTRAL_HEADER.to_le_bytes())?;
+        // Made by Unix, so the external attributes hold the file mode
+        out.write_all(&(3 << 8 | ZIP_VERSION).to_le_bytes())?;
+        out.write_all(&ZIP_VERSION.to_le_bytes())?;
+        out.write_all(&ZIP_UTF8_FLAG.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(&record.time.to_le_bytes())?;
+        out.write_all(&record.date.to_le_bytes())?;
+        out.write_all(&record.crc.to_le_bytes())?;
+        out.write_all(&record.size.to_le_bytes())?;
+        out.write_all(&record.size.to_le_bytes())?;
+        out.write_all(&(record.name.len() as u16).to_le_bytes())?;
+        out.write_all(&[0; 8])?; // extra field, comment, disk number, internal attributes
+        out.write_all(&(record.mode << 16).to_le_bytes())?;
+        out.write_all(&record.offset.to_le_bytes())?;
+        out.write_all(record.name.as_bytes())?;
+    }
+    let directory_end = out.stream_position()?;
+    let too_big = || too_large("zip archive larger than 4 GiB");
+    let directory_size = u32::try_from(directory_end - directory_start).map_err(|_| too_big())?;
+    let directory_start = u32::try_from(directory_start).map_err(|_| too_big())?;
+
+    out.write_all(&ZIP_END_OF_CENTRAL_DIR.to_le_bytes())?;
+    out.write_all(&[0; 4])?; // disk numbers
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&directory_size.to_le_bytes())?;
+    out.write_all(&directory_start.to_le_bytes())?;
+    out.write_all(&0u16.to_le_bytes())?;
+    Ok(total)
+}
+
+/// Converts a time to the MS-DOS time and date fields of zip headers, in
+/// local time and clamped to the 1980-2107 range they can represent
+fn dos_date_time(time: SystemTime) -> (u16, u16) {
+    let time: DateTime<Local> = time.into();
+    if time.year() < 1980 {
+        return (0, 1 << 5 | 1);
+    }
+    let year = (time.year() - 1980).min(127) as u16;
+    let date = year << 9 | (time.month() as u16) << 5 | time.day() as u16;
+    let time =
+        (time.hour() as u16) << 11 | (time.minute() as u16) << 5 | (time.second() / 2) as u16;
+    (time, date)
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o100_444
+    } else {
+        0o100_644
+    }
+}
+
+// === Tar ===
+
+const TAR_BLOCK: usize = 512;
+
+fn write_tar<W: Write>(out: &mut W, entries: &[ArchiveEntry]) -> Result<u64, TookaError> {
+    let mut total = 0;
+    for entry in entries {
+        let mut file = fs::File::open(&entry.path)?;
+        let metadata = file.metadata()?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        out.write_all(&tar_header(
+            &entry.name,
+            metadata.len(),
+            file_mode(&metadata) & 0o7777,
+            mtime,
+        )?)?;
+
+        let size = io::copy(&mut file, out)?;
+        if size != metadata.len() {
+            return Err(TookaError::FileOperationError(format!(
+                "'{}' changed size while being archived",
+                entry.path.display()
+            )));
+        }
+        let padding = (TAR_BLOCK - size as usize % TAR_BLOCK) % TAR_BLOCK;
+        out.write_all(&[0; TAR_BLOCK][..padding])?;
+        total += size;
+    }
+    // The end of the archive is marked by two empty blocks
+    out.write_all(&[0; 2 * TAR_BLOCK])?;
+    Ok(total)
+}
+
+/// Builds the ustar header of a regular file
+fn tar_header(name: &str, size: u64, mode: u32, mtime: u64) -> Result<[u8; TAR_BLOCK], TookaError> {
+    let (prefix, name) = split_tar_name(name)
+        .ok_or_else(|| too_large(&format!("name '{name}' is too long for tar")))?;
+    if size >= 1 << 33 {
+        return Err(too_large(&format!("'{name}' is larger than 8 GiB")));
+    }
+
+    let mut header = [0u8; TAR_BLOCK];
+    let mut put = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    put(0, name.as_bytes());
+    put(100, format!("{mode:07o}\0").as_bytes());
+    put(108, b"0000000\0"); // uid
+    put(116, b"0000000\0"); // gid
+    put(124, format!("{size:011o}\0").as_bytes());
+    put(
+        136,
+        format!("{:011o}\0", mtime.min(0o77_777_777_777)).as_bytes(),
+    );
+    put(148, b"        "); // checksum, counted as spaces
+    put(156, b"0"); // regular file
+    put(257, b"ustar\0");
+    put(263, b"00");
+    put(345, prefix.as_bytes());
+
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+    Ok(header)
+}
+
+/// Splits a name into the prefix and name fields of a ustar header, at a `/`
+/// if it doesn't fit into the 100-byte name field
+fn split_tar_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+    name.match_indices('/')
+        .map(|(at, _)| (&name[..at], &name[at + 1..]))
+        .find(|(prefix, rest)| prefix.len() <= 155 && rest.len() <= 100 && !rest.is_empty())
+}
+
+// === Gzip ===
+
+/// Largest amount of data in a stored deflate block
+const STORED_BLOCK_MAX: usize = u16::MAX as usize;
+
+/// Writes a gzip stream of uncompressed deflate blocks
+struct GzipWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    crc: Crc32,
+    size: u32,
+}
+
+impl<W: Write> GzipWriter<W> {
+    fn new(mut inner: W) -> io::Result<Self> {
+        // Magic, deflate, no flags, no mtime, no extra flags, Unix
+        inner.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3])?;
+        Ok(Self {
+            inner,
+            buffer: Vec::with_capacity(STORED_BLOCK_MAX),
+            crc: Crc32::new(),
+            size: 0,
+        })
+    }
+
+    fn write_block(&mut self, last: bool) -> io::Result<()> {
+        let len = self.buffer.len() as u16;
+        self.inner.write_all(&[u8::from(last)])?;
+        self.inner.write_all(&len.to_le_bytes())?;
+        self.inner.write_all(&(!len).to_le_bytes())?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes the final block and the trailer, returning the inner writer
+    fn finish(mut self) -> io::Result<W> {
+        self.write_block(true)?;
+        self.inner.write_all(&self.crc.finish().to_le_bytes())?;
+        self.inner.write_all(&self.size.to_le_bytes())?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for GzipWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let len = data.len().min(STORED_BLOCK_MAX - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..len]);
+        self.crc.update(&data[..len]);
+        // The size is stored modulo 2^32
+        self.size = self.size.wrapping_add(len as u32);
+        if self.buffer.len() == STORED_BLOCK_MAX {
+            self.write_block(false)?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// === CRC-32 ===
+
+/// CRC-32 (IEEE) checksum, as used by zip and gzip
+pub(crate) struct Crc32 {
+    value: u32,
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 1 {
+                0xEDB8_8320 ^ (value >> 1)
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+};
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self { value: !0 }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.value =
+                CRC32_TABLE[((self.value ^ u32::from(byte)) & 0xFF) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        !self.value
+    }
+}
+
+/// Copies `from` into `to`, returning the CRC-32 and length of the data
+fn copy_with_crc<R: Read, W: Write>(from: &mut R, to: &mut W) -> io::Result<(u32, u64)> {
+    let mut crc = Crc32::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = match from.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        crc.update(&buffer[..read]);
+        to.write_all(&buffer[..read])?;
+        size += read as u64;
+    }
+    Ok((crc.finish(), size))
+}
@@ -0,0 +1,105 @@
+use std::fs;
+
+use crate::file::archive::{ArchiveEntry, Crc32, write_archive};
+use crate::rules::rule::ArchiveFormat;
+use tempfile::tempdir;
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[test]
+fn test_crc32() {
+    let mut crc = Crc32::new();
+    crc.update(b"hello");
+    assert_eq!(crc.finish(), 0x3610_a686);
+
+    let mut split = Crc32::new();
+    split.update(b"hel");
+    split.update(b"lo");
+    assert_eq!(split.finish(), crc.finish());
+}
+
+#[test]
+fn test_write_zip_archive() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "hello").unwrap();
+    fs::write(dir.path().join("b.txt"), "world!").unwrap();
+    let entries = [
+        ArchiveEntry {
+            path: dir.path().join("a.txt"),
+            name: "a.txt".into(),
+        },
+        ArchiveEntry {
+            path: dir.path().join("b.txt"),
+            name: "docs/b.txt".into(),
+        },
+    ];
+
+    let destination = dir.path().join("out/files.zip");
+    let bytes = write_archive(&destination, ArchiveFormat::Zip, &entries).unwrap();
+    assert_eq!(bytes, 11);
+
+    let zip = fs::read(&destination).unwrap();
+    assert_eq!(read_u32(&zip, 0), 0x0403_4b50);
+    // Stored method, CRC and sizes of the first entry
+    assert_eq!(read_u16(&zip, 8), 0);
+    assert_eq!(read_u32(&zip, 14), 0x3610_a686);
+    assert_eq!(read_u32(&zip, 18), 5);
+    assert_eq!(read_u32(&zip, 22), 5);
+    assert_eq!(&zip[30..35], b"a.txt");
+    assert_eq!(&zip[35..40], b"hello");
+    assert!(find(&zip, b"docs/b.txt").is_some());
+
+    let end = zip.len() - 22;
+    assert_eq!(read_u32(&zip, end), 0x0605_4b50);
+    assert_eq!(read_u16(&zip, end + 10), 2);
+    let central = read_u32(&zip, end + 16) as usize;
+    assert_eq!(read_u32(&zip, central), 0x0201_4b50);
+}
+
+#[test]
+fn test_write_tar_gz_archive() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "hello").unwrap();
+    let entries = [ArchiveEntry {
+        path: dir.path().join("a.txt"),
+        name: "nested/a.txt".into(),
+    }];
+
+    let destination = dir.path().join("files.tar.gz");
+    let bytes = write_archive(&destination, ArchiveFormat::TarGz, &entries).unwrap();
+    assert_eq!(bytes, 5);
+
+    let gz = fs::read(&destination).unwrap();
+    assert_eq!(&gz[..3], &[0x1f, 0x8b, 8]);
+    // Header, one content block and two end blocks, stored uncompressed
+    let tar_len = 4 * 512;
+    assert_eq!(read_u32(&gz, gz.len() - 4), tar_len);
+    let header = find(&gz, b"nested/a.txt").unwrap();
+    assert_eq!(&gz[header + 257..header + 262], b"ustar");
+    assert_eq!(&gz[header + 512..header + 517], b"hello");
+}
+
+#[test]
+fn test_write_archive_missing_file() {
+    let dir = tempdir().unwrap();
+    let entries = [ArchiveEntry {
+        path: dir.path().join("missing.txt"),
+        name: "missing.txt".into(),
+    }];
+
+    let destination = dir.path().join("files.zip");
+    assert!(write_archive(&destination, ArchiveFormat::Zip, &entries).is_err());
+    assert!(!destination.exists());
+}
@@ -0,0 +1,1182 @@
+//! Compression of single files for the `compress` action.
+//!
+//! Gzip output is compressed with LZ77 and the fixed Huffman codes of deflate.
+//! Zstandard output uses the same LZ77 matching, with literals stored as they
+//! are and sequences coded with the predefined FSE tables. Bzip2 output goes
+//! through the usual Burrows-Wheeler transform, move-to-front and Huffman
+//! coding. Files are written to a temporary file next to the destination and renamed
+//! into place, with the permissions of the source file.
+
+use crate::{core::error::TookaError, file::archive::Crc32, rules::rule::CompressionFormat};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// Compresses `source` into a new file at `destination`, replacing any
+/// existing file, and returns the size of `source` in bytes.
+///
+/// # Errors
+/// Returns a `TookaError` if `source` can't be read or `destination` can't be
+/// written.
+pub(crate) fn compress_file(
+    source: &Path,
+    destination: &Path,
+    format: CompressionFormat,
+) -> Result<u64, TookaError> {
+    let parent = destination.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+    let mut input = File::open(source)?;
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+
+    let mut out = BufWriter::new(temp.as_file_mut());
+    let size = match format {
+        CompressionFormat::Gzip => {
+            let mut gzip = GzipEncoder::new(&mut out)?;
+            let size = io::copy(&mut input, &mut gzip)?;
+            gzip.finish()?;
+            size
+        }
+        CompressionFormat::Zstd => {
+            let mut zstd = ZstdEncoder::new(&mut out)?;
+            let size = io::copy(&mut input, &mut zstd)?;
+            zstd.finish()?;
+            size
+        }
+        CompressionFormat::Bzip2 => {
+            let mut bzip2 = Bzip2Encoder::new(&mut out)?;
+            let size = io::copy(&mut input, &mut bzip2)?;
+            bzip2.finish()?;
+            size
+        }
+    };
+    out.flush()?;
+    drop(out);
+    // Temporary files are only readable by their owner, keep the mode of the source
+    temp.as_file()
+        .set_permissions(input.metadata()?.permissions())?;
+
+    temp.persist(destination)
+        .map_err(|e| TookaError::Io(e.error))?;
+    Ok(size)
+}
+
+// === Gzip ===
+
+/// Writes a gzip stream of deflate blocks with fixed Huffman codes
+struct GzipEncoder<W: Write> {
+    deflate: Deflater<W>,
+    crc: Crc32,
+    size: u32,
+}
+
+impl<W: Write> GzipEncoder<W> {
+    fn new(mut inner: W) -> io::Result<Self> {
+        // Magic, deflate, no flags, no mtime, no extra flags, Unix
+        inner.write_all(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 3])?;
+        Ok(Self {
+            deflate: Deflater::new(inner),
+            crc: Crc32::new(),
+            size: 0,
+        })
+    }
+
+    /// Writes the remaining data and the trailer
+    fn finish(self) -> io::Result<W> {
+        let mut inner = self.deflate.finish()?;
+        inner.write_all(&self.crc.finish().to_le_bytes())?;
+        inner.write_all(&self.size.to_le_bytes())?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for GzipEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.crc.update(data);
+        // The size is stored modulo 2^32
+        #[allow(clippy::cast_possible_truncation)]
+        let len = data.len() as u32;
+        self.size = self.size.wrapping_add(len);
+        self.deflate.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.deflate.out.inner.flush()
+    }
+}
+
+/// Size of the deflate window
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// Input needed after a position to find the longest match there
+const LOOKAHEAD: usize = MAX_MATCH + MIN_MATCH;
+/// Amount of buffered input that is encoded as one block
+const BLOCK_INPUT: usize = 64 * 1024;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// LZ77 compressor writing a raw deflate stream (RFC 1951)
+struct Deflater<W: Write> {
+    out: BitWriter<W>,
+    matches: MatchFinder,
+}
+
+impl<W: Write> Deflater<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            out: BitWriter::new(inner),
+            matches: MatchFinder::new(WINDOW_SIZE),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.matches.push(data);
+        if self.matches.pending() >= BLOCK_INPUT + LOOKAHEAD {
+            self.encode_block(false)?;
+        }
+        Ok(data.len())
+    }
+
+    /// Encodes the pending input and an empty final block
+    fn finish(mut self) -> io::Result<W> {
+        self.encode_block(true)?;
+        self.out.write_bits(1, 1)?;
+        self.out.write_bits(1, 2)?;
+        self.out.write_code(0, 7)?; // End of block
+        self.out.finish()
+    }
+
+    /// Encodes pending input as one block with fixed Huffman codes. Unless
+    /// `last` is set, input too close to the end to find a full match is kept.
+    fn encode_block(&mut self, last: bool) -> io::Result<()> {
+        let pending = if last {
+            self.matches.pending()
+        } else {
+            self.matches.pending().saturating_sub(LOOKAHEAD)
+        };
+        if pending == 0 {
+            return Ok(());
+        }
+
+        self.out.write_bits(0, 1)?; // Not the final block
+        self.out.write_bits(1, 2)?; // Fixed Huffman codes
+        let end = self.matches.pos + pending;
+        while self.matches.pos < end {
+            let (length, distance) = self.matches.longest_match(MAX_MATCH);
+            if length >= MIN_MATCH {
+                self.write_match(length, distance)?;
+                self.matches.advance(length);
+            } else {
+                self.write_literal(u16::from(self.matches.current()))?;
+                self.matches.advance(1);
+            }
+        }
+        self.write_literal(256)?; // End of block
+        self.matches.slide();
+        Ok(())
+    }
+
+    fn write_literal(&mut self, value: u16) -> io::Result<()> {
+        match value {
+            0..=143 => self.out.write_code(0x30 + value, 8),
+            144..=255 => self.out.write_code(0x190 + value - 144, 9),
+            256..=279 => self.out.write_code(value - 256, 7),
+            _ => self.out.write_code(0xc0 + value - 280, 8),
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_match(&mut self, length: usize, distance: usize) -> io::Result<()> {
+        let code = LENGTH_BASE.partition_point(|&base| usize::from(base) <= length) - 1;
+        self.write_literal(257 + code as u16)?;
+        self.out.write_bits(
+            (length - usize::from(LENGTH_BASE[code])) as u32,
+            LENGTH_EXTRA[code],
+        )?;
+
+        let code = DISTANCE_BASE.partition_point(|&base| usize::from(base) <= distance) - 1;
+        self.out.write_code(code as u16, 5)?;
+        self.out.write_bits(
+            (distance - usize::from(DISTANCE_BASE[code])) as u32,
+            DISTANCE_EXTRA[code],
+        )
+    }
+}
+
+// === LZ77 matching ===
+
+const HASH_BITS: u32 = 15;
+/// Bytes hashed to find match candidates, the shortest match that can be found
+const HASH_LENGTH: usize = 3;
+/// Candidates compared per position, trading speed for compression
+const MAX_CHAIN: usize = 64;
+
+/// Finds earlier occurrences of the input at the current position, using
+/// hash chains over a sliding window. Shared by the gzip and zstd encoders.
+struct MatchFinder {
+    /// Largest distance of a match
+    window: usize,
+    /// Up to a window of already encoded input, followed by pending input
+    data: Vec<u8>,
+    /// Stream position of `data[0]`
+    base: usize,
+    /// Index in `data` of the next byte to encode
+    pos: usize,
+    /// Most recent stream position + 1 of each hash of three bytes, 0 if none
+    head: Vec<usize>,
+    /// Previous stream position + 1 with the same hash, by position in the window
+    prev: Vec<usize>,
+}
+
+impl MatchFinder {
+    fn new(window: usize) -> Self {
+        Self {
+            window,
+            data: Vec::new(),
+            base: 0,
+            pos: 0,
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; window],
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    /// Number of bytes not encoded yet
+    fn pending(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Byte at the current position
+    fn current(&self) -> u8 {
+        self.data[self.pos]
+    }
+
+    fn hash(&self, index: usize) -> Option<usize> {
+        let bytes = self.data.get(index..index + HASH_LENGTH)?;
+        let value = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        Some((value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize)
+    }
+
+    /// Moves past `count` bytes, recording each position in the hash chains
+    fn advance(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(hash) = self.hash(self.pos) {
+                let position = self.base + self.pos;
+                self.prev[position % self.window] = self.head[hash];
+                self.head[hash] = position + 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Returns the length and distance of the longest earlier match of the
+    /// input at the current position, at most `max_length` bytes long, or a
+    /// length of 0
+    fn longest_match(&self, max_length: usize) -> (usize, usize) {
+        let Some(hash) = self.hash(self.pos) else {
+            return (0, 0);
+        };
+        let position = self.base + self.pos;
+        let max_length = max_length.min(self.pending());
+        let (mut best_length, mut best_distance) = (0, 0);
+
+        let mut candidate = self.head[hash];
+        for _ in 0..MAX_CHAIN {
+            // Positions are stored + 1, 0 ends the chain
+            let Some(candidate_position) = candidate.checked_sub(1) else {
+                break;
+            };
+            let distance = position - candidate_position;
+            if distance > self.window || candidate_position < self.base {
+                break;
+            }
+            let start = candidate_position - self.base;
+            let length = self.data[start..]
+                .iter()
+                .zip(&self.data[self.pos..self.pos + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best_length {
+                (best_length, best_distance) = (length, distance);
+                if length == max_length {
+                    break;
+                }
+            }
+            let next = self.prev[candidate_position % self.window];
+            // An older position that was overwritten in `prev` ends the chain
+            if next >= candidate {
+                break;
+            }
+            candidate = next;
+        }
+        (best_length, best_distance)
+    }
+
+    /// Drops encoded input that is out of reach of later matches
+    fn slide(&mut self) {
+        if self.pos > self.window {
+            let drop = self.pos - self.window;
+            self.data.drain(..drop);
+            self.base += drop;
+            self.pos -= drop;
+        }
+    }
+}
+
+// === Zstandard ===
+
+const ZSTD_MAGIC: u32 = 0xfd2f_b528;
+/// Window of 128 KiB, as large as a block
+const ZSTD_WINDOW_LOG: u8 = 17;
+const ZSTD_WINDOW_SIZE: usize = 1 << ZSTD_WINDOW_LOG;
+/// Largest amount of input in one block
+const ZSTD_BLOCK_SIZE: usize = 128 * 1024;
+/// Shorter matches cost more as a sequence than as literals
+const ZSTD_MIN_MATCH: usize = 4;
+const ZSTD_MAX_MATCH: usize = 64 * 1024;
+
+/// Block types of the block header
+const ZSTD_RAW_BLOCK: u32 = 0;
+const ZSTD_COMPRESSED_BLOCK: u32 = 2;
+
+/// Baselines and extra bits of the literals length codes
+const LITERALS_LENGTH_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LITERALS_LENGTH_EXTRA: [u8; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+/// Baselines and extra bits of the match length codes
+const MATCH_LENGTH_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const MATCH_LENGTH_EXTRA: [u8; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+
+/// Predefined FSE distributions of the literals length, match length and
+/// offset codes (RFC 8878, section 3.1.1.3.2.2); -1 stands for a probability
+/// below 1
+const LITERALS_LENGTH_DISTRIBUTION: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const MATCH_LENGTH_DISTRIBUTION: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OFFSET_DISTRIBUTION: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// A match and the literals before it
+struct Sequence {
+    literals_length: usize,
+    match_length: usize,
+    distance: usize,
+}
+
+/// Writes a zstd frame of blocks holding LZ77 sequences (RFC 8878)
+struct ZstdEncoder<W: Write> {
+    inner: W,
+    matches: MatchFinder,
+    literals_lengths: FseTable,
+    match_lengths: FseTable,
+    offsets: FseTable,
+}
+
+impl<W: Write> ZstdEncoder<W> {
+    fn new(mut inner: W) -> io::Result<Self> {
+        inner.write_all(&ZSTD_MAGIC.to_le_bytes())?;
+        // No content size, checksum or dictionary; then the window descriptor
+        inner.write_all(&[0, (ZSTD_WINDOW_LOG - 10) << 3])?;
+        Ok(Self {
+            inner,
+            matches: MatchFinder::new(ZSTD_WINDOW_SIZE),
+            literals_lengths: FseTable::new(&LITERALS_LENGTH_DISTRIBUTION, 6),
+            match_lengths: FseTable::new(&MATCH_LENGTH_DISTRIBUTION, 6),
+            offsets: FseTable::new(&OFFSET_DISTRIBUTION, 5),
+        })
+    }
+
+    /// Encodes the pending input, ending with the last block
+    fn finish(mut self) -> io::Result<W> {
+        while self.matches.pending() > ZSTD_BLOCK_SIZE {
+            self.encode_block(false)?;
+        }
+        self.encode_block(true)?;
+        Ok(self.inner)
+    }
+
+    /// Encodes up to a block of pending input, as a compressed block or, if
+    /// that doesn't save space, as a raw one
+    fn encode_block(&mut self, last: bool) -> io::Result<()> {
+        let start = self.matches.pos;
+        let end = start + self.matches.pending().min(ZSTD_BLOCK_SIZE);
+        let mut literals = Vec::new();
+        let mut sequences = Vec::new();
+        let mut literals_start = start;
+        while self.matches.pos < end {
+            let pos = self.matches.pos;
+            let (length, distance) = self.matches.longest_match(ZSTD_MAX_MATCH.min(end - pos));
+            if length >= ZSTD_MIN_MATCH {
+                literals.extend_from_slice(&self.matches.data[literals_start..pos]);
+                sequences.push(Sequence {
+                    literals_length: pos - literals_start,
+                    match_length: length,
+                    distance,
+                });
+                self.matches.advance(length);
+                literals_start = self.matches.pos;
+            } else {
+                self.matches.advance(1);
+            }
+        }
+        literals.extend_from_slice(&self.matches.data[literals_start..end]);
+
+        let block = self.compressed_block(&literals, &sequences)?;
+        if block.len() < end - start {
+            self.write_block_header(last, ZSTD_COMPRESSED_BLOCK, block.len())?;
+            self.inner.write_all(&block)?;
+        } else {
+            self.write_block_header(last, ZSTD_RAW_BLOCK, end - start)?;
+            self.inner.write_all(&self.matches.data[start..end])?;
+        }
+        self.matches.slide();
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_block_header(&mut self, last: bool, kind: u32, size: usize) -> io::Result<()> {
+        let header = u32::from(last) | (kind << 1) | ((size as u32) << 3);
+        self.inner.write_all(&header.to_le_bytes()[..3])
+    }
+
+    /// Literals section with raw literals, followed by the sequences section
+    #[allow(clippy::cast_possible_truncation)]
+    fn compressed_block(&self, literals: &[u8], sequences: &[Sequence]) -> io::Result<Vec<u8>> {
+        let mut block = Vec::with_capacity(literals.len() + sequences.len() * 4 + 8);
+        let size = literals.len();
+        match size {
+            0..32 => block.push((size << 3) as u8),
+            32..4096 => block.extend_from_slice(&(((size << 4) | 0b0100) as u16).to_le_bytes()),
+            _ => block.extend_from_slice(&(((size << 4) | 0b1100) as u32).to_le_bytes()[..3]),
+        }
+        block.extend_from_slice(literals);
+
+        let count = sequences.len();
+        match count {
+            0..128 => block.push(count as u8),
+            128..0x7f00 => block.extend_from_slice(&[((count >> 8) + 0x80) as u8, count as u8]),
+            _ => {
+                block.push(0xff);
+                block.extend_from_slice(&((count - 0x7f00) as u16).to_le_bytes());
+            }
+        }
+        if count == 0 {
+            return Ok(block);
+        }
+        // All three codes use the predefined distributions
+        block.push(0);
+        self.encode_sequences(sequences, &mut block)?;
+        Ok(block)
+    }
+
+    /// Writes the sequences as a bitstream that is read backwards: the last
+    /// sequence is encoded first, and the initial states end the stream
+    fn encode_sequences(&self, sequences: &[Sequence], out: &mut Vec<u8>) -> io::Result<()> {
+        let codes: Vec<_> = sequences.iter().map(SequenceCodes::of).collect();
+        let mut bits = BitWriter::new(out);
+        let last = &codes[codes.len() - 1];
+        let mut literals_length_state = self.literals_lengths.initial_state(last.literals_length.0);
+        let mut match_length_state = self.match_lengths.initial_state(last.match_length.0);
+        let mut offset_state = self.offsets.initial_state(last.offset.0);
+        last.write_extra_bits(&mut bits)?;
+
+        for code in codes.iter().rev().skip(1) {
+            offset_state = self
+                .offsets
+                .encode(code.offset.0, offset_state, &mut bits)?;
+            match_length_state =
+                self.match_lengths
+                    .encode(code.match_length.0, match_length_state, &mut bits)?;
+            literals_length_state = self.literals_lengths.encode(
+                code.literals_length.0,
+                literals_length_state,
+                &mut bits,
+            )?;
+            code.write_extra_bits(&mut bits)?;
+        }
+
+        bits.write_bits(match_length_state, self.match_lengths.log)?;
+        bits.write_bits(offset_state, self.offsets.log)?;
+        bits.write_bits(literals_length_state, self.literals_lengths.log)?;
+        // End marker, the decoder starts reading after the highest set bit
+        bits.write_bits(1, 1)?;
+        bits.finish()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ZstdEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.matches.push(data);
+        // A block is only known to be full once more input follows it
+        while self.matches.pending() > ZSTD_BLOCK_SIZE {
+            self.encode_block(false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Codes of a sequence, each with its extra bits as (code, extra value, extra bit count)
+struct SequenceCodes {
+    literals_length: (u8, u32, u8),
+    match_length: (u8, u32, u8),
+    offset: (u8, u32, u8),
+}
+
+impl SequenceCodes {
+    #[allow(clippy::cast_possible_truncation)]
+    fn of(sequence: &Sequence) -> Self {
+        let length_code = |base: &[u32], extra: &[u8], value: usize| {
+            let value = value as u32;
+            let code = base.partition_point(|&b| b <= value) - 1;
+            (code as u8, value - base[code], extra[code])
+        };
+        // Offsets 1 to 3 refer to repeated offsets, which are never used here
+        let offset = sequence.distance as u32 + 3;
+        let offset_code = offset.ilog2() as u8;
+        Self {
+            literals_length: length_code(
+                &LITERALS_LENGTH_BASE,
+                &LITERALS_LENGTH_EXTRA,
+                sequence.literals_length,
+            ),
+            match_length: length_code(
+                &MATCH_LENGTH_BASE,
+                &MATCH_LENGTH_EXTRA,
+                sequence.match_length,
+            ),
+            offset: (offset_code, offset - (1 << offset_code), offset_code),
+        }
+    }
+
+    /// Writes the extra bits in the reverse of the order they are read in
+    fn write_extra_bits<W: Write>(&self, bits: &mut BitWriter<W>) -> io::Result<()> {
+        bits.write_bits(self.literals_length.1, self.literals_length.2)?;
+        bits.write_bits(self.match_length.1, self.match_length.2)?;
+        bits.write_bits(self.offset.1, self.offset.2)
+    }
+}
+
+/// Finite state entropy table of a distribution, built as the decoder
+/// builds it and used backwards to encode
+struct FseTable {
+    log: u8,
+    /// Decoding state to take after each symbol, given the state that follows,
+    /// indexed by `symbol << log | next_state`
+    previous: Vec<u32>,
+    /// Number of bits the decoder reads in each state, and the state those
+    /// bits are added to
+    transitions: Vec<(u8, u32)>,
+}
+
+impl FseTable {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn new(distribution: &[i16], log: u8) -> Self {
+        let size = 1usize << log;
+        let mut symbols = vec![0usize; size];
+        // Symbols with a probability below 1 take the last states
+        let mut high = size - 1;
+        for (symbol, _) in distribution.iter().enumerate().filter(|(_, p)| **p == -1) {
+            symbols[high] = symbol;
+            high -= 1;
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut position = 0;
+        for (symbol, &probability) in distribution.iter().enumerate() {
+            for _ in 0..probability.max(0) {
+                symbols[position] = symbol;
+                position = (position + step) & (size - 1);
+                while position > high {
+                    position = (position + step) & (size - 1);
+                }
+            }
+        }
+
+        let mut next: Vec<usize> = distribution
+            .iter()
+            .map(|&p| if p == -1 { 1 } else { p as usize })
+            .collect();
+        let mut previous = vec![0; distribution.len() << log];
+        let mut transitions = Vec::with_capacity(size);
+        for (state, &symbol) in symbols.iter().enumerate() {
+            let count = next[symbol];
+            next[symbol] += 1;
+            let bits = log - count.ilog2() as u8;
+            let baseline = (count << bits) - size;
+            for next_state in baseline..baseline + (1 << bits) {
+                previous[(symbol << log) | next_state] = state as u32;
+            }
+            transitions.push((bits, baseline as u32));
+        }
+        Self {
+            log,
+            previous,
+            transitions,
+        }
+    }
+
+    /// Any state decoding `symbol`, to start encoding with
+    fn initial_state(&self, symbol: u8) -> u32 {
+        self.previous[usize::from(symbol) << self.log]
+    }
+
+    /// Writes the bits that lead from a state decoding `symbol` to `state`,
+    /// and returns that state
+    fn encode<W: Write>(&self, symbol: u8, state: u32, bits: &mut BitWriter<W>) -> io::Result<u32> {
+        let previous = self.previous[(usize::from(symbol) << self.log) | state as usize];
+        let (count, baseline) = self.transitions[previous as usize];
+        bits.write_bits(state - baseline, count)?;
+        Ok(previous)
+    }
+}
+
+// === Bzip2 ===
+
+/// Block size in units of 100 kB, as in `bzip2 -9`
+const BZIP2_LEVEL: u8 = 9;
+/// Largest block after the initial run-length encoding, as in bzip2 itself
+const BZIP2_BLOCK_SIZE: usize = 100_000 * BZIP2_LEVEL as usize - 19;
+const BZIP2_BLOCK_MAGIC: u64 = 0x3141_5926_5359;
+const BZIP2_END_MAGIC: u64 = 0x1772_4538_5090;
+/// Symbols coded with the same Huffman table
+const BZIP2_GROUP_SIZE: usize = 50;
+/// Longest Huffman code; the format allows 20, bzip2 itself uses 17
+const BZIP2_MAX_CODE_LENGTH: u8 = 17;
+/// Rounds of refining the Huffman tables to the groups using them
+const BZIP2_TABLE_ITERATIONS: usize = 4;
+const RUN_A: u16 = 0;
+const RUN_B: u16 = 1;
+
+/// Writes a bzip2 stream: runs of 4 or more equal bytes are shortened, then
+/// each block goes through the Burrows-Wheeler transform, move-to-front and
+/// Huffman coding
+struct Bzip2Encoder<W: Write> {
+    out: MsbBitWriter<W>,
+    /// Run-length encoded input of the current block
+    block: Vec<u8>,
+    /// Checksum of the input of the current block
+    crc: Bzip2Crc,
+    combined_crc: u32,
+    /// Byte repeated by the run not added to the block yet, and its length
+    run: (u8, usize),
+}
+
+impl<W: Write> Bzip2Encoder<W> {
+    fn new(inner: W) -> io::Result<Self> {
+        let mut out = MsbBitWriter::new(inner);
+        for byte in [b'B', b'Z', b'h', b'0' + BZIP2_LEVEL] {
+            out.write_bits(u32::from(byte), 8)?;
+        }
+        Ok(Self {
+            out,
+            block: Vec::new(),
+            crc: Bzip2Crc::new(),
+            combined_crc: 0,
+            run: (0, 0),
+        })
+    }
+
+    /// Writes the remaining input and the end of the stream
+    fn finish(mut self) -> io::Result<W> {
+        self.flush_run()?;
+        if !self.block.is_empty() {
+            self.write_block()?;
+        }
+        self.out.write_bits((BZIP2_END_MAGIC >> 24) as u32, 24)?;
+        self.out
+            .write_bits((BZIP2_END_MAGIC & 0xff_ffff) as u32, 24)?;
+        self.out.write_bits(self.combined_crc, 32)?;
+        self.out.finish()
+    }
+
+    /// Adds the current run to the block, starting a new block if it's full.
+    /// Runs of 4 or more bytes are stored as 4 bytes and the number of extra ones.
+    #[allow(clippy::cast_possible_truncation)]
+    fn flush_run(&mut self) -> io::Result<()> {
+        let (byte, length) = std::mem::take(&mut self.run);
+        if length == 0 {
+            return Ok(());
+        }
+        if self.block.len() + 5 > BZIP2_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        if length < 4 {
+            self.block.extend(std::iter::repeat_n(byte, length));
+        } else {
+            self.block.extend_from_slice(&[byte; 4]);
+            self.block.push((length - 4) as u8);
+        }
+        for _ in 0..length {
+            self.crc.update(byte);
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_block(&mut self) -> io::Result<()> {
+        let block = std::mem::take(&mut self.block);
+        let crc = std::mem::replace(&mut self.crc, Bzip2Crc::new()).finish();
+        self.combined_crc = self.combined_crc.rotate_left(1) ^ crc;
+
+        let (last_column, origin) = burrows_wheeler(&block);
+        let mut in_use = [false; 256];
+        for &byte in &block {
+            in_use[usize::from(byte)] = true;
+        }
+        let symbols = move_to_front(&last_column, &in_use);
+        let alphabet_size = in_use.iter().filter(|&&used| used).count() + 2;
+        let (tables, selectors) = huffman_tables(&symbols, alphabet_size);
+
+        let out = &mut self.out;
+        out.write_bits((BZIP2_BLOCK_MAGIC >> 24) as u32, 24)?;
+        out.write_bits((BZIP2_BLOCK_MAGIC & 0xff_ffff) as u32, 24)?;
+        out.write_bits(crc, 32)?;
+        out.write_bits(0, 1)?; // Not randomised
+        out.write_bits(origin as u32, 24)?;
+
+        // Which ranges of 16 byte values are used, then which bytes in them
+        let ranges: Vec<&[bool]> = in_use.chunks(16).collect();
+        for range in &ranges {
+            out.write_bits(u32::from(range.contains(&true)), 1)?;
+        }
+        for range in ranges.iter().filter(|range| range.contains(&true)) {
+            for &used in *range {
+                out.write_bits(u32::from(used), 1)?;
+            }
+        }
+
+        out.write_bits(tables.len() as u32, 3)?;
+        out.write_bits(selectors.len() as u32, 15)?;
+        // Selectors are move-to-front encoded, each written in unary
+        let mut order: Vec<u8> = (0..tables.len() as u8).collect();
+        for &selector in &selectors {
+            let index = order.iter().position(|&t| t == selector).unwrap_or(0);
+            order[..=index].rotate_right(1);
+            for _ in 0..index {
+                out.write_bits(1, 1)?;
+            }
+            out.write_bits(0, 1)?;
+        }
+
+        // Code lengths as differences to the previous one
+        for table in &tables {
+            let mut length = table.lengths[0];
+            out.write_bits(u32::from(length), 5)?;
+            for &target in &table.lengths {
+                while length < target {
+                    out.write_bits(0b10, 2)?;
+                    length += 1;
+                }
+                while length > target {
+                    out.write_bits(0b11, 2)?;
+                    length -= 1;
+                }
+                out.write_bits(0, 1)?;
+            }
+        }
+
+        for (group, &selector) in symbols.chunks(BZIP2_GROUP_SIZE).zip(&selectors) {
+            let table = &tables[usize::from(selector)];
+            for &symbol in group {
+                let symbol = usize::from(symbol);
+                out.write_bits(table.codes[symbol], table.lengths[symbol])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Bzip2Encoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        for &byte in data {
+            let (run_byte, length) = self.run;
+            if length > 0 && byte == run_byte && length < 255 {
+                self.run.1 += 1;
+            } else {
+                self.flush_run()?;
+                self.run = (byte, 1);
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.inner.flush()
+    }
+}
+
+/// Returns the last column of the sorted rotations of `block`, and the row
+/// of the rotation starting at the first byte
+#[allow(clippy::cast_possible_truncation)]
+fn burrows_wheeler(block: &[u8]) -> (Vec<u8>, usize) {
+    let n = block.len();
+    let mut rows: Vec<u32> = (0..n as u32).collect();
+    // Ranks of the rotations by their first `width` bytes, doubling each round
+    let mut rank: Vec<u32> = block.iter().map(|&b| u32::from(b)).collect();
+    let mut keys = vec![0u64; n];
+    let mut width = 1;
+    loop {
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = (u64::from(rank[i]) << 32) | u64::from(rank[(i + width) % n]);
+        }
+        rows.sort_unstable_by_key(|&row| keys[row as usize]);
+        let mut distinct = 0;
+        for i in 0..n {
+            if i > 0 && keys[rows[i] as usize] != keys[rows[i - 1] as usize] {
+                distinct += 1;
+            }
+            rank[rows[i] as usize] = distinct;
+        }
+        // Done once all rotations differ, or they repeat the same pattern
+        if distinct as usize == n - 1 || width >= n {
+            break;
+        }
+        width *= 2;
+    }
+
+    let last_column = rows
+        .iter()
+        .map(|&row| block[(row as usize + n - 1) % n])
+        .collect();
+    let origin = rows.iter().position(|&row| row == 0).unwrap_or(0);
+    (last_column, origin)
+}
+
+/// Move-to-front encodes `data` over the used bytes, with runs of the front
+/// byte written as bijective base-2 numbers of `RUN_A` (1) and `RUN_B` (2)
+/// digits, and ends with the end-of-block symbol
+#[allow(clippy::cast_possible_truncation)]
+fn move_to_front(data: &[u8], in_use: &[bool; 256]) -> Vec<u16> {
+    let mut order: Vec<u8> = (0..=255u8).filter(|&b| in_use[usize::from(b)]).collect();
+    let end_of_block = order.len() as u16 + 1;
+    let mut symbols = Vec::with_capacity(data.len() / 2 + 1);
+    let mut zeros = 0usize;
+    let flush_zeros = |zeros: &mut usize, symbols: &mut Vec<u16>| {
+        while *zeros > 0 {
+            if *zeros % 2 == 1 {
+                symbols.push(RUN_A);
+                *zeros = (*zeros - 1) / 2;
+            } else {
+                symbols.push(RUN_B);
+                *zeros = (*zeros - 2) / 2;
+            }
+        }
+    };
+    for &byte in data {
+        let index = order.iter().position(|&b| b == byte).unwrap_or(0);
+        if index == 0 {
+            zeros += 1;
+            continue;
+        }
+        flush_zeros(&mut zeros, &mut symbols);
+        order[..=index].rotate_right(1);
+        symbols.push(index as u16 + 1);
+    }
+    flush_zeros(&mut zeros, &mut symbols);
+    symbols.push(end_of_block);
+    symbols
+}
+
+/// Huffman code lengths and codes of a table
+struct HuffmanTable {
+    lengths: Vec<u8>,
+    codes: Vec<u32>,
+}
+
+/// Builds the Huffman tables for `symbols` and picks a table for each group
+/// of 50 symbols, refining the tables to the groups that use them
+#[allow(clippy::cast_possible_truncation)]
+fn huffman_tables(symbols: &[u16], alphabet_size: usize) -> (Vec<HuffmanTable>, Vec<u8>) {
+    let table_count = match symbols.len() {
+        0..200 => 2,
+        200..600 => 3,
+        600..1200 => 4,
+        1200..2400 => 5,
+        _ => 6,
+    };
+    let mut frequencies = vec![0u32; alphabet_size];
+    for &symbol in symbols {
+        frequencies[usize::from(symbol)] += 1;
+    }
+
+    // Start with each table covering a range of symbols of similar total frequency
+    let mut lengths = vec![vec![0u8; alphabet_size]; table_count];
+    let mut remaining = symbols.len() as u32;
+    let mut first = 0;
+    for (t, table) in lengths.iter_mut().enumerate() {
+        let target = remaining / (table_count - t) as u32;
+        let mut last = first;
+        let mut covered = 0;
+        while last < alphabet_size && (covered < target || last == first) {
+            covered += frequencies[last];
+            last += 1;
+        }
+        for (symbol, length) in table.iter_mut().enumerate() {
+            *length = if (first..last).contains(&symbol) {
+                0
+            } else {
+                15
+            };
+        }
+        remaining = remaining.saturating_sub(covered);
+        first = last.min(alphabet_size - 1);
+    }
+
+    let mut selectors = Vec::new();
+    for _ in 0..BZIP2_TABLE_ITERATIONS {
+        let mut table_frequencies = vec![vec![0u32; alphabet_size]; table_count];
+        selectors.clear();
+        for group in symbols.chunks(BZIP2_GROUP_SIZE) {
+            let cost = |table: &Vec<u8>| -> u32 {
+                group
+                    .iter()
+                    .map(|&s| u32::from(table[usize::from(s)]))
+                    .sum()
+            };
+            let best = (0..table_count)
+                .min_by_key(|&t| cost(&lengths[t]))
+                .unwrap_or(0);
+            selectors.push(best as u8);
+            for &symbol in group {
+                table_frequencies[best][usize::from(symbol)] += 1;
+            }
+        }
+        lengths = table_frequencies
+            .iter()
+            .map(|frequencies| code_lengths(frequencies, BZIP2_MAX_CODE_LENGTH))
+            .collect();
+    }
+
+    let tables = lengths
+        .into_iter()
+        .map(|lengths| {
+            let codes = canonical_codes(&lengths);
+            HuffmanTable { lengths, codes }
+        })
+        .collect();
+    (tables, selectors)
+}
+
+/// Huffman code lengths for `frequencies`, at most `max_length` long. Every
+/// symbol gets a code, even if it doesn't occur.
+#[allow(clippy::cast_possible_truncation)]
+fn code_lengths(frequencies: &[u32], max_length: u8) -> Vec<u8> {
+    let mut weights: Vec<u64> = frequencies.iter().map(|&f| u64::from(f.max(1))).collect();
+    loop {
+        // Leaves are nodes 0..n, the nodes merged from them follow
+        let n = weights.len();
+        let mut parents = vec![0usize; n];
+        let mut heap: BinaryHeap<Reverse<(u64, usize)>> = weights
+            .iter()
+            .enumerate()
+            .map(|(node, &weight)| Reverse((weight, node)))
+            .collect();
+        while let (Some(Reverse((a_weight, a))), Some(Reverse((b_weight, b)))) =
+            (heap.pop(), heap.pop())
+        {
+            let node = parents.len();
+            parents.push(0);
+            parents[a] = node;
+            parents[b] = node;
+            heap.push(Reverse((a_weight + b_weight, node)));
+        }
+        let root = parents.len() - 1;
+        let lengths: Vec<u8> = (0..n)
+            .map(|leaf| {
+                let mut depth = 0;
+                let mut node = leaf;
+                while node != root {
+                    node = parents[node];
+                    depth += 1;
+                }
+                depth
+            })
+            .collect();
+        if lengths.iter().all(|&length| length <= max_length) {
+            return lengths;
+        }
+        // Flatten the distribution until the tree is shallow enough
+        for weight in &mut weights {
+            *weight = *weight / 2 + 1;
+        }
+    }
+}
+
+/// Assigns codes in order of length, then symbol, as bzip2 expects
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut codes = vec![0; lengths.len()];
+    let mut code = 0;
+    for length in 1..=lengths.iter().copied().max().unwrap_or(0) {
+        for (symbol, _) in lengths.iter().enumerate().filter(|(_, l)| **l == length) {
+            codes[symbol] = code;
+            code += 1;
+        }
+        code <<= 1;
+    }
+    codes
+}
+
+/// CRC-32 of bzip2, which shifts bits in most significant bit first unlike
+/// the CRC-32 of gzip
+struct Bzip2Crc {
+    value: u32,
+}
+
+const BZIP2_CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 0x8000_0000 != 0 {
+                (value << 1) ^ 0x04C1_1DB7
+            } else {
+                value << 1
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+};
+
+impl Bzip2Crc {
+    fn new() -> Self {
+        Self { value: !0 }
+    }
+
+    fn update(&mut self, byte: u8) {
+        self.value =
+            (self.value << 8) ^ BZIP2_CRC_TABLE[((self.value >> 24) ^ u32::from(byte)) as usize];
+    }
+
+    fn finish(&self) -> u32 {
+        !self.value
+    }
+}
+
+/// Writes bits most significant bit first, as bzip2 expects
+struct MsbBitWriter<W: Write> {
+    inner: W,
+    bits: u64,
+    count: u8,
+}
+
+impl<W: Write> MsbBitWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u8) -> io::Result<()> {
+        self.bits = (self.bits << count) | u64::from(value);
+        self.count += count;
+        while self.count >= 8 {
+            self.count -= 8;
+            self.inner.write_all(&[(self.bits >> self.count) as u8])?;
+        }
+        self.bits &= (1 << self.count) - 1;
+        Ok(())
+    }
+
+    /// Pads the last byte with zero bits and returns the inner writer
+    fn finish(mut self) -> io::Result<W> {
+        if self.count > 0 {
+            self.inner
+                .write_all(&[(self.bits << (8 - self.count)) as u8])?;
+        }
+        Ok(self.inner)
+    }
+}
+
+// === Bit output ===
+
+/// Writes bits least significant bit first, as deflate and zstd expect
+struct BitWriter<W: Write> {
+    inner: W,
+    bits: u64,
+    count: u8,
+}
+
+impl<W: Write> BitWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, count: u8) -> io::Result<()> {
+        self.bits |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.inner.write_all(&[self.bits as u8])?;
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+        Ok(())
+    }
+
+    /// Writes a Huffman code, which is stored most significant bit first
+    fn write_code(&mut self, code: u16, length: u8) -> io::Result<()> {
+        let reversed = code.reverse_bits() >> (16 - length);
+        self.write_bits(u32::from(reversed), length)
+    }
+
+    /// Pads the last byte with zero bits and returns the inner writer
+    fn finish(mut self) -> io::Result<W> {
+        if self.count > 0 {
+            self.inner.write_all(&[self.bits as u8])?;
+        }
+        Ok(self.inner)
+    }
+}
@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::file::archive::Crc32;
+use crate::file::compress::compress_file;
+use crate::rules::rule::CompressionFormat;
+use tempfile::tempdir;
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Minimal inflater for the stored and fixed Huffman blocks written by the encoder
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data[self.bit / 8];
+            value |= u32::from((byte >> (self.bit % 8)) & 1) << i;
+            self.bit += 1;
+        }
+        value
+    }
+
+    /// Reads a Huffman code, which is packed starting with its most significant bit
+    fn code(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |code, _| (code << 1) | self.bits(1))
+    }
+
+    fn literal_or_length(&mut self) -> usize {
+        let code = self.code(7);
+        if code <= 23 {
+            return 256 + code as usize;
+        }
+        let code = (code << 1) | self.bits(1);
+        match code {
+            48..=191 => (code - 48) as usize,
+            192..=199 => 280 + (code - 192) as usize,
+            _ => 144 + (((code << 1) | self.bits(1)) - 400) as usize,
+        }
+    }
+}
+
+fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut reader = BitReader { data, bit: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1) == 1;
+        match reader.bits(2) {
+            0 => {
+                reader.bit = reader.bit.div_ceil(8) * 8;
+                let len = reader.bits(16) as usize;
+                reader.bits(16);
+                let start = reader.bit / 8;
+                out.extend_from_slice(&data[start..start + len]);
+                reader.bit += len * 8;
+            }
+            1 => loop {
+                let symbol = reader.literal_or_length();
+                match symbol {
+                    0..=255 => out.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let index = symbol - 257;
+                        let len = LENGTH_BASE[index] + reader.bits(LENGTH_EXTRA[index]) as usize;
+                        let index = reader.code(5) as usize;
+                        let distance =
+                            DISTANCE_BASE[index] + reader.bits(DISTANCE_EXTRA[index]) as usize;
+                        for _ in 0..len {
+                            out.push(out[out.len() - distance]);
+                        }
+                    }
+                }
+            },
+            kind => panic!("unexpected block type {kind}"),
+        }
+        if last {
+            return out;
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// Checks the gzip header and trailer and returns the decompressed data
+fn gunzip(bytes: &[u8]) -> Vec<u8> {
+    assert_eq!(&bytes[..3], &[0x1f, 0x8b, 8]);
+    let data = inflate(&bytes[10..bytes.len() - 8]);
+
+    let mut crc = Crc32::new();
+    crc.update(&data);
+    assert_eq!(read_u32(bytes, bytes.len() - 8), crc.finish());
+    assert_eq!(read_u32(bytes, bytes.len() - 4), data.len() as u32);
+    data
+}
+
+fn sample_data() -> Vec<u8> {
+    let mut data = Vec::new();
+    for i in 0..20_000u32 {
+        data.extend_from_slice(format!("line {} of the log, level {}\n", i, i % 7).as_bytes());
+    }
+    // Some bytes that don't repeat
+    let mut state = 0x1234_5678u32;
+    for _ in 0..5_000 {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        data.push((state >> 16) as u8);
+    }
+    data
+}
+
+#[test]
+fn test_gzip_round_trip() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("app.log");
+    let destination = dir.path().join("out/app.log.gz");
+    let data = sample_data();
+    fs::write(&source, &data).unwrap();
+
+    let size = compress_file(&source, &destination, CompressionFormat::Gzip).unwrap();
+
+    let compressed = fs::read(&destination).unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert!(compressed.len() < data.len() / 4);
+    assert_eq!(gunzip(&compressed), data);
+}
+
+#[test]
+fn test_gzip_empty_file() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("empty.txt");
+    let destination = dir.path().join("empty.txt.gz");
+    fs::write(&source, b"").unwrap();
+
+    compress_file(&source, &destination, CompressionFormat::Gzip).unwrap();
+
+    assert!(gunzip(&fs::read(&destination).unwrap()).is_empty());
+}
+
+const LITERALS_LENGTH_BASE: [u32; 36] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 18, 20, 22, 24, 28, 32, 40, 48, 64,
+    128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+const LITERALS_LENGTH_EXTRA: [u32; 36] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 3, 3, 4, 6, 7, 8, 9, 10, 11,
+    12, 13, 14, 15, 16,
+];
+const MATCH_LENGTH_BASE: [u32; 53] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27,
+    28, 29, 30, 31, 32, 33, 34, 35, 37, 39, 41, 43, 47, 51, 59, 67, 83, 99, 131, 259, 515, 1027,
+    2051, 4099, 8195, 16387, 32771, 65539,
+];
+const MATCH_LENGTH_EXTRA: [u32; 53] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 1, 1, 1, 2, 2, 3, 3, 4, 4, 5, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+];
+const LITERALS_LENGTH_DISTRIBUTION: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+const MATCH_LENGTH_DISTRIBUTION: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OFFSET_DISTRIBUTION: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Reads a zstd sequences bitstream from its end, after the end marker
+struct BackwardReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl<'a> BackwardReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let last = data[data.len() - 1];
+        assert_ne!(last, 0, "missing end marker");
+        let marker = (data.len() - 1) * 8 + 7 - last.leading_zeros() as usize;
+        Self { data, bit: marker }
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |value, _| {
+            self.bit -= 1;
+            (value << 1) | u32::from((self.data[self.bit / 8] >> (self.bit % 8)) & 1)
+        })
+    }
+}
+
+/// Symbol, number of bits and baseline of each state of a predefined FSE table
+fn fse_decoding_table(distribution: &[i16], log: u32) -> Vec<(usize, u32, u32)> {
+    let size = 1usize << log;
+    let mut symbols = vec![0; size];
+    let mut high = size - 1;
+    for (symbol, _) in distribution.iter().enumerate().filter(|(_, p)| **p == -1) {
+        symbols[high] = symbol;
+        high -= 1;
+    }
+    let step = (size >> 1) + (size >> 3) + 3;
+    let mut position = 0;
+    for (symbol, &probability) in distribution.iter().enumerate() {
+        for _ in 0..probability.max(0) {
+            symbols[position] = symbol;
+            position = (position + step) & (size - 1);
+            while position > high {
+                position = (position + step) & (size - 1);
+            }
+        }
+    }
+    let mut next: Vec<u32> = distribution.iter().map(|&p| p.max(1) as u32).collect();
+    symbols
+        .into_iter()
+        .map(|symbol| {
+            let state = next[symbol];
+            next[symbol] += 1;
+            let bits = log - state.ilog2();
+            (symbol, bits, (state << bits) - size as u32)
+        })
+        .collect()
+}
+
+/// Minimal decoder for the raw literals and predefined FSE tables written by the encoder
+fn unzstd(bytes: &[u8]) -> Vec<u8> {
+    assert_eq!(read_u32(bytes, 0), 0xfd2f_b528);
+    assert_eq!(bytes[4], 0, "unexpected frame header flags");
+    let literals_lengths = fse_decoding_table(&LITERALS_LENGTH_DISTRIBUTION, 6);
+    let match_lengths = fse_decoding_table(&MATCH_LENGTH_DISTRIBUTION, 6);
+    let offsets = fse_decoding_table(&OFFSET_DISTRIBUTION, 5);
+
+    let mut out = Vec::new();
+    let mut at = 6;
+    loop {
+        let header =
+            u32::from(bytes[at]) | u32::from(bytes[at + 1]) << 8 | u32::from(bytes[at + 2]) << 16;
+        let size = (header >> 3) as usize;
+        at += 3;
+        let block = &bytes[at..at + size];
+        at += size;
+        match (header >> 1) & 3 {
+            0 => out.extend_from_slice(block),
+            2 => {
+                assert_eq!(block[0] & 3, 0, "expected raw literals");
+                let (literals_size, header_size) = match (block[0] >> 2) & 3 {
+                    0 | 2 => (usize::from(block[0] >> 3), 1),
+                    1 => ((usize::from(block[0]) >> 4) | usize::from(block[1]) << 4, 2),
+                    _ => (
+                        (usize::from(block[0]) >> 4)
+                            | usize::from(block[1]) << 4
+                            | usize::from(block[2]) << 12,
+                        3,
+                    ),
+                };
+                let mut literals = block[header_size..header_size + literals_size].iter();
+                let mut rest = &block[header_size + literals_size..];
+                let count = match rest[0] {
+                    0..128 => {
+                        rest = &rest[1..];
+                        usize::from(block[header_size + literals_size])
+                    }
+                    255 => {
+                        let count = usize::from(u16::from_le_bytes([rest[1], rest[2]])) + 0x7f00;
+                        rest = &rest[3..];
+                        count
+                    }
+                    first => {
+                        let count = (usize::from(first) - 0x80) << 8 | usize::from(rest[1]);
+                        rest = &rest[2..];
+                        count
+                    }
+                };
+                if count > 0 {
+                    assert_eq!(rest[0], 0, "expected predefined tables");
+                    let mut reader = BackwardReader::new(&rest[1..]);
+                    let mut literals_length_state = reader.bits(6) as usize;
+                    let mut offset_state = reader.bits(5) as usize;
+                    let mut match_length_state = reader.bits(6) as usize;
+                    for i in 0..count {
+                        let (ll_code, ll_bits, ll_base) = literals_lengths[literals_length_state];
+                        let (ml_code, ml_bits, ml_base) = match_lengths[match_length_state];
+                        let (of_code, of_bits, of_base) = offsets[offset_state];
+                        let offset = (1 << of_code) + reader.bits(of_code as u32);
+                        let match_length =
+                            MATCH_LENGTH_BASE[ml_code] + reader.bits(MATCH_LENGTH_EXTRA[ml_code]);
+                        let literals_length = LITERALS_LENGTH_BASE[ll_code]
+                            + reader.bits(LITERALS_LENGTH_EXTRA[ll_code]);
+                        assert!(offset > 3, "unexpected repeated offset");
+                        out.extend(literals.by_ref().take(literals_length as usize));
+                        let distance = offset as usize - 3;
+                        for _ in 0..match_length {
+                            out.push(out[out.len() - distance]);
+                        }
+                        if i + 1 < count {
+                            literals_length_state = (ll_base + reader.bits(ll_bits)) as usize;
+                            match_length_state = (ml_base + reader.bits(ml_bits)) as usize;
+                            offset_state = (of_base + reader.bits(of_bits)) as usize;
+                        }
+                    }
+                    assert_eq!(reader.bit, 0);
+                }
+                out.extend(literals);
+            }
+            kind => panic!("unexpected block type {kind}"),
+        }
+        if header & 1 == 1 {
+            assert_eq!(at, bytes.len());
+            return out;
+        }
+    }
+}
+
+/// Reads bits most significant bit first
+struct MsbBitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl MsbBitReader<'_> {
+    fn bits(&mut self, count: u32) -> u32 {
+        (0..count).fold(0, |value, _| {
+            let bit = (self.data[self.bit / 8] >> (7 - self.bit % 8)) & 1;
+            self.bit += 1;
+            (value << 1) | u32::from(bit)
+        })
+    }
+
+    fn magic(&mut self) -> u64 {
+        (u64::from(self.bits(24)) << 24) | u64::from(self.bits(24))
+    }
+}
+
+fn bzip2_crc(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Minimal bzip2 decoder, checking the block and stream checksums
+fn bunzip2(bytes: &[u8]) -> Vec<u8> {
+    assert_eq!(&bytes[..3], b"BZh");
+    let mut reader = MsbBitReader {
+        data: bytes,
+        bit: 32,
+    };
+    let mut out = Vec::new();
+    let mut combined_crc = 0u32;
+    while reader.magic() == 0x3141_5926_5359 {
+        let crc = reader.bits(32);
+        assert_eq!(reader.bits(1), 0, "unexpected randomised block");
+        let origin = reader.bits(24) as usize;
+
+        let ranges = reader.bits(16);
+        let mut used = Vec::new();
+        for range in (0..16).filter(|range| ranges & (0x8000 >> range) != 0) {
+            let bytes = reader.bits(16);
+            used.extend(
+                (0..16)
+                    .filter(|b| bytes & (0x8000 >> b) != 0)
+                    .map(|b| range * 16 + b),
+            );
+        }
+        let alphabet_size = used.len() + 2;
+
+        let table_count = reader.bits(3) as usize;
+        let mut order: Vec<usize> = (0..table_count).collect();
+        let selectors: Vec<usize> = (0..reader.bits(15))
+            .map(|_| {
+                let mut index = 0;
+                while reader.bits(1) == 1 {
+                    index += 1;
+                }
+                order[..=index].rotate_right(1);
+                order[0]
+            })
+            .collect();
+        let tables: Vec<HashMap<(u32, u32), usize>> = (0..table_count)
+            .map(|_| {
+                let mut length = reader.bits(5);
+                let lengths: Vec<u32> = (0..alphabet_size)
+                    .map(|_| {
+                        while reader.bits(1) == 1 {
+                            if reader.bits(1) == 0 {
+                                length += 1;
+                            } else {
+                                length -= 1;
+                            }
+                        }
+                        length
+                    })
+                    .collect();
+                let mut codes = HashMap::new();
+                let mut code = 0;
+                for length in 1..=20 {
+                    for (symbol, _) in lengths.iter().enumerate().filter(|(_, l)| **l == length) {
+                        codes.insert((length, code), symbol);
+                        code += 1;
+                    }
+                    code <<= 1;
+                }
+                codes
+            })
+            .collect();
+
+        // Huffman decoding, undoing the move-to-front and zero runs
+        let mut order: Vec<u8> = used.iter().map(|&b| b as u8).collect();
+        let mut last_column = Vec::new();
+        let (mut run, mut run_weight) = (0, 1);
+        'symbols: for selector in &selectors {
+            for _ in 0..50 {
+                let (mut length, mut code) = (0, 0);
+                let symbol = loop {
+                    code = (code << 1) | reader.bits(1);
+                    length += 1;
+                    if let Some(&symbol) = tables[*selector].get(&(length, code)) {
+                        break symbol;
+                    }
+                };
+                if symbol <= 1 {
+                    run += run_weight << symbol;
+                    run_weight <<= 1;
+                    continue;
+                }
+                last_column.extend(std::iter::repeat_n(order[0], run));
+                (run, run_weight) = (0, 1);
+                if symbol == alphabet_size - 1 {
+                    break 'symbols;
+                }
+                order[..symbol].rotate_right(1);
+                last_column.push(order[0]);
+            }
+        }
+
+        // Inverse Burrows-Wheeler transform
+        let mut starts = [0usize; 256];
+        for &byte in &last_column {
+            starts[usize::from(byte)] += 1;
+        }
+        let mut total = 0;
+        for start in &mut starts {
+            (*start, total) = (total, total + *start);
+        }
+        let mut next = vec![0; last_column.len()];
+        for (i, &byte) in last_column.iter().enumerate() {
+            next[starts[usize::from(byte)]] = i;
+            starts[usize::from(byte)] += 1;
+        }
+        let mut block = Vec::new();
+        let mut position = next[origin];
+        for _ in 0..last_column.len() {
+            block.push(last_column[position]);
+            position = next[position];
+        }
+
+        // Runs of 4 equal bytes are followed by the number of extra ones
+        let mut data = Vec::new();
+        let mut bytes = block.into_iter();
+        let (mut previous, mut run) = (None, 0);
+        while let Some(byte) = bytes.next() {
+            data.push(byte);
+            run = if previous == Some(byte) { run + 1 } else { 1 };
+            previous = Some(byte);
+            if run == 4 {
+                let extra = bytes.next().unwrap();
+                data.extend(std::iter::repeat_n(byte, usize::from(extra)));
+                (previous, run) = (None, 0);
+            }
+        }
+        assert_eq!(bzip2_crc(&data), crc);
+        combined_crc = combined_crc.rotate_left(1) ^ crc;
+        out.extend(data);
+    }
+    assert_eq!(reader.bits(32), combined_crc);
+    out
+}
+
+#[test]
+fn test_zstd_round_trip() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("app.log");
+    let destination = dir.path().join("out/app.log.zst");
+    let data = sample_data();
+    fs::write(&source, &data).unwrap();
+
+    let size = compress_file(&source, &destination, CompressionFormat::Zstd).unwrap();
+
+    let compressed = fs::read(&destination).unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert!(compressed.len() < data.len() / 4);
+    assert_eq!(unzstd(&compressed), data);
+}
+
+#[test]
+fn test_zstd_empty_file() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("empty.txt");
+    let destination = dir.path().join("empty.txt.zst");
+    fs::write(&source, b"").unwrap();
+
+    compress_file(&source, &destination, CompressionFormat::Zstd).unwrap();
+
+    assert!(unzstd(&fs::read(&destination).unwrap()).is_empty());
+}
+
+#[test]
+fn test_bzip2_round_trip() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("app.log");
+    let destination = dir.path().join("out/app.log.bz2");
+    let mut data = sample_data();
+    // Long runs are shortened before the block is sorted
+    data.extend_from_slice(&[b'-'; 1000]);
+    data.extend_from_slice(b"aaaabbbbb");
+    fs::write(&source, &data).unwrap();
+
+    let size = compress_file(&source, &destination, CompressionFormat::Bzip2).unwrap();
+
+    let compressed = fs::read(&destination).unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert!(compressed.len() < data.len() / 4);
+    assert_eq!(bunzip2(&compressed), data);
+}
+
+#[test]
+fn test_bzip2_empty_file() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("empty.txt");
+    let destination = dir.path().join("empty.txt.bz2");
+    fs::write(&source, b"").unwrap();
+
+    compress_file(&source, &destination, CompressionFormat::Bzip2).unwrap();
+
+    assert!(bunzip2(&fs::read(&destination).unwrap()).is_empty());
+}
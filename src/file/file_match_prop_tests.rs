@@ -0,0 +1,355 @@
+//! Property-based tests for `match_rule_matcher`.
+//!
+//! Inputs are generated from a seeded RNG so every failure can be reproduced
+//! from the case number printed in the assertion message.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::{TempDir, tempdir};
+
+use super::file_match::match_rule_matcher;
+use crate::rules::rule::{
+    Action, Conditions, ConflictStrategy, DateRange, DeleteAction, MetadataField, MoveAction,
+    PermissionCondition, Range, Rule,
+};
+
+/// Number of generated cases per property
+const CASES: u64 = 256;
+
+/// Types that can be generated from a random source
+trait Arbitrary: Sized {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self;
+}
+
+fn maybe<T>(rng: &mut fastrand::Rng, f: impl FnOnce(&mut fastrand::Rng) -> T) -> Option<T> {
+    if rng.bool() { Some(f(rng)) } else { None }
+}
+
+fn pick(rng: &mut fastrand::Rng, choices: &[&str]) -> String {
+    choices[rng.usize(..choices.len())].to_string()
+}
+
+impl Arbitrary for Range {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self {
+        Range {
+            min: maybe(rng, |r| r.u64(0..64)),
+            max: maybe(rng, |r| r.u64(0..64)),
+        }
+    }
+}
+
+impl Arbitrary for DateRange {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self {
+        const DATES: [&str; 5] = ["now", "-7d", "+1y", "2020-01-01", "2025-06-20T12:00:00Z"];
+        DateRange {
+            from: maybe(rng, |r| pick(r, &DATES)),
+            to: maybe(rng, |r| pick(r, &DATES)),
+        }
+    }
+}
+
+impl Arbitrary for MetadataField {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self {
+        MetadataField {
+            key: pick(rng, &["EXIF:DateTime", "EXIF:Model", "EXIF:Make"]),
+            value: maybe(rng, |r| pick(r, &["*", "Canon*", "2024:*"])),
+            value_type: Default::default(),
+            comparison: Default::default(),
+        }
+    }
+}
+
+impl Arbitrary for Conditions {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self {
+        Conditions {
+            any: maybe(rng, fastrand::Rng::bool),
+            filename: maybe(rng, |r| pick(r, &[r".*\.jpg$", "^test", "[invalid", ".*"])),
+            extensions: maybe(rng, |r| {
+                (0..r.usize(0..3))
+                    .map(|_| pick(r, &["jpg", "png", "txt", "pdf"]))
+                    .collect()
+            }),
+            path: maybe(rng, |r| pick(r, &["**/*.jpg", "/tmp/**", "*"])),
+            path_any: maybe(rng, |r| {
+                (0..r.usize(0..3))
+                    .map(|_| pick(r, &["**/*.png", "/tmp/**", "[invalid"]))
+                    .collect()
+            }),
+            exclude_path: maybe(rng, |r| {
+                (0..r.usize(0..2))
+                    .map(|_| pick(r, &["**/__thumb__/**", "/tmp/**", "[invalid"]))
+                    .collect()
+            }),
+            min_depth: maybe(rng, |r| r.usize(0..3)),
+            max_depth: maybe(rng, |r| r.usize(0..3)),
+            size_kb: maybe(rng, Range::arbitrary),
+            is_empty_file: maybe(rng, fastrand::Rng::bool),
+            mime_type: maybe(rng, |r| pick(r, &["image/*", "image/jpeg", "text/plain"])),
+            created_date: maybe(rng, DateRange::arbitrary),
+            modified_date: maybe(rng, DateRange::arbitrary),
+            file_age_days: maybe(rng, Range::arbitrary),
+            is_symlink: maybe(rng, fastrand::Rng::bool),
+            hardlink_count: maybe(rng, Range::arbitrary),
+            permissions: maybe(rng, |r| PermissionCondition {
+                readable: maybe(r, fastrand::Rng::bool),
+                writable: maybe(r, fastrand::Rng::bool),
+                executable: maybe(r, fastrand::Rng::bool),
+            }),
+            case_sensitive: maybe(rng, fastrand::Rng::bool),
+            content_matches: maybe(rng, |r| pick(r, &["ERROR", "^%PDF", "[invalid"])),
+            content_max_bytes: None,
+            content_hash: None,
+            metadata: maybe(rng, |r| {
+                (0..r.usize(0..2))
+                    .map(|_| MetadataField::arbitrary(r))
+                    .collect()
+            }),
+            all: None,
+            any_of: None,
+        }
+    }
+}
+
+impl Arbitrary for Rule {
+    fn arbitrary(rng: &mut fastrand::Rng) -> Self {
+        let id = format!("rule_{}", rng.u32(..));
+        let action = if rng.bool() {
+            Action::Move(MoveAction {
+                to: "/tmp/tooka_proptest".to_string(),
+                preserve_structure: rng.bool(),
+                preserve_depth: None,
+                allow_directory: false,
+                verify_after_move: false,
+                if_newer_than: None,
+                if_older_than: None,
+                on_conflict: ConflictStrategy::Overwrite,
+            })
+        } else {
+            Action::Delete(DeleteAction {
+                trash: rng.bool(),
+                missing_ok: rng.bool(),
+            })
+        };
+        Rule {
+            name: id.clone(),
+            id,
+            enabled: rng.bool(),
+            description: None,
+            priority: rng.u32(0..100),
+            when: Conditions::arbitrary(rng),
+            then: vec![action],
+            after: None,
+            tags: None,
+        }
+    }
+}
+
+/// Runs `property` for `CASES` seeded cases
+fn check(property: impl Fn(u64, &mut fastrand::Rng)) {
+    for case in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(case);
+        property(case, &mut rng);
+    }
+}
+
+/// Creates a file with random content and the given name inside a fresh temp dir
+fn synthetic_file(rng: &mut fastrand::Rng, name: &str) -> (TempDir, PathBuf) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join(name);
+    let content: Vec<u8> = (0..rng.usize(0..4096)).map(|_| rng.u8(..)).collect();
+    fs::write(&path, content).unwrap();
+    (dir, path)
+}
+
+fn empty_conditions(any: Option<bool>) -> Conditions {
+    Conditions {
+        any,
+        filename: None,
+        extensions: None,
+        path: None,
+        path_any: None,
+        exclude_path: None,
+        min_depth: None,
+        max_depth: None,
+        size_kb: None,
+        is_empty_file: None,
+        mime_type: None,
+        created_date: None,
+        modified_date: None,
+        file_age_days: None,
+        is_symlink: None,
+        hardlink_count: None,
+        permissions: None,
+        case_sensitive: None,
+        content_matches: None,
+        content_max_bytes: None,
+        content_hash: None,
+        metadata: None,
+        all: None,
+        any_of: None,
+    }
+}
+
+#[test]
+fn prop_empty_conditions_match_every_file() {
+    check(|case, rng| {
+        let name = pick(rng, &["a.jpg", "b.png", "notes.txt", "noext", ".hidden"]);
+        let (_dir, path) = synthetic_file(rng, &name);
+        let conditions = empty_conditions(maybe(rng, fastrand::Rng::bool));
+
+        assert!(
+            match_rule_matcher(&path, &conditions),
+            "case {case}: empty conditions should match {}",
+            path.display()
+        );
+    });
+}
+
+#[test]
+fn prop_jpg_never_matches_png_only_extension() {
+    check(|case, rng| {
+        let (_dir, path) = synthetic_file(rng, "photo.jpg");
+        let mut conditions = Conditions::arbitrary(rng);
+        conditions.any = Some(false);
+        conditions.extensions = Some(vec!["png".to_string()]);
+
+        assert!(
+            !match_rule_matcher(&path, &conditions),
+            "case {case}: .jpg file matched png-only conditions {conditions:?}"
+        );
+    });
+}
+
+#[test]
+fn prop_not_applicable_rules_never_match() {
+    check(|case, rng| {
+        let name = pick(rng, &["a.jpg", "b.PNG", "notes.txt", "noext"]);
+        let (_dir, path) = synthetic_file(rng, &name);
+        let rule = Rule::arbitrary(rng);
+
+        if !rule.applicable_to(&path) {
+            assert!(
+                !match_rule_matcher(&path, &rule.when),
+                "case {case}: {} matched {:?} although not applicable",
+                path.display(),
+                rule.when
+            );
+        }
+    });
+}
+
+#[test]
+fn prop_matching_is_deterministic() {
+    check(|case, rng| {
+        let (_dir, path) = synthetic_file(rng, "sample.jpg");
+        let rule = Rule::arbitrary(rng);
+
+        let first = match_rule_matcher(&path, &rule.when);
+        let second = match_rule_matcher(&path, &rule.when);
+
+        assert_eq!(
+            first, second,
+            "case {case}: non-deterministic result for {:?}",
+            rule.when
+        );
+    });
+}
+
+/// Sets one condition on `conditions` that either matches or fails for `path`.
+fn set_controlled_condition(
+    conditions: &mut Conditions,
+    slot: usize,
+    should_match: bool,
+    path: &Path,
+) {
+    let parent = path.parent().unwrap().display().to_string();
+    match slot {
+        0 => {
+            conditions.filename = Some(if should_match { "^sample" } else { "^other" }.into());
+        }
+        1 => {
+            conditions.extensions =
+                Some(vec![if should_match { "jpg" } else { "png" }.to_string()]);
+        }
+        2 => {
+            conditions.path = Some(if should_match {
+                format!("{parent}/*")
+            } else {
+                format!("{parent}/missing/*")
+            });
+        }
+        3 => {
+            conditions.size_kb = Some(if should_match {
+                Range {
+                    min: None,
+                    max: Some(1024),
+                }
+            } else {
+                Range {
+                    min: Some(1024),
+                    max: None,
+                }
+            });
+        }
+        4 => conditions.is_symlink = Some(!should_match),
+        _ => {
+            conditions.mime_type = Some(if should_match { "image/*" } else { "text/*" }.into());
+        }
+    }
+}
+
+#[test]
+fn prop_any_is_shorthand_for_any_of() {
+    check(|case, rng| {
+        let (_dir, path) = synthetic_file(rng, "sample.jpg");
+        let mut flat = empty_conditions(Some(true));
+        let mut nested = empty_conditions(None);
+        let mut any_of = Vec::new();
+
+        for slot in 0..6 {
+            if rng.bool() {
+                let should_match = rng.bool();
+                set_controlled_condition(&mut flat, slot, should_match, &path);
+                let mut single = empty_conditions(None);
+                set_controlled_condition(&mut single, slot, should_match, &path);
+                any_of.push(single);
+            }
+        }
+        if !any_of.is_empty() {
+            nested.any_of = Some(any_of);
+        }
+
+        assert_eq!(
+            match_rule_matcher(&path, &flat),
+            match_rule_matcher(&path, &nested),
+            "case {case}: {flat:?} and {nested:?} disagree"
+        );
+    });
+}
+
+#[test]
+fn prop_any_matches_iff_at_least_one_condition_matches() {
+    check(|case, rng| {
+        let (_dir, path) = synthetic_file(rng, "sample.jpg");
+        let mut conditions = empty_conditions(Some(true));
+
+        let mut slots: Vec<usize> = (0..6).collect();
+        rng.shuffle(&mut slots);
+        let k = rng.usize(1..=slots.len());
+
+        let mut m = 0;
+        for &slot in &slots[..k] {
+            let should_match = rng.bool();
+            if should_match {
+                m += 1;
+            }
+            set_controlled_condition(&mut conditions, slot, should_match, &path);
+        }
+
+        assert_eq!(
+            match_rule_matcher(&path, &conditions),
+            m > 0,
+            "case {case}: k={k}, m={m}, conditions {conditions:?}"
+        );
+    });
+}
@@ -1,10 +1,14 @@
+use chrono::Datelike;
 use std::fs::{self};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
 use super::file_match;
-use crate::rules::rule::{DateRange, MetadataField, Range};
+use crate::rules::rule::{
+    Conditions, DateRange, HashAlgorithm, HashCondition, MetadataComparison, MetadataField,
+    MetadataValueType, PermissionCondition, Range,
+};
 
 // Helper to create a temp file and rename it to a given filename
 fn create_temp_file_with_name(filename: &str) -> PathBuf {
@@ -39,8 +43,8 @@ fn test_match_filename_regex() {
     let matching_path = create_temp_file_with_name("match_test.jpg");
     let non_matching_path = create_temp_file_with_name("fail_test.png");
 
-    assert!(file_match::match_filename_regex(&matching_path, r"match_.*\.jpg").unwrap());
-    assert!(!file_match::match_filename_regex(&non_matching_path, r"match_.*\.jpg").unwrap());
+    assert!(file_match::match_filename_regex(&matching_path, r"match_.*\.jpg", true).unwrap());
+    assert!(!file_match::match_filename_regex(&non_matching_path, r"match_.*\.jpg", true).unwrap());
 }
 
 #[test]
@@ -50,14 +54,151 @@ fn test_match_extensions() {
 
     assert!(file_match::match_extensions(
         &matching_path,
-        &["jpg".to_string()]
+        &["jpg".to_string()],
+        true
     ));
     assert!(!file_match::match_extensions(
         &non_matching_path,
-        &["jpg".to_string()]
+        &["jpg".to_string()],
+        true
     ));
 }
 
+#[test]
+fn test_match_case_insensitive() {
+    let upper_path = create_temp_file_with_name("HOLIDAY_01.JPG");
+    let extensions = ["jpg".to_string()];
+
+    assert!(!file_match::match_extensions(
+        &upper_path,
+        &extensions,
+        true
+    ));
+    assert!(file_match::match_extensions(
+        &upper_path,
+        &extensions,
+        false
+    ));
+    assert!(!file_match::match_filename_regex(&upper_path, r"^holiday_.*\.jpg$", true).unwrap());
+    assert!(file_match::match_filename_regex(&upper_path, r"^holiday_.*\.jpg$", false).unwrap());
+
+    let mut conditions: Conditions =
+        serde_yaml::from_str("extensions: [jpg]\ncase_sensitive: false\n").unwrap();
+    assert!(file_match::match_rule_matcher(&upper_path, &conditions));
+    conditions.case_sensitive = None;
+    assert!(!file_match::match_rule_matcher(&upper_path, &conditions));
+}
+
+#[test]
+fn test_match_content_hash() {
+    let path = create_temp_file_with_name("payload.bin");
+    fs::write(&path, "hello").unwrap();
+    let condition = |algorithm, value: &str| HashCondition {
+        algorithm,
+        value: value.to_string(),
+        max_size_mb: None,
+    };
+
+    let sha256 = "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824";
+    assert!(file_match::match_content_hash(
+        &path,
+        &condition(HashAlgorithm::Sha256, sha256)
+    ));
+    assert!(file_match::match_content_hash(
+        &path,
+        &condition(HashAlgorithm::Md5, "5d41402abc4b2a76b9719d911017c592")
+    ));
+    assert!(!file_match::match_content_hash(
+        &path,
+        &condition(HashAlgorithm::Md5, "d41d8cd98f00b204e9800998ecf8427e")
+    ));
+
+    // Files above the size limit are not hashed
+    fs::write(&path, vec![0u8; 1024 * 1024 + 1]).unwrap();
+    let limited = HashCondition {
+        max_size_mb: Some(1),
+        ..condition(HashAlgorithm::Md5, "b6d81b360a5672d80c27430f39153e2c")
+    };
+    assert!(!file_match::match_content_hash(&path, &limited));
+    fs::write(&path, vec![0u8; 1024 * 1024]).unwrap();
+    assert!(file_match::match_content_hash(&path, &limited));
+
+    let conditions: Conditions = serde_yaml::from_str(
+        "content_hash:\n  algorithm: md5\n  value: b6d81b360a5672d80c27430f39153e2c\n",
+    )
+    .unwrap();
+    assert!(file_match::match_rule_matcher(&path, &conditions));
+}
+
+#[test]
+fn test_match_nested_conditions() {
+    let small_pdf = create_temp_file_with_name("small_report.pdf");
+    let large_pdf = create_temp_file_with_name("large_report.pdf");
+    fs::write(&large_pdf, vec![0u8; 2048 * 1024]).unwrap();
+    let large_txt = create_temp_file_with_name("large_notes.txt");
+    fs::write(&large_txt, vec![0u8; 2048 * 1024]).unwrap();
+
+    // pdf AND (size > 1 MB OR name starts with "small")
+    let conditions: Conditions = serde_yaml::from_str(
+        "extensions: [pdf]\nany_of:\n  - size_kb: {min: 1024}\n  - filename: '^small'\n",
+    )
+    .unwrap();
+    assert!(file_match::match_rule_matcher(&small_pdf, &conditions));
+    assert!(file_match::match_rule_matcher(&large_pdf, &conditions));
+    assert!(!file_match::match_rule_matcher(&large_txt, &conditions));
+
+    let conditions: Conditions = serde_yaml::from_str(
+        "any: true\nextensions: [txt]\nall:\n  - extensions: [pdf]\n  - size_kb: {max: 1}\n",
+    )
+    .unwrap();
+    assert!(file_match::match_rule_matcher(&small_pdf, &conditions));
+    assert!(!file_match::match_rule_matcher(&large_pdf, &conditions));
+    assert!(file_match::match_rule_matcher(&large_txt, &conditions));
+
+    // Sub-conditions inherit case_sensitive
+    let upper_pdf = create_temp_file_with_name("SCAN.PDF");
+    let mut conditions: Conditions =
+        serde_yaml::from_str("case_sensitive: false\nall:\n  - all:\n      - extensions: [pdf]\n")
+            .unwrap();
+    assert!(file_match::match_rule_matcher(&upper_pdf, &conditions));
+    conditions.case_sensitive = None;
+    assert!(!file_match::match_rule_matcher(&upper_pdf, &conditions));
+}
+
+#[test]
+fn test_match_unset_conditions_are_skipped() {
+    let txt = create_temp_file_with_extension("txt");
+
+    // Unset conditions don't count as matches, even with OR logic
+    let conditions: Conditions = serde_yaml::from_str("any: true\nextensions: [pdf]\n").unwrap();
+    assert!(!file_match::match_rule_matcher(&txt, &conditions));
+    let conditions: Conditions =
+        serde_yaml::from_str("any: true\nextensions: [pdf]\nsize_kb: {max: 1}\n").unwrap();
+    assert!(file_match::match_rule_matcher(&txt, &conditions));
+
+    // Without any condition set, every file matches
+    let conditions: Conditions = serde_yaml::from_str("any: true\n").unwrap();
+    assert!(file_match::match_rule_matcher(&txt, &conditions));
+    let conditions: Conditions = serde_yaml::from_str("{}").unwrap();
+    assert!(file_match::match_rule_matcher(&txt, &conditions));
+}
+
+#[test]
+fn test_match_content_regex() {
+    let path = create_temp_file_with_name("app.log");
+    fs::write(&path, "2024-03-01 12:00 ERROR: disk full\nretrying\n").unwrap();
+
+    assert!(file_match::match_content_regex(&path, r"ERROR: disk full", 4096).unwrap());
+    assert!(!file_match::match_content_regex(&path, r"WARN", 4096).unwrap());
+    // Only the first bytes are searched
+    assert!(!file_match::match_content_regex(&path, r"ERROR", 10).unwrap());
+    assert!(file_match::match_content_regex(&path, r"[invalid", 4096).is_err());
+
+    let conditions: Conditions =
+        serde_yaml::from_str("extensions: [log]\ncontent_matches: 'disk full'\n").unwrap();
+    assert!(file_match::match_rule_matcher(&path, &conditions));
+}
+
 #[test]
 fn test_match_path() {
     let matching_path = create_temp_file_in_dir("photos/match.jpg");
@@ -87,6 +228,109 @@ fn test_match_size_kb() {
     assert!(file_match::match_size_kb(&large_meta, &range));
 }
 
+#[test]
+fn test_match_path_any() {
+    let path = Path::new("/home/user/Desktop/report.pdf");
+    let patterns = vec![
+        "/home/user/Downloads/**".to_string(),
+        "/home/user/Desktop/*.pdf".to_string(),
+    ];
+
+    assert!(file_match::match_path_any(path, &patterns).unwrap());
+    assert!(!file_match::match_path_any(path, &patterns[..1]).unwrap());
+    assert!(!file_match::match_path_any(path, &[]).unwrap());
+    assert!(file_match::match_path_any(path, &["[invalid".to_string()]).is_err());
+}
+
+#[test]
+fn test_exclude_path_preserve_thumbs() {
+    let dir = tempfile::tempdir().unwrap();
+    let thumbs = dir.path().join("__thumb__");
+    fs::create_dir(&thumbs).unwrap();
+    let photo = dir.path().join("photo.jpg");
+    let files = [
+        photo.clone(),
+        thumbs.join("photo.jpg"),
+        thumbs.join("other.jpg"),
+    ];
+    for file in &files {
+        fs::write(file, b"jpeg").unwrap();
+    }
+
+    // preserve_thumbs: move all JPEGs except the thumbnails
+    let conditions: Conditions =
+        serde_yaml::from_str("extensions: [jpg]\nexclude_path: ['**/__thumb__/**']\n").unwrap();
+    let matched: Vec<_> = files
+        .iter()
+        .filter(|file| file_match::match_rule_matcher(file, &conditions))
+        .collect();
+    assert_eq!(matched, [&photo]);
+
+    // Exclusion also wins with OR logic
+    let conditions: Conditions = serde_yaml::from_str(
+        "any: true\nextensions: [jpg]\nfilename: '^photo'\nexclude_path: ['**/__thumb__/**']\n",
+    )
+    .unwrap();
+    assert!(!file_match::match_rule_matcher(&files[1], &conditions));
+    assert!(file_match::is_excluded(
+        &files[2],
+        &["**/__thumb__/*".to_string()]
+    ));
+    assert!(!file_match::is_excluded(&photo, &["[invalid".to_string()]));
+}
+
+#[test]
+fn test_match_depth() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    let files = [
+        dir.path().join("top.txt"),
+        dir.path().join("a/one.txt"),
+        dir.path().join("a/b/two.txt"),
+    ];
+    for file in &files {
+        fs::write(file, b"text").unwrap();
+    }
+    let depth = |yaml: &str| -> Vec<bool> {
+        let conditions: Conditions = serde_yaml::from_str(yaml).unwrap();
+        files
+            .iter()
+            .map(|file| file_match::match_rule_matcher_in(file, dir.path(), &conditions))
+            .collect()
+    };
+
+    assert_eq!(depth("max_depth: 0"), [true, false, false]);
+    assert_eq!(depth("min_depth: 1"), [false, true, true]);
+    assert_eq!(depth("min_depth: 1\nmax_depth: 1"), [false, true, false]);
+
+    // Without the source folder or outside of it, depth conditions never match
+    let conditions: Conditions = serde_yaml::from_str("max_depth: 5").unwrap();
+    assert!(!file_match::match_rule_matcher(&files[0], &conditions));
+    assert!(!file_match::match_rule_matcher_in(
+        &files[0],
+        &dir.path().join("a"),
+        &conditions
+    ));
+}
+
+#[test]
+fn test_match_is_empty_file() {
+    let empty = create_temp_file_with_name("stub.part");
+    let full = create_temp_file_with_name("movie.mkv");
+    fs::write(&full, b"data").unwrap();
+
+    let metadata = fs::metadata(&empty).unwrap();
+    assert!(file_match::match_is_empty_file(&metadata, true));
+    assert!(!file_match::match_is_empty_file(&metadata, false));
+    let metadata = fs::metadata(&full).unwrap();
+    assert!(!file_match::match_is_empty_file(&metadata, true));
+    assert!(file_match::match_is_empty_file(&metadata, false));
+
+    let conditions: Conditions = serde_yaml::from_str("is_empty_file: true").unwrap();
+    assert!(file_match::match_rule_matcher(&empty, &conditions));
+    assert!(!file_match::match_rule_matcher(&full, &conditions));
+}
+
 #[test]
 fn test_match_mime_type() {
     let jpg_path = create_temp_file_with_extension("jpg");
@@ -96,6 +340,94 @@ fn test_match_mime_type() {
     assert!(!file_match::match_mime_type(&txt_path, "image/*"));
 }
 
+#[test]
+fn test_match_mime_type_list() {
+    let jpg_path = create_temp_file_with_extension("jpg");
+    let png_path = create_temp_file_with_extension("png");
+    let txt_path = create_temp_file_with_extension("txt");
+
+    assert!(file_match::match_mime_type(
+        &jpg_path,
+        "image/jpeg, image/png"
+    ));
+    assert!(file_match::match_mime_type(
+        &png_path,
+        "image/jpeg,image/png"
+    ));
+    assert!(!file_match::match_mime_type(
+        &txt_path,
+        "image/jpeg, image/png"
+    ));
+
+    assert!(!file_match::match_mime_type(
+        &jpg_path,
+        "image/*, !image/jpeg"
+    ));
+    assert!(file_match::match_mime_type(
+        &png_path,
+        "image/*, !image/jpeg"
+    ));
+    assert!(file_match::match_mime_type(&txt_path, "!image/*"));
+    assert!(!file_match::match_mime_type(&png_path, "!image/*"));
+}
+
+#[test]
+fn test_parse_mime_type_filter() {
+    let filters = file_match::parse_mime_type_filter(" image/jpeg ,, !image/* ,text/plain");
+
+    assert_eq!(
+        filters,
+        vec![
+            file_match::MimeFilter {
+                pattern: "image/jpeg".to_string(),
+                negated: false,
+            },
+            file_match::MimeFilter {
+                pattern: "image/*".to_string(),
+                negated: true,
+            },
+            file_match::MimeFilter {
+                pattern: "text/plain".to_string(),
+                negated: false,
+            },
+        ]
+    );
+    assert!(file_match::parse_mime_type_filter("").is_empty());
+}
+
+#[test]
+fn test_match_mime_type_extensionless() {
+    let samples: [(&str, &[u8], &str); 3] = [
+        (
+            "photo",
+            &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'],
+            "image/jpeg",
+        ),
+        ("image", b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR", "image/png"),
+        (
+            "document",
+            b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n",
+            "application/pdf",
+        ),
+    ];
+
+    for (name, content, mime) in samples {
+        let path = create_temp_file_with_name(&format!("{name}_{}", std::process::id()));
+        fs::write(&path, content).unwrap();
+
+        assert_eq!(
+            file_match::detect_mime_from_bytes(&path).as_deref(),
+            Some(mime)
+        );
+        assert!(
+            file_match::match_mime_type(&path, mime),
+            "{name} should match {mime}"
+        );
+        assert!(!file_match::match_mime_type(&path, "text/*"));
+        fs::remove_file(&path).unwrap();
+    }
+}
+
 #[test]
 fn test_match_date_range_mod() {
     let file = NamedTempFile::new().unwrap();
@@ -111,6 +443,31 @@ fn test_match_date_range_mod() {
     assert!(file_match::match_date_range_mod(&meta, &range));
 }
 
+#[test]
+fn test_match_date_range_mod_partial_dates() {
+    let file = NamedTempFile::new().unwrap();
+    let meta = file.as_file().metadata().unwrap();
+
+    let today = chrono::Utc::now().naive_utc().date();
+    let year = today.format("%Y").to_string();
+    let month = today.format("%Y-%m").to_string();
+
+    for (from, to) in [(&year, &year), (&month, &month)] {
+        let range = DateRange {
+            from: Some(from.clone()),
+            to: Some(to.clone()),
+        };
+        assert!(file_match::match_date_range_mod(&meta, &range));
+    }
+
+    let last_year = (today.year() - 1).to_string();
+    let range = DateRange {
+        from: None,
+        to: Some(last_year),
+    };
+    assert!(!file_match::match_date_range_mod(&meta, &range));
+}
+
 #[test]
 fn test_match_date_range_created() {
     let file = NamedTempFile::new().unwrap();
@@ -147,6 +504,114 @@ fn test_match_is_symlink() {
     assert!(file_match::match_is_symlink(&symlink_meta, true));
 }
 
+#[cfg(unix)]
+#[test]
+fn test_match_file_age_days() {
+    let file = NamedTempFile::new().unwrap();
+    let day = std::time::Duration::from_secs(24 * 60 * 60);
+    let set_modified = |time: std::time::SystemTime| {
+        file.as_file().set_modified(time).unwrap();
+        file.as_file().metadata().unwrap()
+    };
+    let range = |min, max| Range { min, max };
+
+    let meta = set_modified(std::time::SystemTime::now() - 45 * day);
+    assert!(file_match::match_file_age_days(
+        &meta,
+        &range(Some(30), None)
+    ));
+    assert!(file_match::match_file_age_days(
+        &meta,
+        &range(Some(45), Some(45))
+    ));
+    assert!(!file_match::match_file_age_days(
+        &meta,
+        &range(None, Some(44))
+    ));
+
+    let meta = set_modified(std::time::SystemTime::now());
+    assert!(!file_match::match_file_age_days(
+        &meta,
+        &range(Some(30), None)
+    ));
+    assert!(file_match::match_file_age_days(
+        &meta,
+        &range(None, Some(0))
+    ));
+
+    // Files modified in the future are treated as age 0
+    let meta = set_modified(std::time::SystemTime::now() + 10 * day);
+    assert!(file_match::match_file_age_days(
+        &meta,
+        &range(Some(0), Some(0))
+    ));
+}
+
+#[test]
+fn test_match_hardlink_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("original.txt");
+    fs::write(&file, "linked").unwrap();
+    let at_least_two = Range {
+        min: Some(2),
+        max: None,
+    };
+    let single = Range {
+        min: None,
+        max: Some(1),
+    };
+
+    let metadata = fs::symlink_metadata(&file).unwrap();
+    assert!(!file_match::match_hardlink_count(&metadata, &at_least_two));
+    assert!(file_match::match_hardlink_count(&metadata, &single));
+
+    fs::hard_link(&file, dir.path().join("link.txt")).unwrap();
+    let metadata = fs::symlink_metadata(&file).unwrap();
+    assert!(file_match::match_hardlink_count(&metadata, &at_least_two));
+    assert!(!file_match::match_hardlink_count(&metadata, &single));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_match_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("install.sh");
+    fs::write(&script, "#!/bin/sh").unwrap();
+    let condition = |readable, writable, executable| PermissionCondition {
+        readable,
+        writable,
+        executable,
+    };
+    let executable = condition(None, None, Some(true));
+
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+    let metadata = fs::symlink_metadata(&script).unwrap();
+    assert!(!file_match::match_permissions(&metadata, &executable));
+    assert!(file_match::match_permissions(
+        &metadata,
+        &condition(Some(true), Some(true), Some(false))
+    ));
+
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o544)).unwrap();
+    let metadata = fs::symlink_metadata(&script).unwrap();
+    assert!(file_match::match_permissions(&metadata, &executable));
+    assert!(!file_match::match_permissions(
+        &metadata,
+        &condition(None, Some(true), None)
+    ));
+    assert!(file_match::match_permissions(
+        &metadata,
+        &condition(None, None, None)
+    ));
+
+    let conditions: Conditions =
+        serde_yaml::from_str("permissions:\n  executable: true\n").unwrap();
+    assert!(file_match::match_rule_matcher(&script, &conditions));
+    assert!(serde_yaml::from_str::<Conditions>("permissions:\n  setuid: true\n").is_err());
+}
+
 #[test]
 fn test_match_metadata_field_nonexistent() {
     let path = NamedTempFile::new().unwrap().into_temp_path().to_path_buf();
@@ -154,8 +619,87 @@ fn test_match_metadata_field_nonexistent() {
     let field = MetadataField {
         key: "EXIF:DateTimeOriginal".to_string(),
         value: Some("*".to_string()),
+        value_type: Default::default(),
+        comparison: Default::default(),
     };
 
     // No EXIF data in a blank temp file
     assert!(!file_match::match_metadata_field(&path, &field));
 }
+
+#[test]
+fn test_match_metadata_field_xattr() {
+    let file = NamedTempFile::new().unwrap();
+    if xattr::set(file.path(), "user.tooka.project", b"alpha-2024").is_err() {
+        // The filesystem does not support user extended attributes
+        return;
+    }
+
+    let field = |value: &str| MetadataField {
+        key: "user.tooka.project".to_string(),
+        value: Some(value.to_string()),
+        value_type: Default::default(),
+        comparison: Default::default(),
+    };
+    assert!(file_match::match_metadata_field(
+        file.path(),
+        &field("alpha-*")
+    ));
+    assert!(!file_match::match_metadata_field(
+        file.path(),
+        &field("beta-*")
+    ));
+
+    let missing = MetadataField {
+        key: "user.tooka.missing".to_string(),
+        value: None,
+        value_type: Default::default(),
+        comparison: Default::default(),
+    };
+    assert!(!file_match::match_metadata_field(file.path(), &missing));
+}
+
+#[test]
+fn test_match_metadata_field_typed_comparisons() {
+    let file = NamedTempFile::new().unwrap();
+    if xattr::set(file.path(), "user.tooka.iso", b"800").is_err()
+        || xattr::set(file.path(), "user.tooka.taken", b"2024:03:01 12:00:00").is_err()
+    {
+        // The filesystem does not support user extended attributes
+        return;
+    }
+
+    let matches = |key: &str, value: &str, value_type, comparison| {
+        let field = MetadataField {
+            key: key.to_string(),
+            value: Some(value.to_string()),
+            value_type,
+            comparison,
+        };
+        file_match::match_metadata_field(file.path(), &field)
+    };
+    use MetadataComparison as C;
+    use MetadataValueType as T;
+
+    let iso = "user.tooka.iso";
+    assert!(matches(iso, "400", T::Integer, C::GreaterThan));
+    assert!(!matches(iso, "800", T::Integer, C::GreaterThan));
+    assert!(matches(iso, "1600", T::Integer, C::LessThan));
+    assert!(matches(iso, "800", T::Integer, C::Equals));
+    assert!(matches(iso, "100..800", T::Integer, C::Between));
+    assert!(!matches(iso, "1000..3200", T::Integer, C::Between));
+    // Strings are matched as globs unless `contains` is used
+    assert!(!matches(iso, "8", T::String, C::Equals));
+    assert!(matches(iso, "8", T::String, C::Contains));
+    assert!(matches(iso, "799.5", T::Float, C::GreaterThan));
+
+    let taken = "user.tooka.taken";
+    assert!(matches(taken, "2024-01-01", T::Date, C::GreaterThan));
+    assert!(matches(
+        taken,
+        "2024-01-01..2024-06-30",
+        T::Date,
+        C::Between
+    ));
+    assert!(!matches(taken, "2024-03-01", T::Date, C::LessThan));
+}
@@ -5,18 +5,124 @@
 
 use crate::{
     core::error::TookaError,
-    rules::rule::{Action, CopyAction, DeleteAction, ExecuteAction, MoveAction, RenameAction},
-    utils::rename_pattern::{evaluate_template, extract_metadata},
+    file::compress::compress_file,
+    rules::rule::{
+        Action, ArchiveAction, CompressAction, ConflictStrategy, CopyAction, DeleteAction,
+        ExecuteAction, HardlinkAction, MoveAction, MoveAndHardlinkAction, RenameAction,
+        SetPermissionsAction, SymlinkAction, TouchAction, parse_modified_bound,
+    },
+    utils::rename_pattern::{
+        TemplateContext, evaluate_template, extract_metadata, substitute_captures,
+    },
 };
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{HashMap, HashSet},
     fs,
+    io::{BufReader, Read},
+    ops::Deref,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        Arc, LazyLock, Mutex, OnceLock, PoisonError,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, SystemTime},
 };
 
+/// How often a command with a timeout is checked for completion
+const EXECUTE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Hook run before each action, see [`set_pre_action_hook`]
+type PreActionHook = Box<dyn Fn(&Path, &Action, bool) -> bool + Send + Sync>;
+/// Hook run after each action, see [`set_post_action_hook`]
+type PostActionHook = Box<dyn Fn(&Path, &Action, &FileOperationResult) + Send + Sync>;
+
+static PRE_ACTION_HOOK: OnceLock<PreActionHook> = OnceLock::new();
+static POST_ACTION_HOOK: OnceLock<PostActionHook> = OnceLock::new();
+
+/// Destinations claimed by moves, copies and links that are still running,
+/// with the number of claims on each. Files are sorted in parallel, so a
+/// destination that doesn't exist on disk yet may already be taken.
+static RESERVED_DESTINATIONS: LazyLock<Mutex<HashMap<PathBuf, usize>>> =
+    LazyLock::new(Mutex::default);
+
 /// Result of a file operation, containing the new path of the file and the action performed.
 pub struct FileOperationResult {
     pub new_path: PathBuf,
     pub action: String,
+    /// Number of bytes moved, copied or deleted; 0 for other actions
+    pub bytes_affected: u64,
+}
+
+/// Retry policy for filesystem calls that fail with transient errors, such as
+/// files locked by another process or network filesystems.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one; 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds
+    pub initial_delay_ms: u64,
+    /// Factor applied to the delay after each retry
+    pub backoff_multiplier: f64,
+}
+
+impl Default for RetryConfig {
+    /// No retries, matching the behavior of plain filesystem calls
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_delay_ms: 100,
+            backoff_multiplier: 2.0,
+        }
+    }
+}
+
+/// Options controlling how file operations are performed.
+#[derive(Debug, Clone, Default)]
+pub struct FileOperationOptions {
+    /// Retry policy for move, copy and delete operations
+    pub retry: RetryConfig,
+    /// Moves a file deleted without `trash` to this path instead of removing
+    /// it, so it can be restored later
+    pub delete_to: Option<PathBuf>,
+    /// Extra metadata for the placeholders of rename templates, such as the
+    /// `filename` regex captures used as `{{1}}`, `{{2}}`, ...
+    pub template_values: HashMap<String, String>,
+    /// Destinations already picked in this dry run. Share one between all
+    /// files of a run so conflicts are resolved as in the real run.
+    pub dry_run_destinations: DryRunDestinations,
+}
+
+/// Destinations picked by the moves, copies and links of one dry run.
+///
+/// A dry run writes nothing, so without it every file aiming at the same
+/// path would see that path as free. Clones share the same set.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunDestinations(Arc<Mutex<HashSet<PathBuf>>>);
+
+/// Sets a hook that is called with the file, the action and the dry-run flag
+/// before every action, e.g. to ask for confirmation.
+///
+/// If the hook returns `false`, the action is skipped and reported as
+/// `hook_skipped`. The hook can only be set once; later calls are ignored.
+pub fn set_pre_action_hook(hook: impl Fn(&Path, &Action, bool) -> bool + Send + Sync + 'static) {
+    if PRE_ACTION_HOOK.set(Box::new(hook)).is_err() {
+        log::warn!("Pre-action hook already set, ignoring new hook");
+    }
+}
+
+/// Sets a hook that is called with the file, the action and its result after
+/// every action that succeeded or was skipped, e.g. for audit logging.
+///
+/// The hook can only be set once; later calls are ignored.
+pub fn set_post_action_hook(
+    hook: impl Fn(&Path, &Action, &FileOperationResult) + Send + Sync + 'static,
+) {
+    if POST_ACTION_HOOK.set(Box::new(hook)).is_err() {
+        log::warn!("Post-action hook already set, ignoring new hook");
+    }
 }
 
 /// Executes a file operation specified by the given action on the provided file path.
@@ -37,6 +143,27 @@ pub fn execute_action(
     action: &Action,
     dry_run: bool,
     source_path: &Path,
+) -> Result<FileOperationResult, TookaError> {
+    execute_action_with_options(
+        file_path,
+        action,
+        dry_run,
+        source_path,
+        &FileOperationOptions::default(),
+    )
+}
+
+/// Same as [`execute_action`], with explicit [`FileOperationOptions`] such as a retry policy.
+///
+/// # Errors
+/// Returns a `TookaError` on failure, or `TookaError::ExhaustedRetries` if a
+/// retried operation kept failing.
+pub fn execute_action_with_options(
+    file_path: &Path,
+    action: &Action,
+    dry_run: bool,
+    source_path: &Path,
+    options: &FileOperationOptions,
 ) -> Result<FileOperationResult, TookaError> {
     log::info!(
         "Executing action '{:?}' on file: {} (dry_run: {})",
@@ -45,17 +172,55 @@ pub fn execute_action(
         dry_run
     );
 
+    let result = if PRE_ACTION_HOOK
+        .get()
+        .is_some_and(|hook| !hook(file_path, action, dry_run))
+    {
+        log::info!("Pre-action hook skipped file: {}", file_path.display());
+        FileOperationResult {
+            new_path: file_path.to_path_buf(),
+            action: "hook_skipped".to_string(),
+            bytes_affected: 0,
+        }
+    } else {
+        run_action(file_path, action, dry_run, source_path, options)?
+    };
+
+    if let Some(hook) = POST_ACTION_HOOK.get() {
+        hook(file_path, action, &result);
+    }
+    Ok(result)
+}
+
+/// Dispatches an action to its handler
+fn run_action(
+    file_path: &Path,
+    action: &Action,
+    dry_run: bool,
+    source_path: &Path,
+    options: &FileOperationOptions,
+) -> Result<FileOperationResult, TookaError> {
     match action {
-        Action::Move(inner) => handle_move(file_path, inner, dry_run, source_path),
-        Action::Copy(inner) => handle_copy(file_path, inner, dry_run, source_path),
-        Action::Rename(inner) => handle_rename(file_path, inner, dry_run),
-        Action::Delete(inner) => handle_delete(file_path, inner, dry_run),
+        Action::Move(inner) => handle_move(file_path, inner, dry_run, source_path, options),
+        Action::Copy(inner) => handle_copy(file_path, inner, dry_run, source_path, options),
+        Action::Rename(inner) => handle_rename(file_path, inner, dry_run, options),
+        Action::Delete(inner) => handle_delete(file_path, inner, dry_run, options),
         Action::Execute(inner) => handle_execute(file_path, inner, dry_run),
+        Action::MoveAndHardlink(inner) => {
+            handle_move_and_hardlink(file_path, inner, dry_run, source_path, options)
+        }
+        Action::Archive(inner) => handle_archive(file_path, inner),
+        Action::Symlink(inner) => handle_symlink(file_path, inner, dry_run, source_path, options),
+        Action::Hardlink(inner) => handle_hardlink(file_path, inner, dry_run, source_path, options),
+        Action::Compress(inner) => handle_compress(file_path, inner, dry_run, source_path),
+        Action::SetPermissions(inner) => handle_set_permissions(file_path, inner, dry_run),
+        Action::Touch(inner) => handle_touch(file_path, inner, dry_run),
         Action::Skip => {
             log::info!("Skipping file: {}", file_path.display());
             Ok(FileOperationResult {
                 new_path: file_path.to_path_buf(),
                 action: "skip".to_string(),
+                bytes_affected: 0,
             })
         }
     }
@@ -66,6 +231,7 @@ fn handle_move(
     action: &MoveAction,
     dry_run: bool,
     source_path: &Path,
+    options: &FileOperationOptions,
 ) -> Result<FileOperationResult, TookaError> {
     log::debug!(
         "Handling move action: {:?} for file: {}",
@@ -73,7 +239,30 @@ fn handle_move(
         file_path.display()
     );
 
-    let new_path = compute_destination(file_path, action, source_path);
+    if !modified_in_window(
+        file_path,
+        action.if_newer_than.as_deref(),
+        action.if_older_than.as_deref(),
+    )? {
+        return Ok(condition_not_met(file_path));
+    }
+
+    let Some(new_path) = compute_destination(file_path, action, source_path, dry_run, options)?
+    else {
+        return Ok(conflict_skipped(file_path));
+    };
+
+    if file_path.is_dir() {
+        if !action.allow_directory {
+            return Err(TookaError::FileOperationError(format!(
+                "{} is a directory; set allow_directory to move it",
+                file_path.display()
+            )));
+        }
+        return handle_move_directory(file_path, new_path.to_path_buf(), dry_run, &options.retry);
+    }
+
+    let bytes_affected = file_size(file_path);
 
     if dry_run {
         log::debug!("Dry run: would move file to: {}", new_path.display());
@@ -82,20 +271,307 @@ fn handle_move(
         if let Some(parent) = new_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::rename(file_path, &new_path)?;
+        let checksum = if action.verify_after_move {
+            Some(hash_file(file_path)?)
+        } else {
+            None
+        };
+        move_file(file_path, &new_path, checksum.as_deref(), &options.retry)?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: new_path.to_path_buf(),
+        action: "move".to_string(),
+        bytes_affected,
+    })
+}
+
+/// Moves a file like [`handle_move`]. With `link_back`, the file is hard-linked
+/// into its destination first and the original path is kept, which leaves the
+/// same data reachable from both paths.
+fn handle_move_and_hardlink(
+    file_path: &Path,
+    action: &MoveAndHardlinkAction,
+    dry_run: bool,
+    source_path: &Path,
+    options: &FileOperationOptions,
+) -> Result<FileOperationResult, TookaError> {
+    let move_action = MoveAction {
+        to: action.to.clone(),
+        preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    };
+    if !action.link_back {
+        let result = handle_move(file_path, &move_action, dry_run, source_path, options)?;
+        return Ok(FileOperationResult {
+            action: "move_and_hardlink".to_string(),
+            ..result
+        });
+    }
+
+    if file_path.is_dir() {
+        return Err(TookaError::FileOperationError(format!(
+            "{} is a directory; directories can't be hard-linked",
+            file_path.display()
+        )));
+    }
+
+    let Some(new_path) =
+        compute_destination(file_path, &move_action, source_path, dry_run, options)?
+    else {
+        return Ok(conflict_skipped(file_path));
+    };
+    let bytes_affected = file_size(file_path);
+
+    if dry_run {
+        log::debug!(
+            "Dry run: would move file to {} and link it back",
+            new_path.display()
+        );
+    } else {
+        log::info!("Moving file to {} and linking it back", new_path.display());
+        let parent = new_path.parent().unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(parent)?;
+        // Linking before removing anything keeps the original path intact if
+        // the destination is on another filesystem. The link gets a temporary
+        // name and is renamed over the destination, replacing a file there
+        // like a move does; dropping it removes the temporary name if the
+        // rename had nothing to do because the destination already links here.
+        let link = tempfile::Builder::new()
+            .prefix(".tooka-link")
+            .make_in(parent, |path| fs::hard_link(file_path, path))
+            .map_err(|e| {
+                TookaError::FileOperationError(format!(
+                    "Failed to hard-link '{}' to '{}': {e}",
+                    file_path.display(),
+                    new_path.display()
+                ))
+            })?
+            .into_temp_path();
+        fs::rename(&link, &new_path)?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: new_path.to_path_buf(),
+        action: "move_and_hardlink".to_string(),
+        bytes_affected,
+    })
+}
+
+/// Moves a file, falling back to copy-and-delete when the destination is on a
+/// different filesystem.
+///
+/// If `checksum` is given, the destination must have that SHA-256 hash. On a
+/// mismatch after a copy, the copy is removed and the source is kept; after a
+/// rename, the file is renamed back. Either way the source is left in place
+/// and [`TookaError::ChecksumMismatch`] is returned.
+pub(crate) fn move_file(
+    file_path: &Path,
+    new_path: &Path,
+    checksum: Option<&str>,
+    retry: &RetryConfig,
+) -> Result<(), TookaError> {
+    match with_retry(retry, "move", || fs::rename(file_path, new_path)) {
+        Ok(()) => {
+            if let Some(expected) = checksum {
+                if let Err(e) = verify_checksum(new_path, expected) {
+                    log::error!(
+                        "Moved file is corrupted, restoring '{}'",
+                        file_path.display()
+                    );
+                    fs::rename(new_path, file_path)?;
+                    return Err(e);
+                }
+            }
+        }
+        Err(TookaError::Io(e)) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            log::debug!("File is on another filesystem, copying it instead");
+            with_retry(retry, "copy", || atomic_copy(file_path, new_path))?;
+            if let Some(expected) = checksum {
+                if let Err(e) = verify_checksum(new_path, expected) {
+                    log::error!(
+                        "Copied file is corrupted, keeping '{}'",
+                        file_path.display()
+                    );
+                    fs::remove_file(new_path)?;
+                    return Err(e);
+                }
+            }
+            with_retry(retry, "move", || fs::remove_file(file_path))?;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+/// Computes the SHA-256 hash of a file as a lowercase hex string, reading it in chunks.
+pub(crate) fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checks that the file at `path` has the SHA-256 hash `expected`.
+pub(crate) fn verify_checksum(path: &Path, expected: &str) -> Result<(), TookaError> {
+    let actual = hash_file(path)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(TookaError::ChecksumMismatch {
+            path: path.to_path_buf(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+/// Moves a whole directory, falling back to copy-and-delete when the
+/// destination is on a different filesystem.
+fn handle_move_directory(
+    dir_path: &Path,
+    new_path: PathBuf,
+    dry_run: bool,
+    retry: &RetryConfig,
+) -> Result<FileOperationResult, TookaError> {
+    let bytes_affected = directory_size(dir_path);
+
+    if dry_run {
+        log::info!(
+            "Dry run: would move directory ({bytes_affected} bytes) to: {}",
+            new_path.display()
+        );
+    } else {
+        log::info!("Moving directory to: {}", new_path.display());
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match fs::rename(dir_path, &new_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                log::debug!("Directory is on another filesystem, copying it instead");
+                copy_dir_recursive(dir_path, &new_path)?;
+                with_retry(retry, "move", || fs::remove_dir_all(dir_path))?;
+            }
+            Err(e) => return Err(e.into()),
+        }
     }
 
     Ok(FileOperationResult {
         new_path,
         action: "move".to_string(),
+        bytes_affected,
     })
 }
 
+/// Whether the file was last modified between the optional `if_newer_than`
+/// and `if_older_than` bounds of a move or copy action
+fn modified_in_window(
+    file_path: &Path,
+    newer_than: Option<&str>,
+    older_than: Option<&str>,
+) -> Result<bool, TookaError> {
+    if newer_than.is_none() && older_than.is_none() {
+        return Ok(true);
+    }
+
+    let modified = fs::metadata(file_path)?.modified()?;
+    for (field, bound, newer) in [
+        ("if_newer_than", newer_than, true),
+        ("if_older_than", older_than, false),
+    ] {
+        let Some(value) = bound else { continue };
+        let bound = parse_modified_bound(field, value).map_err(TookaError::InvalidRule)?;
+        if (newer && modified <= bound) || (!newer && modified >= bound) {
+            log::info!("Skipping {}: {field} {value} not met", file_path.display());
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Result of a move or copy whose modification time condition is not met
+fn condition_not_met(file_path: &Path) -> FileOperationResult {
+    FileOperationResult {
+        new_path: file_path.to_path_buf(),
+        action: "condition_not_met".to_string(),
+        bytes_affected: 0,
+    }
+}
+
+/// Result of a move or copy skipped because its destination already exists
+fn conflict_skipped(file_path: &Path) -> FileOperationResult {
+    FileOperationResult {
+        new_path: file_path.to_path_buf(),
+        action: "conflict_skipped".to_string(),
+        bytes_affected: 0,
+    }
+}
+
+/// Size of the file in bytes, or 0 if it cannot be read (e.g. the result of an
+/// earlier simulated action in a dry run)
+fn file_size(file_path: &Path) -> u64 {
+    fs::symlink_metadata(file_path).map_or(0, |m| m.len())
+}
+
+/// Total size in bytes of all regular files below `dir`
+fn directory_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Copies `from` into a temporary file next to `to` and renames it into place,
+/// so an interrupted copy never leaves a partial file at the destination.
+fn atomic_copy(from: &Path, to: &Path) -> std::io::Result<u64> {
+    let parent = to.parent().unwrap_or_else(|| Path::new("."));
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+    let mut source = fs::File::open(from)?;
+    let bytes = std::io::copy(&mut source, temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+    temp.as_file()
+        .set_permissions(source.metadata()?.permissions())?;
+    temp.persist(to)?;
+    Ok(bytes)
+}
+
+/// Recursively copies `from` to `to`, creating directories as needed.
+pub(crate) fn copy_dir_recursive(from: &Path, to: &Path) -> Result<(), TookaError> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(|e| TookaError::FileOperationError(e.to_string()))?;
+        let relative = entry.path().strip_prefix(from)?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
 fn handle_copy(
     file_path: &Path,
     action: &CopyAction,
     dry_run: bool,
     source_path: &Path,
+    options: &FileOperationOptions,
 ) -> Result<FileOperationResult, TookaError> {
     log::debug!(
         "Handling copy action: {:?} for file: {}",
@@ -103,28 +579,344 @@ fn handle_copy(
         file_path.display()
     );
 
-    let new_path = compute_destination(file_path, action, source_path);
+    if !modified_in_window(
+        file_path,
+        action.if_newer_than.as_deref(),
+        action.if_older_than.as_deref(),
+    )? {
+        return Ok(condition_not_met(file_path));
+    }
 
-    if dry_run {
+    let Some(new_path) = compute_destination(file_path, action, source_path, dry_run, options)?
+    else {
+        return Ok(conflict_skipped(file_path));
+    };
+
+    let bytes_affected = if dry_run {
         log::debug!("Dry run: would copy file to: {}", new_path.display());
+        file_size(file_path)
     } else {
         log::info!("Copying file to: {}", new_path.display());
         if let Some(parent) = new_path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::copy(file_path, &new_path)?;
+        with_retry(&options.retry, "copy", || atomic_copy(file_path, &new_path))?
+    };
+
+    Ok(FileOperationResult {
+        new_path: new_path.to_path_buf(),
+        action: "copy".to_string(),
+        bytes_affected,
+    })
+}
+
+/// Resolves the archive the file belongs to. Nothing is written here: the
+/// sorter collects the files of each archive and writes it once all files
+/// have been processed, see `write_archives` in the sorter.
+fn handle_archive(
+    file_path: &Path,
+    action: &ArchiveAction,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling archive action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    if file_path.is_dir() {
+        return Err(TookaError::FileOperationError(format!(
+            "{} is a directory; only files can be archived",
+            file_path.display()
+        )));
+    }
+
+    let archive_path = resolve_destination(&expand_destination(&action.to, file_path));
+    log::debug!(
+        "File {} goes into archive {}",
+        file_path.display(),
+        archive_path.display()
+    );
+
+    Ok(FileOperationResult {
+        new_path: archive_path,
+        action: "archive".to_string(),
+        bytes_affected: file_size(file_path),
+    })
+}
+
+/// Creates a symbolic link to the file at its destination. The link points to
+/// the file's absolute path.
+fn handle_symlink(
+    file_path: &Path,
+    action: &SymlinkAction,
+    dry_run: bool,
+    source_path: &Path,
+    options: &FileOperationOptions,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling symlink action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    let Some(new_path) = compute_destination(file_path, action, source_path, dry_run, options)?
+    else {
+        return Ok(conflict_skipped(file_path));
+    };
+
+    if dry_run {
+        log::debug!("Dry run: would create symlink: {}", new_path.display());
+    } else {
+        log::info!("Creating symlink: {}", new_path.display());
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        create_symlink(&std::path::absolute(file_path)?, &new_path)?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: new_path.to_path_buf(),
+        action: "symlink".to_string(),
+        bytes_affected: 0,
+    })
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symlink actions are only supported on Unix",
+    ))
+}
+
+/// Creates a hard link to the file at its destination
+fn handle_hardlink(
+    file_path: &Path,
+    action: &HardlinkAction,
+    dry_run: bool,
+    source_path: &Path,
+    options: &FileOperationOptions,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling hardlink action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    if file_path.is_dir() {
+        return Err(TookaError::FileOperationError(format!(
+            "{} is a directory; directories can't be hard-linked",
+            file_path.display()
+        )));
+    }
+
+    let Some(new_path) = compute_destination(file_path, action, source_path, dry_run, options)?
+    else {
+        return Ok(conflict_skipped(file_path));
+    };
+
+    if dry_run {
+        log::debug!("Dry run: would create hard link: {}", new_path.display());
+    } else {
+        log::info!("Creating hard link: {}", new_path.display());
+        if let Some(parent) = new_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::hard_link(file_path, &new_path).map_err(|e| {
+            TookaError::FileOperationError(format!(
+                "Failed to hard-link '{}' to '{}': {e}",
+                file_path.display(),
+                new_path.display()
+            ))
+        })?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: new_path.to_path_buf(),
+        action: "hardlink".to_string(),
+        bytes_affected: 0,
+    })
+}
+
+/// Writes a compressed copy of the file to its destination, replacing any
+/// existing file there. The original file is left untouched.
+fn handle_compress(
+    file_path: &Path,
+    action: &CompressAction,
+    dry_run: bool,
+    source_path: &Path,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling compress action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    if file_path.is_dir() {
+        return Err(TookaError::FileOperationError(format!(
+            "{} is a directory; only files can be compressed",
+            file_path.display()
+        )));
     }
 
+    let mut file_name = destination_path(file_path, action, source_path).into_os_string();
+    file_name.push(".");
+    file_name.push(action.format.extension());
+    let new_path = PathBuf::from(file_name);
+
+    let bytes_affected = if dry_run {
+        log::debug!("Dry run: would compress to: {}", new_path.display());
+        fs::metadata(file_path).map(|m| m.len()).unwrap_or(0)
+    } else {
+        log::info!(
+            "Compressing {} to {} ({})",
+            file_path.display(),
+            new_path.display(),
+            action.format.name()
+        );
+        compress_file(file_path, &new_path, action.format)?
+    };
+
     Ok(FileOperationResult {
         new_path,
-        action: "copy".to_string(),
+        action: "compress".to_string(),
+        bytes_affected,
     })
 }
 
+fn handle_set_permissions(
+    file_path: &Path,
+    action: &SetPermissionsAction,
+    dry_run: bool,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling set_permissions action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    if dry_run {
+        log::debug!(
+            "Dry run: would set mode {:#o} on: {}",
+            action.mode,
+            file_path.display()
+        );
+    } else {
+        set_mode(file_path, action.mode)?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: file_path.to_path_buf(),
+        action: "set_permissions".to_string(),
+        bytes_affected: 0,
+    })
+}
+
+#[cfg(unix)]
+fn set_mode(file_path: &Path, mode: u32) -> Result<(), TookaError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    log::info!("Setting mode {mode:#o} on {}", file_path.display());
+    fs::set_permissions(file_path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+/// Unix modes don't apply elsewhere, so the action does nothing
+#[cfg(not(unix))]
+fn set_mode(file_path: &Path, mode: u32) -> Result<(), TookaError> {
+    log::warn!(
+        "set_permissions is not supported on this platform, leaving {} unchanged (mode {mode:#o})",
+        file_path.display()
+    );
+    Ok(())
+}
+
+fn handle_touch(
+    file_path: &Path,
+    action: &TouchAction,
+    dry_run: bool,
+) -> Result<FileOperationResult, TookaError> {
+    log::debug!(
+        "Handling touch action: {:?} for file: {}",
+        action,
+        file_path.display()
+    );
+
+    let modified = match &action.set_modified {
+        Some(value) => {
+            parse_modified_bound("set_modified", value).map_err(TookaError::InvalidRule)?
+        }
+        None => SystemTime::now(),
+    };
+    let created = action
+        .set_created
+        .as_deref()
+        .map(|value| parse_modified_bound("set_created", value))
+        .transpose()
+        .map_err(TookaError::InvalidRule)?;
+
+    if dry_run {
+        log::info!(
+            "Dry run: would set modified time {} on: {}",
+            chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+            file_path.display()
+        );
+        if let Some(created) = created {
+            log::info!(
+                "Dry run: would set creation time {} on: {}",
+                chrono::DateTime::<chrono::Local>::from(created).to_rfc3339(),
+                file_path.display()
+            );
+        }
+    } else {
+        let mut times = fs::FileTimes::new().set_modified(modified);
+        if let Some(created) = created {
+            times = with_created(times, created, file_path);
+        }
+        fs::File::options()
+            .write(true)
+            .open(file_path)?
+            .set_times(times)?;
+    }
+
+    Ok(FileOperationResult {
+        new_path: file_path.to_path_buf(),
+        action: "touch".to_string(),
+        bytes_affected: 0,
+    })
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn with_created(times: fs::FileTimes, created: SystemTime, _file_path: &Path) -> fs::FileTimes {
+    #[cfg(target_os = "macos")]
+    use std::os::macos::fs::FileTimesExt;
+    #[cfg(windows)]
+    use std::os::windows::fs::FileTimesExt;
+
+    times.set_created(created)
+}
+
+/// Other platforms offer no way to change the creation time, so only the
+/// modification time is updated
+#[cfg(not(any(windows, target_os = "macos")))]
+fn with_created(times: fs::FileTimes, _created: SystemTime, file_path: &Path) -> fs::FileTimes {
+    log::warn!(
+        "Setting the creation time is not supported on this platform, leaving it unchanged on {}",
+        file_path.display()
+    );
+    times
+}
+
 fn handle_rename(
     file_path: &Path,
     action: &RenameAction,
     dry_run: bool,
+    options: &FileOperationOptions,
 ) -> Result<FileOperationResult, TookaError> {
     log::debug!(
         "Handling rename action: {:?} for file: {}",
@@ -132,9 +924,21 @@ fn handle_rename(
         file_path.display()
     );
 
-    let metadata = extract_metadata(file_path)?;
+    let mut metadata = extract_metadata(file_path)?;
+    metadata.extend(options.template_values.clone());
 
-    let new_name = evaluate_template(&action.to, file_path, &metadata);
+    let mut template = action.to.clone();
+    if let Some(pattern) = &action.from_regex {
+        let file_stem = file_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        match regex::Regex::new(pattern)?.captures(file_stem) {
+            Some(caps) => template = substitute_captures(&action.to, &caps),
+            None => log::debug!(
+                "from_regex '{pattern}' does not match '{file_stem}', using placeholders only"
+            ),
+        }
+    }
+
+    let new_name = evaluate_template(&template, &TemplateContext::new(file_path, metadata));
     log::debug!("New file name: {new_name}");
 
     let new_path = file_path.with_file_name(new_name);
@@ -149,6 +953,7 @@ fn handle_rename(
     Ok(FileOperationResult {
         new_path,
         action: "rename".to_string(),
+        bytes_affected: 0,
     })
 }
 
@@ -157,6 +962,7 @@ fn handle_delete(
     file_path: &Path,
     action: &DeleteAction,
     dry_run: bool,
+    options: &FileOperationOptions,
 ) -> Result<FileOperationResult, TookaError> {
     log::debug!(
         "Handling delete action: {:?} for file: {}",
@@ -164,6 +970,12 @@ fn handle_delete(
         file_path.display()
     );
 
+    if action.missing_ok && !dry_run && fs::symlink_metadata(file_path).is_err() {
+        return Ok(skipped_delete(file_path));
+    }
+
+    let bytes_affected = file_size(file_path);
+
     if dry_run {
         log::debug!("Dry run: would delete file: {}", file_path.display());
     } else if action.trash {
@@ -172,17 +984,45 @@ fn handle_delete(
         trash::delete(file_path).map_err(|e| {
             TookaError::FileOperationError(format!("Failed to move file to trash: {e}"))
         })?;
+    } else if let Some(keep_at) = &options.delete_to {
+        log::info!(
+            "Deleting file: {} (kept at {})",
+            file_path.display(),
+            keep_at.display()
+        );
+        if let Some(parent) = keep_at.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(file_path, keep_at, None, &options.retry)?;
     } else {
         log::info!("Deleting file permanently: {}", file_path.display());
-        fs::remove_file(file_path)?;
+        match with_retry(&options.retry, "delete", || fs::remove_file(file_path)) {
+            Err(TookaError::Io(e))
+                if action.missing_ok && e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                return Ok(skipped_delete(file_path));
+            }
+            result => result?,
+        }
     }
 
     Ok(FileOperationResult {
         new_path: "[deleted]".into(),
         action: "delete".into(),
+        bytes_affected,
     })
 }
 
+/// Result of a delete action on a file that was already gone
+fn skipped_delete(file_path: &Path) -> FileOperationResult {
+    log::debug!("File already deleted, skipping: {}", file_path.display());
+    FileOperationResult {
+        new_path: "[deleted]".into(),
+        action: "delete_skipped".into(),
+        bytes_affected: 0,
+    }
+}
+
 /// Handles the execute action for a file, executing a command or script specified in the action.
 fn handle_execute(
     file_path: &Path,
@@ -201,6 +1041,15 @@ fn handle_execute(
             action.command,
             action.args.join(" ")
         );
+        if let Some(timeout_secs) = action.timeout_seconds {
+            log::debug!("Dry run: command would be killed after {timeout_secs} second(s)");
+        }
+    } else if let Some(timeout_secs) = action.timeout_seconds {
+        log::info!(
+            "Executing command: {} (timeout: {timeout_secs}s)",
+            action.command
+        );
+        run_with_timeout(action, timeout_secs)?;
     } else {
         log::info!("Executing command: {}", action.command);
         let output = std::process::Command::new(&action.command)
@@ -221,18 +1070,291 @@ fn handle_execute(
     Ok(FileOperationResult {
         new_path: file_path.to_path_buf(),
         action: "execute".into(),
+        bytes_affected: 0,
     })
 }
 
-fn compute_destination<A>(file_path: &Path, action: &A, source_path: &Path) -> PathBuf
+/// Runs a filesystem call, retrying with exponential backoff while it fails
+/// with an error that is likely to be transient.
+pub(crate) fn with_retry<T>(
+    retry: &RetryConfig,
+    operation: &str,
+    mut call: impl FnMut() -> std::io::Result<T>,
+) -> Result<T, TookaError> {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut delay_ms = retry.initial_delay_ms as f64;
+    let mut attempt = 1;
+
+    loop {
+        let err = match call() {
+            Ok(value) => return Ok(value),
+            Err(e) if !is_transient(&e) => return Err(e.into()),
+            Err(e) => e,
+        };
+
+        if attempt >= max_attempts {
+            if max_attempts == 1 {
+                return Err(err.into());
+            }
+            return Err(TookaError::ExhaustedRetries {
+                attempts: attempt,
+                last_error: Box::new(err.into()),
+            });
+        }
+
+        log::warn!(
+            "{operation} failed ({err}), retrying (attempt {} of {max_attempts}) in {delay_ms:.0}ms",
+            attempt + 1
+        );
+        thread::sleep(Duration::from_millis(delay_ms as u64));
+        delay_ms *= retry.backoff_multiplier;
+        attempt += 1;
+    }
+}
+
+/// Returns true for IO errors worth retrying: permission errors caused by
+/// locks and, on Windows, sharing violations.
+fn is_transient(err: &std::io::Error) -> bool {
+    /// `ERROR_SHARING_VIOLATION`: the file is open in another process
+    #[cfg(windows)]
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    #[cfg(windows)]
+    if err.raw_os_error() == Some(ERROR_SHARING_VIOLATION) {
+        return true;
+    }
+
+    err.kind() == std::io::ErrorKind::PermissionDenied
+}
+
+/// Runs the command of an execute action and kills it once `timeout_secs` have elapsed.
+///
+/// A timer thread signals the timeout over a channel; dropping the cancel
+/// sender when the command exits early stops the timer right away.
+fn run_with_timeout(action: &ExecuteAction, timeout_secs: u64) -> Result<(), TookaError> {
+    let mut child = Command::new(&action.command)
+        .args(&action.args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| TookaError::FileOperationError(format!("Failed to execute command: {e}")))?;
+
+    let (timeout_tx, timeout_rx) = mpsc::channel();
+    let (cancel_tx, cancel_rx) = mpsc::channel::<()>();
+    let timeout = Duration::from_secs(timeout_secs);
+    thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = cancel_rx.recv_timeout(timeout) {
+            let _ = timeout_tx.send(());
+        }
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        match timeout_rx.recv_timeout(EXECUTE_POLL_INTERVAL) {
+            Ok(()) => {
+                log::warn!(
+                    "Command '{}' timed out after {timeout_secs}s, killing it",
+                    action.command
+                );
+                child.kill()?;
+                child.wait()?;
+                return Err(TookaError::ExecuteTimeout {
+                    command: action.command.clone(),
+                    timeout_secs,
+                });
+            }
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {}
+        }
+    };
+    drop(cancel_tx);
+
+    if !status.success() {
+        return Err(TookaError::FileOperationError(format!(
+            "Command failed with status: {status}"
+        )));
+    }
+    Ok(())
+}
+
+/// Destination claimed by [`compute_destination`]; other files can't pick the
+/// same path until it is dropped, by which time the file is in place.
+struct ReservedDestination {
+    path: PathBuf,
+}
+
+impl Deref for ReservedDestination {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for ReservedDestination {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ReservedDestination {
+    fn drop(&mut self) {
+        let mut reserved = RESERVED_DESTINATIONS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = reserved.get_mut(&self.path) {
+            *count -= 1;
+            if *count == 0 {
+                reserved.remove(&self.path);
+            }
+        }
+    }
+}
+
+/// Computes where a move or copy puts the file and applies the action's
+/// `on_conflict` strategy if a file already exists there, or another file
+/// being sorted at the same time has claimed it.
+///
+/// Returns `None` if the destination is taken and the strategy is
+/// [`ConflictStrategy::Skip`]. The returned destination stays reserved until
+/// it is dropped, so hold on to it until the file is in place. In a dry run,
+/// it also stays taken in `options.dry_run_destinations` for the rest of the run.
+///
+/// # Errors
+/// Returns `TookaError::FileOperationError` if the destination is taken and
+/// the strategy is [`ConflictStrategy::Error`].
+fn compute_destination<A>(
+    file_path: &Path,
+    action: &A,
+    source_path: &Path,
+    dry_run: bool,
+    options: &FileOperationOptions,
+) -> Result<Option<ReservedDestination>, TookaError>
+where
+    A: HasToAndPreserveStructure,
+{
+    let destination = destination_path(file_path, action, source_path);
+    // Checking and claiming happen under the same lock, so two files can't
+    // both see the same path as free
+    let mut reserved = RESERVED_DESTINATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let mut planned = dry_run.then(|| {
+        options
+            .dry_run_destinations
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    });
+    let taken = |path: &Path| {
+        reserved.contains_key(path)
+            || planned
+                .as_ref()
+                .is_some_and(|planned| planned.contains(path))
+            || fs::symlink_metadata(path).is_ok()
+    };
+
+    let path = if !taken(&destination) {
+        destination
+    } else {
+        match action.on_conflict() {
+            ConflictStrategy::Overwrite => {
+                log::debug!("Overwriting existing file: {}", destination.display());
+                destination
+            }
+            ConflictStrategy::Skip => {
+                log::info!(
+                    "Skipping {}: {} already exists",
+                    file_path.display(),
+                    destination.display()
+                );
+                return Ok(None);
+            }
+            ConflictStrategy::Rename => {
+                let renamed = find_non_conflicting_path(&destination, taken);
+                log::debug!(
+                    "{} already exists, using {} instead",
+                    destination.display(),
+                    renamed.display()
+                );
+                renamed
+            }
+            ConflictStrategy::Error => {
+                return Err(TookaError::FileOperationError(format!(
+                    "Destination already exists: {}",
+                    destination.display()
+                )));
+            }
+        }
+    };
+
+    if let Some(planned) = &mut planned {
+        planned.insert(path.clone());
+    }
+    *reserved.entry(path.clone()).or_default() += 1;
+    Ok(Some(ReservedDestination { path }))
+}
+
+/// Returns the first of `dest` with `_1`, `_2`... appended to its file stem
+/// for which `is_taken` is false, e.g. `report_1.pdf` for `report.pdf`
+pub(crate) fn find_non_conflicting_path(dest: &Path, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+    let stem = dest
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = dest
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1u64..)
+        .map(|n| dest.with_file_name(format!("{stem}_{n}{extension}")))
+        .find(|candidate| !is_taken(candidate))
+        .unwrap_or_else(|| dest.to_path_buf())
+}
+
+/// Path a move or copy puts the file at, before handling conflicts
+fn destination_path<A>(file_path: &Path, action: &A, source_path: &Path) -> PathBuf
 where
     A: HasToAndPreserveStructure,
 {
     log::debug!("Computing destination for file: {}", file_path.display());
-    let to = action.to();
+    let mut destination = resolve_destination(&expand_destination(action.to(), file_path));
     let preserve_structure = action.preserve_structure();
 
-    let destination = match to.chars().next() {
+    if preserve_structure {
+        log::debug!(
+            "Preserving directory structure for file: {}",
+            file_path.display()
+        );
+        let relative_path = file_path.strip_prefix(source_path).unwrap_or(file_path);
+        destination.join(relative_path)
+    } else if let Some(depth) = action.preserve_depth() {
+        log::debug!(
+            "Preserving last {depth} directories for file: {}",
+            file_path.display()
+        );
+        let relative_path = file_path.strip_prefix(source_path).unwrap_or(file_path);
+        let components: Vec<_> = relative_path.components().collect();
+        // The file name itself is always kept, on top of `depth` directories
+        let keep = (depth as usize).saturating_add(1).min(components.len());
+        destination.extend(&components[components.len() - keep..]);
+        destination
+    } else {
+        log::debug!(
+            "Not preserving directory structure for file: {}",
+            file_path.display()
+        );
+        let file_name = file_path.file_name().unwrap_or_default();
+        destination.join(file_name)
+    }
+}
+
+/// Turns an expanded destination into a path: `./...` stays relative, a leading
+/// `~` is the home directory and anything else is absolute
+fn resolve_destination(to: &str) -> PathBuf {
+    match to.chars().next() {
         Some('.') => {
             log::debug!("Destination is a relative path: {to}");
             PathBuf::from(to)
@@ -247,28 +1369,33 @@ where
             log::debug!("Destination is an absolute path: {to}");
             PathBuf::from("/").join(to.trim_start_matches('/'))
         }
-    };
+    }
+}
 
-    if preserve_structure {
-        log::debug!(
-            "Preserving directory structure for file: {}",
-            file_path.display()
-        );
-        let relative_path = file_path.strip_prefix(source_path).unwrap_or(file_path);
-        destination.join(relative_path)
-    } else {
-        log::debug!(
-            "Not preserving directory structure for file: {}",
+/// Expands `{{...}}` placeholders such as `{{year}}` or `{{mime_family}}` in a
+/// destination. Files without readable metadata get empty date placeholders.
+fn expand_destination(to: &str, file_path: &Path) -> String {
+    if !to.contains("{{") {
+        return to.to_string();
+    }
+
+    let metadata = extract_metadata(file_path).unwrap_or_else(|e| {
+        log::warn!(
+            "Failed to read metadata of {} for destination template: {e}",
             file_path.display()
         );
-        let file_name = file_path.file_name().unwrap_or_default();
-        destination.join(file_name)
-    }
+        HashMap::new()
+    });
+    let expanded = evaluate_template(to, &TemplateContext::new(file_path, metadata));
+    log::debug!("Expanded destination template '{to}' to '{expanded}'");
+    expanded
 }
 
 trait HasToAndPreserveStructure {
     fn to(&self) -> &str;
     fn preserve_structure(&self) -> bool;
+    fn preserve_depth(&self) -> Option<u32>;
+    fn on_conflict(&self) -> ConflictStrategy;
 }
 
 impl HasToAndPreserveStructure for MoveAction {
@@ -278,6 +1405,12 @@ impl HasToAndPreserveStructure for MoveAction {
     fn preserve_structure(&self) -> bool {
         self.preserve_structure
     }
+    fn preserve_depth(&self) -> Option<u32> {
+        self.preserve_depth
+    }
+    fn on_conflict(&self) -> ConflictStrategy {
+        self.on_conflict
+    }
 }
 
 impl HasToAndPreserveStructure for CopyAction {
@@ -287,4 +1420,58 @@ impl HasToAndPreserveStructure for CopyAction {
     fn preserve_structure(&self) -> bool {
         self.preserve_structure
     }
+    fn preserve_depth(&self) -> Option<u32> {
+        self.preserve_depth
+    }
+    fn on_conflict(&self) -> ConflictStrategy {
+        self.on_conflict
+    }
+}
+
+// Links can't replace an existing file, so an existing destination is an error
+
+impl HasToAndPreserveStructure for SymlinkAction {
+    fn to(&self) -> &str {
+        &self.to
+    }
+    fn preserve_structure(&self) -> bool {
+        self.preserve_structure
+    }
+    fn preserve_depth(&self) -> Option<u32> {
+        None
+    }
+    fn on_conflict(&self) -> ConflictStrategy {
+        ConflictStrategy::Error
+    }
+}
+
+impl HasToAndPreserveStructure for HardlinkAction {
+    fn to(&self) -> &str {
+        &self.to
+    }
+    fn preserve_structure(&self) -> bool {
+        self.preserve_structure
+    }
+    fn preserve_depth(&self) -> Option<u32> {
+        None
+    }
+    fn on_conflict(&self) -> ConflictStrategy {
+        ConflictStrategy::Error
+    }
+}
+
+// The compressed file has its own extension and always replaces an older one
+impl HasToAndPreserveStructure for CompressAction {
+    fn to(&self) -> &str {
+        &self.to
+    }
+    fn preserve_structure(&self) -> bool {
+        self.preserve_structure
+    }
+    fn preserve_depth(&self) -> Option<u32> {
+        None
+    }
+    fn on_conflict(&self) -> ConflictStrategy {
+        ConflictStrategy::Overwrite
+    }
 }
@@ -1,9 +1,18 @@
-use std::{fs, os::unix::fs::PermissionsExt};
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::Path,
+};
 
 use super::file_ops;
 use crate::{
+    core::error::TookaError,
     rules::rule::ExecuteAction,
-    rules::rule::{Action, CopyAction, DeleteAction, MoveAction, RenameAction},
+    rules::rule::{
+        Action, CompressAction, CompressionFormat, ConflictStrategy, CopyAction, DeleteAction,
+        HardlinkAction, MoveAction, MoveAndHardlinkAction, RenameAction, SetPermissionsAction,
+        SymlinkAction, TouchAction,
+    },
 };
 use tempfile::{NamedTempFile, TempDir, tempdir};
 
@@ -22,6 +31,12 @@ fn test_move_file() {
     let move_action = Action::Move(MoveAction {
         to: dest_dir.to_str().unwrap().to_string(),
         preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
     });
 
     let result = file_ops::execute_action(&src_path, &move_action, false, dir.path()).unwrap();
@@ -29,6 +44,65 @@ fn test_move_file() {
     assert!(!src_path.exists());
 }
 
+#[test]
+fn test_move_to_templated_destination() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("photo.jpg");
+    fs::write(&src_path, b"not really a jpeg").unwrap();
+    let modified: chrono::DateTime<chrono::Local> =
+        fs::metadata(&src_path).unwrap().modified().unwrap().into();
+
+    let move_action = Action::Move(MoveAction {
+        to: format!(
+            "{}/{{{{year}}}}/{{{{month}}}}/{{{{mime_family}}}}_{{{{ext}}}}",
+            dir.path().join("archive").display()
+        ),
+        preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+
+    let result = file_ops::execute_action(&src_path, &move_action, true, dir.path()).unwrap();
+    let expected = dir
+        .path()
+        .join("archive")
+        .join(modified.format("%Y").to_string())
+        .join(modified.format("%m").to_string())
+        .join("image_jpg")
+        .join("photo.jpg");
+    assert_eq!(result.new_path, expected);
+}
+
+#[test]
+fn test_move_with_preserve_depth() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("source");
+    let nested = source.join("a/b/c");
+    fs::create_dir_all(&nested).unwrap();
+    let src_path = nested.join("file.txt");
+    fs::write(&src_path, "content").unwrap();
+
+    let dest_dir = dir.path().join("dest");
+    let move_action = Action::Move(MoveAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: Some(2),
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+
+    let result = file_ops::execute_action(&src_path, &move_action, false, &source).unwrap();
+    assert_eq!(result.new_path, dest_dir.join("b/c/file.txt"));
+    assert!(result.new_path.exists());
+}
+
 #[test]
 fn test_copy_file() {
     let (dir, src_file) = setup_temp_dir_and_file();
@@ -38,6 +112,10 @@ fn test_copy_file() {
     let copy_action = Action::Copy(CopyAction {
         to: dest_dir.to_str().unwrap().to_string(),
         preserve_structure: false,
+        preserve_depth: None,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
     });
 
     let result = file_ops::execute_action(&src_path, &copy_action, false, dir.path()).unwrap();
@@ -45,6 +123,289 @@ fn test_copy_file() {
     assert!(src_path.exists());
 }
 
+#[test]
+fn test_symlink_file() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+
+    let dest_dir = dir.path().join("links");
+    let action = Action::Symlink(SymlinkAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+    });
+
+    let result = file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(result.action, "symlink");
+    assert!(result.new_path.is_symlink());
+    assert_eq!(fs::read_link(&result.new_path).unwrap(), src_path);
+    assert!(src_path.exists());
+
+    // A link can't replace an existing file
+    assert!(matches!(
+        file_ops::execute_action(&src_path, &action, false, dir.path()),
+        Err(TookaError::FileOperationError(_))
+    ));
+}
+
+#[test]
+fn test_hardlink_file() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    fs::write(&src_path, "shared").unwrap();
+
+    let dest_dir = dir.path().join("links");
+    let action = Action::Hardlink(HardlinkAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+    });
+
+    let result = file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(result.action, "hardlink");
+    assert_eq!(fs::read_to_string(&result.new_path).unwrap(), "shared");
+    assert_eq!(fs::metadata(&src_path).unwrap().nlink(), 2);
+}
+
+#[test]
+fn test_compress_file() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    fs::write(&src_path, "log line\n".repeat(100)).unwrap();
+    let file_name = src_path.file_name().unwrap().to_str().unwrap();
+
+    let dest_dir = dir.path().join("compressed");
+    let action = Action::Compress(CompressAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        format: CompressionFormat::Gzip,
+        preserve_structure: false,
+    });
+
+    let result = file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(result.action, "compress");
+    assert_eq!(result.new_path, dest_dir.join(format!("{file_name}.gz")));
+    assert_eq!(result.bytes_affected, 900);
+    let compressed = fs::read(&result.new_path).unwrap();
+    assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+    assert!(compressed.len() < 900);
+    // The original is left in place
+    assert_eq!(
+        fs::read_to_string(&src_path).unwrap(),
+        "log line\n".repeat(100)
+    );
+
+    // Compressing again replaces the compressed file
+    fs::write(&src_path, "new").unwrap();
+    let result = file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(result.new_path, dest_dir.join(format!("{file_name}.gz")));
+    assert_eq!(result.bytes_affected, 3);
+    assert_ne!(fs::read(&result.new_path).unwrap(), compressed);
+}
+
+#[test]
+fn test_compress_dry_run() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    let dest_dir = dir.path().join("compressed");
+    let action = Action::Compress(CompressAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        format: CompressionFormat::Zstd,
+        preserve_structure: false,
+    });
+
+    let result = file_ops::execute_action(&src_path, &action, true, dir.path()).unwrap();
+    assert_eq!(result.action, "compress");
+    assert_eq!(result.new_path.extension().unwrap(), "zst");
+    assert!(!dest_dir.exists());
+}
+
+#[test]
+fn test_set_permissions() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    fs::set_permissions(&src_path, fs::Permissions::from_mode(0o644)).unwrap();
+    let action = Action::SetPermissions(SetPermissionsAction { mode: 0o755 });
+    let mode = || fs::metadata(&src_path).unwrap().permissions().mode() & 0o7777;
+
+    let result = file_ops::execute_action(&src_path, &action, true, dir.path()).unwrap();
+    assert_eq!(result.action, "set_permissions");
+    assert_eq!(result.new_path, src_path);
+    assert_eq!(mode(), 0o644);
+
+    let result = file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(result.new_path, src_path);
+    assert_eq!(mode(), 0o755);
+}
+
+#[test]
+fn test_touch() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    fs::File::options()
+        .write(true)
+        .open(&src_path)
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+    let mtime = || fs::metadata(&src_path).unwrap().mtime();
+
+    let action = Action::Touch(TouchAction {
+        set_modified: Some("2024-01-01T00:00:00Z".into()),
+        set_created: None,
+    });
+    let result = file_ops::execute_action(&src_path, &action, true, dir.path()).unwrap();
+    assert_eq!(result.action, "touch");
+    assert_eq!(result.new_path, src_path);
+    assert_eq!(mtime(), 1_000_000_000);
+
+    file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert_eq!(mtime(), 1_704_067_200);
+
+    // Without a date the modification time becomes the current time
+    let action = Action::Touch(TouchAction::default());
+    file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap();
+    assert!(mtime() > 1_704_067_200);
+}
+
+#[test]
+fn test_symlink_and_hardlink_dry_run() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+    let dest_dir = dir.path().join("links");
+    let to = dest_dir.to_str().unwrap().to_string();
+
+    for action in [
+        Action::Symlink(SymlinkAction {
+            to: to.clone(),
+            preserve_structure: false,
+        }),
+        Action::Hardlink(HardlinkAction {
+            to: to.clone(),
+            preserve_structure: false,
+        }),
+    ] {
+        let result = file_ops::execute_action(&src_path, &action, true, dir.path()).unwrap();
+        assert_eq!(
+            result.new_path,
+            dest_dir.join(src_path.file_name().unwrap())
+        );
+        assert!(!dest_dir.exists());
+    }
+}
+
+#[test]
+fn test_move_and_copy_on_conflict() {
+    let dir = tempdir().unwrap();
+    let dest_dir = dir.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(dest_dir.join("report.txt"), "existing").unwrap();
+    let move_with = |on_conflict| {
+        Action::Move(MoveAction {
+            to: dest_dir.to_str().unwrap().to_string(),
+            preserve_structure: false,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict,
+        })
+    };
+    let src_path = dir.path().join("report.txt");
+    fs::write(&src_path, "new").unwrap();
+
+    let result = file_ops::execute_action(
+        &src_path,
+        &move_with(ConflictStrategy::Skip),
+        false,
+        dir.path(),
+    )
+    .unwrap();
+    assert_eq!(result.action, "conflict_skipped");
+    assert_eq!(result.new_path, src_path);
+    assert!(src_path.exists());
+
+    let result = file_ops::execute_action(
+        &src_path,
+        &move_with(ConflictStrategy::Error),
+        false,
+        dir.path(),
+    );
+    assert!(matches!(result, Err(TookaError::FileOperationError(_))));
+    assert!(src_path.exists());
+
+    let result = file_ops::execute_action(
+        &src_path,
+        &move_with(ConflictStrategy::Rename),
+        false,
+        dir.path(),
+    )
+    .unwrap();
+    assert_eq!(result.new_path, dest_dir.join("report_1.txt"));
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("report.txt")).unwrap(),
+        "existing"
+    );
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("report_1.txt")).unwrap(),
+        "new"
+    );
+
+    fs::write(&src_path, "newer").unwrap();
+    let copy_action = Action::Copy(CopyAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: None,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+    let result = file_ops::execute_action(&src_path, &copy_action, false, dir.path()).unwrap();
+    assert_eq!(result.new_path, dest_dir.join("report.txt"));
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("report.txt")).unwrap(),
+        "newer"
+    );
+}
+
+#[test]
+fn test_find_non_conflicting_path() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("report.pdf");
+    assert_eq!(
+        file_ops::find_non_conflicting_path(&dest, Path::exists),
+        dir.path().join("report_1.pdf")
+    );
+
+    fs::write(&dest, "").unwrap();
+    fs::write(dir.path().join("report_1.pdf"), "").unwrap();
+    assert_eq!(
+        file_ops::find_non_conflicting_path(&dest, Path::exists),
+        dir.path().join("report_2.pdf")
+    );
+
+    let no_extension = dir.path().join("README");
+    fs::write(&no_extension, "").unwrap();
+    assert_eq!(
+        file_ops::find_non_conflicting_path(&no_extension, Path::exists),
+        dir.path().join("README_1")
+    );
+}
+
+#[test]
+fn test_find_non_conflicting_path_after_999() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("photo.jpg");
+    fs::write(&dest, "").unwrap();
+    for n in 1..=999 {
+        fs::write(dir.path().join(format!("photo_{n}.jpg")), "").unwrap();
+    }
+
+    assert_eq!(
+        file_ops::find_non_conflicting_path(&dest, Path::exists),
+        dir.path().join("photo_1000.jpg")
+    );
+}
+
 #[test]
 fn test_rename_file() {
     let (dir, src_file) = setup_temp_dir_and_file();
@@ -52,6 +413,7 @@ fn test_rename_file() {
 
     let rename_action = Action::Rename(RenameAction {
         to: "renamed_{{ext}}".to_string(),
+        from_regex: None,
     });
 
     let result = file_ops::execute_action(&src_path, &rename_action, false, dir.path()).unwrap();
@@ -64,13 +426,36 @@ fn test_delete_file() {
     let (dir, src_file) = setup_temp_dir_and_file();
     let src_path = src_file.path().to_path_buf();
 
-    let delete_action = Action::Delete(DeleteAction { trash: false });
+    let delete_action = Action::Delete(DeleteAction {
+        trash: false,
+        missing_ok: false,
+    });
 
     let result = file_ops::execute_action(&src_path, &delete_action, false, dir.path()).unwrap();
     assert!(!src_path.exists());
     assert_eq!(result.action, "delete");
 }
 
+#[test]
+fn test_delete_missing_file() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("already_gone.txt");
+
+    let strict = Action::Delete(DeleteAction {
+        trash: false,
+        missing_ok: false,
+    });
+    let result = file_ops::execute_action(&src_path, &strict, false, dir.path());
+    assert!(matches!(result, Err(TookaError::Io(_))));
+
+    let lenient = Action::Delete(DeleteAction {
+        trash: true,
+        missing_ok: true,
+    });
+    let result = file_ops::execute_action(&src_path, &lenient, false, dir.path()).unwrap();
+    assert_eq!(result.action, "delete_skipped");
+}
+
 #[test]
 fn test_execute_file() {
     let (dir, src_file) = setup_temp_dir_and_file();
@@ -85,12 +470,154 @@ fn test_execute_file() {
     let execute_action = Action::Execute(ExecuteAction {
         command: script_path.to_str().unwrap().to_string(),
         args: vec![],
+        timeout_seconds: None,
     });
 
     let result = file_ops::execute_action(&src_path, &execute_action, false, dir.path()).unwrap();
     assert_eq!(result.action, "execute");
 }
 
+#[test]
+fn test_execute_with_timeout_completes() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+
+    let execute_action = Action::Execute(ExecuteAction {
+        command: "true".to_string(),
+        args: vec![],
+        timeout_seconds: Some(5),
+    });
+
+    let result = file_ops::execute_action(&src_path, &execute_action, false, dir.path()).unwrap();
+    assert_eq!(result.action, "execute");
+}
+
+#[test]
+fn test_execute_timeout_kills_command() {
+    let (dir, src_file) = setup_temp_dir_and_file();
+    let src_path = src_file.path().to_path_buf();
+
+    let execute_action = Action::Execute(ExecuteAction {
+        command: "sleep".to_string(),
+        args: vec!["10".to_string()],
+        timeout_seconds: Some(1),
+    });
+
+    let start = std::time::Instant::now();
+    let result = file_ops::execute_action(&src_path, &execute_action, false, dir.path());
+
+    assert!(matches!(
+        result,
+        Err(TookaError::ExecuteTimeout { ref command, timeout_secs: 1 }) if command == "sleep"
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn test_rename_with_regex_captures() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("project_v3.txt");
+    fs::write(&src_path, "content").unwrap();
+
+    let rename_action = Action::Rename(RenameAction {
+        to: "$1_release_$2.{{ext}}".to_string(),
+        from_regex: Some(r"^(.+)_v(\d+)$".to_string()),
+    });
+
+    let result = file_ops::execute_action(&src_path, &rename_action, false, dir.path()).unwrap();
+    assert_eq!(result.new_path, dir.path().join("project_release_3.txt"));
+    assert!(result.new_path.exists());
+}
+
+#[test]
+fn test_rename_with_filename_capture_placeholders() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("2024-03_invoice.pdf");
+    fs::write(&src_path, "content").unwrap();
+
+    let rename_action = Action::Rename(RenameAction {
+        to: "{{2}}_{{1}}_{{3}}.{{ext}}".to_string(),
+        from_regex: None,
+    });
+    let options = file_ops::FileOperationOptions {
+        template_values: [("1", "2024"), ("2", "invoice")]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into(),
+        ..Default::default()
+    };
+
+    let result = file_ops::execute_action_with_options(
+        &src_path,
+        &rename_action,
+        true,
+        dir.path(),
+        &options,
+    )
+    .unwrap();
+    // Groups without a value are replaced with nothing
+    assert_eq!(result.new_path, dir.path().join("invoice_2024_.pdf"));
+}
+
+#[test]
+fn test_rename_with_unmatched_regex_uses_placeholders() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("notes.txt");
+    fs::write(&src_path, "content").unwrap();
+
+    let rename_action = Action::Rename(RenameAction {
+        to: "{{filename}}_archived.txt".to_string(),
+        from_regex: Some(r"^(.+)_v(\d+)$".to_string()),
+    });
+
+    let result = file_ops::execute_action(&src_path, &rename_action, true, dir.path()).unwrap();
+    assert_eq!(result.new_path, dir.path().join("notes_archived.txt"));
+}
+
+#[test]
+fn test_rename_with_size_placeholders() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("report.pdf");
+    fs::write(&src_path, vec![0u8; 1536 * 1024]).unwrap();
+
+    let rename_action = Action::Rename(RenameAction {
+        to: "archive_{{size_human}}_{{size_mb}}_{{size_kb}}_{{size_bytes}}_{{filename}}.{{ext}}"
+            .to_string(),
+        from_regex: None,
+    });
+
+    let result = file_ops::execute_action(&src_path, &rename_action, true, dir.path()).unwrap();
+    assert_eq!(
+        result.new_path,
+        dir.path().join("archive_1.5MiB_2_1536_1572864_report.pdf")
+    );
+}
+
+#[cfg(feature = "pdf-meta")]
+#[test]
+fn test_rename_with_pdf_placeholders() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("scan_0042.pdf");
+    fs::write(
+        &src_path,
+        "%PDF-1.4\n1 0 obj\n<< /Title (Annual Report) /Author (Jane Doe) \
+         /CreationDate (D:20240301120000Z) >>\nendobj\n\
+         trailer\n<< /Size 2 /Info 1 0 R >>\n%%EOF\n",
+    )
+    .unwrap();
+
+    let rename_action = Action::Rename(RenameAction {
+        to: "{{pdf_author}} - {{pdf_title}}{{pdf_subject}} ({{pdf_creation_date|date:%Y}}).pdf"
+            .to_string(),
+        from_regex: None,
+    });
+
+    let result = file_ops::execute_action(&src_path, &rename_action, true, dir.path()).unwrap();
+    assert_eq!(
+        result.new_path,
+        dir.path().join("Jane Doe - Annual Report (2024).pdf")
+    );
+}
+
 #[test]
 fn test_skip_file() {
     let (dir, src_file) = setup_temp_dir_and_file();
@@ -102,3 +629,338 @@ fn test_skip_file() {
     assert_eq!(result.action, "skip");
     assert!(src_path.exists());
 }
+
+#[test]
+fn test_with_retry_retries_transient_errors() {
+    let retry = file_ops::RetryConfig {
+        max_attempts: 3,
+        initial_delay_ms: 1,
+        backoff_multiplier: 2.0,
+    };
+
+    let mut calls = 0;
+    let result = file_ops::with_retry(&retry, "test", || {
+        calls += 1;
+        if calls < 3 {
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        } else {
+            Ok(calls)
+        }
+    });
+    assert_eq!(result.unwrap(), 3);
+
+    let mut calls = 0;
+    let result: Result<(), _> = file_ops::with_retry(&retry, "test", || {
+        calls += 1;
+        Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+    });
+    assert_eq!(calls, 3);
+    assert!(matches!(
+        result,
+        Err(TookaError::ExhaustedRetries { attempts: 3, .. })
+    ));
+}
+
+#[test]
+fn test_with_retry_does_not_retry_other_errors() {
+    let retry = file_ops::RetryConfig {
+        max_attempts: 5,
+        ..Default::default()
+    };
+
+    let mut calls = 0;
+    let result: Result<(), _> = file_ops::with_retry(&retry, "test", || {
+        calls += 1;
+        Err(std::io::Error::from(std::io::ErrorKind::NotFound))
+    });
+    assert_eq!(calls, 1);
+    assert!(matches!(result, Err(TookaError::Io(_))));
+
+    // The default configuration keeps the plain IO error
+    let result: Result<(), _> =
+        file_ops::with_retry(&file_ops::RetryConfig::default(), "test", || {
+            Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+        });
+    assert!(matches!(result, Err(TookaError::Io(_))));
+}
+
+#[test]
+fn test_move_directory_requires_allow_directory() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("album");
+    fs::create_dir_all(src_dir.join("nested")).unwrap();
+    fs::write(src_dir.join("nested/photo.jpg"), "content").unwrap();
+
+    let dest_dir = dir.path().join("archive");
+    let mut move_action = MoveAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    };
+
+    let result = file_ops::execute_action(
+        &src_dir,
+        &Action::Move(move_action.clone()),
+        false,
+        dir.path(),
+    );
+    assert!(matches!(result, Err(TookaError::FileOperationError(_))));
+    assert!(src_dir.exists());
+
+    move_action.allow_directory = true;
+    let result =
+        file_ops::execute_action(&src_dir, &Action::Move(move_action), false, dir.path()).unwrap();
+    assert_eq!(result.new_path, dest_dir.join("album"));
+    assert!(result.new_path.join("nested/photo.jpg").exists());
+    assert!(!src_dir.exists());
+}
+
+#[test]
+fn test_move_with_verify_after_move() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("archive.tar");
+    fs::write(&src_path, "archived content").unwrap();
+    let checksum = file_ops::hash_file(&src_path).unwrap();
+
+    let move_action = Action::Move(MoveAction {
+        to: dir.path().join("vault").to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: true,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+
+    let result = file_ops::execute_action(&src_path, &move_action, false, dir.path()).unwrap();
+    assert!(!src_path.exists());
+    assert_eq!(file_ops::hash_file(&result.new_path).unwrap(), checksum);
+}
+
+#[test]
+fn test_verify_checksum_mismatch() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("file.txt");
+    fs::write(&path, "content").unwrap();
+    let checksum = file_ops::hash_file(&path).unwrap();
+
+    assert!(file_ops::verify_checksum(&path, &checksum).is_ok());
+    fs::write(&path, "c0ntent").unwrap();
+    assert!(matches!(
+        file_ops::verify_checksum(&path, &checksum),
+        Err(TookaError::ChecksumMismatch { expected, .. }) if expected == checksum
+    ));
+}
+
+#[test]
+fn test_pre_and_post_action_hooks() {
+    use std::sync::{Arc, Mutex};
+
+    // Hooks are global, so only act on files in this test's directory
+    let dir = tempdir().unwrap();
+    let root = dir.path().to_path_buf();
+    let pre_root = root.clone();
+    file_ops::set_pre_action_hook(move |path, _, _| {
+        !(path.starts_with(&pre_root) && path.extension().is_some_and(|ext| ext == "blocked"))
+    });
+    let audit = Arc::new(Mutex::new(Vec::new()));
+    let post_audit = Arc::clone(&audit);
+    file_ops::set_post_action_hook(move |path, _, result| {
+        if path.starts_with(&root) {
+            post_audit
+                .lock()
+                .unwrap()
+                .push((path.to_path_buf(), result.action.clone()));
+        }
+    });
+
+    let allowed = dir.path().join("allowed.txt");
+    let blocked = dir.path().join("secret.blocked");
+    fs::write(&allowed, "allowed").unwrap();
+    fs::write(&blocked, "blocked").unwrap();
+    let delete = Action::Delete(DeleteAction {
+        trash: false,
+        missing_ok: false,
+    });
+
+    let allowed_result = file_ops::execute_action(&allowed, &delete, false, dir.path()).unwrap();
+    let blocked_result = file_ops::execute_action(&blocked, &delete, false, dir.path()).unwrap();
+
+    assert_eq!(allowed_result.action, "delete");
+    assert!(!allowed.exists());
+    assert_eq!(blocked_result.action, "hook_skipped");
+    assert_eq!(blocked_result.new_path, blocked);
+    assert!(blocked.exists());
+    assert_eq!(
+        *audit.lock().unwrap(),
+        [
+            (allowed, "delete".to_string()),
+            (blocked, "hook_skipped".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_copy_and_move_if_newer_or_older_than() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("backup.db");
+    fs::write(&src_path, "data").unwrap();
+    let modified = chrono::DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&src_path)
+        .unwrap()
+        .set_modified(modified.into())
+        .unwrap();
+
+    let backup = dir.path().join("backup");
+    let copy = |newer: Option<&str>, older: Option<&str>| {
+        let action = Action::Copy(CopyAction {
+            to: backup.to_str().unwrap().to_string(),
+            preserve_structure: false,
+            preserve_depth: None,
+            if_newer_than: newer.map(str::to_string),
+            if_older_than: older.map(str::to_string),
+            on_conflict: ConflictStrategy::Overwrite,
+        });
+        file_ops::execute_action(&src_path, &action, false, dir.path()).unwrap()
+    };
+
+    let skipped = copy(Some("2024-03-02T00:00:00Z"), None);
+    assert_eq!(skipped.action, "condition_not_met");
+    assert_eq!(skipped.new_path, src_path);
+    assert!(!backup.exists());
+    assert_eq!(
+        copy(None, Some("2024-03-01T00:00:00Z")).action,
+        "condition_not_met"
+    );
+    let copied = copy(
+        Some("2024-02-01T00:00:00Z"),
+        Some("2024-04-01T00:00:00+02:00"),
+    );
+    assert_eq!(copied.action, "copy");
+    assert!(backup.join("backup.db").exists());
+
+    let move_action = Action::Move(MoveAction {
+        to: dir.path().join("archive").to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: None,
+        allow_directory: false,
+        verify_after_move: false,
+        if_newer_than: Some("2025-01-01T00:00:00Z".to_string()),
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+    let result = file_ops::execute_action(&src_path, &move_action, false, dir.path()).unwrap();
+    assert_eq!(result.action, "condition_not_met");
+    assert!(src_path.exists());
+}
+
+#[test]
+fn test_move_and_hardlink() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempdir().unwrap();
+    let linked = dir.path().join("photo.jpg");
+    let moved = dir.path().join("scan.jpg");
+    fs::write(&linked, "photo").unwrap();
+    fs::write(&moved, "scan").unwrap();
+    let library = dir.path().join("library");
+    let action = |link_back| {
+        Action::MoveAndHardlink(MoveAndHardlinkAction {
+            to: library.to_str().unwrap().to_string(),
+            link_back,
+        })
+    };
+
+    let result = file_ops::execute_action(&linked, &action(true), false, dir.path()).unwrap();
+    assert_eq!(result.action, "move_and_hardlink");
+    assert_eq!(result.new_path, library.join("photo.jpg"));
+    assert!(linked.exists());
+    assert_eq!(
+        fs::metadata(&linked).unwrap().ino(),
+        fs::metadata(&result.new_path).unwrap().ino()
+    );
+
+    let result = file_ops::execute_action(&moved, &action(false), false, dir.path()).unwrap();
+    assert!(!moved.exists());
+    assert_eq!(fs::read_to_string(result.new_path).unwrap(), "scan");
+
+    // An existing destination is replaced, as by a move
+    let notes = dir.path().join("notes.txt");
+    fs::write(&notes, "new").unwrap();
+    fs::write(library.join("notes.txt"), "old").unwrap();
+    let result = file_ops::execute_action(&notes, &action(true), false, dir.path()).unwrap();
+    assert_eq!(fs::read_to_string(&result.new_path).unwrap(), "new");
+    assert_eq!(
+        fs::metadata(&notes).unwrap().ino(),
+        fs::metadata(&result.new_path).unwrap().ino()
+    );
+
+    // Linking again to a destination that already is the same file keeps both
+    let result = file_ops::execute_action(&linked, &action(true), false, dir.path()).unwrap();
+    assert_eq!(
+        fs::metadata(&linked).unwrap().ino(),
+        fs::metadata(&result.new_path).unwrap().ino()
+    );
+    let mut names: Vec<_> = fs::read_dir(&library)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["notes.txt", "photo.jpg", "scan.jpg"]);
+}
+
+#[test]
+fn test_copy_dir_recursive() {
+    let dir = tempdir().unwrap();
+    let src_dir = dir.path().join("src");
+    fs::create_dir_all(src_dir.join("a/b")).unwrap();
+    fs::write(src_dir.join("top.txt"), "top").unwrap();
+    fs::write(src_dir.join("a/b/deep.txt"), "deep").unwrap();
+
+    let dest_dir = dir.path().join("dest");
+    file_ops::copy_dir_recursive(&src_dir, &dest_dir).unwrap();
+
+    assert_eq!(fs::read_to_string(dest_dir.join("top.txt")).unwrap(), "top");
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("a/b/deep.txt")).unwrap(),
+        "deep"
+    );
+}
+
+#[test]
+fn test_copy_leaves_no_temp_files() {
+    let dir = tempdir().unwrap();
+    let src_path = dir.path().join("video.mp4");
+    fs::write(&src_path, vec![7u8; 64 * 1024]).unwrap();
+    fs::set_permissions(&src_path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let dest_dir = dir.path().join("backup");
+    let copy_action = Action::Copy(CopyAction {
+        to: dest_dir.to_str().unwrap().to_string(),
+        preserve_structure: false,
+        preserve_depth: None,
+        if_newer_than: None,
+        if_older_than: None,
+        on_conflict: ConflictStrategy::Overwrite,
+    });
+
+    let result = file_ops::execute_action(&src_path, &copy_action, false, dir.path()).unwrap();
+    assert_eq!(
+        fs::read(&result.new_path).unwrap(),
+        fs::read(&src_path).unwrap()
+    );
+    assert_eq!(result.bytes_affected, 64 * 1024);
+    assert_eq!(
+        fs::metadata(&result.new_path).unwrap().permissions().mode() & 0o777,
+        0o644
+    );
+    assert_eq!(fs::read_dir(&dest_dir).unwrap().count(), 1);
+}
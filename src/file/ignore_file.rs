@@ -0,0 +1,167 @@
+//! Support for `.tookaignore` files, which exempt files from sorting.
+//!
+//! A `.tookaignore` file uses `.gitignore` syntax and applies to the directory
+//! it is in and everything below it:
+//! - Blank lines and lines starting with `#` are ignored
+//! - A leading `!` re-includes files excluded by an earlier pattern
+//! - A trailing `/` only matches directories
+//! - A pattern containing a `/` other than a trailing one is relative to the
+//!   directory of the `.tookaignore` file; otherwise it matches names at any depth
+//! - `*`, `?`, `[...]` and `**` work as in `.gitignore`
+//!
+//! Patterns of deeper files are checked after those of their parents, and the
+//! last matching pattern wins. Files inside an excluded directory can't be
+//! re-included.
+
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the per-directory ignore file
+pub const IGNORE_FILE_NAME: &str = ".tookaignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A single pattern of a `.tookaignore` file
+#[derive(Debug, Clone)]
+struct IgnorePattern {
+    /// Directory of the `.tookaignore` file the pattern came from
+    base: PathBuf,
+    pattern: Pattern,
+    /// Whether the pattern re-includes matching paths
+    negated: bool,
+    /// Whether the pattern only matches directories
+    dir_only: bool,
+    /// Whether the pattern matches the path relative to `base` instead of the name
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    /// Parses a line of a `.tookaignore` file, returning `None` for blank
+    /// lines, comments and invalid patterns
+    fn parse(line: &str, base: &Path) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+
+        match Pattern::new(line) {
+            Ok(pattern) => Some(Self {
+                base: base.to_path_buf(),
+                pattern,
+                negated,
+                dir_only,
+                anchored,
+            }),
+            Err(e) => {
+                log::warn!(
+                    "Ignoring invalid pattern '{line}' in {}: {e}",
+                    base.join(IGNORE_FILE_NAME).display()
+                );
+                None
+            }
+        }
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if !self.anchored {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            return self.pattern.matches_with(name, MATCH_OPTIONS);
+        }
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let relative: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        self.pattern
+            .matches_with(&relative.join("/"), MATCH_OPTIONS)
+    }
+}
+
+/// Decides which paths below a root directory are excluded by `.tookaignore`
+/// files, reading each directory's file once.
+#[derive(Debug, Default)]
+pub(crate) struct IgnoreMatcher {
+    /// Patterns that apply inside each visited directory, shallowest first
+    patterns: HashMap<PathBuf, Arc<Vec<IgnorePattern>>>,
+}
+
+impl IgnoreMatcher {
+    /// Whether `path` is excluded by the `.tookaignore` files of its parent
+    /// directories up to `root`. The ignore files themselves are always excluded.
+    pub(crate) fn is_ignored(&mut self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        if path
+            .file_name()
+            .is_some_and(|name| name == IGNORE_FILE_NAME)
+        {
+            return true;
+        }
+        let Some(parent) = path.parent().filter(|p| p.starts_with(root)) else {
+            return false;
+        };
+
+        self.patterns_for(root, parent)
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(path, is_dir))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+
+    /// Patterns of `dir` and all its parents up to `root`
+    fn patterns_for(&mut self, root: &Path, dir: &Path) -> Arc<Vec<IgnorePattern>> {
+        if let Some(patterns) = self.patterns.get(dir) {
+            return Arc::clone(patterns);
+        }
+
+        let inherited = match dir.parent() {
+            Some(parent) if dir != root && parent.starts_with(root) => {
+                self.patterns_for(root, parent)
+            }
+            _ => Arc::default(),
+        };
+        let patterns = match fs::read_to_string(dir.join(IGNORE_FILE_NAME)) {
+            Ok(content) => {
+                log::debug!("Loaded {}", dir.join(IGNORE_FILE_NAME).display());
+                let own: Vec<_> = content
+                    .lines()
+                    .filter_map(|line| IgnorePattern::parse(line, dir))
+                    .collect();
+                if own.is_empty() {
+                    inherited
+                } else {
+                    let mut combined = Vec::with_capacity(inherited.len() + own.len());
+                    combined.extend(inherited.iter().cloned());
+                    combined.extend(own);
+                    Arc::new(combined)
+                }
+            }
+            Err(_) => inherited,
+        };
+
+        self.patterns
+            .insert(dir.to_path_buf(), Arc::clone(&patterns));
+        patterns
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::sorter::collect_files;
+use tempfile::tempdir;
+
+/// Creates the given files (with parent directories) below `root`
+fn create_files(root: &Path, files: &[&str]) {
+    for file in files {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+}
+
+/// Collects files below `root` as sorted paths relative to it
+fn collected(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = collect_files(root)
+        .unwrap()
+        .into_iter()
+        .map(|f| f.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_tookaignore_excludes_files_and_directories() {
+    let dir = tempdir().unwrap();
+    create_files(
+        dir.path(),
+        &[
+            "keep.txt",
+            "debug.log",
+            "build/output.bin",
+            "docs/build.md",
+            "docs/notes.log",
+            "photos/raw/img.cr2",
+            "photos/img.jpg",
+        ],
+    );
+    fs::write(
+        dir.path().join(".tookaignore"),
+        "# comment\n\n*.log\nbuild/\n/photos/raw\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        collected(dir.path()),
+        [
+            PathBuf::from("docs/build.md"),
+            PathBuf::from("keep.txt"),
+            PathBuf::from("photos/img.jpg"),
+        ]
+    );
+}
+
+#[test]
+fn test_deeper_tookaignore_overrides_parent() {
+    let dir = tempdir().unwrap();
+    create_files(
+        dir.path(),
+        &[
+            "a.tmp",
+            "project/b.tmp",
+            "project/important.tmp",
+            "project/src/main.rs",
+        ],
+    );
+    fs::write(dir.path().join(".tookaignore"), "*.tmp\n").unwrap();
+    fs::write(
+        dir.path().join("project/.tookaignore"),
+        "!important.tmp\nsrc/**/*.rs\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        collected(dir.path()),
+        [PathBuf::from("project/important.tmp")]
+    );
+}
+
+#[test]
+fn test_tookaignore_does_not_apply_to_sibling_directories() {
+    let dir = tempdir().unwrap();
+    create_files(dir.path(), &["music/song.mp3", "videos/clip.mp3"]);
+    fs::write(dir.path().join("music/.tookaignore"), "*.mp3\n").unwrap();
+
+    assert_eq!(collected(dir.path()), [PathBuf::from("videos/clip.mp3")]);
+}
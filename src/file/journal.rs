@@ -0,0 +1,242 @@
+//! Journal of the file operations of sort runs, used to roll a run back.
+//!
+//! The journal is a JSON Lines file: each line is a [`JournalEntry`] for one
+//! move, copy, rename or delete, appended as soon as the operation succeeded
+//! so the journal stays usable after a crash. Entries of the same run share a
+//! `run_id`. Deleted files are moved into a `.tooka_trash` folder next to the
+//! journal instead of being removed, so they can be restored.
+
+use crate::{
+    core::error::TookaError,
+    file::file_ops::{RetryConfig, move_file},
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::SystemTime,
+};
+
+/// Name of the journal file in the logs folder
+pub const JOURNAL_FILE_NAME: &str = "journal.json";
+
+/// Name of the folder next to the journal that keeps deleted files
+pub const TRASH_FOLDER_NAME: &str = ".tooka_trash";
+
+/// Kind of operation recorded in the journal
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    /// Moved to `destination_path`; undone by moving it back
+    Move,
+    /// Copied to `destination_path`, the original is untouched; undone by
+    /// deleting the destination
+    Copy,
+    /// Renamed to `destination_path`; undone by renaming it back
+    Rename,
+    /// Deleted; undone by restoring `trash_path` if the file was kept there
+    Delete,
+}
+
+impl JournalAction {
+    /// Name of the action as stored in the journal
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Move => "move",
+            Self::Copy => "copy",
+            Self::Rename => "rename",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+/// A single operation of a sort run
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Start time of the run in nanoseconds since the Unix epoch, shared by
+    /// all entries of the run
+    pub run_id: u64,
+    /// Time of the operation in nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+    pub action: JournalAction,
+    /// ID of the rule the action belongs to
+    pub rule_id: String,
+    /// Path of the file before the operation
+    pub original_path: PathBuf,
+    /// Path of the file after the operation, `[deleted]` for deletes
+    pub destination_path: PathBuf,
+    /// Where a deleted file is kept, `None` if it went to the system trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trash_path: Option<PathBuf>,
+}
+
+/// Appends the operations of one sort run to a journal file. Safe to share
+/// between the threads of a run.
+#[derive(Debug)]
+pub struct Journal {
+    run_id: u64,
+    trash_dir: PathBuf,
+    /// Number of files moved to `trash_dir` so far, to give each a unique name
+    trashed: AtomicUsize,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// Opens the journal at `path` for a new run, creating it if needed.
+    ///
+    /// # Errors
+    /// Returns `TookaError::Io` if the journal can't be created or opened.
+    pub fn open(path: &Path) -> Result<Self, TookaError> {
+        let parent = path.parent().unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(parent)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let run_id = now_ns();
+        log::debug!("Journaling run {run_id} to {}", path.display());
+
+        Ok(Self {
+            run_id,
+            trash_dir: parent.join(TRASH_FOLDER_NAME).join(run_id.to_string()),
+            trashed: AtomicUsize::new(0),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// ID shared by the entries of this run
+    pub fn run_id(&self) -> u64 {
+        self.run_id
+    }
+
+    /// Returns a new path in this run's trash folder to keep `file_path` at
+    pub(crate) fn trash_path(&self, file_path: &Path) -> PathBuf {
+        let n = self.trashed.fetch_add(1, Ordering::Relaxed) + 1;
+        let name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.trash_dir.join(format!("{n}_{name}"))
+    }
+
+    /// Appends an operation of this run to the journal.
+    ///
+    /// # Errors
+    /// Returns `TookaError` if the entry can't be written.
+    pub fn record(
+        &self,
+        action: JournalAction,
+        rule_id: &str,
+        original_path: &Path,
+        destination_path: &Path,
+        trash_path: Option<&Path>,
+    ) -> Result<(), TookaError> {
+        let entry = JournalEntry {
+            run_id: self.run_id,
+            timestamp_ns: now_ns(),
+            action,
+            rule_id: rule_id.to_string(),
+            original_path: original_path.to_path_buf(),
+            destination_path: destination_path.to_path_buf(),
+            trash_path: trash_path.map(Path::to_path_buf),
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| TookaError::Other("Journal lock poisoned".into()))?;
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads all entries of the journal at `path`, oldest first. A missing
+/// journal has no entries; lines that can't be parsed are skipped.
+///
+/// # Errors
+/// Returns `TookaError::Io` if the journal exists but can't be read.
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>, TookaError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("Skipping line {} of {}: {e}", i + 1, path.display()),
+        }
+    }
+    Ok(entries)
+}
+
+/// Replaces the journal at `path` with `entries`
+pub(crate) fn write_journal(path: &Path, entries: &[JournalEntry]) -> Result<(), TookaError> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+    for entry in entries {
+        serde_json::to_writer(&mut temp, entry)?;
+        temp.write_all(b"\n")?;
+    }
+    temp.persist(path).map_err(|e| TookaError::Io(e.error))?;
+    Ok(())
+}
+
+/// Undoes the operation of `entry`. Files are never moved over an existing file.
+///
+/// # Errors
+/// Returns `TookaError` if the file to restore is missing, its original path
+/// is taken, or the filesystem call fails.
+pub(crate) fn undo(entry: &JournalEntry) -> Result<(), TookaError> {
+    let restore_from = match entry.action {
+        JournalAction::Copy => {
+            log::info!("Removing copy '{}'", entry.destination_path.display());
+            fs::remove_file(&entry.destination_path)?;
+            return Ok(());
+        }
+        JournalAction::Move | JournalAction::Rename => &entry.destination_path,
+        JournalAction::Delete => entry.trash_path.as_ref().ok_or_else(|| {
+            TookaError::FileOperationError(format!(
+                "'{}' was moved to the system trash; restore it from there",
+                entry.original_path.display()
+            ))
+        })?,
+    };
+
+    if fs::symlink_metadata(&entry.original_path).is_ok() {
+        return Err(TookaError::FileOperationError(format!(
+            "Can't restore '{}': the path is taken",
+            entry.original_path.display()
+        )));
+    }
+    log::info!(
+        "Restoring '{}' from '{}'",
+        entry.original_path.display(),
+        restore_from.display()
+    );
+    if let Some(parent) = entry.original_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(
+        restore_from,
+        &entry.original_path,
+        None,
+        &RetryConfig::default(),
+    )
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
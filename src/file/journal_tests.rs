@@ -0,0 +1,90 @@
+use std::fs;
+
+use crate::file::journal::{Journal, JournalAction, TRASH_FOLDER_NAME, read_journal, undo};
+use tempfile::tempdir;
+
+#[test]
+fn test_journal_records_entries() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("logs/journal.json");
+    assert!(read_journal(&path).unwrap().is_empty());
+
+    let journal = Journal::open(&path).unwrap();
+    journal
+        .record(
+            JournalAction::Move,
+            "docs",
+            &dir.path().join("a.txt"),
+            &dir.path().join("docs/a.txt"),
+            None,
+        )
+        .unwrap();
+    let trash_path = journal.trash_path(&dir.path().join("b.txt"));
+    assert!(trash_path.starts_with(dir.path().join("logs").join(TRASH_FOLDER_NAME)));
+    assert_ne!(trash_path, journal.trash_path(&dir.path().join("b.txt")));
+    journal
+        .record(
+            JournalAction::Delete,
+            "cleanup",
+            &dir.path().join("b.txt"),
+            "[deleted]".as_ref(),
+            Some(&trash_path),
+        )
+        .unwrap();
+
+    let entries = read_journal(&path).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.run_id == journal.run_id()));
+    assert_eq!(entries[0].action, JournalAction::Move);
+    assert_eq!(entries[0].rule_id, "docs");
+    assert!(entries[0].trash_path.is_none());
+    assert_eq!(entries[1].trash_path.as_ref(), Some(&trash_path));
+    assert!(entries[0].timestamp_ns <= entries[1].timestamp_ns);
+
+    let content = fs::read_to_string(&path).unwrap();
+    assert_eq!(content.lines().count(), 2);
+    assert!(content.contains(r#""action":"delete""#));
+}
+
+#[test]
+fn test_read_journal_skips_invalid_lines() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("journal.json");
+    let journal = Journal::open(&path).unwrap();
+    journal
+        .record(
+            JournalAction::Copy,
+            "rule",
+            "a".as_ref(),
+            "b".as_ref(),
+            None,
+        )
+        .unwrap();
+    let mut content = fs::read_to_string(&path).unwrap();
+    content.push_str("{not json\n\n");
+    fs::write(&path, content).unwrap();
+
+    assert_eq!(read_journal(&path).unwrap().len(), 1);
+}
+
+#[test]
+fn test_undo_does_not_overwrite() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("journal.json");
+    let (original, moved) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+    fs::write(&original, "new file").unwrap();
+    fs::write(&moved, "moved file").unwrap();
+    let journal = Journal::open(&path).unwrap();
+    journal
+        .record(JournalAction::Move, "rule", &original, &moved, None)
+        .unwrap();
+    let entry = &read_journal(&path).unwrap()[0];
+
+    assert!(undo(entry).is_err());
+    assert_eq!(fs::read_to_string(&original).unwrap(), "new file");
+
+    fs::remove_file(&original).unwrap();
+    undo(entry).unwrap();
+    assert_eq!(fs::read_to_string(&original).unwrap(), "moved file");
+    assert!(!moved.exists());
+}
@@ -1,7 +1,21 @@
+pub(crate) mod archive;
+pub(crate) mod compress;
 pub mod file_match;
 pub mod file_ops;
+pub mod ignore_file;
+pub mod journal;
 
+#[cfg(test)]
+mod archive_tests;
+#[cfg(test)]
+mod compress_tests;
+#[cfg(test)]
+mod file_match_prop_tests;
 #[cfg(test)]
 mod file_match_tests;
 #[cfg(test)]
 mod file_ops_tests;
+#[cfg(test)]
+mod ignore_file_tests;
+#[cfg(test)]
+mod journal_tests;
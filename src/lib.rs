@@ -0,0 +1,44 @@
+//! Core library for Tooka, a fast, rule-based file organizer.
+//!
+//! This crate contains everything the `tooka` CLI is built on: rule
+//! definitions and the rules file, file matching and file operations,
+//! the sorter, report generation, configuration, and logging.
+//!
+//! # Stability
+//!
+//! The types and functions re-exported from [`prelude`] are the stable API
+//! and follow semantic versioning. Public enums such as [`rules::rule::Action`]
+//! and [`core::error::TookaError`] are `#[non_exhaustive]`: new variants can
+//! be added in minor releases, so match on them with a wildcard arm.
+//!
+//! Everything else is public so the CLI can use it, but is considered
+//! internal and exempt from semver guarantees. Items hidden from the
+//! documentation (`#[doc(hidden)]`) are implementation details and may
+//! change or disappear in any release.
+
+pub mod common;
+pub mod core;
+pub mod file;
+pub mod rules;
+pub mod utils;
+
+/// Re-exports of the stable API for library users.
+///
+/// ```no_run
+/// use tooka::prelude::*;
+/// ```
+pub mod prelude {
+    pub use crate::common::config::Config;
+    pub use crate::core::context::Context;
+    pub use crate::core::error::TookaError;
+    pub use crate::core::report::ReportBuilder;
+    pub use crate::core::sorter::{
+        MatchResult, SortOptions, SortProgress, collect_files, sort_files,
+    };
+    pub use crate::rules::rule::{
+        Action, ArchiveAction, ArchiveFormat, CompressAction, CompressionFormat, Conditions,
+        ConflictStrategy, CopyAction, DeleteAction, ExecuteAction, HardlinkAction, MoveAction,
+        RenameAction, Rule, SetPermissionsAction, SymlinkAction, TouchAction,
+    };
+    pub use crate::rules::rules_file::RulesFile;
+}
@@ -1,16 +1,11 @@
 mod cli;
 mod commands;
-mod common;
 mod completions;
-mod core;
-mod file;
-mod rules;
-mod utils;
 
-use crate::common::logger::init_logger;
-use crate::core::context::{init_config, init_rules_file};
 use anyhow::Result;
 use clap::Parser;
+use tooka::common::logger::init_logger;
+use tooka::core::context::init_context;
 
 #[derive(Parser)]
 #[clap(
@@ -32,10 +27,13 @@ enum Commands {
     Config(commands::config::ConfigArgs),
     Export(commands::export::ExportArgs),
     List(commands::list::ListArgs),
+    Logs(commands::logs::LogsArgs),
     Remove(commands::remove::RemoveArgs),
+    Rollback(commands::rollback::RollbackArgs),
     Sort(commands::sort::SortArgs),
     Toggle(commands::toggle::ToggleArgs),
     Template(commands::template::TemplateArgs),
+    Update(commands::update::UpdateArgs),
     Validate(commands::validate::ValidateArgs),
 }
 
@@ -61,9 +59,18 @@ fn main() {
 fn run() -> Result<()> {
     let cli = Cli::parse();
 
-    init_config()?;
-    init_logger()?;
-    init_rules_file()?;
+    // A config that fails to load is exactly what a reset should fix
+    if let Commands::Config(args) = &cli.command
+        && args.reset
+    {
+        return commands::config::run(args);
+    }
+
+    // Writing to main.log may truncate it, which would lose the entries `logs` is about to read
+    if !matches!(cli.command, Commands::Logs(_)) {
+        init_logger()?;
+    }
+    init_context()?;
 
     log::info!("Tooka CLI started");
 
@@ -72,11 +79,14 @@ fn run() -> Result<()> {
         Commands::Add(args) => commands::add::run(&args)?,
         Commands::Export(args) => commands::export::run(args)?,
         Commands::List(args) => commands::list::run(args)?,
+        Commands::Logs(args) => commands::logs::run(&args)?,
         Commands::Remove(args) => commands::remove::run(&args)?,
+        Commands::Rollback(args) => commands::rollback::run(&args)?,
         Commands::Sort(args) => commands::sort::run(args)?,
         Commands::Toggle(args) => commands::toggle::run(&args)?,
         Commands::Completions(args) => completions::run(&args)?,
         Commands::Template(args) => commands::template::run(args)?,
+        Commands::Update(args) => commands::update::run(&args)?,
         Commands::Validate(args) => commands::validate::run(&args)?,
     }
 
@@ -1,3 +1,6 @@
 pub mod rule;
 pub mod rules_file;
 pub mod template;
+
+#[cfg(test)]
+mod rules_file_tests;
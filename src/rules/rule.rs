@@ -2,14 +2,17 @@
 //! Includes rule conditions, actions, and validation logic ensuring rule correctness.
 //! Supports complex matching criteria such as filename patterns, metadata, size, dates, etc.
 
-use std::{fs, path::Path};
+use std::{fs, path::Path, time::SystemTime};
 
 use crate::core::error::RuleValidationError;
+use crate::file::file_match::match_extensions;
 use crate::utils::date_parser::parse_date;
-use serde::{Deserialize, Serialize};
+use crate::utils::rename_pattern::template_capture_groups;
+use colored::Colorize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
 /// Represents a rule for file operations, specifying when it applies and what actions to take.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Rule {
     /// Unique identifier for the rule.
@@ -26,10 +29,36 @@ pub struct Rule {
     pub when: Conditions,
     /// Actions to perform when conditions match.
     pub then: Vec<Action>,
+    /// IDs of rules that must be evaluated before this one, regardless of priority.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Vec<String>>,
+    /// Labels grouping related rules, e.g. `media` or `cleanup`, see
+    /// [`crate::rules::rules_file::RulesFile::filter_by_tag`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
+/// Partial update of a [`Rule`]: every field that is set replaces the
+/// corresponding field of the rule, see [`Rule::update_from_yaml`].
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RuleUpdate {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub enabled: Option<bool>,
+    pub description: Option<String>,
+    pub priority: Option<u32>,
+    pub when: Option<Conditions>,
+    pub then: Option<Vec<Action>>,
+    pub after: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Maximum nesting depth of `all` and `any_of` sub-conditions
+pub const MAX_CONDITION_DEPTH: usize = 8;
+
 /// Contains matching criteria to determine when a rule applies.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Conditions {
     /// If true, matches if any condition is true (logical OR); otherwise all must match (AND).
@@ -42,43 +71,396 @@ pub struct Conditions {
     pub extensions: Option<Vec<String>>,
     /// Glob pattern for file path matching.
     pub path: Option<String>,
+    /// Glob patterns for file path matching; any one of them must match.
+    pub path_any: Option<Vec<String>>,
+    /// Glob patterns of paths to leave alone; a file whose path matches any of
+    /// them never matches, whatever the other conditions say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_path: Option<Vec<String>>,
+    /// Minimum directory depth below the source folder; files directly inside
+    /// it are at depth 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_depth: Option<usize>,
+    /// Maximum directory depth below the source folder, e.g. `0` for top-level
+    /// files only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
     /// File size range in KB.
     pub size_kb: Option<Range>,
-    /// MIME type filter.
+    /// If true, matches only empty (0 byte) files; if false, only non-empty
+    /// ones. Takes precedence over `size_kb`: a `size_kb` range that
+    /// contradicts it fails validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_empty_file: Option<bool>,
+    /// MIME type filter, optionally a comma-separated list with `!` negations.
     pub mime_type: Option<String>,
+    /// Regex pattern to match against the beginning of the file's content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_matches: Option<String>,
+    /// Number of bytes of content read for `content_matches`, 4096 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_max_bytes: Option<usize>,
+    /// Hash of the whole content, e.g. to find copies of a known file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<HashCondition>,
     /// Date range when the file was created.
     pub created_date: Option<DateRange>,
     /// Date range when the file was modified.
     pub modified_date: Option<DateRange>,
+    /// Number of whole days since the file was last modified, e.g. `min: 30`
+    /// for files older than a month. Files modified in the future have age 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_age_days: Option<Range>,
     /// Whether the file is a symbolic link.
     pub is_symlink: Option<bool>,
+    /// Number of hard links to the file, e.g. `min: 2` for hard-linked files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardlink_count: Option<Range>,
+    /// Permission bits of the file, e.g. `executable: true`. Unix only,
+    /// elsewhere every file passes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionCondition>,
+    /// If false, `filename` and `extensions` ignore case, so `jpg` also matches `.JPG`.
+    /// Defaults to true.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
     /// Additional metadata fields for matching.
     #[serde(default)]
     pub metadata: Option<Vec<MetadataField>>,
+    /// Sub-conditions that must all match, e.g. to combine with `any_of`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all: Option<Vec<Conditions>>,
+    /// Sub-conditions of which at least one must match. `any: true` is a
+    /// shorthand for listing each condition here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<Vec<Conditions>>,
+}
+
+impl Conditions {
+    /// Returns the number of conditions that are set, not counting `any`,
+    /// `case_sensitive` and `content_max_bytes`. The conditions in `all` and
+    /// `any_of` are counted one by one.
+    ///
+    /// An empty `metadata` list is not counted since it matches every file.
+    pub fn condition_count(&self) -> usize {
+        let own = [
+            self.filename.is_some(),
+            self.extensions.is_some(),
+            self.path.is_some(),
+            self.path_any.is_some(),
+            self.exclude_path.is_some(),
+            self.min_depth.is_some(),
+            self.max_depth.is_some(),
+            self.size_kb.is_some(),
+            self.is_empty_file.is_some(),
+            self.mime_type.is_some(),
+            self.content_matches.is_some(),
+            self.content_hash.is_some(),
+            self.created_date.is_some(),
+            self.modified_date.is_some(),
+            self.file_age_days.is_some(),
+            self.is_symlink.is_some(),
+            self.hardlink_count.is_some(),
+            self.permissions.is_some(),
+            self.metadata
+                .as_ref()
+                .is_some_and(|fields| !fields.is_empty()),
+        ]
+        .into_iter()
+        .filter(|set| *set)
+        .count();
+        let nested: usize = [&self.all, &self.any_of]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(Conditions::condition_count)
+            .sum();
+        own + nested
+    }
+
+    /// Returns true if no conditions are set, so every file matches.
+    pub fn is_trivially_true(&self) -> bool {
+        self.condition_count() == 0
+    }
+
+    /// Returns a one-line summary of the conditions that are set,
+    /// e.g. `extensions=jpg,png and size_kb=10..`.
+    pub fn summary(&self) -> String {
+        let range = |min: Option<String>, max: Option<String>| match (min, max) {
+            (None, None) => "any".to_string(),
+            (min, max) => format!("{}..{}", min.unwrap_or_default(), max.unwrap_or_default()),
+        };
+
+        let mut parts = Vec::new();
+        if let Some(filename) = &self.filename {
+            parts.push(format!("filename={filename}"));
+        }
+        if let Some(extensions) = &self.extensions {
+            parts.push(format!("extensions={}", extensions.join(",")));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("path={path}"));
+        }
+        if let Some(paths) = &self.path_any {
+            parts.push(format!("path_any={}", paths.join(",")));
+        }
+        if let Some(paths) = &self.exclude_path {
+            parts.push(format!("exclude_path={}", paths.join(",")));
+        }
+        if self.min_depth.is_some() || self.max_depth.is_some() {
+            let depth = range(
+                self.min_depth.map(|v| v.to_string()),
+                self.max_depth.map(|v| v.to_string()),
+            );
+            parts.push(format!("depth={depth}"));
+        }
+        if let Some(size) = &self.size_kb {
+            let size = range(
+                size.min.map(|v| v.to_string()),
+                size.max.map(|v| v.to_string()),
+            );
+            parts.push(format!("size_kb={size}"));
+        }
+        if let Some(is_empty_file) = self.is_empty_file {
+            parts.push(format!("is_empty_file={is_empty_file}"));
+        }
+        if let Some(mime_type) = &self.mime_type {
+            parts.push(format!("mime_type={mime_type}"));
+        }
+        if let Some(pattern) = &self.content_matches {
+            parts.push(format!("content_matches={pattern}"));
+        }
+        if let Some(hash) = &self.content_hash {
+            parts.push(format!(
+                "content_hash={}:{}",
+                hash.algorithm.name(),
+                hash.value
+            ));
+        }
+        if let Some(dates) = &self.created_date {
+            parts.push(format!(
+                "created_date={}",
+                range(dates.from.clone(), dates.to.clone())
+            ));
+        }
+        if let Some(dates) = &self.modified_date {
+            parts.push(format!(
+                "modified_date={}",
+                range(dates.from.clone(), dates.to.clone())
+            ));
+        }
+        if let Some(age) = &self.file_age_days {
+            let age = range(
+                age.min.map(|v| v.to_string()),
+                age.max.map(|v| v.to_string()),
+            );
+            parts.push(format!("file_age_days={age}"));
+        }
+        if let Some(is_symlink) = self.is_symlink {
+            parts.push(format!("is_symlink={is_symlink}"));
+        }
+        if let Some(count) = &self.hardlink_count {
+            let count = range(
+                count.min.map(|v| v.to_string()),
+                count.max.map(|v| v.to_string()),
+            );
+            parts.push(format!("hardlink_count={count}"));
+        }
+        if let Some(permissions) = &self.permissions {
+            let flags: Vec<_> = [
+                ("readable", permissions.readable),
+                ("writable", permissions.writable),
+                ("executable", permissions.executable),
+            ]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| format!("{name}={value}")))
+            .collect();
+            parts.push(format!("permissions({})", flags.join(",")));
+        }
+        for field in self.metadata.iter().flatten() {
+            let operator = match field.comparison {
+                MetadataComparison::Equals => "=",
+                MetadataComparison::Contains => "~",
+                MetadataComparison::GreaterThan => ">",
+                MetadataComparison::LessThan => "<",
+                MetadataComparison::Between => " in ",
+            };
+            parts.push(format!(
+                "{}{operator}{}",
+                field.key,
+                field.value.as_deref().unwrap_or("*")
+            ));
+        }
+
+        for (subconditions, separator) in [(&self.all, " and "), (&self.any_of, " or ")] {
+            if let Some(subconditions) = subconditions {
+                let summaries: Vec<_> = subconditions.iter().map(Conditions::summary).collect();
+                parts.push(format!("({})", summaries.join(separator)));
+            }
+        }
+
+        if parts.is_empty() {
+            return "every file".to_string();
+        }
+        let separator = if self.any.unwrap_or(false) {
+            " or "
+        } else {
+            " and "
+        };
+        parts.join(separator)
+    }
 }
 
 /// Represents a single metadata field to match against
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct MetadataField {
     /// Metadata field key (e.g., "EXIF:DateTime")
     pub key: String,
-    /// Optional value to match against the field
+    /// Optional value to match against the field; for `between`, an inclusive
+    /// `min..max` range
     pub value: Option<String>,
+    /// Type the field and `value` are parsed as before comparing them
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub value_type: MetadataValueType,
+    /// How the field is compared with `value`
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub comparison: MetadataComparison,
 }
 
-/// Represents a data range for matching files
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// How the value of a metadata field is interpreted
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataValueType {
+    /// Text, compared with a glob pattern or as a substring
+    #[default]
+    String,
+    /// Whole number, e.g. an ISO speed
+    Integer,
+    /// Decimal number; EXIF fractions such as `1/100` are converted
+    Float,
+    /// Date in any format accepted by date conditions, or EXIF's `YYYY:MM:DD HH:MM:SS`
+    Date,
+}
+
+/// How a metadata field is compared with the expected value
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataComparison {
+    /// Glob match for strings, equality for other types
+    #[default]
+    Equals,
+    /// Substring match, only for strings
+    Contains,
+    /// Field is greater than the value
+    GreaterThan,
+    /// Field is less than the value
+    LessThan,
+    /// Field is within the inclusive `min..max` range
+    Between,
+}
+
+/// Parsed non-string metadata value
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub(crate) enum MetadataValue {
+    Integer(i64),
+    Float(f64),
+    Date(chrono::DateTime<chrono::Utc>),
+}
+
+impl MetadataValueType {
+    /// Parses `value` as this type; `None` for strings or unparsable values
+    pub(crate) fn parse(self, value: &str) -> Option<MetadataValue> {
+        let value = value.trim();
+        match self {
+            Self::String => None,
+            Self::Integer => value.parse().ok().map(MetadataValue::Integer),
+            Self::Float => value.parse().ok().map(MetadataValue::Float),
+            Self::Date => parse_date(value)
+                .ok()
+                .or_else(|| {
+                    ["%Y:%m:%d %H:%M:%S", "%Y-%m-%d %H:%M:%S"]
+                        .iter()
+                        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(value, fmt).ok())
+                        .map(|dt| dt.and_utc())
+                })
+                .map(MetadataValue::Date),
+        }
+    }
+}
+
+impl MetadataField {
+    /// Checks that `comparison` suits `value_type` and that `value` parses as it
+    fn validate_value(&self) -> Result<(), String> {
+        use MetadataComparison as C;
+
+        let Some(value) = &self.value else {
+            return match self.comparison {
+                C::Equals => Ok(()),
+                _ => Err(format!(
+                    "Metadata key '{}' needs a value to compare",
+                    self.key
+                )),
+            };
+        };
+        let is_string = self.value_type == MetadataValueType::String;
+        match self.comparison {
+            C::Contains if !is_string => {
+                return Err(format!(
+                    "Metadata key '{}': 'contains' only works with string values",
+                    self.key
+                ));
+            }
+            C::GreaterThan | C::LessThan | C::Between if is_string => {
+                return Err(format!(
+                    "Metadata key '{}': ordered comparisons need an integer, float or date value_type",
+                    self.key
+                ));
+            }
+            _ => {}
+        }
+        if is_string {
+            return Ok(());
+        }
+
+        let parse = |v: &str| {
+            self.value_type.parse(v).ok_or_else(|| {
+                format!(
+                    "Metadata key '{}': '{v}' is not a valid {:?} value",
+                    self.key, self.value_type
+                )
+            })
+        };
+        if self.comparison == C::Between {
+            let (min, max) = value.split_once("..").ok_or_else(|| {
+                format!(
+                    "Metadata key '{}': 'between' needs a 'min..max' value",
+                    self.key
+                )
+            })?;
+            if parse(min)? > parse(max)? {
+                return Err(format!("Metadata key '{}': range min > max", self.key));
+            }
+        } else {
+            parse(value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a data range for matching files, such as a size in KB or a
+/// hard link count
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Range {
-    /// Minimum size in KB (inclusive)
+    /// Minimum value (inclusive)
     pub min: Option<u64>,
-    /// Maximum size in KB (inclusive)
+    /// Maximum value (inclusive)
     pub max: Option<u64>,
 }
 
 /// Represents a date range for matching files
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct DateRange {
     /// Optional start date in RFC3339 format (inclusive)
@@ -87,9 +469,68 @@ pub struct DateRange {
     pub to: Option<String>,
 }
 
+/// Matches files by their Unix permission bits. A flag is set if any of the
+/// owner, group or other bits allows it, e.g. `executable` for mode `0o744`.
+/// Flags that are not given are not checked.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable: Option<bool>,
+}
+
+/// Matches files whose content has a known hash
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HashCondition {
+    /// Hash algorithm, SHA-256 by default
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// Expected hash as a hex string, in any case
+    pub value: String,
+    /// Files larger than this many MB are not hashed and never match, 500 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+}
+
+/// Hash algorithm of a [`HashCondition`]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Md5,
+}
+
+impl HashAlgorithm {
+    /// Name of the algorithm as written in rules
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Md5 => "md5",
+        }
+    }
+
+    /// Number of hex digits of a hash
+    pub fn hex_len(self) -> usize {
+        match self {
+            Self::Sha256 => 64,
+            Self::Md5 => 32,
+        }
+    }
+}
+
 /// Represents an action to perform when a rule matches
-#[derive(Debug, Serialize, Deserialize, Clone)]
+///
+/// New actions may be added in minor releases, so matches on this enum
+/// outside of Tooka need a wildcard arm.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "action", rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum Action {
     /// Move the file to a new location
     Move(MoveAction),
@@ -101,12 +542,84 @@ pub enum Action {
     Delete(DeleteAction),
     /// Executes a CLI command or script
     Execute(ExecuteAction),
+    /// Move the file, optionally keeping a hard link at the original location
+    #[serde(rename = "move_and_hardlink")]
+    MoveAndHardlink(MoveAndHardlinkAction),
+    /// Add the file to an archive shared by all files of the sort run
+    Archive(ArchiveAction),
+    /// Create a symbolic link to the file, leaving the file in place
+    Symlink(SymlinkAction),
+    /// Create a hard link to the file, leaving the file in place
+    Hardlink(HardlinkAction),
+    /// Write a compressed copy of the file, leaving the file in place
+    Compress(CompressAction),
+    /// Change the permission bits of the file, e.g. to make scripts executable
+    #[serde(rename = "set_permissions")]
+    SetPermissions(SetPermissionsAction),
+    /// Update the modification and creation times of the file in place
+    Touch(TouchAction),
     /// Skip the file without any action
     Skip,
 }
 
+impl Action {
+    /// Returns a short description of the action, e.g. `move -> ~/Pictures`.
+    pub fn summary(&self) -> String {
+        match self {
+            Action::Move(inner) => format!("move -> {}", inner.to),
+            Action::Copy(inner) => format!("copy -> {}", inner.to),
+            Action::Rename(inner) => format!("rename -> {}", inner.to),
+            Action::Delete(inner) if inner.trash => "delete (trash)".to_string(),
+            Action::Delete(_) => "delete".to_string(),
+            Action::Execute(inner) if inner.args.is_empty() => {
+                format!("execute {}", inner.command)
+            }
+            Action::Execute(inner) => format!("execute {} {}", inner.command, inner.args.join(" ")),
+            Action::MoveAndHardlink(inner) if inner.link_back => {
+                format!("move (hard link back) -> {}", inner.to)
+            }
+            Action::MoveAndHardlink(inner) => format!("move -> {}", inner.to),
+            Action::Archive(inner) => format!("archive -> {}", inner.to),
+            Action::Symlink(inner) => format!("symlink -> {}", inner.to),
+            Action::Hardlink(inner) => format!("hardlink -> {}", inner.to),
+            Action::Compress(inner) => {
+                format!("compress ({}) -> {}", inner.format.name(), inner.to)
+            }
+            Action::SetPermissions(inner) => format!("set permissions {:#o}", inner.mode),
+            Action::Touch(inner) => match (&inner.set_modified, &inner.set_created) {
+                (None, None) => "touch".to_string(),
+                (Some(modified), None) => format!("touch (modified={modified})"),
+                (None, Some(created)) => format!("touch (created={created})"),
+                (Some(modified), Some(created)) => {
+                    format!("touch (modified={modified}, created={created})")
+                }
+            },
+            Action::Skip => "skip".to_string(),
+        }
+    }
+
+    /// Returns the action type as written in the `action` field of a rule.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Move(_) => "move",
+            Action::Copy(_) => "copy",
+            Action::Rename(_) => "rename",
+            Action::Delete(_) => "delete",
+            Action::Execute(_) => "execute",
+            Action::MoveAndHardlink(_) => "move_and_hardlink",
+            Action::Archive(_) => "archive",
+            Action::Symlink(_) => "symlink",
+            Action::Hardlink(_) => "hardlink",
+            Action::Compress(_) => "compress",
+            Action::SetPermissions(_) => "set_permissions",
+            Action::Touch(_) => "touch",
+            Action::Skip => "skip",
+        }
+    }
+}
+
 /// Represents a move action, specifying the destination path and whether to preserve structure
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct MoveAction {
     /// Destination path where the file should be moved
@@ -114,10 +627,28 @@ pub struct MoveAction {
     /// If true, preserves the directory structure relative to the source path
     #[serde(default)]
     pub preserve_structure: bool,
+    /// Preserves only the last N directories of the path relative to the source path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preserve_depth: Option<u32>,
+    /// If true, whole directories may be moved instead of only regular files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_directory: bool,
+    /// If true, the SHA-256 of the moved file is compared with the source's
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verify_after_move: bool,
+    /// Only act on files last modified after this RFC 3339 date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_newer_than: Option<String>,
+    /// Only act on files last modified before this RFC 3339 date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_older_than: Option<String>,
+    /// What to do when a file already exists at the destination
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub on_conflict: ConflictStrategy,
 }
 
 /// Represents a copy action, specifying the destination path and whether to preserve structure
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct CopyAction {
     /// Destination path where the file should be copied
@@ -125,33 +656,249 @@ pub struct CopyAction {
     /// If true, preserves the directory structure relative to the source path
     #[serde(default)]
     pub preserve_structure: bool,
+    /// Preserves only the last N directories of the path relative to the source path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preserve_depth: Option<u32>,
+    /// Only act on files last modified after this RFC 3339 date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_newer_than: Option<String>,
+    /// Only act on files last modified before this RFC 3339 date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_older_than: Option<String>,
+    /// What to do when a file already exists at the destination
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub on_conflict: ConflictStrategy,
+}
+
+/// What a move or copy does when a file already exists at its destination
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Leave the file where it is
+    Skip,
+    /// Append `_1`, `_2`... to the file name until it is free
+    Rename,
+    /// Fail the action
+    Error,
+}
+
+/// Represents a move that can leave a hard link to the moved file at its
+/// original location, so it appears in both places while using disk space once.
+/// A file already at the destination is replaced, with or without the link.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MoveAndHardlinkAction {
+    /// Destination path where the file should be moved
+    pub to: String,
+    /// If true, a hard link to the new location is left at the original path;
+    /// both paths must be on the same filesystem
+    #[serde(default)]
+    pub link_back: bool,
+}
+
+/// Represents a symlink action: a symbolic link to the file is created in the
+/// destination directory. Only supported on Unix.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SymlinkAction {
+    /// Directory where the link should be created
+    pub to: String,
+    /// If true, preserves the directory structure relative to the source path
+    #[serde(default)]
+    pub preserve_structure: bool,
+}
+
+/// Represents a hardlink action: a hard link to the file is created in the
+/// destination directory, which must be on the same filesystem
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HardlinkAction {
+    /// Directory where the link should be created
+    pub to: String,
+    /// If true, preserves the directory structure relative to the source path
+    #[serde(default)]
+    pub preserve_structure: bool,
+}
+
+/// Represents an archive action: every file of a sort run whose archive path
+/// expands to the same file is stored in one archive, written once all files
+/// have been processed. The original files are left in place.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveAction {
+    /// Path of the archive, can include placeholders such as `{{year}}` or `{{ext}}`
+    pub to: String,
+    /// Archive format, zip by default
+    #[serde(default)]
+    pub format: ArchiveFormat,
+    /// If true, files are stored under their path relative to the source path
+    /// instead of just their name
+    #[serde(default)]
+    pub preserve_structure: bool,
+}
+
+/// Format of the archive written by an [`ArchiveAction`]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    /// Zip archive
+    #[default]
+    Zip,
+    /// Gzip-compressed tar archive
+    #[serde(alias = "tar.gz")]
+    TarGz,
+}
+
+/// Represents a compress action: a compressed copy of the file named
+/// `<file name>.gz`, `.zst` or `.bz2` is written to the destination directory,
+/// replacing any existing file. The original file is left in place and later
+/// actions of the rule still apply to it, so e.g. a following `delete`
+/// removes the original.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CompressAction {
+    /// Directory where the compressed file should be written
+    pub to: String,
+    /// Compression format, gzip by default
+    #[serde(default)]
+    pub format: CompressionFormat,
+    /// If true, preserves the directory structure relative to the source path
+    #[serde(default)]
+    pub preserve_structure: bool,
+}
+
+/// Format of the file written by a [`CompressAction`]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionFormat {
+    /// Gzip, `.gz`
+    #[default]
+    Gzip,
+    /// Zstandard, `.zst`
+    Zstd,
+    /// Bzip2, `.bz2`
+    Bzip2,
+}
+
+impl CompressionFormat {
+    /// Name of the format as written in rules
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Bzip2 => "bzip2",
+        }
+    }
+
+    /// Extension appended to the name of compressed files
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+            Self::Bzip2 => "bz2",
+        }
+    }
+}
+
+/// Highest mode accepted by a [`SetPermissionsAction`], including the setuid,
+/// setgid and sticky bits
+pub const MAX_PERMISSION_MODE: u32 = 0o7777;
+
+/// Sets the permission bits of the file in place. Only supported on Unix,
+/// elsewhere the action does nothing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SetPermissionsAction {
+    /// New mode, written as a decimal number or as an octal string such as
+    /// `"0o755"`. YAML also reads an unquoted `0o755` as octal.
+    #[serde(
+        deserialize_with = "deserialize_mode",
+        serialize_with = "serialize_mode"
+    )]
+    pub mode: u32,
+}
+
+/// Reads a file mode given as a number, or as a string holding a decimal or
+/// `0o`-prefixed octal number
+fn deserialize_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Mode {
+        Number(u32),
+        Text(String),
+    }
+
+    match Mode::deserialize(deserializer)? {
+        Mode::Number(mode) => Ok(mode),
+        Mode::Text(text) => {
+            let text = text.trim();
+            match text.strip_prefix("0o") {
+                Some(octal) => u32::from_str_radix(octal, 8).ok(),
+                None => text.parse().ok(),
+            }
+            .ok_or_else(|| de::Error::custom(format!("invalid mode '{text}', expected e.g. 0o755")))
+        }
+    }
+}
+
+/// Writes a file mode as an octal string, e.g. `"0o755"`
+fn serialize_mode<S: Serializer>(mode: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{mode:#o}"))
+}
+
+/// Updates the timestamps of the file in place, like the `touch` command
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TouchAction {
+    /// New modification time as an RFC 3339 date, defaults to the time the
+    /// action runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_modified: Option<String>,
+    /// New creation time as an RFC 3339 date, left unchanged when omitted.
+    /// Only supported on Windows and macOS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_created: Option<String>,
 }
 
 /// Represents a rename action, specifying the new name for the file
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct RenameAction {
-    /// New name for the file, can include metadata placeholders
+    /// New name for the file, can include metadata placeholders, and the
+    /// capture groups of the rule's `filename` regex as `{{1}}`, `{{2}}`, ...
     pub to: String,
+    /// Regex matched against the file name without extension; its capture
+    /// groups can be used as `$1`, `$2`, ... in `to`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_regex: Option<String>,
 }
 
 /// Represents a delete action, specifying whether to move the file to trash
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct DeleteAction {
     /// If true, moves the file to the trash instead of permanently deleting it
     #[serde(default)]
     pub trash: bool,
+    /// If true, a file that no longer exists is skipped instead of failing the action
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub missing_ok: bool,
 }
 
 /// Represents an execute action, specifying the command to run and its arguments
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct ExecuteAction {
     /// Command to execute, can be a shell command or script
     pub command: String,
     /// Arguments to pass to the command
     pub args: Vec<String>,
+    /// Kills the command if it runs longer than this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
 }
 
 /// Validates the rule's fields and consistency.
@@ -208,7 +955,102 @@ impl Rule {
             return Err(RuleValidationError::NoActions(self.id.clone()));
         }
 
-        if let Some(metadata) = &self.when.metadata {
+        if let Some(after) = &self.after {
+            if after.iter().any(|id| id.trim().is_empty()) {
+                return Err(RuleValidationError::InvalidFormat(format!(
+                    "rule {}: 'after' entries must be non-empty rule IDs",
+                    self.id
+                )));
+            }
+        }
+
+        if let Some(tags) = &self.tags {
+            if let Some(tag) = tags.iter().find(|tag| !is_valid_tag(tag)) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!(
+                        "Invalid tag '{tag}': tags may only contain letters, digits and hyphens"
+                    ),
+                ));
+            }
+        }
+
+        self.validate_conditions(&self.when, 0)?;
+
+        if let Some(value) = self.action_validation() {
+            return value;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the regexes, ranges and dates of `conditions` and its
+    /// sub-conditions, which are `depth` levels below the rule's `when`
+    fn validate_conditions(
+        &self,
+        conditions: &Conditions,
+        depth: usize,
+    ) -> Result<(), RuleValidationError> {
+        if depth > MAX_CONDITION_DEPTH {
+            return Err(RuleValidationError::InvalidCondition(
+                self.id.clone(),
+                format!("Conditions are nested more than {MAX_CONDITION_DEPTH} levels deep"),
+            ));
+        }
+
+        if let Some(filename) = &conditions.filename {
+            if let Err(e) = regex::Regex::new(filename) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!("Invalid regex in 'filename': {e}"),
+                ));
+            }
+        }
+
+        for pattern in conditions.exclude_path.iter().flatten() {
+            if let Err(e) = glob::Pattern::new(pattern) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!("Invalid glob in 'exclude_path': {e}"),
+                ));
+            }
+        }
+
+        if let Some(pattern) = &conditions.content_matches {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!("Invalid regex in 'content_matches': {e}"),
+                ));
+            }
+        }
+        if conditions.content_max_bytes == Some(0) {
+            return Err(RuleValidationError::InvalidCondition(
+                self.id.clone(),
+                "content_max_bytes must be greater than 0".into(),
+            ));
+        }
+
+        if let Some(hash) = &conditions.content_hash {
+            let (value, len) = (hash.value.trim(), hash.algorithm.hex_len());
+            if value.len() != len || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!(
+                        "content_hash value must be {len} hex digits for {}",
+                        hash.algorithm.name()
+                    ),
+                ));
+            }
+            if hash.max_size_mb == Some(0) {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    "content_hash max_size_mb must be greater than 0".into(),
+                ));
+            }
+        }
+
+        if let Some(metadata) = &conditions.metadata {
             let mut keys = std::collections::HashSet::new();
             for field in metadata {
                 if !keys.insert(&field.key) {
@@ -217,10 +1059,13 @@ impl Rule {
                         format!("Duplicate metadata key '{}'", field.key),
                     ));
                 }
+                if let Err(msg) = field.validate_value() {
+                    return Err(RuleValidationError::InvalidCondition(self.id.clone(), msg));
+                }
             }
         }
 
-        if let Some(size) = &self.when.size_kb {
+        if let Some(size) = &conditions.size_kb {
             if let (Some(min), Some(max)) = (size.min, size.max) {
                 if min > max {
                     return Err(RuleValidationError::InvalidCondition(
@@ -229,11 +1074,54 @@ impl Rule {
                     ));
                 }
             }
+            // An empty file has size 0, so only ranges down to 0 KB allow it
+            let contradiction = match conditions.is_empty_file {
+                Some(true) => size.min.is_some_and(|min| min > 0),
+                Some(false) => size.max == Some(0),
+                None => false,
+            };
+            if contradiction {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    "size_kb contradicts is_empty_file".into(),
+                ));
+            }
+        }
+
+        if let Some(count) = &conditions.hardlink_count {
+            if let (Some(min), Some(max)) = (count.min, count.max) {
+                if min > max {
+                    return Err(RuleValidationError::InvalidCondition(
+                        self.id.clone(),
+                        "Invalid hardlink_count range: min > max".into(),
+                    ));
+                }
+            }
+        }
+
+        if let (Some(min), Some(max)) = (conditions.min_depth, conditions.max_depth) {
+            if min > max {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    "Invalid depth range: min_depth > max_depth".into(),
+                ));
+            }
+        }
+
+        if let Some(age) = &conditions.file_age_days {
+            if let (Some(min), Some(max)) = (age.min, age.max) {
+                if min > max {
+                    return Err(RuleValidationError::InvalidCondition(
+                        self.id.clone(),
+                        "Invalid file_age_days range: min > max".into(),
+                    ));
+                }
+            }
         }
 
         for (label, date_range) in [
-            ("created_date", &self.when.created_date),
-            ("modified_date", &self.when.modified_date),
+            ("created_date", &conditions.created_date),
+            ("modified_date", &conditions.modified_date),
         ] {
             if let Some(range) = date_range {
                 if let Some(from) = &range.from {
@@ -255,13 +1143,165 @@ impl Rule {
             }
         }
 
-        if let Some(value) = self.action_validation() {
-            return value;
+        for (label, subconditions) in [("all", &conditions.all), ("any_of", &conditions.any_of)] {
+            let Some(subconditions) = subconditions else {
+                continue;
+            };
+            if subconditions.is_empty() {
+                return Err(RuleValidationError::InvalidCondition(
+                    self.id.clone(),
+                    format!("'{label}' must contain at least one condition"),
+                ));
+            }
+            for subcondition in subconditions {
+                self.validate_conditions(subcondition, depth + 1)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Applies a partial update given as YAML, e.g. `"priority: 10"`, and
+    /// validates the result.
+    ///
+    /// Fields missing from the patch are left unchanged; `when` and `then` are
+    /// replaced as a whole. The rule is only modified if the patched rule is valid.
+    ///
+    /// # Errors
+    /// Returns a [`RuleValidationError`] if the patch is not valid YAML, contains
+    /// unknown fields, or the patched rule fails validation.
+    pub fn update_from_yaml(&mut self, patch_yaml: &str) -> Result<(), RuleValidationError> {
+        let patch: serde_yaml::Value = serde_yaml::from_str(patch_yaml)
+            .map_err(|e| RuleValidationError::InvalidFormat(format!("YAML parsing failed: {e}")))?;
+        let update: RuleUpdate = if patch.is_null() {
+            RuleUpdate::default()
+        } else {
+            serde_yaml::from_value(patch).map_err(|e| {
+                RuleValidationError::InvalidFormat(format!("Invalid rule update: {e}"))
+            })?
+        };
+
+        let mut patched = self.clone();
+        patched.apply_update(update);
+        patched.validate(true)?;
+        *self = patched;
+        Ok(())
+    }
+
+    fn apply_update(&mut self, update: RuleUpdate) {
+        let RuleUpdate {
+            id,
+            name,
+            enabled,
+            description,
+            priority,
+            when,
+            then,
+            after,
+            tags,
+        } = update;
+        if let Some(id) = id {
+            self.id = id;
+        }
+        if let Some(name) = name {
+            self.name = name;
+        }
+        if let Some(enabled) = enabled {
+            self.enabled = enabled;
+        }
+        if description.is_some() {
+            self.description = description;
+        }
+        if let Some(priority) = priority {
+            self.priority = priority;
+        }
+        if let Some(when) = when {
+            self.when = when;
+        }
+        if let Some(then) = then {
+            self.then = then;
+        }
+        if after.is_some() {
+            self.after = after;
+        }
+        if tags.is_some() {
+            self.tags = tags;
+        }
+    }
+
+    /// Quick check on the extension of `file_path` alone: returns false if the
+    /// rule can't match the file because its `extensions` condition fails,
+    /// without reading the file. A true result still needs the full match.
+    ///
+    /// Rules without `extensions` or with `any: true` apply to every file.
+    pub fn applicable_to(&self, file_path: &Path) -> bool {
+        if self.when.any == Some(true) {
+            return true;
+        }
+        self.when.extensions.as_ref().is_none_or(|extensions| {
+            match_extensions(
+                file_path,
+                extensions,
+                self.when.case_sensitive.unwrap_or(true),
+            )
+        })
+    }
+
+    /// Returns true if the rule carries `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t == tag))
+    }
+
+    /// Formats the rule for terminal display: the ID, colored status, priority
+    /// and name on the first line, then one line each for conditions and actions.
+    pub fn pretty_print(&self) -> String {
+        let status = if self.enabled {
+            "enabled".green()
+        } else {
+            "disabled".red()
+        };
+        let actions: Vec<String> = self.then.iter().map(Action::summary).collect();
+
+        let mut text = format!(
+            "{} [{status}] {} {}\n  {} {}\n  {} {}\n",
+            self.id.bold(),
+            format!("P{}", self.priority).cyan(),
+            self.name,
+            "when:".bright_black(),
+            self.when.summary(),
+            "then:".bright_black(),
+            actions.join(", ")
+        );
+        if let Some(tags) = self.tags.as_ref().filter(|tags| !tags.is_empty()) {
+            text.push_str(&format!(
+                "  {} {}\n",
+                "tags:".bright_black(),
+                tags.join(", ")
+            ));
+        }
+        text
+    }
+
+    /// Checks that the capture groups used as `{{1}}`, `{{2}}`, ... in a
+    /// rename template exist in the rule's `filename` regex
+    fn capture_group_error(&self, template: &str) -> Option<String> {
+        let group = template_capture_groups(template).into_iter().max()?;
+        let Some(pattern) = &self.when.filename else {
+            return Some(format!(
+                "Rename target uses capture group {{{{{group}}}}}, which needs a 'filename' condition"
+            ));
+        };
+        // An invalid pattern is reported by the condition validation
+        let groups = regex::Regex::new(pattern).ok()?.captures_len() - 1;
+        (group > groups).then(|| {
+            format!(
+                "Rename target uses capture group {{{{{group}}}}}, but the 'filename' pattern has {groups} group(s)"
+            )
+        })
+    }
+
     fn action_validation(&self) -> Option<Result<(), RuleValidationError>> {
         // Action validation
         for (i, action) in self.then.iter().enumerate() {
@@ -274,6 +1314,29 @@ impl Rule {
                             "Missing destination path".into(),
                         )));
                     }
+                    if inner.preserve_structure && inner.preserve_depth.is_some() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "preserve_structure and preserve_depth cannot be used together".into(),
+                        )));
+                    }
+                    if let Err(e) = validate_modified_bounds(
+                        inner.if_newer_than.as_deref(),
+                        inner.if_older_than.as_deref(),
+                    ) {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            e,
+                        )));
+                    }
+                    if inner.allow_directory {
+                        log::warn!(
+                            "Rule {}: Move action with allow_directory enabled can relocate entire directory trees",
+                            self.id
+                        );
+                    }
                 }
                 Action::Copy(inner) => {
                     if inner.to.trim().is_empty() {
@@ -283,6 +1346,88 @@ impl Rule {
                             "Missing destination path".into(),
                         )));
                     }
+                    if inner.preserve_structure && inner.preserve_depth.is_some() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "preserve_structure and preserve_depth cannot be used together".into(),
+                        )));
+                    }
+                    if let Err(e) = validate_modified_bounds(
+                        inner.if_newer_than.as_deref(),
+                        inner.if_older_than.as_deref(),
+                    ) {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            e,
+                        )));
+                    }
+                }
+                Action::MoveAndHardlink(MoveAndHardlinkAction { to, .. })
+                | Action::Symlink(SymlinkAction { to, .. })
+                | Action::Hardlink(HardlinkAction { to, .. }) => {
+                    if to.trim().is_empty() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "Missing destination path".into(),
+                        )));
+                    }
+                }
+                Action::Archive(inner) => {
+                    if inner.to.trim().is_empty() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "Missing archive path".into(),
+                        )));
+                    }
+                    // Archives are written after all files are processed, so later
+                    // actions would run before the file is archived
+                    if i + 1 < self.then.len() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "archive must be the last action of a rule".into(),
+                        )));
+                    }
+                }
+                Action::Compress(inner) => {
+                    if inner.to.trim().is_empty() {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "Missing destination path".into(),
+                        )));
+                    }
+                }
+                Action::SetPermissions(inner) => {
+                    if inner.mode > MAX_PERMISSION_MODE {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            format!("mode {:#o} is above {MAX_PERMISSION_MODE:#o}", inner.mode),
+                        )));
+                    }
+                }
+                Action::Touch(inner) => {
+                    let dates = [
+                        ("set_modified", &inner.set_modified),
+                        ("set_created", &inner.set_created),
+                    ];
+                    for (field, value) in dates {
+                        if let Some(Err(e)) = value
+                            .as_deref()
+                            .map(|value| parse_modified_bound(field, value))
+                        {
+                            return Some(Err(RuleValidationError::InvalidAction(
+                                self.id.clone(),
+                                i,
+                                e,
+                            )));
+                        }
+                    }
                 }
                 Action::Rename(inner) => {
                     if inner.to.trim().is_empty() {
@@ -292,8 +1437,30 @@ impl Rule {
                             "Missing rename target path".into(),
                         )));
                     }
+                    if let Some(pattern) = &inner.from_regex {
+                        if let Err(e) = regex::Regex::new(pattern) {
+                            return Some(Err(RuleValidationError::InvalidAction(
+                                self.id.clone(),
+                                i,
+                                format!("Invalid from_regex: {e}"),
+                            )));
+                        }
+                    }
+                    if let Some(message) = self.capture_group_error(&inner.to) {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            message,
+                        )));
+                    }
                 }
                 Action::Delete(inner) => {
+                    if self.when.is_trivially_true() {
+                        log::warn!(
+                            "Rule {}: Delete action without any conditions will delete every file",
+                            self.id
+                        );
+                    }
                     if inner.trash && !self.when.is_symlink.unwrap_or(false) {
                         log::warn!(
                             "Rule {}: Delete action with trash enabled but file is not marked as symlink",
@@ -309,6 +1476,13 @@ impl Rule {
                             "Missing command to execute".into(),
                         )));
                     }
+                    if inner.timeout_seconds == Some(0) {
+                        return Some(Err(RuleValidationError::InvalidAction(
+                            self.id.clone(),
+                            i,
+                            "Timeout must be at least 1 second".into(),
+                        )));
+                    }
                 }
                 Action::Skip => {}
             }
@@ -322,3 +1496,37 @@ impl Rule {
 struct RulesWrapper {
     pub rules: Vec<Rule>,
 }
+
+/// Parses the RFC 3339 date of an `if_newer_than` or `if_older_than` option,
+/// or of a timestamp set by a [`TouchAction`]
+pub(crate) fn parse_modified_bound(field: &str, value: &str) -> Result<SystemTime, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(SystemTime::from)
+        .map_err(|e| format!("Invalid {field} date '{value}': {e}"))
+}
+
+/// Checks that both bounds parse and leave a non-empty time window
+fn validate_modified_bounds(newer: Option<&str>, older: Option<&str>) -> Result<(), String> {
+    let newer = newer
+        .map(|value| parse_modified_bound("if_newer_than", value))
+        .transpose()?;
+    let older = older
+        .map(|value| parse_modified_bound("if_older_than", value))
+        .transpose()?;
+    if let (Some(newer), Some(older)) = (newer, older) {
+        if newer >= older {
+            return Err("if_newer_than must be before if_older_than".into());
+        }
+    }
+    Ok(())
+}
+
+/// Whether a value equals its type's default, to leave defaults out of YAML
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Tags are non-empty and only contain ASCII letters, digits and hyphens
+fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
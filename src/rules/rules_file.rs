@@ -3,14 +3,81 @@
 //! Handles reading from and writing to disk, rule validation, and rule management
 //! within Tooka's file operation rules system.
 
-use crate::{core::context, core::error::TookaError, rules::rule::Rule};
+use crate::{
+    common::config::Config,
+    core::context,
+    core::error::{RuleValidationError, TookaError},
+    rules::rule::{Action, Rule},
+    utils::toml,
+};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
-    io::Read,
+    io::{BufReader, Read},
     path::{Path, PathBuf},
 };
 
+/// How rules whose ID is already present are handled when merging rule sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep the existing rule and skip the new one
+    #[default]
+    SkipDuplicates,
+    /// Replace the existing rule with the new one
+    Overwrite,
+    /// Fail on the first duplicate ID
+    Error,
+}
+
+/// Which rules come first when two rule sets are merged, see
+/// [`RulesFile::merge_with_precedence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePrecedence {
+    /// Keep every priority, so the rules of both sets interleave
+    #[default]
+    Unchanged,
+    /// The rules already in the rules file come before the merged ones
+    Existing,
+    /// The merged rules come before the rules already in the rules file
+    Other,
+}
+
+/// Format of a file holding rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RulesFormat {
+    /// YAML, the format of `rules.yaml`
+    #[default]
+    Yaml,
+    /// TOML, with the same structure as the YAML format
+    Toml,
+    /// JSON, with the same structure as the YAML format
+    Json,
+}
+
+impl RulesFormat {
+    /// Detects the format from the extension of `path`: `.toml` files are
+    /// TOML, `.json` files JSON, and any other file is read as YAML.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Self::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
+}
+
+/// A likely logic error between two rules, found by [`RulesFile::conflicting_rules`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictInfo {
+    /// Rule that takes precedence, or that causes the conflict
+    pub rule_id_a: String,
+    /// Rule affected by the conflict
+    pub rule_id_b: String,
+    /// Human-readable explanation of the conflict
+    pub reason: String,
+}
+
 /// Top-level struct for the `rules.yaml` file containing all rules.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RulesFile {
@@ -25,9 +92,30 @@ impl RulesFile {
     /// # Errors
     /// Returns an error if the file cannot be read or parsed.
     pub fn load() -> Result<Self, TookaError> {
-        log::debug!("Loading rules from file");
+        Self::load_from(&Self::rules_file_path()?)
+    }
 
-        let path = Self::rules_file_path()?;
+    /// Loads the rules configured in `config`.
+    ///
+    /// A `rules_folder` takes precedence over `rules_file`; its files are merged
+    /// with [`MergeStrategy::SkipDuplicates`].
+    ///
+    /// # Errors
+    /// Returns an error if the rules can't be read or parsed.
+    pub fn load_for(config: &Config) -> Result<Self, TookaError> {
+        match &config.rules_folder {
+            Some(folder) => Self::import_from_dir(folder, MergeStrategy::SkipDuplicates),
+            None => Self::load_from(&config.rules_file),
+        }
+    }
+
+    /// Loads all rules from the given `rules.yaml` file path, sorted by priority.
+    /// Creates an empty file if none exists.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load_from(path: &Path) -> Result<Self, TookaError> {
+        log::debug!("Loading rules from file: {}", path.display());
 
         if !path.exists() {
             log::warn!(
@@ -35,7 +123,7 @@ impl RulesFile {
                 path.display()
             );
             let empty = Self::default();
-            Self::write_to_file(&path, &empty)?;
+            Self::write_to_file(path, &empty)?;
             return Ok(empty);
         }
 
@@ -46,13 +134,231 @@ impl RulesFile {
             )));
         }
 
-        let content = fs::read_to_string(&path)?;
-        let rules: Self = serde_yaml::from_str(&content)?;
+        let mut rules = Self::from_reader(BufReader::new(fs::File::open(path)?))?;
+        rules.sort_by_priority();
+
+        log::debug!("Successfully loaded {} rules", rules.rules.len());
+        Ok(rules)
+    }
+
+    /// Loads rules from a YAML, TOML or JSON file with a top-level `rules`
+    /// list, see [`RulesFormat::from_path`], sorted by priority.
+    ///
+    /// Unlike [`RulesFile::load_from`], a missing file is an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load_any(path: &Path) -> Result<Self, TookaError> {
+        match RulesFormat::from_path(path) {
+            RulesFormat::Toml => Self::load_from_toml(path),
+            RulesFormat::Json => Self::load_from_json(path),
+            RulesFormat::Yaml => {
+                let mut rules = Self::from_reader(BufReader::new(fs::File::open(path)?))?;
+                rules.sort_by_priority();
+                Ok(rules)
+            }
+        }
+    }
+
+    /// Loads and merges all `.yaml` and `.yml` files in a directory.
+    ///
+    /// Files are read in alphabetical order. Each file may hold a single rule
+    /// or a top-level `rules:` list, like files passed to `tooka add`.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or a file can't be read or parsed,
+    /// or if a duplicate ID is found with [`MergeStrategy::Error`].
+    pub fn import_from_dir(dir: &Path, strategy: MergeStrategy) -> Result<Self, TookaError> {
+        log::debug!("Importing rules from directory: {}", dir.display());
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| ext == "yaml" || ext == "yml")
+            })
+            .collect();
+        paths.sort();
+
+        let mut merged = Self::default();
+        for path in paths {
+            let content = fs::read_to_string(&path)?;
+            let parsed = if content.trim_start().starts_with("rules:") {
+                Self::from_yaml_str(&content)?
+            } else {
+                Self {
+                    rules: vec![serde_yaml::from_str(&content)?],
+                }
+            };
+            log::debug!(
+                "Loaded {} rule(s) from {}",
+                parsed.rules.len(),
+                path.display()
+            );
+            merged.merge(parsed, strategy)?;
+        }
+
+        merged.sort_by_priority();
+        Ok(merged)
+    }
+
+    /// Merges the rules of `other` into this rules file using `strategy`
+    /// for rules whose ID already exists. Does not save to disk.
+    ///
+    /// # Errors
+    /// Returns an error if a duplicate ID is found with [`MergeStrategy::Error`],
+    /// in which case no rule is merged.
+    pub fn merge(&mut self, other: Self, strategy: MergeStrategy) -> Result<(), TookaError> {
+        if strategy == MergeStrategy::Error {
+            // Also catches IDs repeated within `other`
+            let mut ids: HashSet<&str> = self.rules.iter().map(|r| r.id.as_str()).collect();
+            if let Some(rule) = other.rules.iter().find(|rule| !ids.insert(&rule.id)) {
+                return Err(TookaError::InvalidRule(format!(
+                    "Rule ID '{}' already exists",
+                    rule.id
+                )));
+            }
+        }
+
+        for rule in other.rules {
+            match (self.rules.iter().position(|r| r.id == rule.id), strategy) {
+                (None, _) => self.rules.push(rule),
+                (Some(_), MergeStrategy::SkipDuplicates) => {
+                    log::warn!("Skipping duplicate rule ID '{}'", rule.id);
+                }
+                (Some(pos), MergeStrategy::Overwrite) => self.rules[pos] = rule,
+                (Some(_), MergeStrategy::Error) => unreachable!("duplicates are rejected above"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges `other` like [`RulesFile::merge`], then sorts by priority with
+    /// the rule set chosen by `precedence` first.
+    ///
+    /// The priorities of that set are raised above those of the other set,
+    /// keeping their relative order, so the result stays ordered when the
+    /// rules are sorted by priority again.
+    ///
+    /// # Errors
+    /// Returns an error if a duplicate ID is found with [`MergeStrategy::Error`],
+    /// in which case no rule is merged.
+    pub fn merge_with_precedence(
+        &mut self,
+        mut other: Self,
+        strategy: MergeStrategy,
+        precedence: MergePrecedence,
+    ) -> Result<(), TookaError> {
+        let snapshot = self.rules.clone();
+        match precedence {
+            MergePrecedence::Unchanged => {}
+            MergePrecedence::Existing => raise_priorities(&mut self.rules, &other.rules),
+            MergePrecedence::Other => raise_priorities(&mut other.rules, &self.rules),
+        }
+        if let Err(e) = self.merge(other, strategy) {
+            self.rules = snapshot;
+            return Err(e);
+        }
+        self.sort_by_priority();
+        Ok(())
+    }
+
+    /// Parses a rules file from a YAML string with a top-level `rules:` key.
+    ///
+    /// # Errors
+    /// Returns an error if the YAML is malformed or does not match the rules schema.
+    pub fn from_yaml_str(content: &str) -> Result<Self, TookaError> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Parses a rules file from a TOML string with a top-level `rules` array of tables.
+    ///
+    /// # Errors
+    /// Returns an error if the TOML is malformed or does not match the rules schema.
+    pub fn from_toml_str(content: &str) -> Result<Self, TookaError> {
+        toml::from_str(content)
+    }
+
+    /// Serializes the rules to TOML, in the format read by [`RulesFile::from_toml_str`].
+    ///
+    /// # Errors
+    /// Returns an error if the rules can't be represented in TOML.
+    pub fn to_toml_string(&self) -> Result<String, TookaError> {
+        toml::to_string(self)
+    }
+
+    /// Loads all rules from a TOML file, sorted by priority.
+    ///
+    /// Unlike [`RulesFile::load_from`], a missing file is an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load_from_toml(path: &Path) -> Result<Self, TookaError> {
+        log::debug!("Loading TOML rules from file: {}", path.display());
+
+        let mut rules = Self::from_toml_str(&fs::read_to_string(path)?)?;
+        rules.sort_by_priority();
+
+        log::debug!("Successfully loaded {} rules", rules.rules.len());
+        Ok(rules)
+    }
+
+    /// Saves the rules to a TOML file, creating its parent directories.
+    ///
+    /// # Errors
+    /// Returns an error if the rules can't be serialized or the file cannot be written.
+    pub fn save_as_toml(&self, path: &Path) -> Result<(), TookaError> {
+        let content = self.to_toml_string()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+        log::debug!("Saved {} rules to {}", self.rules.len(), path.display());
+        Ok(())
+    }
+
+    /// Loads all rules from a JSON file with a top-level `rules` array, sorted by priority.
+    ///
+    /// Unlike [`RulesFile::load_from`], a missing file is an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load_from_json(path: &Path) -> Result<Self, TookaError> {
+        log::debug!("Loading JSON rules from file: {}", path.display());
+
+        let mut rules: Self = serde_json::from_reader(BufReader::new(fs::File::open(path)?))?;
+        rules.sort_by_priority();
 
         log::debug!("Successfully loaded {} rules", rules.rules.len());
         Ok(rules)
     }
 
+    /// Saves the rules to a pretty-printed JSON file, creating its parent directories.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save_as_json(&self, path: &Path) -> Result<(), TookaError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::debug!("Saved {} rules to {}", self.rules.len(), path.display());
+        Ok(())
+    }
+
+    /// Parses a rules file from a reader, like [`RulesFile::from_yaml_str`]
+    /// without first reading the YAML into a `String`.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the YAML is malformed or does not
+    /// match the rules schema.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, TookaError> {
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+
     /// Saves the current set of rules to the rules file on disk.
     ///
     /// # Errors
@@ -65,9 +371,32 @@ impl RulesFile {
         Ok(())
     }
 
-    /// Adds rule(s) from a YAML file path.
-    /// Supports single or multiple rules depending on YAML content.
+    /// Applies `updater` to the rules and saves them.
+    ///
+    /// The rules are only saved if `updater` succeeds. If either `updater` or
+    /// the save fails, the rules are restored to their previous state so the
+    /// in-memory rules never diverge from the file on disk.
+    ///
+    /// # Errors
+    /// Returns the error of `updater`, or an error if the file cannot be written.
+    pub fn atomic_update<F>(&mut self, updater: F) -> Result<(), TookaError>
+    where
+        F: FnOnce(&mut Vec<Rule>) -> Result<(), TookaError>,
+    {
+        let snapshot = self.rules.clone();
+        let result = updater(&mut self.rules).and_then(|()| self.save());
+        if result.is_err() {
+            log::debug!("Rule update failed, restoring previous rules");
+            self.rules = snapshot;
+        }
+        result
+    }
+
+    /// Adds rule(s) from a YAML, TOML or JSON file path, see [`RulesFormat::from_path`].
+    /// Supports a single rule or a top-level `rules` list.
     /// Optionally overwrites existing rules with the same ID.
+    /// If `priority` is set, it replaces the priority of every imported rule
+    /// before validation.
     ///
     /// # Errors
     /// Returns an error if the file can't be read, parsed, or validation fails.
@@ -75,66 +404,106 @@ impl RulesFile {
         &mut self,
         file_path: &str,
         overwrite: bool,
+        priority: Option<u32>,
     ) -> Result<(), TookaError> {
         log::debug!("Adding rule(s) from file: {file_path}");
 
         let mut content = String::new();
         fs::File::open(file_path)?.read_to_string(&mut content)?;
 
-        if content.trim_start().starts_with("rules:") {
-            self.add_multiple_rules(&content, overwrite)
-        } else {
-            self.add_single_rule(&content, overwrite)
+        match RulesFormat::from_path(Path::new(file_path)) {
+            RulesFormat::Toml => {
+                let document = toml::parse(&content)?;
+                if document.get("rules").is_some() {
+                    let parsed: RulesFile = toml::from_value(document)?;
+                    self.add_multiple_rules(parsed.rules, overwrite, priority)
+                } else {
+                    self.add_single_rule(toml::from_value(document)?, overwrite, priority)
+                }
+            }
+            RulesFormat::Json => {
+                let document: serde_json::Value = serde_json::from_str(&content)?;
+                if document.get("rules").is_some() {
+                    let parsed: RulesFile = serde_json::from_value(document)?;
+                    self.add_multiple_rules(parsed.rules, overwrite, priority)
+                } else {
+                    self.add_single_rule(serde_json::from_value(document)?, overwrite, priority)
+                }
+            }
+            RulesFormat::Yaml if content.trim_start().starts_with("rules:") => {
+                let parsed: RulesFile = serde_yaml::from_str(&content)?;
+                self.add_multiple_rules(parsed.rules, overwrite, priority)
+            }
+            RulesFormat::Yaml => {
+                self.add_single_rule(serde_yaml::from_str(&content)?, overwrite, priority)
+            }
         }
     }
 
-    /// Add a single rule from a YAML string, optionally overwriting existing rules
-    fn add_single_rule(&mut self, yaml: &str, overwrite: bool) -> Result<(), TookaError> {
-        let rule: Rule = serde_yaml::from_str(yaml)?;
+    /// Add a single parsed rule, optionally overwriting existing rules
+    fn add_single_rule(
+        &mut self,
+        mut rule: Rule,
+        overwrite: bool,
+        priority: Option<u32>,
+    ) -> Result<(), TookaError> {
         log::debug!("Parsed new rule: {rule:?}");
-        rule.validate(true)?;
-
-        if let Some(pos) = self.rules.iter().position(|r| r.id == rule.id) {
-            if overwrite {
-                self.rules[pos] = rule;
-                self.save()?;
-                return Ok(());
-            }
-            return Err(TookaError::InvalidRule(format!(
-                "Rule ID '{}' already exists",
-                rule.id
-            )));
+        if let Some(priority) = priority {
+            log::debug!("Overriding priority of rule '{}' with {priority}", rule.id);
+            rule.priority = priority;
         }
+        rule.validate(true)?;
 
-        self.rules.push(rule);
-        self.save()?;
-        Ok(())
-    }
-
-    /// Add multiple rules from a YAML string, optionally overwriting existing rules
-    fn add_multiple_rules(&mut self, yaml: &str, overwrite: bool) -> Result<(), TookaError> {
-        let parsed: RulesFile = serde_yaml::from_str(yaml)?;
-
-        for rule in parsed.rules {
-            log::debug!("Parsed rule: {rule:?}");
-            rule.validate(true)?;
-
-            if let Some(pos) = self.rules.iter().position(|r| r.id == rule.id) {
-                if overwrite {
-                    self.rules[pos] = rule;
-                } else {
+        self.atomic_update(|rules| {
+            if let Some(pos) = rules.iter().position(|r| r.id == rule.id) {
+                if !overwrite {
                     return Err(TookaError::InvalidRule(format!(
                         "Rule ID '{}' already exists",
                         rule.id
                     )));
                 }
+                rules[pos] = rule;
             } else {
-                self.rules.push(rule);
+                rules.push(rule);
             }
-        }
+            sort_by_priority(rules);
+            Ok(())
+        })
+    }
 
-        self.save()?;
-        Ok(())
+    /// Add multiple parsed rules, optionally overwriting existing rules
+    fn add_multiple_rules(
+        &mut self,
+        new_rules: Vec<Rule>,
+        overwrite: bool,
+        priority: Option<u32>,
+    ) -> Result<(), TookaError> {
+        self.atomic_update(|rules| {
+            for mut rule in new_rules {
+                log::debug!("Parsed rule: {rule:?}");
+                if let Some(priority) = priority {
+                    log::debug!("Overriding priority of rule '{}' with {priority}", rule.id);
+                    rule.priority = priority;
+                }
+                rule.validate(true)?;
+
+                if let Some(pos) = rules.iter().position(|r| r.id == rule.id) {
+                    if overwrite {
+                        rules[pos] = rule;
+                    } else {
+                        return Err(TookaError::InvalidRule(format!(
+                            "Rule ID '{}' already exists",
+                            rule.id
+                        )));
+                    }
+                } else {
+                    rules.push(rule);
+                }
+            }
+
+            sort_by_priority(rules);
+            Ok(())
+        })
     }
 
     /// Removes a rule identified by its ID.
@@ -144,16 +513,120 @@ impl RulesFile {
     pub fn remove_rule(&mut self, rule_id: &str) -> Result<(), TookaError> {
         log::debug!("Removing rule with id: {rule_id}");
 
-        if let Some(pos) = self.rules.iter().position(|r| r.id == rule_id) {
-            self.rules.remove(pos);
-            self.save()?;
-            log::debug!("Successfully removed rule with id: {rule_id}");
+        let pos = self.position_of(rule_id)?;
+        self.atomic_update(|rules| {
+            rules.remove(pos);
             Ok(())
-        } else {
-            Err(TookaError::RuleNotFound(format!(
-                "Rule with id '{rule_id}' not found"
-            )))
+        })?;
+        log::debug!("Successfully removed rule with id: {rule_id}");
+        Ok(())
+    }
+
+    /// Duplicates the rule `source_id` under the ID `new_id` and saves.
+    ///
+    /// # Errors
+    /// Returns an error if `source_id` is not found, `new_id` already exists,
+    /// or the rules file cannot be saved.
+    pub fn clone_rule(&mut self, source_id: &str, new_id: &str) -> Result<(), TookaError> {
+        self.clone_rule_as(source_id, new_id, None)
+    }
+
+    /// Duplicates the rule `source_id` under the ID `new_id` with a new name and saves.
+    ///
+    /// # Errors
+    /// Returns an error if `source_id` is not found, `new_id` already exists,
+    /// or the rules file cannot be saved.
+    pub fn clone_rule_with_name(
+        &mut self,
+        source_id: &str,
+        new_id: &str,
+        new_name: &str,
+    ) -> Result<(), TookaError> {
+        self.clone_rule_as(source_id, new_id, Some(new_name))
+    }
+
+    fn clone_rule_as(
+        &mut self,
+        source_id: &str,
+        new_id: &str,
+        new_name: Option<&str>,
+    ) -> Result<(), TookaError> {
+        log::debug!("Cloning rule '{source_id}' as '{new_id}'");
+
+        if self.rules.iter().any(|r| r.id == new_id) {
+            return Err(TookaError::InvalidRule(format!(
+                "Rule ID '{new_id}' already exists"
+            )));
         }
+
+        let mut rule = self.find_rule(source_id).ok_or_else(|| {
+            TookaError::RuleNotFound(format!("Rule with id '{source_id}' not found"))
+        })?;
+        rule.id = new_id.to_string();
+        if let Some(name) = new_name {
+            rule.name = name.to_string();
+        }
+
+        self.atomic_update(|rules| {
+            rules.push(rule);
+            sort_by_priority(rules);
+            Ok(())
+        })?;
+        log::debug!("Successfully cloned rule '{source_id}' as '{new_id}'");
+        Ok(())
+    }
+
+    /// Moves a rule to `new_position` (clamped to the end of the list) without
+    /// changing its priority, and saves.
+    ///
+    /// Rules are kept sorted by priority on load, so the position only decides
+    /// the order among rules with equal priority.
+    ///
+    /// # Errors
+    /// Returns an error if the rule ID is not found or the rules file cannot be saved.
+    pub fn reorder(&mut self, rule_id: &str, new_position: usize) -> Result<(), TookaError> {
+        log::debug!("Moving rule '{rule_id}' to position {new_position}");
+
+        let pos = self.position_of(rule_id)?;
+        let new_position = new_position.min(self.rules.len() - 1);
+        self.atomic_update(|rules| {
+            let rule = rules.remove(pos);
+            rules.insert(new_position, rule);
+            Ok(())
+        })?;
+        log::debug!("Successfully moved rule '{rule_id}' to position {new_position}");
+        Ok(())
+    }
+
+    /// Moves a rule directly before the rule `before_id` and saves.
+    ///
+    /// # Errors
+    /// Returns an error if either rule ID is not found or the rules file cannot be saved.
+    pub fn move_before(&mut self, rule_id: &str, before_id: &str) -> Result<(), TookaError> {
+        let pos = self.position_of(rule_id)?;
+        let target = self.position_of(before_id)?;
+        // Removing the rule first shifts the target left if it came after it
+        let new_position = if pos < target { target - 1 } else { target };
+        self.reorder(rule_id, new_position)
+    }
+
+    /// Moves a rule directly after the rule `after_id` and saves.
+    ///
+    /// # Errors
+    /// Returns an error if either rule ID is not found or the rules file cannot be saved.
+    pub fn move_after(&mut self, rule_id: &str, after_id: &str) -> Result<(), TookaError> {
+        let pos = self.position_of(rule_id)?;
+        let target = self.position_of(after_id)?;
+        let new_position = if pos <= target { target } else { target + 1 };
+        self.reorder(rule_id, new_position)
+    }
+
+    /// Returns the index of a rule, or `RuleNotFound`
+    fn position_of(&self, rule_id: &str) -> Result<usize, TookaError> {
+        self.rules
+            .iter()
+            .position(|r| r.id == rule_id)
+            .ok_or_else(|| TookaError::RuleNotFound(format!("Rule with id '{rule_id}' not found")))
     }
 
     /// Finds a rule by its ID.
@@ -164,19 +637,38 @@ impl RulesFile {
         self.rules.iter().find(|r| r.id == rule_id).cloned()
     }
 
-    /// Exports a rule by ID either to a file or prints it to stdout.
+    /// Exports a rule by ID as YAML, either to a file or to stdout.
     ///
     /// # Errors
     /// Returns an error if the rule ID is not found or if writing to file fails.
     pub fn export_rule(&self, rule_id: &str, out_path: Option<&str>) -> Result<(), TookaError> {
+        self.export_rule_as(rule_id, out_path, RulesFormat::Yaml)
+    }
+
+    /// Exports a rule by ID in the given format, either to a file or to stdout.
+    ///
+    /// # Errors
+    /// Returns an error if the rule ID is not found, if the rule can't be
+    /// serialized, or if writing to file fails.
+    pub fn export_rule_as(
+        &self,
+        rule_id: &str,
+        out_path: Option<&str>,
+        format: RulesFormat,
+    ) -> Result<(), TookaError> {
         log::debug!(
-            "Exporting rule with id: {} to {}",
+            "Exporting rule with id: {} as {:?} to {}",
             rule_id,
+            format,
             out_path.unwrap_or("stdout")
         );
 
         if let Some(rule) = self.rules.iter().find(|r| r.id == rule_id) {
-            let content = serde_yaml::to_string(rule)?;
+            let content = match format {
+                RulesFormat::Yaml => serde_yaml::to_string(rule)?,
+                RulesFormat::Toml => toml::to_string(rule)?,
+                RulesFormat::Json => serde_json::to_string_pretty(rule)?,
+            };
             if let Some(path) = out_path {
                 fs::write(path, content)?;
                 log::debug!("Exported rule {rule_id} to {path}");
@@ -198,6 +690,76 @@ impl RulesFile {
         self.rules.clone()
     }
 
+    /// Formats the rules for display in a terminal, one block per rule with the
+    /// ID, status, priority and name, followed by a summary of its conditions and
+    /// actions. Disabled rules are left out unless `show_disabled` is set.
+    pub fn pretty_print(&self, show_disabled: bool) -> String {
+        self.rules
+            .iter()
+            .filter(|rule| show_disabled || rule.enabled)
+            .map(Rule::pretty_print)
+            .collect()
+    }
+
+    /// Returns the rules carrying `tag`, in file order.
+    ///
+    /// A leading `#` is ignored, so `#media` and `media` select the same rules.
+    pub fn filter_by_tag(&self, tag: &str) -> RulesFile {
+        let tag = tag.strip_prefix('#').unwrap_or(tag);
+        RulesFile {
+            rules: self
+                .rules
+                .iter()
+                .filter(|rule| rule.has_tag(tag))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns an iterator over the enabled rules, in file order.
+    pub fn enabled_rules(&self) -> impl Iterator<Item = &Rule> + '_ {
+        self.rules.iter().filter(|rule| rule.enabled)
+    }
+
+    /// Returns an iterator over the disabled rules, in file order.
+    pub fn disabled_rules(&self) -> impl Iterator<Item = &Rule> + '_ {
+        self.rules.iter().filter(|rule| !rule.enabled)
+    }
+
+    /// Returns the number of enabled rules.
+    pub fn enabled_count(&self) -> usize {
+        self.enabled_rules().count()
+    }
+
+    /// Returns the number of disabled rules.
+    pub fn disabled_count(&self) -> usize {
+        self.disabled_rules().count()
+    }
+
+    /// Returns the total number of rules, enabled or not.
+    pub fn total_rules(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Shorthand for [`RulesFile::enabled_count`].
+    pub fn total_enabled(&self) -> usize {
+        self.enabled_count()
+    }
+
+    /// Counts enabled rules per action type, e.g. `"move" -> 3`.
+    ///
+    /// A rule with several actions of the same type is counted once for that type.
+    pub fn rule_count_by_action(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for rule in self.enabled_rules() {
+            let names: HashSet<&str> = rule.then.iter().map(Action::name).collect();
+            for name in names {
+                *counts.entry(name.to_string()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
     /// Toggles the `enabled` flag of a rule identified by its ID.
     ///
     /// # Errors
@@ -205,20 +767,55 @@ impl RulesFile {
     pub fn toggle_rule(&mut self, rule_id: &str) -> Result<(), TookaError> {
         log::debug!("Toggling rule with id: {rule_id}");
 
-        if let Some(rule) = self.rules.iter_mut().find(|r| r.id == rule_id) {
-            rule.enabled = !rule.enabled;
-            self.save()?;
-            log::debug!("Successfully toggled rule with id: {rule_id}");
+        let pos = self.position_of(rule_id)?;
+        self.atomic_update(|rules| {
+            rules[pos].enabled = !rules[pos].enabled;
             Ok(())
-        } else {
-            Err(TookaError::RuleNotFound(format!(
-                "Rule with id '{rule_id}' not found"
-            )))
+        })?;
+        log::debug!("Successfully toggled rule with id: {rule_id}");
+        Ok(())
+    }
+
+    /// Applies a partial YAML update to the rule identified by its ID and saves,
+    /// see [`Rule::update_from_yaml`].
+    ///
+    /// # Errors
+    /// Returns an error if the rule ID is not found, the patch is invalid, the
+    /// patch changes the ID to one that already exists, or saving fails.
+    pub fn update_rule(&mut self, id: &str, patch_yaml: &str) -> Result<(), TookaError> {
+        log::debug!("Updating rule '{id}' with patch: {patch_yaml}");
+
+        let pos =
+            self.rules.iter().position(|r| r.id == id).ok_or_else(|| {
+                TookaError::RuleNotFound(format!("Rule with id '{id}' not found"))
+            })?;
+
+        let mut rule = self.rules[pos].clone();
+        rule.update_from_yaml(patch_yaml)?;
+        if rule.id != id && self.rules.iter().any(|r| r.id == rule.id) {
+            return Err(TookaError::InvalidRule(format!(
+                "Rule ID '{}' already exists",
+                rule.id
+            )));
         }
+
+        self.atomic_update(|rules| {
+            rules[pos] = rule;
+            sort_by_priority(rules);
+            Ok(())
+        })?;
+        log::debug!("Successfully updated rule '{id}'");
+        Ok(())
     }
 
-    /// Creates an optimized rules file with rule filtering and priority sorting
-    /// Only includes enabled rules in the result
+    /// Prepares the rules for sorting: keeps the enabled rules, restricted to
+    /// `rule_filter` if given, and orders them by priority and `after`
+    /// dependencies so the first matching rule is the one to apply.
+    ///
+    /// # Errors
+    /// Returns `TookaError::RuleNotFound` if a filtered ID doesn't exist or no
+    /// enabled rule is left, and `TookaError::CircularDependency` if the
+    /// `after` references form a cycle.
     pub fn optimized_with_filter(self, rule_filter: Option<&[String]>) -> Result<Self, TookaError> {
         let filtered_rules = if let Some(rule_ids) = rule_filter {
             let mut filtered = Vec::with_capacity(rule_ids.len());
@@ -248,13 +845,167 @@ impl RulesFile {
             ));
         }
 
-        // Sort by priority (higher priority numbers come first)
-        let mut indexed_rules: Vec<(usize, Rule)> = enabled_rules.into_iter().enumerate().collect();
-        indexed_rules.sort_by(|a, b| b.1.priority.cmp(&a.1.priority).then(a.0.cmp(&b.0)));
+        // A rule filter may list rules in any order, so sort again
+        let mut optimized = Self {
+            rules: enabled_rules,
+        };
+        optimized.sort_by_priority();
+        optimized.sort_by_dependencies()?;
+        Ok(optimized)
+    }
 
-        Ok(Self {
-            rules: indexed_rules.into_iter().map(|(_, rule)| rule).collect(),
-        })
+    /// Returns true if the rules are ready for sorting, that is only enabled
+    /// rules already in the order [`RulesFile::optimized_with_filter`] produces.
+    pub fn is_ready(&self) -> bool {
+        if self.rules.is_empty() || self.rules.iter().any(|rule| !rule.enabled) {
+            return false;
+        }
+        let mut ordered = self.clone();
+        ordered.sort_by_priority();
+        ordered.sort_by_dependencies().is_ok()
+            && ordered
+                .rules
+                .iter()
+                .zip(&self.rules)
+                .all(|(a, b)| a.id == b.id)
+    }
+
+    /// Reorders rules so every rule comes after the rules listed in its `after`
+    /// field, keeping the current order otherwise. IDs in `after` that are not
+    /// part of this rule set are ignored.
+    ///
+    /// # Errors
+    /// Returns `TookaError::CircularDependency` with the IDs of the rules
+    /// involved if the `after` references form a cycle.
+    pub fn sort_by_dependencies(&mut self) -> Result<(), TookaError> {
+        let index_of: HashMap<&str, usize> = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| (rule.id.as_str(), i))
+            .collect();
+
+        let mut dependents = vec![Vec::new(); self.rules.len()];
+        let mut in_degree = vec![0usize; self.rules.len()];
+        for (i, rule) in self.rules.iter().enumerate() {
+            for dep in rule.after.iter().flatten() {
+                if let Some(&j) = index_of.get(dep.as_str()) {
+                    dependents[j].push(i);
+                    in_degree[i] += 1;
+                }
+            }
+        }
+
+        // Kahn's algorithm, always taking the earliest ready rule to keep priority order
+        let mut ready: BTreeSet<usize> = (0..self.rules.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.rules.len());
+        while let Some(i) = ready.pop_first() {
+            order.push(i);
+            for &j in &dependents[i] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    ready.insert(j);
+                }
+            }
+        }
+
+        if order.len() < self.rules.len() {
+            let cycle = (0..self.rules.len())
+                .filter(|&i| in_degree[i] > 0)
+                .map(|i| self.rules[i].id.clone())
+                .collect();
+            return Err(TookaError::CircularDependency(cycle));
+        }
+
+        let mut rules: Vec<Option<Rule>> = std::mem::take(&mut self.rules)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.rules = order.into_iter().filter_map(|i| rules[i].take()).collect();
+        Ok(())
+    }
+
+    /// Validates every rule with [`Rule::validate`] and returns the ID and
+    /// error of each invalid rule, in file order. An empty list means all
+    /// rules are valid.
+    pub fn validate_all(&self, deep: bool) -> Vec<(String, RuleValidationError)> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.validate(deep).err().map(|e| (rule.id.clone(), e)))
+            .collect()
+    }
+
+    /// Detects enabled rules that are likely to interact in unintended ways:
+    ///
+    /// - rules with identical conditions but different actions, where the
+    ///   lower-priority rule can never fire
+    /// - rules moving files into a directory matched by the `path` condition of
+    ///   a rule, so moved files are picked up again on the next run
+    /// - rules moving files to the same destination, which may overwrite each other
+    pub fn conflicting_rules(&self) -> Vec<ConflictInfo> {
+        let mut rules: Vec<&Rule> = self.enabled_rules().collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+
+        let mut conflicts = Vec::new();
+        for (i, a) in rules.iter().enumerate() {
+            for b in &rules[i + 1..] {
+                if a.when == b.when && a.then != b.then {
+                    conflicts.push(ConflictInfo {
+                        rule_id_a: a.id.clone(),
+                        rule_id_b: b.id.clone(),
+                        reason: format!(
+                            "Identical conditions; '{}' always matches first, so '{}' never fires",
+                            a.id, b.id
+                        ),
+                    });
+                }
+
+                let shared: Vec<&str> = move_destinations(a)
+                    .filter(|dest| move_destinations(b).any(|other| other == *dest))
+                    .collect();
+                for dest in shared {
+                    conflicts.push(ConflictInfo {
+                        rule_id_a: a.id.clone(),
+                        rule_id_b: b.id.clone(),
+                        reason: format!(
+                            "Both rules move files to '{dest}'; files with the same name overwrite each other"
+                        ),
+                    });
+                }
+            }
+        }
+
+        for a in &rules {
+            for dest in move_destinations(a) {
+                for b in &rules {
+                    let Some(prefix) = b.when.path.as_deref().and_then(glob_literal_dir) else {
+                        continue;
+                    };
+                    if Path::new(dest).starts_with(prefix) {
+                        conflicts.push(ConflictInfo {
+                            rule_id_a: a.id.clone(),
+                            rule_id_b: b.id.clone(),
+                            reason: format!(
+                                "'{}' moves files to '{dest}', which is inside the path '{}' matched by '{}'",
+                                a.id,
+                                b.when.path.as_deref().unwrap_or_default(),
+                                b.id
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Sorts rules by priority, highest first. Rules with equal priority keep
+    /// their original order, so the first matching rule is always the one to apply.
+    pub fn sort_by_priority(&mut self) {
+        sort_by_priority(&mut self.rules);
     }
 
     /// Helper function to get the path to the rules file
@@ -275,3 +1026,42 @@ impl RulesFile {
         Ok(())
     }
 }
+
+/// Raises the priorities of `rules` by the same amount so the lowest is above
+/// the highest priority of `others`
+fn raise_priorities(rules: &mut [Rule], others: &[Rule]) {
+    let (Some(lowest), Some(highest)) = (
+        rules.iter().map(|rule| rule.priority).min(),
+        others.iter().map(|rule| rule.priority).max(),
+    ) else {
+        return;
+    };
+    let offset = highest.saturating_add(1).saturating_sub(lowest);
+    for rule in rules {
+        rule.priority = rule.priority.saturating_add(offset);
+    }
+}
+
+/// Sorts rules by priority, highest first, keeping the order of equal priorities
+fn sort_by_priority(rules: &mut [Rule]) {
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+}
+
+/// Returns the normalized destinations of all move actions of a rule
+fn move_destinations(rule: &Rule) -> impl Iterator<Item = &str> {
+    rule.then.iter().filter_map(|action| match action {
+        Action::Move(inner) => Some(inner.to.trim_end_matches('/')),
+        Action::MoveAndHardlink(inner) => Some(inner.to.trim_end_matches('/')),
+        _ => None,
+    })
+}
+
+/// Returns the directory part of a glob pattern before its first wildcard,
+/// e.g. `/home/user/Downloads` for `/home/user/Downloads/**/*.jpg`
+fn glob_literal_dir(pattern: &str) -> Option<&str> {
+    let literal = pattern
+        .find(['*', '?', '[', '{'])
+        .map_or(pattern, |pos| &pattern[..pos]);
+    let dir = literal.rfind('/').map(|pos| &literal[..pos])?;
+    (!dir.is_empty()).then_some(dir)
+}
@@ -0,0 +1,1355 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::context::{self, test_context};
+    use crate::core::error::{RuleValidationError, TookaError};
+    use crate::rules::rule::{
+        Action, ArchiveAction, ArchiveFormat, CompressAction, CompressionFormat, Conditions,
+        ConflictStrategy, HardlinkAction, MAX_CONDITION_DEPTH, MetadataComparison, MetadataField,
+        MetadataValueType, MoveAction, Rule, SetPermissionsAction, SymlinkAction, TouchAction,
+    };
+    use crate::rules::rules_file::{MergePrecedence, MergeStrategy, RulesFile, RulesFormat};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// Helper function to build a rule YAML document with the given ID and priority
+    fn rule_yaml(id: &str, priority: u32) -> String {
+        format!(
+            "id: {id}\nname: {id}\nenabled: true\npriority: {priority}\nwhen:\n  extensions: [txt]\nthen:\n  - action: skip\n"
+        )
+    }
+
+    #[test]
+    fn test_import_from_dir_merges_yaml_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a_media.yaml"), rule_yaml("media", 1)).unwrap();
+        fs::write(
+            dir.path().join("b_documents.yml"),
+            format!(
+                "rules:\n  - {}\n  - {}",
+                rule_yaml("docs", 2).replace('\n', "\n    "),
+                rule_yaml("media", 5).replace('\n', "\n    ")
+            ),
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a rule").unwrap();
+
+        let rules = RulesFile::import_from_dir(dir.path(), MergeStrategy::SkipDuplicates).unwrap();
+
+        let ids: Vec<_> = rules.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["docs", "media"]);
+        // The first file wins for duplicate IDs
+        assert_eq!(rules.rules[1].priority, 1);
+    }
+
+    #[test]
+    fn test_from_reader_matches_from_yaml_str() {
+        let yaml = format!(
+            "rules:\n  - {}",
+            rule_yaml("docs", 2).replace('\n', "\n    ")
+        );
+        let from_reader = RulesFile::from_reader(yaml.as_bytes()).unwrap();
+        let from_str = RulesFile::from_yaml_str(&yaml).unwrap();
+        assert_eq!(from_reader.rules.len(), 1);
+        assert_eq!(from_reader.rules[0].id, from_str.rules[0].id);
+
+        assert!(matches!(
+            RulesFile::from_reader("rules: [".as_bytes()),
+            Err(TookaError::Yaml(_))
+        ));
+    }
+
+    /// A rule using metadata fields, date ranges, nested conditions and several actions
+    fn complex_rule_yaml() -> &'static str {
+        r#"id: photos
+name: "Photos \"2024\""
+enabled: true
+description: "Sorts camera photos\nby date"
+priority: 7
+when:
+  any: false
+  filename: '^IMG_(\d+)\.jpe?g$'
+  extensions: [jpg, jpeg]
+  size_kb:
+    min: 10
+  mime_type: image/jpeg
+  created_date:
+    from: "2024-01-01"
+    to: "2024-12-31T23:59:59Z"
+  modified_date:
+    from: "2023-06-01"
+  file_age_days:
+    max: 365
+  case_sensitive: false
+  metadata:
+    - key: "EXIF:DateTime"
+      value: "2024:*"
+    - key: "EXIF:ISOSpeed"
+      value: "100..800"
+      value_type: integer
+      comparison: between
+  any_of:
+    - path: "**/Camera/**"
+    - path_any: ["**/DCIM/**", "**/Phone/**"]
+      hardlink_count:
+        min: 1
+        max: 1
+then:
+  - action: copy
+    to: /backup/photos
+    preserve_structure: true
+    on_conflict: rename
+  - action: rename
+    to: "photo_{{1}}.{{ext}}"
+  - action: move
+    to: /photos/{{metadata.EXIF:DateTime|date:%Y}}
+    preserve_structure: false
+    verify_after_move: true
+  - action: skip
+after: [cleanup]
+"#
+    }
+
+    #[test]
+    fn test_toml_round_trip_complex_rule() {
+        let rule: Rule = serde_yaml::from_str(complex_rule_yaml()).unwrap();
+        let rules = RulesFile {
+            rules: vec![rule.clone()],
+        };
+
+        let toml = rules.to_toml_string().unwrap();
+        let parsed = RulesFile::from_toml_str(&toml).unwrap();
+
+        assert_eq!(parsed.rules.len(), 1);
+        let parsed_rule = &parsed.rules[0];
+        assert_eq!(parsed_rule.id, rule.id);
+        assert_eq!(parsed_rule.name, rule.name);
+        assert_eq!(parsed_rule.enabled, rule.enabled);
+        assert_eq!(parsed_rule.description, rule.description);
+        assert_eq!(parsed_rule.priority, rule.priority);
+        assert_eq!(parsed_rule.after, rule.after);
+
+        let (when, expected) = (&parsed_rule.when, &rule.when);
+        assert_eq!(when.any, expected.any);
+        assert_eq!(when.filename, expected.filename);
+        assert_eq!(when.extensions, expected.extensions);
+        assert_eq!(when.size_kb, expected.size_kb);
+        assert_eq!(when.mime_type, expected.mime_type);
+        assert_eq!(when.created_date, expected.created_date);
+        assert_eq!(when.modified_date, expected.modified_date);
+        assert_eq!(when.file_age_days, expected.file_age_days);
+        assert_eq!(when.case_sensitive, expected.case_sensitive);
+        assert_eq!(when.metadata, expected.metadata);
+        assert_eq!(when.any_of, expected.any_of);
+        assert!(
+            when.metadata
+                .as_ref()
+                .is_some_and(|fields| fields.len() == 2)
+        );
+
+        assert_eq!(parsed_rule.then.len(), 4);
+        for (action, expected) in parsed_rule.then.iter().zip(&rule.then) {
+            assert_eq!(action, expected);
+        }
+        assert_eq!(parsed_rule, &rule);
+    }
+
+    #[test]
+    fn test_toml_rules_file_load_and_save() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested/rules.toml");
+        let mut low: Rule = serde_yaml::from_str(&rule_yaml("low", 1)).unwrap();
+        low.description = Some("Low priority".into());
+        let high: Rule = serde_yaml::from_str(&rule_yaml("high", 5)).unwrap();
+        let rules = RulesFile {
+            rules: vec![low, high],
+        };
+
+        rules.save_as_toml(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("[[rules]]\nid = \"low\""));
+        assert!(content.contains("[[rules.then]]\naction = \"skip\""));
+
+        let loaded = RulesFile::load_from_toml(&path).unwrap();
+        let ids: Vec<_> = loaded.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["high", "low"]);
+        assert_eq!(loaded.rules[1].description.as_deref(), Some("Low priority"));
+
+        assert!(RulesFile::load_from_toml(&dir.path().join("missing.toml")).is_err());
+        assert!(matches!(
+            RulesFile::from_toml_str("[[rules]]\nid = "),
+            Err(TookaError::Toml(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_rule_from_toml_file() {
+        let dir = tempdir().unwrap();
+        let single = dir.path().join("single.toml");
+        fs::write(
+            &single,
+            "id = \"docs\"\nname = \"Docs\"\nenabled = true\npriority = 2\n\n[when]\nextensions = [\"pdf\"]\nsize_kb = { max = 2048 }\n\n[[then]]\naction = \"move\"\nto = \"/docs\"\npreserve_structure = false\n",
+        )
+        .unwrap();
+        let multiple = dir.path().join("multiple.TOML");
+        fs::write(
+            &multiple,
+            "[[rules]]\nid = \"a\"\nname = \"a\"\nenabled = true\npriority = 1\nwhen = { extensions = [\"txt\"] }\nthen = [{ action = \"skip\" }]\n\n[[rules]]\nid = \"b\"\nname = \"b\"\nenabled = false\npriority = 3\nwhen = { any = true }\nthen = [{ action = \"delete\" }]\n",
+        )
+        .unwrap();
+
+        let (_context_dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        context::get_locked_config().unwrap().rules_file = dir.path().join("rules.yaml");
+        let mut rules = RulesFile::default();
+        let added_single = rules.add_rule_from_file(single.to_str().unwrap(), false, None);
+        let added_multiple = rules.add_rule_from_file(multiple.to_str().unwrap(), false, None);
+        context::restore(snapshot);
+
+        added_single.unwrap();
+        added_multiple.unwrap();
+
+        let ids: Vec<_> = rules.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["b", "docs", "a"]);
+        let docs = rules.find_rule("docs").unwrap();
+        assert_eq!(docs.when.size_kb.and_then(|range| range.max), Some(2048));
+        assert!(matches!(&docs.then[0], Action::Move(inner) if inner.to == "/docs"));
+
+        assert_eq!(RulesFormat::from_path(&single), RulesFormat::Toml);
+        assert_eq!(RulesFormat::from_path(&multiple), RulesFormat::Toml);
+        assert_eq!(
+            RulesFormat::from_path(&dir.path().join("rules.yml")),
+            RulesFormat::Yaml
+        );
+    }
+
+    /// Path of a file in `tests/fixtures`
+    fn fixture(name: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_load_from_json_matches_yaml() {
+        let from_json = RulesFile::load_from_json(&fixture("sample_rules.json")).unwrap();
+        let from_yaml = RulesFile::load_from(&fixture("sample_rules.yaml")).unwrap();
+
+        assert_eq!(from_json.rules.len(), 3);
+        assert_eq!(from_json.rules, from_yaml.rules);
+        // Sorted by priority like YAML rules
+        let ids: Vec<_> = from_json.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["screenshots", "invoices", "old-logs"]);
+        for rule in &from_json.rules {
+            rule.validate(true).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_json_save_and_add_rule_from_file() {
+        let dir = tempdir().unwrap();
+        let rules = RulesFile::load_from_json(&fixture("sample_rules.json")).unwrap();
+        let path = dir.path().join("out/rules.json");
+
+        rules.save_as_json(&path).unwrap();
+        assert_eq!(RulesFile::load_from_json(&path).unwrap().rules, rules.rules);
+        assert!(matches!(
+            RulesFile::load_from_json(&dir.path().join("missing.json")),
+            Err(TookaError::Io(_))
+        ));
+
+        let single = dir.path().join("single.json");
+        fs::write(
+            &single,
+            r#"{"id": "docs", "name": "Docs", "enabled": true, "priority": 9, "when": {"extensions": ["pdf"]}, "then": [{"action": "skip"}]}"#,
+        )
+        .unwrap();
+        let (_context_dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        context::get_locked_config().unwrap().rules_file = dir.path().join("rules.yaml");
+        let mut added = RulesFile::default();
+        let added_multiple = added.add_rule_from_file(path.to_str().unwrap(), false, None);
+        let added_single = added.add_rule_from_file(single.to_str().unwrap(), false, None);
+        context::restore(snapshot);
+
+        added_multiple.unwrap();
+        added_single.unwrap();
+        assert_eq!(added.rules.len(), 4);
+        assert_eq!(added.rules[0].id, "docs");
+        assert_eq!(RulesFormat::from_path(&single), RulesFormat::Json);
+    }
+
+    #[test]
+    fn test_export_rule_as_toml() {
+        let dir = tempdir().unwrap();
+        let rule: Rule = serde_yaml::from_str(complex_rule_yaml()).unwrap();
+        let rules = RulesFile {
+            rules: vec![rule.clone()],
+        };
+        let out = dir.path().join("photos.toml");
+
+        rules
+            .export_rule_as("photos", Some(out.to_str().unwrap()), RulesFormat::Toml)
+            .unwrap();
+
+        let exported: Rule =
+            crate::utils::toml::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(exported, rule);
+        assert!(matches!(
+            rules.export_rule_as("missing", None, RulesFormat::Toml),
+            Err(TookaError::RuleNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), rule_yaml("dup", 1)).unwrap();
+        fs::write(dir.path().join("b.yaml"), rule_yaml("dup", 9)).unwrap();
+
+        let overwritten = RulesFile::import_from_dir(dir.path(), MergeStrategy::Overwrite).unwrap();
+        assert_eq!(overwritten.rules.len(), 1);
+        assert_eq!(overwritten.rules[0].priority, 9);
+
+        let result = RulesFile::import_from_dir(dir.path(), MergeStrategy::Error);
+        assert!(matches!(result, Err(TookaError::InvalidRule(msg)) if msg.contains("dup")));
+    }
+
+    /// Helper function to build a rules file from (ID, priority) pairs
+    fn rules_with(rules: &[(&str, u32)]) -> RulesFile {
+        let rules = rules
+            .iter()
+            .map(|(id, priority)| serde_yaml::from_str(&rule_yaml(id, *priority)).unwrap())
+            .collect();
+        RulesFile { rules }
+    }
+
+    fn ids(rules: &RulesFile) -> Vec<&str> {
+        rules.rules.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merge_with_precedence() {
+        let base = rules_with(&[("a_high", 50), ("a_low", 1), ("shared", 10)]);
+        let extra = rules_with(&[("b_high", 5), ("b_low", 2), ("shared", 3)]);
+
+        let mut merged = base.clone();
+        merged
+            .merge_with_precedence(
+                extra.clone(),
+                MergeStrategy::Overwrite,
+                MergePrecedence::Other,
+            )
+            .unwrap();
+        assert_eq!(
+            ids(&merged),
+            ["b_high", "shared", "b_low", "a_high", "a_low"]
+        );
+
+        let mut merged = base.clone();
+        merged
+            .merge_with_precedence(
+                extra.clone(),
+                MergeStrategy::SkipDuplicates,
+                MergePrecedence::Existing,
+            )
+            .unwrap();
+        assert_eq!(
+            ids(&merged),
+            ["a_high", "shared", "a_low", "b_high", "b_low"]
+        );
+
+        let mut merged = base.clone();
+        merged
+            .merge_with_precedence(extra, MergeStrategy::Overwrite, MergePrecedence::Unchanged)
+            .unwrap();
+        assert_eq!(
+            ids(&merged),
+            ["a_high", "b_high", "shared", "b_low", "a_low"]
+        );
+    }
+
+    #[test]
+    fn test_merge_with_precedence_error_keeps_rules() {
+        let base = rules_with(&[("a", 1), ("shared", 2)]);
+        let mut merged = base.clone();
+
+        let result = merged.merge_with_precedence(
+            rules_with(&[("b", 1), ("shared", 1)]),
+            MergeStrategy::Error,
+            MergePrecedence::Existing,
+        );
+
+        assert!(matches!(result, Err(TookaError::InvalidRule(msg)) if msg.contains("shared")));
+        let priorities =
+            |rules: &RulesFile| -> Vec<u32> { rules.rules.iter().map(|r| r.priority).collect() };
+        assert_eq!(ids(&merged), ids(&base));
+        assert_eq!(priorities(&merged), priorities(&base));
+    }
+
+    #[test]
+    fn test_load_any_detects_format() {
+        let dir = tempdir().unwrap();
+        let rules = rules_with(&[("one", 1), ("two", 2)]);
+        let json = dir.path().join("extra.json");
+        let yaml = dir.path().join("extra.yaml");
+        rules.save_as_json(&json).unwrap();
+        fs::write(&yaml, serde_yaml::to_string(&rules).unwrap()).unwrap();
+
+        assert_eq!(ids(&RulesFile::load_any(&json).unwrap()), ["two", "one"]);
+        assert_eq!(ids(&RulesFile::load_any(&yaml).unwrap()), ["two", "one"]);
+        assert!(RulesFile::load_any(&dir.path().join("missing.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_sort_by_priority_is_stable() {
+        let yaml = format!(
+            "rules:\n  - {}\n  - {}\n  - {}",
+            rule_yaml("low", 1).replace('\n', "\n    "),
+            rule_yaml("first_high", 5).replace('\n', "\n    "),
+            rule_yaml("second_high", 5).replace('\n', "\n    ")
+        );
+        let mut rules = RulesFile::from_yaml_str(&yaml).unwrap();
+
+        rules.sort_by_priority();
+
+        let ids: Vec<_> = rules.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["first_high", "second_high", "low"]);
+    }
+
+    #[test]
+    fn test_preserve_depth_conflicts_with_preserve_structure() {
+        let yaml = "id: nested\nname: nested\nenabled: true\npriority: 1\nwhen:\n  extensions: [txt]\nthen:\n  - action: move\n    to: /dest\n    preserve_structure: true\n    preserve_depth: 2\n";
+        let rules =
+            RulesFile::from_yaml_str(&format!("rules:\n  - {}", yaml.replace('\n', "\n    ")))
+                .unwrap();
+
+        let result = rules.rules[0].validate(true);
+        assert!(matches!(
+            result,
+            Err(RuleValidationError::InvalidAction(id, 0, _)) if id == "nested"
+        ));
+    }
+
+    /// Helper function to parse a rule and replace its actions with a single move
+    fn move_rule(id: &str, priority: u32, to: &str) -> Rule {
+        let mut rule: Rule = serde_yaml::from_str(&rule_yaml(id, priority)).unwrap();
+        rule.then = vec![Action::Move(MoveAction {
+            to: to.to_string(),
+            preserve_structure: false,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict: ConflictStrategy::Overwrite,
+        })];
+        rule
+    }
+
+    #[test]
+    fn test_conflicting_rules_identical_conditions() {
+        let rules = RulesFile {
+            rules: vec![
+                move_rule("low", 1, "/dest/low"),
+                move_rule("high", 5, "/dest/high"),
+            ],
+        };
+
+        let conflicts = rules.conflicting_rules();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule_id_a, "high");
+        assert_eq!(conflicts[0].rule_id_b, "low");
+        assert!(conflicts[0].reason.contains("never fires"));
+    }
+
+    #[test]
+    fn test_conflicting_rules_destination_inside_watched_path() {
+        let mover = move_rule("mover", 1, "/home/user/Downloads/sorted");
+        let mut watcher = move_rule("watcher", 1, "/elsewhere");
+        watcher.when.extensions = Some(vec!["jpg".to_string()]);
+        watcher.when.path = Some("/home/user/Downloads/**/*.jpg".to_string());
+
+        let conflicts = RulesFile {
+            rules: vec![mover, watcher],
+        }
+        .conflicting_rules();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule_id_a, "mover");
+        assert_eq!(conflicts[0].rule_id_b, "watcher");
+        assert!(conflicts[0].reason.contains("inside the path"));
+    }
+
+    #[test]
+    fn test_conflicting_rules_shared_destination() {
+        let first = move_rule("first", 2, "/dest/shared/");
+        let mut second = move_rule("second", 1, "/dest/shared");
+        second.when.extensions = Some(vec!["log".to_string()]);
+
+        let conflicts = RulesFile {
+            rules: vec![first, second],
+        }
+        .conflicting_rules();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule_id_a, "first");
+        assert!(conflicts[0].reason.contains("/dest/shared"));
+    }
+
+    #[test]
+    fn test_clone_rule() {
+        // Saving goes through the global config, so point it at a temp dir
+        let (dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        context::get_locked_config().unwrap().rules_file = dir.path().join("clone_rules.yaml");
+
+        let mut rules = RulesFile {
+            rules: vec![move_rule("original", 1, "/dest")],
+        };
+        rules.clone_rule("original", "copy").unwrap();
+        rules
+            .clone_rule_with_name("original", "named_copy", "Named copy")
+            .unwrap();
+        let missing = rules.clone_rule("missing", "other");
+        let duplicate = rules.clone_rule("original", "copy");
+        context::restore(snapshot);
+
+        let ids: Vec<_> = rules.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["original", "copy", "named_copy"]);
+        assert_eq!(rules.rules[1].name, "original");
+        assert_eq!(rules.rules[2].name, "Named copy");
+        assert_eq!(rules.rules[1].then, rules.rules[0].then);
+        assert!(matches!(missing, Err(TookaError::RuleNotFound(_))));
+        assert!(
+            matches!(duplicate, Err(TookaError::InvalidRule(msg)) if msg.contains("already exists"))
+        );
+    }
+
+    #[test]
+    fn test_reorder_and_move_before_after() {
+        // Saving goes through the global config, so point it at a temp dir
+        let (dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        context::get_locked_config().unwrap().rules_file = dir.path().join("reorder_rules.yaml");
+
+        let mut rules = RulesFile {
+            rules: ["a", "b", "c", "d"]
+                .iter()
+                .map(|id| move_rule(id, 1, &format!("/dest/{id}")))
+                .collect(),
+        };
+        let ids = |rules: &RulesFile| -> Vec<String> {
+            rules.rules.iter().map(|r| r.id.clone()).collect()
+        };
+
+        rules.reorder("a", 99).unwrap();
+        assert_eq!(ids(&rules), ["b", "c", "d", "a"]);
+        rules.move_before("a", "c").unwrap();
+        assert_eq!(ids(&rules), ["b", "a", "c", "d"]);
+        rules.move_after("b", "c").unwrap();
+        assert_eq!(ids(&rules), ["a", "c", "b", "d"]);
+        rules.move_after("d", "a").unwrap();
+        assert_eq!(ids(&rules), ["a", "d", "c", "b"]);
+        let missing = rules.move_before("a", "missing");
+        context::restore(snapshot);
+
+        assert!(matches!(missing, Err(TookaError::RuleNotFound(_))));
+    }
+
+    #[test]
+    fn test_condition_count_and_trivially_true() {
+        let mut rule = move_rule("counted", 1, "/dest");
+        assert_eq!(rule.when.condition_count(), 1);
+        assert!(!rule.when.is_trivially_true());
+
+        rule.when.extensions = None;
+        rule.when.any = Some(true);
+        rule.when.metadata = Some(vec![]);
+        assert_eq!(rule.when.condition_count(), 0);
+        assert!(rule.when.is_trivially_true());
+
+        rule.when.mime_type = Some("image/*".to_string());
+        rule.when.is_symlink = Some(false);
+        assert_eq!(rule.when.condition_count(), 2);
+    }
+
+    #[test]
+    fn test_enabled_and_disabled_rules() {
+        let mut disabled = move_rule("disabled", 1, "/dest/disabled");
+        disabled.enabled = false;
+        let rules = RulesFile {
+            rules: vec![move_rule("enabled", 1, "/dest/enabled"), disabled],
+        };
+
+        let enabled: Vec<_> = rules.enabled_rules().map(|r| r.id.as_str()).collect();
+        let disabled: Vec<_> = rules.disabled_rules().map(|r| r.id.as_str()).collect();
+        assert_eq!(enabled, ["enabled"]);
+        assert_eq!(disabled, ["disabled"]);
+        assert_eq!(rules.enabled_count(), 1);
+        assert_eq!(rules.disabled_count(), 1);
+    }
+
+    #[test]
+    fn test_rule_count_by_action() {
+        let mut multi = move_rule("multi", 1, "/dest/a");
+        multi.then.push(multi.then[0].clone());
+        multi.then.push(Action::Skip);
+        let mut disabled = move_rule("disabled", 1, "/dest/b");
+        disabled.enabled = false;
+        let rules = RulesFile {
+            rules: vec![move_rule("single", 1, "/dest/c"), multi, disabled],
+        };
+
+        let counts = rules.rule_count_by_action();
+        assert_eq!(counts.get("move"), Some(&2));
+        assert_eq!(counts.get("skip"), Some(&1));
+        assert_eq!(counts.len(), 2);
+        assert_eq!(rules.total_rules(), 3);
+        assert_eq!(rules.total_enabled(), 2);
+    }
+
+    #[test]
+    fn test_sort_by_dependencies() {
+        let mut compress = move_rule("compress", 1, "/dest/compressed");
+        compress.after = Some(vec!["unknown".to_string()]);
+        let mut archive = move_rule("archive", 10, "/dest/archive");
+        archive.after = Some(vec!["compress".to_string()]);
+        let mut rules = RulesFile {
+            rules: vec![archive, move_rule("other", 5, "/dest/other"), compress],
+        };
+
+        let optimized = rules.clone().optimized_with_filter(None).unwrap();
+        let ids: Vec<_> = optimized.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["other", "compress", "archive"]);
+        assert!(optimized.is_ready());
+        assert!(!rules.is_ready());
+
+        rules.rules[2].after = Some(vec!["archive".to_string()]);
+        assert!(matches!(
+            rules.sort_by_dependencies(),
+            Err(TookaError::CircularDependency(ids)) if ids == ["archive", "compress"]
+        ));
+    }
+
+    #[test]
+    fn test_optimized_with_filter() {
+        let mut disabled = move_rule("disabled", 9, "/dest/disabled");
+        disabled.enabled = false;
+        let rules = RulesFile {
+            rules: vec![
+                move_rule("low", 1, "/dest/low"),
+                disabled,
+                move_rule("high", 5, "/dest/high"),
+            ],
+        };
+        assert!(!rules.is_ready());
+
+        let optimized = rules.clone().optimized_with_filter(None).unwrap();
+        assert_eq!(ids(&optimized), ["high", "low"]);
+        assert!(optimized.is_ready());
+
+        let filter = ["low".to_string(), "disabled".to_string()];
+        let filtered = rules.clone().optimized_with_filter(Some(&filter)).unwrap();
+        assert_eq!(ids(&filtered), ["low"]);
+        assert!(filtered.is_ready());
+
+        let missing = ["missing".to_string()];
+        assert!(matches!(
+            rules.clone().optimized_with_filter(Some(&missing)),
+            Err(TookaError::RuleNotFound(_))
+        ));
+        assert!(!RulesFile::default().is_ready());
+    }
+
+    #[test]
+    fn test_applicable_to() {
+        let mut rule = move_rule("photos", 1, "/dest");
+        rule.when.extensions = Some(vec!["jpg".to_string(), "png".to_string()]);
+        assert!(rule.applicable_to(Path::new("/src/photo.jpg")));
+        assert!(!rule.applicable_to(Path::new("/src/photo.JPG")));
+        assert!(!rule.applicable_to(Path::new("/src/notes.txt")));
+        assert!(!rule.applicable_to(Path::new("/src/noext")));
+
+        // Edits to the conditions are seen right away
+        rule.when.extensions = Some(vec!["txt".to_string()]);
+        assert!(rule.applicable_to(Path::new("/src/notes.txt")));
+        assert!(!rule.applicable_to(Path::new("/src/photo.jpg")));
+
+        let mut any = rule.clone();
+        any.when.any = Some(true);
+        assert!(any.applicable_to(Path::new("/src/notes.txt")));
+
+        let mut insensitive = rule.clone();
+        insensitive.when.case_sensitive = Some(false);
+        assert!(insensitive.applicable_to(Path::new("/src/notes.TXT")));
+
+        let mut without = rule.clone();
+        without.when.extensions = None;
+        assert!(without.applicable_to(Path::new("/src/notes.txt")));
+    }
+
+    #[test]
+    fn test_after_requires_non_empty_ids() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.after = Some(vec![" ".to_string()]);
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let mut rules = RulesFile::default();
+        for (id, tags) in [
+            ("photos", "\ntags: [media, work]"),
+            ("logs", "\ntags: [cleanup]"),
+            ("plain", ""),
+        ] {
+            let yaml = format!("{}{tags}", rule_yaml(id, 1));
+            rules.rules.push(serde_yaml::from_str(&yaml).unwrap());
+        }
+        for rule in &rules.rules {
+            rule.validate(true).unwrap();
+        }
+
+        let ids = |rules: RulesFile| rules.rules.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids(rules.filter_by_tag("media")), ["photos"]);
+        assert_eq!(ids(rules.filter_by_tag("#cleanup")), ["logs"]);
+        assert!(rules.filter_by_tag("Media").rules.is_empty());
+        assert!(rules.rules[0].has_tag("work"));
+        assert!(!rules.rules[2].has_tag("work"));
+        assert!(rules.rules[0].pretty_print().contains("media, work"));
+    }
+
+    #[test]
+    fn test_tags_validation() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.tags = Some(vec!["media".into(), "2024-trip".into()]);
+        assert!(rule.validate(true).is_ok());
+
+        for tag in ["", "two words", "#media", "café"] {
+            rule.tags = Some(vec!["media".into(), tag.into()]);
+            assert!(
+                matches!(
+                    rule.validate(true),
+                    Err(RuleValidationError::InvalidCondition(_, ref msg)) if msg.contains("Invalid tag")
+                ),
+                "tag {tag:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_from_yaml() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.update_from_yaml("priority: 10\nenabled: false")
+            .unwrap();
+        assert_eq!(rule.priority, 10);
+        assert!(!rule.enabled);
+        assert_eq!(rule.name, "rule");
+
+        let unchanged = rule.clone();
+        assert!(matches!(
+            rule.update_from_yaml("colour: red"),
+            Err(RuleValidationError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            rule.update_from_yaml("then: []"),
+            Err(RuleValidationError::NoActions(_))
+        ));
+        assert_eq!(rule, unchanged);
+    }
+
+    #[test]
+    fn test_update_rule() {
+        // Saving goes through the global config, so point it at a temp dir
+        let (dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        context::get_locked_config().unwrap().rules_file = dir.path().join("update_rules.yaml");
+
+        let mut rules = RulesFile {
+            rules: vec![move_rule("a", 1, "/dest/a"), move_rule("b", 1, "/dest/b")],
+        };
+        let updated = rules.update_rule("b", "priority: 5");
+        let missing = rules.update_rule("missing", "priority: 5");
+        let duplicate = rules.update_rule("a", "id: b");
+        context::restore(snapshot);
+
+        updated.unwrap();
+        assert_eq!(rules.rules[0].id, "b");
+        assert_eq!(rules.rules[0].priority, 5);
+        assert!(matches!(missing, Err(TookaError::RuleNotFound(_))));
+        assert!(matches!(duplicate, Err(TookaError::InvalidRule(_))));
+    }
+
+    #[test]
+    fn test_atomic_update_restores_rules_on_failure() {
+        let (dir, isolated) = test_context();
+        let _ = context::init_context_with(isolated);
+        let snapshot = context::snapshot();
+        let mut rules = RulesFile {
+            rules: vec![move_rule("a", 1, "/dest/a")],
+        };
+
+        let rejected = rules.atomic_update(|rules| {
+            rules.clear();
+            Err(TookaError::InvalidRule("rejected".into()))
+        });
+        // A directory can't be written as a file, so saving fails
+        context::get_locked_config().unwrap().rules_file = dir.path().to_path_buf();
+        let unsaved = rules.toggle_rule("a");
+        context::get_locked_config().unwrap().rules_file = dir.path().join("atomic_rules.yaml");
+        let saved = rules.remove_rule("a");
+        context::restore(snapshot);
+
+        assert!(matches!(rejected, Err(TookaError::InvalidRule(_))));
+        assert!(matches!(unsaved, Err(TookaError::Io(_))));
+        saved.unwrap();
+        assert!(rules.rules.is_empty());
+        assert!(
+            fs::read_to_string(dir.path().join("atomic_rules.yaml"))
+                .unwrap()
+                .contains("rules: []")
+        );
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let mut no_name = move_rule("no_name", 1, "/dest");
+        no_name.name = String::new();
+        let mut bad_regex = move_rule("bad_regex", 1, "/dest");
+        bad_regex.when.filename = Some("[invalid".to_string());
+        let mut rules = RulesFile {
+            rules: vec![no_name, move_rule("valid", 1, "/dest"), bad_regex],
+        };
+
+        assert!(rules.validate_all(false).is_empty());
+        let errors = rules.validate_all(true);
+        let ids: Vec<_> = errors.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["no_name", "bad_regex"]);
+        assert!(matches!(errors[0].1, RuleValidationError::MissingName(_)));
+        assert!(matches!(
+            errors[1].1,
+            RuleValidationError::InvalidCondition(..)
+        ));
+
+        rules.rules.retain(|rule| rule.id == "valid");
+        assert!(rules.validate_all(true).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_filename_regex() {
+        for pattern in ["[invalid", "(unclosed"] {
+            let mut rule = move_rule("rule", 1, "/dest");
+            rule.when.filename = Some(pattern.to_string());
+            assert!(matches!(
+                rule.validate(true),
+                Err(RuleValidationError::InvalidCondition(id, msg))
+                    if id == "rule" && msg.starts_with("Invalid regex in 'filename'")
+            ));
+        }
+
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.when.filename = Some(r"^report_\d+\.pdf$".to_string());
+        assert!(rule.validate(true).is_ok());
+    }
+
+    #[test]
+    fn test_is_empty_file_validation() {
+        let range = |min, max| Some(crate::rules::rule::Range { min, max });
+        let mut rule = move_rule("stubs", 1, "/dest");
+        rule.when.is_empty_file = Some(true);
+        rule.when.size_kb = range(Some(1), None);
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidCondition(_, msg)) if msg.contains("is_empty_file")
+        ));
+        rule.when.size_kb = range(Some(0), Some(10));
+        assert!(rule.validate(true).is_ok());
+
+        rule.when.is_empty_file = Some(false);
+        rule.when.size_kb = range(None, Some(0));
+        assert!(rule.validate(true).is_err());
+        rule.when.size_kb = None;
+        assert!(rule.validate(true).is_ok());
+        assert!(rule.when.summary().contains("is_empty_file=false"));
+    }
+
+    #[test]
+    fn test_depth_validation() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.when.min_depth = Some(2);
+        rule.when.max_depth = Some(1);
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidCondition(_, msg)) if msg.contains("min_depth > max_depth")
+        ));
+
+        rule.when.max_depth = Some(2);
+        assert!(rule.validate(true).is_ok());
+        assert!(rule.when.summary().contains("depth=2..2"));
+    }
+
+    #[test]
+    fn test_invalid_exclude_path_glob() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.when.exclude_path = Some(vec!["**/__thumb__/**".to_string(), "[invalid".to_string()]);
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidCondition(id, msg))
+                if id == "rule" && msg.starts_with("Invalid glob in 'exclude_path'")
+        ));
+
+        rule.when.exclude_path = Some(vec!["**/__thumb__/**".to_string()]);
+        assert!(rule.validate(true).is_ok());
+        assert!(rule.when.summary().contains("exclude_path=**/__thumb__/**"));
+    }
+
+    #[test]
+    fn test_content_matches_validation() {
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.when.content_matches = Some("(unclosed".to_string());
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidCondition(_, msg))
+                if msg.starts_with("Invalid regex in 'content_matches'")
+        ));
+
+        rule.when.content_matches = Some("ERROR: disk full".to_string());
+        assert!(rule.validate(true).is_ok());
+        rule.when.content_max_bytes = Some(0);
+        assert!(rule.validate(true).is_err());
+    }
+
+    #[test]
+    fn test_archive_action_parsing_and_validation() {
+        let rules = RulesFile::from_yaml_str(
+            "rules:\n  - id: logs\n    name: logs\n    enabled: true\n    priority: 1\n    when:\n      extensions: [log]\n    then:\n      - action: archive\n        to: ~/Archives/logs.tar.gz\n        format: tar.gz\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            &rules.rules[0].then[0],
+            Action::Archive(ArchiveAction {
+                format: ArchiveFormat::TarGz,
+                preserve_structure: false,
+                ..
+            })
+        ));
+
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.then.insert(
+            0,
+            Action::Archive(ArchiveAction {
+                to: "/archives/files.zip".to_string(),
+                format: ArchiveFormat::Zip,
+                preserve_structure: false,
+            }),
+        );
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidAction(_, 0, msg)) if msg.contains("last action")
+        ));
+
+        rule.then.truncate(1);
+        assert!(rule.validate(true).is_ok());
+        if let Action::Archive(archive) = &mut rule.then[0] {
+            archive.to = " ".to_string();
+        }
+        assert!(rule.validate(true).is_err());
+    }
+
+    #[test]
+    fn test_rename_capture_groups_need_filename_condition() {
+        let rules = |when: &str, to: &str| {
+            RulesFile::from_yaml_str(&format!(
+                "rules:\n  - id: photos\n    name: photos\n    enabled: true\n    priority: 1\n    when:\n{when}    then:\n      - action: rename\n        to: '{to}'\n"
+            ))
+            .unwrap()
+        };
+        let error = |rules: &RulesFile| match rules.rules[0].validate(true) {
+            Err(RuleValidationError::InvalidAction(_, 0, msg)) => msg,
+            other => panic!("unexpected validation result {other:?}"),
+        };
+
+        let with_filename = "      filename: '^(\\w+)_(\\d+)'\n";
+        assert!(
+            rules(with_filename, "{{2}}_{{1}}.{{ext}}").rules[0]
+                .validate(true)
+                .is_ok()
+        );
+        assert!(
+            rules(with_filename, "{{0}}").rules[0]
+                .validate(true)
+                .is_ok()
+        );
+
+        let msg = error(&rules("      extensions: [jpg]\n", "{{1}}.{{ext}}"));
+        assert!(msg.contains("needs a 'filename' condition"), "{msg}");
+        let msg = error(&rules(with_filename, "{{3|date:%Y}}"));
+        assert!(msg.contains("{{3}}") && msg.contains("2 group(s)"), "{msg}");
+    }
+
+    #[test]
+    fn test_file_age_days_condition() {
+        let yaml = |age: &str| {
+            format!(
+                "rules:\n  - id: old\n    name: old\n    enabled: true\n    priority: 1\n    when:\n      file_age_days: {age}\n    then:\n      - action: move\n        to: ~/archive/\n"
+            )
+        };
+
+        let rules = RulesFile::from_yaml_str(&yaml("{ min: 30 }")).unwrap();
+        let rule = &rules.rules[0];
+        let age = rule.when.file_age_days.as_ref().unwrap();
+        assert_eq!((age.min, age.max), (Some(30), None));
+        assert_eq!(rule.when.summary(), "file_age_days=30..");
+        assert!(rule.validate(true).is_ok());
+
+        let rules = RulesFile::from_yaml_str(&yaml("{ min: 30, max: 7 }")).unwrap();
+        assert!(matches!(
+            rules.rules[0].validate(true),
+            Err(RuleValidationError::InvalidCondition(_, msg)) if msg.contains("file_age_days")
+        ));
+    }
+
+    #[test]
+    fn test_symlink_and_hardlink_action_parsing() {
+        let rules = RulesFile::from_yaml_str(
+            "rules:\n  - id: links\n    name: links\n    enabled: true\n    priority: 1\n    when:\n      extensions: [jpg]\n    then:\n      - action: symlink\n        to: /photos/all\n      - action: hardlink\n        to: /photos/backup\n        preserve_structure: true\n",
+        )
+        .unwrap();
+        let rule = &rules.rules[0];
+        assert!(matches!(
+            &rule.then[0],
+            Action::Symlink(SymlinkAction { to, preserve_structure: false }) if to == "/photos/all"
+        ));
+        assert!(matches!(
+            &rule.then[1],
+            Action::Hardlink(HardlinkAction {
+                preserve_structure: true,
+                ..
+            })
+        ));
+        assert!(rule.validate(true).is_ok());
+
+        let mut rule = rule.clone();
+        rule.then[1] = Action::Hardlink(HardlinkAction {
+            to: String::new(),
+            preserve_structure: false,
+        });
+        assert!(matches!(
+            rule.validate(true),
+            Err(RuleValidationError::InvalidAction(_, 1, _))
+        ));
+    }
+
+    #[test]
+    fn test_compress_action_parsing() {
+        let rules = RulesFile::from_yaml_str(
+            "rules:\n  - id: logs\n    name: logs\n    enabled: true\n    priority: 1\n    when:\n      extensions: [log]\n    then:\n      - action: compress\n        to: /logs/archive\n      - action: compress\n        to: /logs/zstd\n        format: zstd\n      - action: delete\n",
+        )
+        .unwrap();
+        let rule = &rules.rules[0];
+        assert!(matches!(
+            &rule.then[0],
+            Action::Compress(CompressAction {
+                format: CompressionFormat::Gzip,
+                preserve_structure: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            &rule.then[1],
+            Action::Compress(CompressAction { to, format: CompressionFormat::Zstd, .. }) if to == "/logs/zstd"
+        ));
+        assert_eq!(rule.then[1].summary(), "compress (zstd) -> /logs/zstd");
+        assert!(rule.validate(true).is_ok());
+
+        let mut rule = rule.clone();
+        rule.then[0] = Action::Compress(CompressAction {
+            to: "/logs/archive".into(),
+            format: CompressionFormat::Bzip2,
+            preserve_structure: false,
+        });
+        assert!(rule.validate(true).is_ok());
+    }
+
+    #[test]
+    fn test_set_permissions_action_parsing() {
+        let yaml = |mode: &str| {
+            format!(
+                "rules:\n  - id: scripts\n    name: scripts\n    enabled: true\n    priority: 1\n    when:\n      extensions: [sh]\n    then:\n      - action: set_permissions\n        mode: {mode}\n"
+            )
+        };
+        let mode = |rules: &RulesFile| match &rules.rules[0].then[0] {
+            Action::SetPermissions(SetPermissionsAction { mode }) => *mode,
+            other => panic!("unexpected action {other:?}"),
+        };
+
+        for (text, expected) in [
+            ("0o755", 0o755),
+            ("'0o755'", 0o755),
+            ("493", 0o755),
+            ("'493'", 0o755),
+        ] {
+            let rules = RulesFile::from_yaml_str(&yaml(text)).unwrap();
+            assert_eq!(mode(&rules), expected, "mode {text}");
+        }
+        assert!(RulesFile::from_yaml_str(&yaml("'0o9'")).is_err());
+        assert!(RulesFile::from_yaml_str(&yaml("rwx")).is_err());
+
+        // Modes are written as octal strings and read back
+        let rules = RulesFile::from_yaml_str(&yaml("0o755")).unwrap();
+        assert_eq!(rules.rules[0].then[0].summary(), "set permissions 0o755");
+        assert!(rules.rules[0].validate(true).is_ok());
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(json.contains(r#""mode":"0o755""#));
+        let from_json: RulesFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(mode(&from_json), 0o755);
+        let toml = rules.to_toml_string().unwrap();
+        assert_eq!(mode(&RulesFile::from_toml_str(&toml).unwrap()), 0o755);
+
+        let rules = RulesFile::from_yaml_str(&yaml("0o17777")).unwrap();
+        assert!(matches!(
+            rules.rules[0].validate(true),
+            Err(RuleValidationError::InvalidAction(_, 0, msg)) if msg.contains("0o17777")
+        ));
+    }
+
+    #[test]
+    fn test_touch_action_parsing() {
+        let yaml = |options: &str| {
+            format!(
+                "rules:\n  - id: stamp\n    name: stamp\n    enabled: true\n    priority: 1\n    when:\n      extensions: [jpg]\n    then:\n      - action: touch\n{options}"
+            )
+        };
+
+        let rules = RulesFile::from_yaml_str(&yaml("")).unwrap();
+        assert_eq!(
+            rules.rules[0].then[0],
+            Action::Touch(TouchAction::default())
+        );
+        assert_eq!(rules.rules[0].then[0].summary(), "touch");
+        assert!(rules.rules[0].validate(true).is_ok());
+
+        let rules = RulesFile::from_yaml_str(&yaml(
+            "        set_modified: 2024-01-01T00:00:00Z\n        set_created: 2023-06-01T12:00:00+02:00\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            rules.rules[0].then[0],
+            Action::Touch(TouchAction {
+                set_modified: Some("2024-01-01T00:00:00Z".into()),
+                set_created: Some("2023-06-01T12:00:00+02:00".into()),
+            })
+        );
+        assert_eq!(
+            rules.rules[0].then[0].summary(),
+            "touch (modified=2024-01-01T00:00:00Z, created=2023-06-01T12:00:00+02:00)"
+        );
+        assert!(rules.rules[0].validate(true).is_ok());
+
+        let rules = RulesFile::from_yaml_str(&yaml("        set_created: yesterday\n")).unwrap();
+        assert!(matches!(
+            rules.rules[0].validate(true),
+            Err(RuleValidationError::InvalidAction(_, 0, msg)) if msg.contains("set_created")
+        ));
+        assert!(RulesFile::from_yaml_str(&yaml("        set_accessed: now\n")).is_err());
+    }
+
+    #[test]
+    fn test_on_conflict_defaults_to_overwrite() {
+        let yaml = |on_conflict: &str| {
+            format!(
+                "rules:\n  - id: docs\n    name: docs\n    enabled: true\n    priority: 1\n    when:\n      extensions: [pdf]\n    then:\n      - action: move\n        to: /docs\n{on_conflict}"
+            )
+        };
+        let on_conflict = |rules: &RulesFile| match &rules.rules[0].then[0] {
+            Action::Move(inner) => inner.on_conflict,
+            other => panic!("unexpected action {other:?}"),
+        };
+
+        let rules = RulesFile::from_yaml_str(&yaml("")).unwrap();
+        assert_eq!(on_conflict(&rules), ConflictStrategy::Overwrite);
+        assert!(
+            !serde_yaml::to_string(&rules)
+                .unwrap()
+                .contains("on_conflict")
+        );
+
+        let rules = RulesFile::from_yaml_str(&yaml("        on_conflict: rename\n")).unwrap();
+        assert_eq!(on_conflict(&rules), ConflictStrategy::Rename);
+        assert!(RulesFile::from_yaml_str(&yaml("        on_conflict: merge\n")).is_err());
+    }
+
+    #[test]
+    fn test_content_hash_validation() {
+        let with_hash = |yaml: &str| {
+            let mut rule = move_rule("rule", 1, "/dest");
+            rule.when.content_hash = Some(serde_yaml::from_str(yaml).unwrap());
+            rule.validate(true)
+        };
+        let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+        assert!(with_hash(&format!("value: {sha256}")).is_ok());
+        assert!(with_hash("{algorithm: md5, value: 5D41402ABC4B2A76B9719D911017C592}").is_ok());
+        for invalid in [
+            "value: abc".to_string(),
+            format!("{{algorithm: md5, value: {sha256}}}"),
+            format!("value: {}", "z".repeat(64)),
+            format!("{{value: {sha256}, max_size_mb: 0}}"),
+        ] {
+            assert!(
+                matches!(
+                    with_hash(&invalid),
+                    Err(RuleValidationError::InvalidCondition(..))
+                ),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_conditions_validation() {
+        let nested = |depth: usize| {
+            let mut conditions = Conditions {
+                extensions: Some(vec!["pdf".to_string()]),
+                ..serde_yaml::from_str("{}").unwrap()
+            };
+            for _ in 0..depth {
+                conditions = Conditions {
+                    all: Some(vec![conditions]),
+                    ..serde_yaml::from_str("{}").unwrap()
+                };
+            }
+            let mut rule = move_rule("rule", 1, "/dest");
+            rule.when = conditions;
+            rule
+        };
+        assert!(nested(MAX_CONDITION_DEPTH).validate(true).is_ok());
+        assert!(matches!(
+            nested(MAX_CONDITION_DEPTH + 1).validate(true),
+            Err(RuleValidationError::InvalidCondition(_, msg)) if msg.contains("nested")
+        ));
+
+        let mut rule = move_rule("rule", 1, "/dest");
+        rule.when.any_of = Some(Vec::new());
+        assert!(rule.validate(true).is_err());
+
+        // Sub-conditions are checked like top-level ones
+        let mut invalid: Conditions = serde_yaml::from_str("{}").unwrap();
+        invalid.filename = Some("(unclosed".to_string());
+        rule.when.any_of = Some(vec![invalid]);
+        assert!(rule.validate(true).is_err());
+    }
+
+    #[test]
+    fn test_nested_conditions_summary_and_count() {
+        let conditions: Conditions = serde_yaml::from_str(
+            "extensions: [pdf]\nany_of:\n  - size_kb: {min: 1024}\n  - filename: '^scan'\n",
+        )
+        .unwrap();
+        assert_eq!(conditions.condition_count(), 3);
+        assert_eq!(
+            conditions.summary(),
+            "extensions=pdf and (size_kb=1024.. or filename=^scan)"
+        );
+
+        let vacuous: Conditions = serde_yaml::from_str("all:\n  - {}\n").unwrap();
+        assert!(vacuous.is_trivially_true());
+    }
+
+    #[test]
+    fn test_modified_bounds_validation() {
+        let with_bounds = |newer: Option<&str>, older: Option<&str>| {
+            let mut rule = move_rule("rule", 1, "/dest");
+            if let Action::Move(inner) = &mut rule.then[0] {
+                inner.if_newer_than = newer.map(str::to_string);
+                inner.if_older_than = older.map(str::to_string);
+            }
+            rule.validate(true)
+        };
+
+        assert!(with_bounds(Some("2024-01-01T00:00:00Z"), None).is_ok());
+        assert!(
+            with_bounds(
+                Some("2024-01-01T00:00:00+02:00"),
+                Some("2024-06-01T00:00:00Z")
+            )
+            .is_ok()
+        );
+        assert!(matches!(
+            with_bounds(Some("2024-01-01"), None),
+            Err(RuleValidationError::InvalidAction(_, 0, msg)) if msg.contains("if_newer_than")
+        ));
+        assert!(matches!(
+            with_bounds(Some("2024-06-01T00:00:00Z"), Some("2024-01-01T00:00:00Z")),
+            Err(RuleValidationError::InvalidAction(..))
+        ));
+    }
+
+    #[test]
+    fn test_metadata_comparison_validation() {
+        let with_field = |value: Option<&str>, value_type, comparison| {
+            let mut rule = move_rule("rule", 1, "/dest");
+            rule.when.metadata = Some(vec![MetadataField {
+                key: "EXIF:ISOSpeed".to_string(),
+                value: value.map(str::to_string),
+                value_type,
+                comparison,
+            }]);
+            rule.validate(true)
+        };
+        use MetadataComparison as C;
+        use MetadataValueType as T;
+
+        assert!(with_field(Some("800"), T::Integer, C::GreaterThan).is_ok());
+        assert!(with_field(Some("100..1600"), T::Integer, C::Between).is_ok());
+        assert!(with_field(Some("2024-01-01..2024-12-31"), T::Date, C::Between).is_ok());
+        assert!(with_field(Some("Canon"), T::String, C::Contains).is_ok());
+
+        for (value, value_type, comparison) in [
+            (Some("fast"), T::Integer, C::GreaterThan),
+            (Some("1600..100"), T::Integer, C::Between),
+            (Some("100"), T::Integer, C::Between),
+            (Some("Canon"), T::String, C::GreaterThan),
+            (Some("1"), T::Float, C::Contains),
+            (None, T::Integer, C::LessThan),
+        ] {
+            assert!(
+                matches!(
+                    with_field(value, value_type, comparison),
+                    Err(RuleValidationError::InvalidCondition(..))
+                ),
+                "{value:?} {value_type:?} {comparison:?} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let mut disabled = move_rule("disabled", 1, "/dest/disabled");
+        disabled.enabled = false;
+        let mut photos = move_rule("photos", 7, "~/Pictures");
+        photos.when.extensions = Some(vec!["jpg".to_string(), "png".to_string()]);
+        let rules = RulesFile {
+            rules: vec![photos, disabled],
+        };
+
+        let output = rules.pretty_print(false);
+        assert!(output.contains("photos"));
+        assert!(output.contains("P7"));
+        assert!(output.contains("extensions=jpg,png"));
+        assert!(output.contains("move -> ~/Pictures"));
+        assert!(!output.contains("disabled"));
+
+        assert!(rules.pretty_print(true).contains("/dest/disabled"));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     core::error::TookaError,
-    rules::rule::{Action, Conditions, DateRange, MetadataField, MoveAction, Range, Rule},
+    rules::rule::{
+        Action, Conditions, ConflictStrategy, DateRange, MetadataField, MoveAction, Range, Rule,
+    },
 };
 
 use serde_yaml;
@@ -18,26 +20,50 @@ pub fn generate_rule_template_yaml() -> Result<String, TookaError> {
             filename: Some(r"^.*\.jpg$".to_string()),
             extensions: Some(vec!["jpg".to_string(), "jpeg".to_string()]),
             path: None,
+            path_any: None,
+            exclude_path: None,
+            min_depth: None,
+            max_depth: None,
             size_kb: Some(Range {
                 min: Some(10),
                 max: Some(5000),
             }),
+            is_empty_file: None,
             mime_type: Some("image/jpeg".to_string()),
             created_date: Some(DateRange {
                 from: None,
                 to: None,
             }),
             modified_date: None,
+            file_age_days: None,
             is_symlink: None,
+            hardlink_count: None,
+            permissions: None,
+            case_sensitive: None,
+            content_matches: None,
+            content_max_bytes: None,
+            content_hash: None,
             metadata: Some(vec![MetadataField {
                 key: "EXIF:DateTime".to_string(),
                 value: None,
+                value_type: Default::default(),
+                comparison: Default::default(),
             }]),
+            all: None,
+            any_of: None,
         },
         then: vec![Action::Move(MoveAction {
             to: "/path/to/destination".to_string(),
             preserve_structure: false,
+            preserve_depth: None,
+            allow_directory: false,
+            verify_after_move: false,
+            if_newer_than: None,
+            if_older_than: None,
+            on_conflict: ConflictStrategy::Overwrite,
         })],
+        after: None,
+        tags: None,
     };
 
     Ok(serde_yaml::to_string(&rule)?)
@@ -1,14 +1,15 @@
 //! Date parsing utilities for Tooka.
 //!
-//! Supports both absolute dates (RFC3339 format) and relative dates
-//! like "now", "-7d", "+2w", etc.
+//! Supports absolute dates (RFC3339 format), partial dates like "2024" or
+//! "2024-06", and relative dates like "now", "-7d", "+2w", etc.
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use std::str::FromStr;
 
 /// Parses a date string that can be either:
 /// - RFC3339 format (e.g., "2025-06-20T00:00:00Z")
 /// - ISO 8601 date format (e.g., "2025-06-20")
+/// - Partial date (e.g., "2025" or "2025-06"), resolved to the start of the period
 /// - Relative format (e.g., "now", "-7d", "+2w", "-1m", "+3y")
 pub fn parse_date(date_str: &str) -> Result<DateTime<Utc>, String> {
     let date_str = date_str.trim();
@@ -30,16 +31,75 @@ pub fn parse_date(date_str: &str) -> Result<DateTime<Utc>, String> {
         }
     }
 
+    // Try to parse as partial date (YYYY or YYYY-MM)
+    if let Some((start, _)) = parse_partial_date(date_str) {
+        return Ok(start.and_time(NaiveTime::MIN).and_utc());
+    }
+
     // Try to parse as relative date
     if let Ok(dt) = parse_relative_date(date_str) {
         return Ok(dt);
     }
 
     Err(format!(
-        "Invalid date format: '{date_str}'. Expected RFC3339, ISO 8601 (YYYY-MM-DD), or relative format (e.g., 'now', '-7d', '+2w')"
+        "Invalid date format: '{date_str}'. Expected RFC3339, ISO 8601 (YYYY-MM-DD), partial date (YYYY or YYYY-MM), or relative format (e.g., 'now', '-7d', '+2w')"
     ))
 }
 
+/// Parses a date used as the start of a range, see [`parse_date`].
+///
+/// Partial dates resolve to the first second of the period, so `"2024"`
+/// becomes `2024-01-01T00:00:00Z`.
+pub fn parse_date_as_range_start(date_str: &str) -> Result<DateTime<Utc>, String> {
+    parse_date(date_str)
+}
+
+/// Parses a date used as the end of a range, see [`parse_date`].
+///
+/// Partial dates resolve to the last second of the period, so `"2024"`
+/// becomes `2024-12-31T23:59:59Z`. Other formats are parsed as by [`parse_date`].
+pub fn parse_date_as_range_end(date_str: &str) -> Result<DateTime<Utc>, String> {
+    match parse_partial_date(date_str.trim()) {
+        Some((_, end)) => {
+            let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).expect("23:59:59 is a valid time");
+            Ok(NaiveDateTime::new(end, end_of_day).and_utc())
+        }
+        None => parse_date(date_str),
+    }
+}
+
+/// Parses a year (`YYYY`) or month (`YYYY-MM`) into its first and last day
+fn parse_partial_date(date_str: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let (year, month) = match date_str.split_once('-') {
+        Some((year, month)) => (year, Some(month)),
+        None => (date_str, None),
+    };
+    if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year: i32 = year.parse().ok()?;
+
+    match month {
+        None => Some((
+            NaiveDate::from_ymd_opt(year, 1, 1)?,
+            NaiveDate::from_ymd_opt(year, 12, 31)?,
+        )),
+        Some(month) => {
+            if !(1..=2).contains(&month.len()) || !month.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let month: u32 = month.parse().ok()?;
+            let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+            let next_month = if month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)
+            }?;
+            Some((start, next_month.pred_opt()?))
+        }
+    }
+}
+
 /// Parses relative date formats like "-7d", "+2w", "-1m", "+3y"
 fn parse_relative_date(date_str: &str) -> Result<DateTime<Utc>, String> {
     let date_str = date_str.trim();
@@ -110,6 +170,32 @@ mod tests {
         assert_eq!(dt.second(), 0);
     }
 
+    #[test]
+    fn test_parse_partial_dates() {
+        let start = parse_date_as_range_start("2024").unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        let end = parse_date_as_range_end("2024").unwrap();
+        assert_eq!(end.to_rfc3339(), "2024-12-31T23:59:59+00:00");
+
+        let start = parse_date_as_range_start("2024-02").unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-02-01T00:00:00+00:00");
+        let end = parse_date_as_range_end("2024-02").unwrap();
+        assert_eq!(end.to_rfc3339(), "2024-02-29T23:59:59+00:00");
+        let end = parse_date_as_range_end("2024-12").unwrap();
+        assert_eq!(end.day(), 31);
+
+        assert_eq!(
+            parse_date("2024").unwrap(),
+            parse_date_as_range_start("2024").unwrap()
+        );
+        assert_eq!(
+            parse_date_as_range_end("2025-06-20").unwrap(),
+            parse_date("2025-06-20").unwrap()
+        );
+        assert!(parse_date("2024-13").is_err());
+        assert!(parse_date("24-06").is_err());
+    }
+
     #[test]
     fn test_parse_now() {
         let result = parse_date("now");
@@ -0,0 +1,76 @@
+//! Terminal display of sorting results.
+
+use crate::core::error::TookaError;
+use crate::core::sorter::MatchResult;
+use comfy_table::{Cell, Color, Table};
+use std::io::Write;
+
+/// Maximum number of characters of a destination shown in the table
+const MAX_DESTINATION_LENGTH: usize = 60;
+
+/// Returns the RGB color of an action, shared by tables and PDF reports,
+/// or `None` for actions without a color of their own
+pub(crate) fn action_color(action: &str) -> Option<(f32, f32, f32)> {
+    match action {
+        "move" | "move_and_hardlink" => Some((0.2, 0.4, 0.8)), // Blue-ish
+        "copy" => Some((0.2, 0.7, 0.3)),                       // Green-ish
+        "archive" | "compress" => Some((0.1, 0.55, 0.55)),     // Teal
+        "symlink" | "hardlink" => Some((0.3, 0.65, 0.85)),     // Light blue
+        "delete" => Some((0.85, 0.3, 0.3)),                    // Red-ish
+        "rename" => Some((0.8, 0.6, 0.2)),                     // Orange-ish
+        "execute" => Some((0.5, 0.2, 0.7)),                    // Purple-ish
+        "set_permissions" | "touch" => Some((0.7, 0.5, 0.1)),  // Amber
+        "skip" => Some((0.6, 0.6, 0.6)),                       // Grey
+        _ => None,
+    }
+}
+
+/// Writes the results as a table with one row per result, colored by action.
+///
+/// # Errors
+/// Returns `TookaError::Io` if writing fails.
+pub fn print_match_table(
+    results: &[MatchResult],
+    writer: &mut dyn Write,
+) -> Result<(), TookaError> {
+    let mut table = Table::new();
+    table.set_header(["File", "Rule", "Action", "Destination"]);
+    for result in results {
+        let destination = truncate_start(
+            &result.new_path.display().to_string(),
+            MAX_DESTINATION_LENGTH,
+        );
+        let row = [
+            result.file_name.as_str(),
+            result.matched_rule_id.as_str(),
+            result.action.as_str(),
+            destination.as_str(),
+        ]
+        .map(|text| match action_color(&result.action) {
+            Some((r, g, b)) => Cell::new(text).fg(Color::Rgb {
+                r: to_u8(r),
+                g: to_u8(g),
+                b: to_u8(b),
+            }),
+            None => Cell::new(text),
+        });
+        table.add_row(row);
+    }
+    writeln!(writer, "{table}")?;
+    Ok(())
+}
+
+/// Shortens `text` to at most `max` characters by replacing its start with `…`,
+/// keeping the end of a path, which is the most telling part
+pub(crate) fn truncate_start(text: &str, max: usize) -> String {
+    let len = text.chars().count();
+    if len <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(len - max + 1).collect();
+    format!("…{kept}")
+}
+
+pub(crate) fn to_u8(component: f32) -> u8 {
+    (component * 255.0).round() as u8
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::sorter::MatchResult;
+    use crate::utils::display::{print_match_table, truncate_start};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_truncate_start_keeps_the_end() {
+        assert_eq!(truncate_start("/short", 10), "/short");
+        assert_eq!(truncate_start("/a/very/long/path.txt", 10), "…/path.txt");
+        assert_eq!(
+            truncate_start("/a/very/long/path.txt", 10).chars().count(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_print_match_table_lists_results() {
+        let results = vec![MatchResult {
+            file_name: "notes.txt".to_string(),
+            action: "move".to_string(),
+            matched_rule_id: "docs".to_string(),
+            current_path: PathBuf::from("/in/notes.txt"),
+            new_path: PathBuf::from("/out/docs/notes.txt"),
+            bytes_affected: 5,
+        }];
+
+        let mut output = Vec::new();
+        print_match_table(&results, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        for text in [
+            "File",
+            "Destination",
+            "notes.txt",
+            "docs",
+            "move",
+            "/out/docs/notes.txt",
+        ] {
+            assert!(output.contains(text), "missing '{text}' in:\n{output}");
+        }
+    }
+}
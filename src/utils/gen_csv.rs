@@ -0,0 +1,22 @@
+//! CSV serialization of sorting results.
+//!
+//! Rows follow [`MatchResult::CSV_HEADER`]. Fields containing commas, quotes
+//! or line breaks are quoted as described in RFC 4180.
+
+use crate::core::{error::TookaError, sorter::MatchResult};
+use std::io::Write;
+
+/// Writes a header row followed by one row per result to `writer`, and
+/// returns the writer.
+///
+/// # Errors
+/// Returns `TookaError` if writing fails.
+pub(crate) fn write_results<W: Write>(writer: W, results: &[MatchResult]) -> Result<W, TookaError> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(MatchResult::CSV_HEADER)?;
+    for result in results {
+        wtr.write_record(result.to_csv_row())?;
+    }
+    wtr.into_inner()
+        .map_err(|e| TookaError::Other(format!("Failed to write CSV: {}", e.error())))
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::sorter::{MatchResult, test_result};
+    use crate::utils::gen_csv::write_results;
+
+    #[test]
+    fn test_write_results_header_and_rows() {
+        let bytes = write_results(Vec::new(), &[test_result("a.txt", "docs", "move")]).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "file_name,action,matched_rule_id,current_path,new_path\n\
+             a.txt,move,docs,/source/a.txt,/dest/docs/a.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_write_results_quotes_special_characters() {
+        let results = [
+            test_result("a,b.txt", "docs", "move"),
+            test_result("line\nbreak.txt", "docs", "move"),
+            test_result("say \"hi\".txt", "docs", "move"),
+        ];
+        let csv = String::from_utf8(write_results(Vec::new(), &results).unwrap()).unwrap();
+        assert!(csv.contains("\"a,b.txt\",move"));
+        assert!(csv.contains("\"/source/line\nbreak.txt\""));
+        assert!(csv.contains("\"say \"\"hi\"\".txt\""));
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let parsed: Vec<MatchResult> = reader
+            .records()
+            .map(|record| {
+                let record = record.unwrap();
+                MatchResult::from_csv_row(&record.iter().collect::<Vec<_>>()).unwrap()
+            })
+            .collect();
+        // CSV rows don't record the bytes affected
+        let expected: Vec<_> = results
+            .iter()
+            .map(|r| MatchResult {
+                bytes_affected: 0,
+                ..r.clone()
+            })
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_write_results_empty() {
+        let bytes = write_results(Vec::new(), &[]).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "file_name,action,matched_rule_id,current_path,new_path\n"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Self-contained HTML reports: a single file with inline CSS, a summary,
+//! an SVG bar chart of the actions and a table of results per rule.
+
+use crate::core::{report::ReportSummary, sorter::MatchResult};
+use crate::utils::display::{action_color, to_u8};
+use chrono::Local;
+use std::{collections::BTreeMap, path::Path};
+
+// Bar chart layout, in pixels
+const CHART_WIDTH: usize = 600;
+const LABEL_WIDTH: usize = 150;
+const COUNT_WIDTH: usize = 50;
+const BAR_HEIGHT: usize = 20;
+const BAR_GAP: usize = 6;
+
+/// Color of actions without a color of their own
+const DEFAULT_COLOR: &str = "#000000";
+
+const STYLE: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;margin:2em;color:#222}\
+h1{margin-bottom:0}\
+.timestamp{color:#666;margin-top:.2em}\
+table{border-collapse:collapse;width:100%;margin-bottom:1.5em}\
+th,td{border:1px solid #ddd;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#f4f4f4}\
+table.summary{width:auto}\
+td.path{font-family:monospace;word-break:break-all}\
+td.action{font-weight:bold}\
+.empty{color:#666}";
+
+/// Generates an HTML report of `results` at `path`.
+///
+/// # Errors
+/// Returns an error if the file can't be written.
+pub(crate) fn generate_html(
+    path: &Path,
+    results: &[MatchResult],
+    summary: &ReportSummary,
+) -> Result<(), std::io::Error> {
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    std::fs::write(path, render_html(results, summary, &timestamp))
+}
+
+/// Renders the HTML report with the given header timestamp.
+///
+/// The output only depends on the arguments, which keeps it reproducible in tests.
+pub(crate) fn render_html(
+    results: &[MatchResult],
+    summary: &ReportSummary,
+    timestamp: &str,
+) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Tooka Sort Report</title>\n");
+    html.push_str(&format!("<style>{STYLE}</style>\n</head>\n<body>\n"));
+    html.push_str("<h1>Tooka Sort Report</h1>\n");
+    html.push_str(&format!(
+        "<p class=\"timestamp\">{}</p>\n",
+        escape(timestamp)
+    ));
+
+    html.push_str("<h2>Summary</h2>\n<table class=\"summary\">\n");
+    for (label, value) in summary.lines() {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            escape(&label),
+            escape(&value)
+        ));
+    }
+    html.push_str("</table>\n");
+    html.push_str(&render_chart(summary));
+
+    if results.is_empty() {
+        html.push_str("<p class=\"empty\">No files were sorted.</p>\n");
+    }
+
+    let mut grouped: BTreeMap<&str, Vec<&MatchResult>> = BTreeMap::new();
+    for result in results {
+        grouped
+            .entry(result.matched_rule_id.as_str())
+            .or_default()
+            .push(result);
+    }
+    for (rule_id, mut entries) in grouped {
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        html.push_str(&format!(
+            "<h2>Rule: {} ({})</h2>\n<table>\n<tr><th>File</th><th>Action</th><th>From</th><th>To</th></tr>\n",
+            escape(rule_id),
+            entries.len()
+        ));
+        for entry in entries {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td class=\"action\" style=\"color:{}\">{}</td><td class=\"path\">{}</td><td class=\"path\">{}</td></tr>\n",
+                escape(&entry.file_name),
+                css_color(&entry.action),
+                escape(&entry.action),
+                escape(&entry.current_path.display().to_string()),
+                escape(&entry.new_path.display().to_string())
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders a horizontal SVG bar chart with the number of results per action
+fn render_chart(summary: &ReportSummary) -> String {
+    let mut counts: Vec<(&String, &usize)> = summary.by_action.iter().collect();
+    if counts.is_empty() {
+        return String::new();
+    }
+    counts.sort();
+
+    let max_count = counts
+        .iter()
+        .map(|(_, count)| **count)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let max_bar_width = CHART_WIDTH - LABEL_WIDTH - COUNT_WIDTH;
+    let height = counts.len() * (BAR_HEIGHT + BAR_GAP);
+
+    let mut svg = format!(
+        "<h2>Actions</h2>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" height=\"{height}\" role=\"img\" aria-label=\"Results per action\">\n"
+    );
+    for (i, (action, count)) in counts.into_iter().enumerate() {
+        let y = i * (BAR_HEIGHT + BAR_GAP);
+        let text_y = y + BAR_HEIGHT * 3 / 4;
+        let width = (count * max_bar_width / max_count).max(1);
+        svg.push_str(&format!(
+            "<text x=\"0\" y=\"{text_y}\" font-size=\"14\">{}</text>\
+<rect x=\"{LABEL_WIDTH}\" y=\"{y}\" width=\"{width}\" height=\"{BAR_HEIGHT}\" fill=\"{}\"/>\
+<text x=\"{}\" y=\"{text_y}\" font-size=\"14\">{count}</text>\n",
+            escape(action),
+            css_color(action),
+            LABEL_WIDTH + width + 5
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Returns the color of an action as a CSS hex color, matching the PDF report
+fn css_color(action: &str) -> String {
+    action_color(action).map_or_else(
+        || DEFAULT_COLOR.to_string(),
+        |(r, g, b)| format!("#{:02x}{:02x}{:02x}", to_u8(r), to_u8(g), to_u8(b)),
+    )
+}
+
+/// Escapes text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
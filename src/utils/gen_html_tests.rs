@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::report::compute_summary;
+    use crate::core::sorter::{MatchResult, SortStats, test_result};
+    use crate::utils::gen_html::render_html;
+
+    /// Fixed header timestamp so the output does not depend on the clock
+    const TIMESTAMP: &str = "2024-01-01 12:00:00";
+
+    /// Helper function to render a report of `results`
+    fn render(results: &[MatchResult]) -> String {
+        let summary = compute_summary(results, &SortStats::from_results(results));
+        render_html(results, &summary, TIMESTAMP)
+    }
+
+    #[test]
+    fn test_render_html_groups_results_by_rule() {
+        let html = render(&[
+            test_result("a.jpg", "photos", "move"),
+            test_result("b.txt", "backup", "copy"),
+            test_result("c.tmp", "temp", "delete"),
+            test_result("d.bin", "none", "skip"),
+        ]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(TIMESTAMP));
+        for rule in ["photos", "backup", "temp", "none"] {
+            assert!(html.contains(&format!("<h2>Rule: {rule} (1)</h2>")));
+        }
+        for action in ["move", "copy", "delete", "skip"] {
+            assert!(html.contains(&format!(">{action}</td>")));
+        }
+        // Rules are listed in alphabetical order
+        assert!(html.find("Rule: backup").unwrap() < html.find("Rule: photos").unwrap());
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn test_render_html_chart_uses_action_colors() {
+        let html = render(&[
+            test_result("a.jpg", "photos", "move"),
+            test_result("b.jpg", "photos", "move"),
+            test_result("c.txt", "backup", "copy"),
+            test_result("d.tmp", "temp", "delete"),
+            test_result("e.bin", "none", "skip"),
+            test_result("f.sh", "scripts", "custom"),
+        ]);
+
+        let svg = &html[html.find("<svg").unwrap()..html.find("</svg>").unwrap()];
+        assert_eq!(svg.matches("<rect").count(), 5);
+        for color in ["#3366cc", "#33b34d", "#d94d4d", "#999999", "#000000"] {
+            assert!(svg.contains(&format!("fill=\"{color}\"")), "{color}");
+        }
+        assert!(svg.contains(">2</text>"));
+    }
+
+    #[test]
+    fn test_render_html_escapes_text() {
+        let html = render(&[test_result("<b>&\"x\".txt", "rule<1>", "move")]);
+        assert!(html.contains("&lt;b&gt;&amp;&quot;x&quot;.txt"));
+        assert!(html.contains("Rule: rule&lt;1&gt;"));
+        assert!(!html.contains("<b>"));
+    }
+
+    #[test]
+    fn test_render_html_empty() {
+        let html = render(&[]);
+        assert!(html.contains("No files were sorted."));
+        assert!(!html.contains("<svg"));
+    }
+}
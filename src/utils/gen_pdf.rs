@@ -1,4 +1,5 @@
-use crate::core::sorter::MatchResult;
+use crate::core::{report::ReportSummary, sorter::MatchResult};
+use crate::utils::display::action_color;
 use chrono::Local;
 use pdf_writer::{Chunk, Content, Name, Pdf, Rect, Ref, Str};
 use std::{collections::BTreeMap, path::Path};
@@ -31,6 +32,7 @@ const RULE_BEFORE_SPACING: f32 = 15.0; // Space before rule title when following
 const RULE_AFTER_SPACING: f32 = 20.0; // Space after rule title before next content
 const RULE_FONT_SIZE_OFFSET: f32 = 2.0;
 const TOTAL_CHANGES_Y_OFFSET: f32 = 15.0;
+const SUMMARY_MAX_CHARS: usize = 90;
 
 // Box and content styling
 const BOX_PADDING: f32 = 20.0;
@@ -66,17 +68,27 @@ struct PDFGenerator {
     y: f32,
     first_page: bool,
     last_rule_id: Option<String>,
-    total_results: usize,
+    summary_lines: Vec<String>,
+    timestamp: String,
 }
 
 impl PDFGenerator {
-    fn new(total_results: usize) -> Self {
+    fn new(summary: &ReportSummary, timestamp: &str) -> Self {
         let mut alloc = Ref::new(1);
         let mut pdf = Pdf::new();
 
         let (font_name, font_id) = Self::init_fonts(&mut pdf, &mut alloc);
         let page_tree_id = alloc.bump();
 
+        let summary_lines: Vec<String> = summary
+            .lines()
+            .into_iter()
+            .map(|(label, value)| truncate_text(&format!("{label}: {value}"), SUMMARY_MAX_CHARS))
+            .collect();
+        // The summary below the title pushes the content of the first page down
+        #[allow(clippy::cast_precision_loss)]
+        let summary_height = summary_lines.len().saturating_sub(1) as f32 * LINE_HEIGHT;
+
         Self {
             pdf,
             alloc,
@@ -87,20 +99,19 @@ impl PDFGenerator {
             content: Content::new(),
             extg_states: vec![],
             page_number: 1,
-            y: PAGE_HEIGHT - MARGIN_TOP - CONTENT_START_OFFSET,
+            y: PAGE_HEIGHT - MARGIN_TOP - CONTENT_START_OFFSET - summary_height,
             first_page: true,
             last_rule_id: None,
-            total_results,
+            summary_lines,
+            timestamp: timestamp.to_string(),
         }
     }
 
-    fn generate(mut self, path: &Path, results: &[MatchResult]) -> Result<(), anyhow::Error> {
+    fn render(mut self, results: &[MatchResult]) -> Vec<u8> {
         let flat_entries = Self::prepare_entries(results);
         self.render_pages(&flat_entries);
         self.finalize();
-
-        std::fs::write(path, self.pdf.finish())?;
-        Ok(())
+        self.pdf.finish()
     }
 
     fn init_fonts(pdf: &mut Pdf, alloc: &mut Ref) -> (Name<'static>, Ref) {
@@ -288,15 +299,14 @@ impl PDFGenerator {
     fn draw_header(&mut self) {
         self.write_text("Tooka Report", TITLE_FONT_SIZE, TITLE_POS_X, TITLE_POS_Y);
 
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let timestamp = std::mem::take(&mut self.timestamp);
         self.write_text(&timestamp, TIMESTAMP_FONT_SIZE, TIME_POS_X, TIME_POS_Y);
 
-        self.write_text(
-            &format!("Total changes: {}", self.total_results),
-            FONT_SIZE,
-            TITLE_POS_X,
-            TITLE_POS_Y - TOTAL_CHANGES_Y_OFFSET,
-        );
+        let mut y = TITLE_POS_Y - TOTAL_CHANGES_Y_OFFSET;
+        for line in std::mem::take(&mut self.summary_lines) {
+            self.write_text(&line, FONT_SIZE, TITLE_POS_X, y);
+            y -= LINE_HEIGHT;
+        }
     }
 
     fn draw_match_result_block(&mut self, result: &MatchResult, y_start: f32) {
@@ -305,15 +315,7 @@ impl PDFGenerator {
         let to_path = PDFGenerator::format_path_with_wrapping(&result.new_path, MAX_PATH_LENGTH);
 
         // Set colors based on action
-        let color = match result.action.as_str() {
-            "move" => (0.2, 0.4, 0.8),    // Blue-ish
-            "copy" => (0.2, 0.7, 0.3),    // Green-ish
-            "delete" => (0.85, 0.3, 0.3), // Red-ish
-            "rename" => (0.8, 0.6, 0.2),  // Orange-ish
-            "execute" => (0.5, 0.2, 0.7), // Purple-ish
-            "skip" => (0.6, 0.6, 0.6),    // Grey
-            _ => (0.0, 0.0, 0.0),         // Default to black
-        };
+        let color = action_color(&result.action).unwrap_or((0.0, 0.0, 0.0));
 
         self.content.set_fill_rgb(color.0, color.1, color.2);
 
@@ -496,9 +498,35 @@ impl PDFGenerator {
     }
 }
 
-pub(crate) fn generate_pdf(path: &Path, results: &[MatchResult]) -> Result<(), anyhow::Error> {
-    let generator = PDFGenerator::new(results.len());
-    generator.generate(path, results)
+pub(crate) fn generate_pdf(
+    path: &Path,
+    results: &[MatchResult],
+    summary: &ReportSummary,
+) -> Result<(), anyhow::Error> {
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    std::fs::write(path, render_pdf(results, summary, &timestamp))?;
+    Ok(())
+}
+
+/// Renders the PDF report with the given header timestamp and returns its bytes.
+///
+/// The output only depends on the arguments, which keeps it reproducible in tests.
+pub(crate) fn render_pdf(
+    results: &[MatchResult],
+    summary: &ReportSummary,
+    timestamp: &str,
+) -> Vec<u8> {
+    PDFGenerator::new(summary, timestamp).render(results)
+}
+
+/// Shortens `text` to at most `max_chars` characters, ending with `...` if cut
+fn truncate_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    truncated.push_str("...");
+    truncated
 }
 
 fn truncate_path(path: &Path, max_len: f32) -> String {
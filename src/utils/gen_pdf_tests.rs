@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::report::compute_summary;
+    use crate::core::sorter::{MatchResult, SortStats};
+    use crate::utils::gen_pdf::render_pdf;
+    use sha2::{Digest, Sha256};
+
+    /// Fixed header timestamp so the output does not depend on the clock
+    const TIMESTAMP: &str = "2024-01-01 12:00:00";
+
+    /// Helper function to create `count` deterministic results spread over three rules
+    fn results(count: usize) -> Vec<MatchResult> {
+        (0..count)
+            .map(|i| {
+                let (rule, action) =
+                    [("photos", "move"), ("backup", "copy"), ("temp", "delete")][i % 3];
+                let file_name = format!("file_{i:03}.txt");
+                MatchResult {
+                    file_name: file_name.clone(),
+                    action: action.to_string(),
+                    matched_rule_id: rule.to_string(),
+                    current_path: format!("/home/user/Downloads/{file_name}").into(),
+                    new_path: format!("/home/user/{rule}/{file_name}").into(),
+                    bytes_affected: 1024 * i as u64,
+                }
+            })
+            .collect()
+    }
+
+    /// Helper function to render a report and return the SHA-256 of its bytes
+    fn pdf_hash(results: &[MatchResult]) -> String {
+        let mut summary = compute_summary(results, &SortStats::from_results(results));
+        summary.duration_ms = 1234;
+        let bytes = render_pdf(results, &summary, TIMESTAMP);
+        assert!(bytes.starts_with(b"%PDF-"));
+        format!("{:x}", Sha256::digest(&bytes))
+    }
+
+    #[test]
+    fn test_render_pdf_is_deterministic() {
+        assert_eq!(pdf_hash(&results(5)), pdf_hash(&results(5)));
+    }
+
+    #[test]
+    fn test_render_pdf_with_link_actions() {
+        let mut linked = results(4);
+        linked[0].action = "symlink".to_string();
+        linked[1].action = "hardlink".to_string();
+        assert_ne!(pdf_hash(&linked), pdf_hash(&results(4)));
+    }
+
+    #[test]
+    fn test_pdf_snapshot_single_page() {
+        insta::assert_snapshot!(pdf_hash(&results(5)));
+    }
+
+    #[test]
+    fn test_pdf_snapshot_multiple_pages() {
+        insta::assert_snapshot!(pdf_hash(&results(60)));
+    }
+
+    #[test]
+    fn test_pdf_snapshot_empty() {
+        insta::assert_snapshot!(pdf_hash(&[]));
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::md5::Md5;
+
+    fn md5_hex(data: &[u8]) -> String {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finish_hex()
+    }
+
+    #[test]
+    fn test_md5_known_digests() {
+        // Test vectors from RFC 1321
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            md5_hex(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn test_md5_split_updates() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let mut split = Md5::new();
+        for chunk in data.chunks(37) {
+            split.update(chunk);
+        }
+        assert_eq!(split.finish_hex(), md5_hex(&data));
+    }
+}
@@ -1,3 +1,30 @@
 pub mod date_parser;
+pub mod display;
+pub(crate) mod gen_csv;
+#[doc(hidden)]
+pub mod gen_html;
+#[cfg(feature = "pdf")]
+#[doc(hidden)]
 pub mod gen_pdf;
+pub(crate) mod md5;
+#[cfg(feature = "pdf-meta")]
+#[doc(hidden)]
+pub mod pdf_meta;
+#[doc(hidden)]
 pub mod rename_pattern;
+pub(crate) mod toml;
+
+#[cfg(test)]
+mod display_tests;
+#[cfg(test)]
+mod gen_csv_tests;
+#[cfg(test)]
+mod gen_html_tests;
+#[cfg(all(test, feature = "pdf"))]
+mod gen_pdf_tests;
+#[cfg(test)]
+mod md5_tests;
+#[cfg(all(test, feature = "pdf-meta"))]
+mod pdf_meta_tests;
+#[cfg(test)]
+mod toml_tests;
@@ -0,0 +1,259 @@
+//! Reading of PDF document properties for Tooka's rename templates.
+//!
+//! Finds the document information dictionary through the `/Info` entry of the
+//! trailer and reads its text entries. Information dictionaries stored inside
+//! compressed object streams are not supported; such files have no properties.
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Information dictionary entries and the template keys they are exposed as
+pub(crate) const PDF_PROPERTIES: [(&str, &str); 5] = [
+    ("Title", "pdf_title"),
+    ("Author", "pdf_author"),
+    ("Subject", "pdf_subject"),
+    ("Creator", "pdf_creator"),
+    ("CreationDate", "pdf_creation_date"),
+];
+
+/// Reads the document properties of a PDF, keyed by template key
+/// (e.g. `pdf_title`). Properties that are missing or can't be decoded are
+/// left out; `pdf_creation_date` is converted to RFC 3339 when possible.
+pub(crate) fn read_pdf_properties(path: &Path) -> HashMap<String, String> {
+    let Ok(bytes) = fs::read(path) else {
+        return HashMap::new();
+    };
+    let Some(info) = find_info_dictionary(&bytes) else {
+        log::debug!("No document information found in {}", path.display());
+        return HashMap::new();
+    };
+
+    let mut properties = HashMap::new();
+    for (name, key) in PDF_PROPERTIES {
+        if let Some(mut value) = dictionary_string(info, name) {
+            if name == "CreationDate" {
+                if let Some(date) = parse_pdf_date(&value) {
+                    value = date.to_rfc3339();
+                }
+            }
+            properties.insert(key.to_string(), value);
+        }
+    }
+    properties
+}
+
+/// Returns the bytes of the information dictionary referenced by the last
+/// `/Info N G R` in the file, which is the newest after incremental updates
+fn find_info_dictionary(bytes: &[u8]) -> Option<&[u8]> {
+    let info_at = rfind(bytes, b"/Info")?;
+    let mut tokens = bytes[info_at + 5..]
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|token| !token.is_empty());
+    let number = tokens.next()?;
+    let generation = tokens.next()?;
+    if !number.iter().all(u8::is_ascii_digit) || !generation.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let header = [number, b" ", generation, b" obj"].concat();
+    let mut search_end = bytes.len();
+    let object_at = loop {
+        let at = rfind(&bytes[..search_end], &header)?;
+        // Don't mistake `10 0 obj` for `0 0 obj`
+        if at == 0 || !bytes[at - 1].is_ascii_digit() {
+            break at + header.len();
+        }
+        search_end = at;
+    };
+
+    let start = object_at + find(&bytes[object_at..], b"<<")?;
+    let end = dictionary_end(bytes, start)?;
+    Some(&bytes[start..end])
+}
+
+/// Returns the index just past the `>>` closing the dictionary at `start`
+fn dictionary_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => i = literal_string_end(bytes, i)?,
+            b'<' if bytes.get(i + 1) == Some(&b'<') => {
+                depth += 1;
+                i += 2;
+            }
+            b'>' if bytes.get(i + 1) == Some(&b'>') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the index just past the `)` closing the literal string at `start`
+fn literal_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Reads the literal or hex string stored under `/name` in a dictionary
+fn dictionary_string(dictionary: &[u8], name: &str) -> Option<String> {
+    let key = [b"/", name.as_bytes()].concat();
+    let mut from = 0;
+    let value_at = loop {
+        let at = from + find(&dictionary[from..], &key)?;
+        let after = at + key.len();
+        // `/Creator` must not match `/CreatorTool`
+        match dictionary.get(after) {
+            Some(b) if b.is_ascii_alphanumeric() => from = after,
+            _ => break after,
+        }
+    };
+
+    let value = dictionary[value_at..].trim_ascii_start();
+    let raw = match value.first()? {
+        b'(' => unescape_literal(&value[1..literal_string_end(value, 0)? - 1]),
+        b'<' => decode_hex(&value[1..find(value, b">")?])?,
+        _ => return None,
+    };
+    Some(decode_text(&raw))
+}
+
+/// Resolves the escape sequences of a literal string's contents
+fn unescape_literal(contents: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(contents.len());
+    let mut i = 0;
+    while i < contents.len() {
+        if contents[i] != b'\\' {
+            out.push(contents[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        let Some(&escaped) = contents.get(i) else {
+            break;
+        };
+        i += 1;
+        match escaped {
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b't' => out.push(b'\t'),
+            b'b' => out.push(0x08),
+            b'f' => out.push(0x0C),
+            b'0'..=b'7' => {
+                let mut value = u32::from(escaped - b'0');
+                for _ in 0..2 {
+                    match contents.get(i) {
+                        Some(d @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(d - b'0');
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                out.push((value & 0xFF) as u8);
+            }
+            // A backslash at the end of a line continues the string
+            b'\r' => {
+                if contents.get(i) == Some(&b'\n') {
+                    i += 1;
+                }
+            }
+            b'\n' => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Decodes the digits of a hex string, ignoring whitespace
+fn decode_hex(digits: &[u8]) -> Option<Vec<u8>> {
+    let mut digits: Vec<u8> = digits
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    // A missing final digit is assumed to be 0
+    if digits.len() % 2 == 1 {
+        digits.push(b'0');
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Decodes a text string, which is UTF-16BE with a byte order mark or
+/// otherwise treated as Latin-1
+fn decode_text(raw: &[u8]) -> String {
+    match raw.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => raw.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+/// Parses a PDF date such as `D:20240301120000+01'00'`. Missing fields
+/// default to the start of the period and a missing offset to UTC.
+pub(crate) fn parse_pdf_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits_len = value.bytes().take_while(u8::is_ascii_digit).count();
+    if digits_len < 4 {
+        return None;
+    }
+    let (digits, zone) = value.split_at(digits_len.min(14));
+    let padded = format!("{digits}{}", &"0101000000"[digits.len() - 4..]);
+    let local = NaiveDateTime::parse_from_str(&padded, "%Y%m%d%H%M%S").ok()?;
+
+    let zone = zone.trim_start_matches(|c: char| c.is_ascii_digit());
+    let offset = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let mut parts = zone[1..].split('\'').filter(|p| !p.is_empty());
+            let hours: i32 = parts.next()?.parse().ok()?;
+            let minutes: i32 = parts.next().map_or(Ok(0), str::parse).ok()?;
+            let seconds = hours * 3600 + minutes * 60;
+            FixedOffset::east_opt(if sign == '-' { -seconds } else { seconds })?
+        }
+        _ => FixedOffset::east_opt(0)?,
+    };
+    offset.from_local_datetime(&local).single()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::pdf_meta::{parse_pdf_date, read_pdf_properties};
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// Helper function to build a minimal PDF whose object 10 is the given
+    /// information dictionary
+    fn pdf_with_info(info: &str) -> Vec<u8> {
+        format!(
+            "%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+             2 0 obj\n<< /Type /Pages /Kids [] /Count 0 >>\nendobj\n\
+             10 0 obj\n{info}\nendobj\n\
+             trailer\n<< /Size 11 /Root 1 0 R /Info 10 0 R >>\n%%EOF\n"
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_read_pdf_properties() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("paper.pdf");
+        fs::write(
+            &path,
+            pdf_with_info(
+                r"<< /Title (On \(Nested\) Parens\051) /Author <FEFF004A00FC 00720067 0065006E>
+                   /CreatorTool (ignored) /Creator (Writer) /CreationDate (D:20240301120000+01'00') >>",
+            ),
+        )
+        .unwrap();
+
+        let properties = read_pdf_properties(&path);
+
+        assert_eq!(properties["pdf_title"], "On (Nested) Parens)");
+        assert_eq!(properties["pdf_author"], "Jürgen");
+        assert_eq!(properties["pdf_creator"], "Writer");
+        assert_eq!(properties["pdf_creation_date"], "2024-03-01T12:00:00+01:00");
+        assert!(!properties.contains_key("pdf_subject"));
+    }
+
+    #[test]
+    fn test_read_pdf_properties_uses_latest_update() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("updated.pdf");
+        let mut bytes = pdf_with_info("<< /Title (Draft) >>");
+        bytes.extend_from_slice(
+            b"10 0 obj\n<< /Title (Final) >>\nendobj\ntrailer\n<< /Size 11 /Info 10 0 R >>\n%%EOF\n",
+        );
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(read_pdf_properties(&path)["pdf_title"], "Final");
+    }
+
+    #[test]
+    fn test_read_pdf_properties_without_info() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.pdf");
+        fs::write(&path, b"%PDF-1.4\ntrailer\n<< /Size 1 >>\n%%EOF\n").unwrap();
+
+        assert!(read_pdf_properties(&path).is_empty());
+        assert!(read_pdf_properties(&dir.path().join("missing.pdf")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_pdf_date() {
+        let date = |value| parse_pdf_date(value).map(|d| d.to_rfc3339());
+
+        assert_eq!(
+            date("D:20240301120000Z").as_deref(),
+            Some("2024-03-01T12:00:00+00:00")
+        );
+        assert_eq!(
+            date("D:199912312359-05'30").as_deref(),
+            Some("1999-12-31T23:59:00-05:30")
+        );
+        assert_eq!(date("D:2024").as_deref(), Some("2024-01-01T00:00:00+00:00"));
+        assert_eq!(date("yesterday"), None);
+    }
+}
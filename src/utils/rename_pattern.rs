@@ -1,5 +1,6 @@
 use crate::core::error::TookaError;
 use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+#[cfg(feature = "exif")]
 use exif::{In, Reader, Tag};
 use regex::Regex;
 use std::collections::HashMap;
@@ -8,22 +9,115 @@ use std::path::Path;
 use std::sync::LazyLock;
 
 /// Cached regex pattern for template matching
-static TEMPLATE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"\{\{(.*?)\}\}").expect("Failed to compile template regex")
-});
-
-/// Evaluates a template string with metadata and file information.
-pub(crate) fn evaluate_template(
-    template: &str,
-    file_path: &Path,
-    metadata: &HashMap<String, String>,
-) -> String {
-    let file_name = file_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_string();
+static TEMPLATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{(.*?)\}\}").expect("Failed to compile template regex"));
+
+/// File information available to `{{...}}` placeholders in templates.
+///
+/// Besides `{{filename}}` and `{{metadata.<key>}}`, templates can use
+/// `{{ext}}`, `{{mime_family}}` (e.g. `image`), `{{year}}`, `{{month}}`
+/// and `{{day}}` of the file's modification date, and the file size as
+/// `{{size_bytes}}`, `{{size_kb}}`, `{{size_mb}}` (rounded) or `{{size_human}}`
+/// (e.g. `1.4MiB`). With the `pdf-meta` feature, PDFs also provide
+/// `{{pdf_title}}`, `{{pdf_author}}`, `{{pdf_subject}}`, `{{pdf_creator}}` and
+/// `{{pdf_creation_date}}`, which are empty when the document doesn't set them.
+/// Numbered placeholders such as `{{1}}` take the metadata entry with that
+/// key, which the sorter fills with the capture groups of the rule's
+/// `filename` regex.
+pub(crate) struct TemplateContext {
+    metadata: HashMap<String, String>,
+    values: HashMap<&'static str, String>,
+}
+
+impl TemplateContext {
+    /// Builds the template context for a file from its extracted metadata.
+    pub(crate) fn new(file_path: &Path, metadata: HashMap<String, String>) -> Self {
+        let mut values = HashMap::new();
+
+        let file_stem = file_path.file_stem().and_then(|s| s.to_str());
+        values.insert("filename", file_stem.unwrap_or("").to_string());
+
+        let ext = file_path.extension().and_then(|s| s.to_str());
+        values.insert("ext", ext.unwrap_or("").to_string());
+
+        let mime_family = mime_guess::from_path(file_path)
+            .first()
+            .map(|mime| mime.type_().to_string());
+        values.insert("mime_family", mime_family.unwrap_or_default());
+
+        let modified = metadata
+            .get("modified")
+            .and_then(|m| DateTime::parse_from_rfc3339(m).ok());
+        for (key, fmt) in [("year", "%Y"), ("month", "%m"), ("day", "%d")] {
+            let value = modified.map(|dt| dt.format(fmt).to_string());
+            values.insert(key, value.unwrap_or_default());
+        }
+
+        if let Some(size) = metadata.get("size").and_then(|s| s.parse::<u64>().ok()) {
+            let rounded = |unit: u64| (size.saturating_add(unit / 2) / unit).to_string();
+            values.insert("size_bytes", size.to_string());
+            values.insert("size_kb", rounded(1024));
+            values.insert("size_mb", rounded(1024 * 1024));
+            values.insert("size_human", format_size_human(size));
+        }
+
+        #[cfg(feature = "pdf-meta")]
+        for (_, key) in crate::utils::pdf_meta::PDF_PROPERTIES {
+            values.insert(key, metadata.get(key).cloned().unwrap_or_default());
+        }
 
+        Self { metadata, values }
+    }
+
+    fn value(&self, key: &str) -> String {
+        if let Some(metadata_key) = key.strip_prefix("metadata.") {
+            self.metadata.get(metadata_key).cloned().unwrap_or_default()
+        } else if capture_group_index(key).is_some() {
+            self.metadata.get(key).cloned().unwrap_or_default()
+        } else {
+            self.values.get(key).cloned().unwrap_or_default()
+        }
+    }
+}
+
+/// Formats a byte count with a binary prefix and one decimal place, without
+/// spaces so it can be used in file names, e.g. `1.4MiB`.
+fn format_size_human(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1}{}", UNITS[unit])
+}
+
+/// Returns the group number of a numbered placeholder key such as `1`
+fn capture_group_index(key: &str) -> Option<usize> {
+    if key.is_empty() || !key.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    key.parse().ok()
+}
+
+/// Returns the capture group numbers used as `{{1}}`, `{{2}}`, ... in `template`.
+pub(crate) fn template_capture_groups(template: &str) -> Vec<usize> {
+    TEMPLATE_REGEX
+        .captures_iter(template)
+        .filter_map(|caps| {
+            let key = caps[1].split('|').next().unwrap_or("").trim();
+            capture_group_index(key)
+        })
+        .collect()
+}
+
+/// Evaluates a template string with the file information in `context`.
+pub(crate) fn evaluate_template(template: &str, context: &TemplateContext) -> String {
     let mut result = template.to_string();
 
     for caps in TEMPLATE_REGEX.captures_iter(template) {
@@ -34,21 +128,45 @@ pub(crate) fn evaluate_template(
         let key = parts.next().unwrap().trim();
         let filters: Vec<&str> = parts.collect();
 
-        let raw_value = if key == "filename" {
-            file_name.clone()
-        } else if let Some(metadata_key) = key.strip_prefix("metadata.") {
-            metadata.get(metadata_key).cloned().unwrap_or_default()
-        } else {
-            String::new()
-        };
-
-        let final_value = apply_filters(raw_value, &filters);
+        let final_value = apply_filters(context.value(key), &filters);
         result = result.replace(full_match, &final_value);
     }
 
     result
 }
 
+/// Replaces `$1`, `$2`, ... in `template` with the matching capture groups.
+/// Groups that did not participate in the match are replaced with nothing,
+/// and `$$` produces a literal `$`.
+pub(crate) fn substitute_captures(template: &str, caps: &regex::Captures) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'$') {
+            chars.next();
+            result.push('$');
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+            digits.push(*d);
+            chars.next();
+        }
+        match digits.parse::<usize>() {
+            Ok(group) => result.push_str(caps.get(group).map_or("", |m| m.as_str())),
+            Err(_) => result.push('$'),
+        }
+    }
+
+    result
+}
+
 fn apply_filters(value: String, filters: &[&str]) -> String {
     let mut val = value;
     for filter in filters {
@@ -88,6 +206,7 @@ pub(crate) fn extract_metadata(file_path: &Path) -> Result<HashMap<String, Strin
     map.insert("size".into(), metadata.len().to_string());
 
     // Attempt to parse EXIF (for JPEG/PNG)
+    #[cfg(feature = "exif")]
     if let Ok(file) = fs::File::open(file_path) {
         if let Ok(reader) = Reader::new().read_from_container(&mut std::io::BufReader::new(file)) {
             for field in reader.fields() {
@@ -103,5 +222,10 @@ pub(crate) fn extract_metadata(file_path: &Path) -> Result<HashMap<String, Strin
         }
     }
 
+    #[cfg(feature = "pdf-meta")]
+    if mime_guess::from_path(file_path).first() == Some(mime_guess::mime::APPLICATION_PDF) {
+        map.extend(crate::utils::pdf_meta::read_pdf_properties(file_path));
+    }
+
     Ok(map)
 }
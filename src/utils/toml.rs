@@ -0,0 +1,721 @@
+//! Reading and writing TOML rule files.
+//!
+//! TOML documents are converted to and from a `serde_yaml::Value`, so rules
+//! use the same serde schema in both formats and keep their field order.
+//! The parser covers TOML 1.0 as far as rule files need it. Dates and times
+//! are read as strings, which is how rules store them.
+
+use crate::core::error::TookaError;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_yaml::{Mapping, Number, Value};
+use std::collections::HashSet;
+
+/// Serializes `value` to a TOML document
+///
+/// # Errors
+/// Returns an error if `value` is not a table at the top level, or holds
+/// values TOML can't represent, such as `null` inside an array.
+pub(crate) fn to_string<T: Serialize>(value: &T) -> Result<String, TookaError> {
+    let value = serde_yaml::to_value(value)?;
+    let Value::Mapping(table) = value else {
+        return Err(TookaError::Toml(
+            "only tables can be written at the top level".into(),
+        ));
+    };
+    let mut out = String::new();
+    write_table(&mut out, &[], &table)?;
+    Ok(out)
+}
+
+/// Parses a TOML document into `T`
+///
+/// # Errors
+/// Returns an error if the TOML is malformed or does not match `T`.
+pub(crate) fn from_str<T: DeserializeOwned>(content: &str) -> Result<T, TookaError> {
+    from_value(parse(content)?)
+}
+
+/// Deserializes `T` from a parsed TOML document
+///
+/// # Errors
+/// Returns an error if `value` does not match `T`.
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, TookaError> {
+    serde_yaml::from_value(value).map_err(|e| TookaError::Toml(e.to_string()))
+}
+
+/// Parses a TOML document into its top-level table
+///
+/// # Errors
+/// Returns an error with the line number if the TOML is malformed.
+pub(crate) fn parse(content: &str) -> Result<Value, TookaError> {
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser
+        .document()
+        .map(Value::Mapping)
+        .map_err(|e| TookaError::Toml(format!("line {}: {e}", parser.line)))
+}
+
+// === Writing ===
+
+/// Writes the entries of a table: plain values first, then sub-tables and
+/// arrays of tables under their own headers, as TOML requires.
+fn write_table(out: &mut String, path: &[String], table: &Mapping) -> Result<(), TookaError> {
+    let entries = table
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| Ok((key_string(key)?, value)))
+        .collect::<Result<Vec<_>, TookaError>>()?;
+
+    for (key, value) in &entries {
+        if !is_table(value) && !is_array_of_tables(value) {
+            out.push_str(&format!("{} = {}\n", format_key(key), inline_value(value)?));
+        }
+    }
+    for (key, value) in &entries {
+        let mut child = path.to_vec();
+        child.push(key.clone());
+        match value {
+            Value::Mapping(table) if is_table(value) => {
+                write_header(out, "[", &child, "]");
+                write_table(out, &child, table)?;
+            }
+            Value::Sequence(items) if is_array_of_tables(value) => {
+                for item in items {
+                    if let Value::Mapping(table) = item {
+                        write_header(out, "[[", &child, "]]");
+                        write_table(out, &child, table)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn write_header(out: &mut String, open: &str, path: &[String], close: &str) {
+    let keys: Vec<String> = path.iter().map(|key| format_key(key)).collect();
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&format!("{open}{}{close}\n", keys.join(".")));
+}
+
+/// Whether a value is written as a `[table]`. Tables holding only plain
+/// values, e.g. ranges, are short enough to be written inline instead.
+fn is_table(value: &Value) -> bool {
+    match value {
+        Value::Mapping(table) => table
+            .values()
+            .any(|value| matches!(value, Value::Mapping(_) | Value::Sequence(_))),
+        _ => false,
+    }
+}
+
+/// Whether a value is a non-empty array of tables, written as `[[array]]`
+fn is_array_of_tables(value: &Value) -> bool {
+    match value {
+        Value::Sequence(items) => {
+            !items.is_empty() && items.iter().all(|item| matches!(item, Value::Mapping(_)))
+        }
+        _ => false,
+    }
+}
+
+fn key_string(key: &Value) -> Result<String, TookaError> {
+    match key {
+        Value::String(key) => Ok(key.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        other => Err(TookaError::Toml(format!("unsupported table key {other:?}"))),
+    }
+}
+
+fn format_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+fn inline_value(value: &Value) -> Result<String, TookaError> {
+    Ok(match value {
+        Value::Null => return Err(TookaError::Toml("null values can't be written".into())),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => format_number(number),
+        Value::String(text) => quote(text),
+        Value::Sequence(items) => {
+            let items = items
+                .iter()
+                .map(inline_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Mapping(table) => {
+            let mut entries = Vec::new();
+            for (key, value) in table {
+                if !value.is_null() {
+                    entries.push(format!(
+                        "{} = {}",
+                        format_key(&key_string(key)?),
+                        inline_value(value)?
+                    ));
+                }
+            }
+            if entries.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+        Value::Tagged(tagged) => {
+            return Err(TookaError::Toml(format!(
+                "tagged value {} can't be written",
+                tagged.tag
+            )));
+        }
+    })
+}
+
+fn format_number(number: &Number) -> String {
+    match number.as_f64() {
+        Some(float) if number.is_f64() => {
+            if float.is_nan() {
+                "nan".to_string()
+            } else if float.is_infinite() {
+                if float > 0.0 { "inf" } else { "-inf" }.to_string()
+            } else {
+                // Debug formatting always includes a fraction or an exponent
+                format!("{float:?}")
+            }
+        }
+        _ => number.to_string(),
+    }
+}
+
+/// Quotes a string as a TOML basic string
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// === Parsing ===
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn document(&mut self) -> Result<Mapping, String> {
+        let mut root = Mapping::new();
+        let mut current: Vec<String> = Vec::new();
+        let mut defined: HashSet<Vec<String>> = HashSet::new();
+
+        loop {
+            self.skip_blank_lines();
+            let Some(c) = self.peek() else {
+                return Ok(root);
+            };
+            if c == '[' {
+                self.pos += 1;
+                let array = self.eat('[');
+                self.skip_spaces();
+                let path = self.key()?;
+                self.skip_spaces();
+                self.expect(']')?;
+                if array {
+                    self.expect(']')?;
+                    push_table(&mut root, &path)?;
+                    // Sub-tables of the previous element may be defined again
+                    defined.retain(|table| !table.starts_with(&path) || table == &path);
+                } else {
+                    if !defined.insert(path.clone()) {
+                        return Err(format!("table [{}] is defined twice", path.join(".")));
+                    }
+                    define_table(&mut root, &path)?;
+                }
+                current = path;
+            } else {
+                let key = self.key()?;
+                self.skip_spaces();
+                self.expect('=')?;
+                self.skip_spaces();
+                let value = self.value()?;
+                let table = table_at(&mut root, &current)?;
+                insert(table, &key, value)?;
+            }
+            self.end_of_line()?;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(format!("expected '{expected}', found '{c}'")),
+            None => Err(format!("expected '{expected}', found end of file")),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if self.eat('\r') {
+                continue;
+            }
+            if !self.eat('\n') {
+                return;
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(format!("expected the end of the line, found '{c}'")),
+        }
+    }
+
+    /// Parses a dotted key such as `a."b.c".d`
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') => {
+                    self.next();
+                    self.basic_string()?
+                }
+                Some('\'') => {
+                    self.next();
+                    self.literal_string()?
+                }
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(match self.peek() {
+                            Some(c) => format!("expected a key, found '{c}'"),
+                            None => "expected a key, found end of file".to_string(),
+                        });
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            keys.push(key);
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(keys);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => {
+                self.pos += 3;
+                self.multiline_string('"').map(Value::String)
+            }
+            Some('\'') if self.starts_with("'''") => {
+                self.pos += 3;
+                self.multiline_string('\'').map(Value::String)
+            }
+            Some('"') => {
+                self.next();
+                self.basic_string().map(Value::String)
+            }
+            Some('\'') => {
+                self.next();
+                self.literal_string().map(Value::String)
+            }
+            Some('[') => {
+                self.next();
+                self.array()
+            }
+            Some('{') => {
+                self.next();
+                self.inline_table()
+            }
+            Some(_) if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Bool(true))
+            }
+            Some(_) if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Bool(false))
+            }
+            Some(_) => self.number_or_date(),
+            None => Err("expected a value, found end of file".to_string()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => text.push(self.escape()?),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(text),
+                Some('\n') | None => return Err("unterminated string".to_string()),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    /// Parses a `"""` or `'''` string after its opening quotes
+    fn multiline_string(&mut self, quote: char) -> Result<String, String> {
+        let closing: String = std::iter::repeat_n(quote, 3).collect();
+        // A newline right after the opening quotes is trimmed
+        if self.starts_with("\r\n") {
+            self.pos += 1;
+        }
+        if self.peek() == Some('\n') {
+            self.next();
+        }
+        let mut text = String::new();
+        loop {
+            if self.starts_with(&closing) {
+                // Up to two quotes may directly precede the closing ones
+                let mut quotes = 0;
+                while self.peek_at(quotes) == Some(quote) {
+                    quotes += 1;
+                }
+                if quotes > 5 {
+                    return Err("too many quotes at the end of a string".to_string());
+                }
+                text.extend(std::iter::repeat_n(quote, quotes - 3));
+                self.pos += quotes;
+                return Ok(text);
+            }
+            match self.next() {
+                None => return Err("unterminated string".to_string()),
+                Some('\\') if quote == '"' => {
+                    if matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                        // A line ending backslash trims all following whitespace
+                        while matches!(self.peek(), Some(' ' | '\t' | '\r' | '\n')) {
+                            self.next();
+                        }
+                    } else {
+                        text.push(self.escape()?);
+                    }
+                }
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        Ok(match self.next() {
+            Some('b') => '\u{8}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => self.unicode_escape(4)?,
+            Some('U') => self.unicode_escape(8)?,
+            Some(c) => return Err(format!("invalid escape '\\{c}'")),
+            None => return Err("unterminated string".to_string()),
+        })
+    }
+
+    fn unicode_escape(&mut self, len: usize) -> Result<char, String> {
+        let digits: String = (0..len).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid unicode escape '{digits}'"))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.eat(']') {
+                return Ok(Value::Sequence(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank_lines();
+            if !self.eat(',') {
+                self.skip_blank_lines();
+                self.expect(']')?;
+                return Ok(Value::Sequence(items));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        let mut table = Mapping::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Mapping(table));
+        }
+        loop {
+            let key = self.key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            insert(&mut table, &key, value)?;
+            self.skip_spaces();
+            if !self.eat(',') {
+                self.expect('}')?;
+                return Ok(Value::Mapping(table));
+            }
+        }
+    }
+
+    fn number_or_date(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.' | ':'))
+        {
+            self.pos += 1;
+        }
+        // A date may be followed by a time, separated by a space
+        if is_date(&self.chars[start..self.pos])
+            && self.peek() == Some(' ')
+            && self.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+            && self.peek_at(3) == Some(':')
+        {
+            self.pos += 1;
+            while self
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | ':'))
+            {
+                self.pos += 1;
+            }
+        }
+        let token: String = self.chars[start..self.pos].iter().collect();
+        if token.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => format!("expected a value, found '{c}'"),
+                None => "expected a value, found end of file".to_string(),
+            });
+        }
+        if is_date(&self.chars[start..self.pos]) || is_time(&token) {
+            return Ok(Value::String(token));
+        }
+        parse_number(&token)
+            .map(Value::Number)
+            .ok_or_else(|| format!("invalid value '{token}'"))
+    }
+}
+
+/// Whether a token starts like a `YYYY-MM-DD` date
+fn is_date(token: &[char]) -> bool {
+    token.len() >= 10
+        && token[..4].iter().all(char::is_ascii_digit)
+        && token[4] == '-'
+        && token[7] == '-'
+}
+
+/// Whether a token is a local time such as `07:32:00`
+fn is_time(token: &str) -> bool {
+    token.len() >= 8 && token.as_bytes()[2] == b':' && token.as_bytes()[5] == b':'
+}
+
+fn parse_number(token: &str) -> Option<Number> {
+    let (negative, unsigned) = match token.as_bytes().first()? {
+        b'-' => (true, &token[1..]),
+        b'+' => (false, &token[1..]),
+        _ => (false, token),
+    };
+    match unsigned {
+        "inf" => {
+            return Some(Number::from(if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }));
+        }
+        "nan" => return Some(Number::from(f64::NAN)),
+        _ => {}
+    }
+    // Underscores are only allowed between digits
+    if unsigned.starts_with('_') || unsigned.ends_with('_') || unsigned.contains("__") {
+        return None;
+    }
+    let digits = unsigned.replace('_', "");
+
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(rest) = digits.strip_prefix(prefix) {
+            if negative || token.starts_with('+') {
+                return None;
+            }
+            return u64::from_str_radix(rest, radix).ok().map(Number::from);
+        }
+    }
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if digits.contains(['.', 'e', 'E']) {
+        let float: f64 = digits.parse().ok()?;
+        return Some(Number::from(if negative { -float } else { float }));
+    }
+    if digits.len() > 1 && digits.starts_with('0') {
+        return None;
+    }
+    if negative {
+        format!("-{digits}").parse::<i64>().ok().map(Number::from)
+    } else {
+        digits.parse::<u64>().ok().map(Number::from)
+    }
+}
+
+/// Returns the table a value or header refers to, descending into the last
+/// element of arrays of tables
+fn descend<'a>(table: &'a mut Mapping, key: &str) -> Result<&'a mut Mapping, String> {
+    let entry = table
+        .entry(Value::String(key.to_string()))
+        .or_insert_with(|| Value::Mapping(Mapping::new()));
+    match entry {
+        Value::Mapping(table) => Ok(table),
+        Value::Sequence(items) => match items.last_mut() {
+            Some(Value::Mapping(table)) => Ok(table),
+            _ => Err(format!("'{key}' is not a table")),
+        },
+        _ => Err(format!("'{key}' is not a table")),
+    }
+}
+
+fn table_at<'a>(root: &'a mut Mapping, path: &[String]) -> Result<&'a mut Mapping, String> {
+    path.iter().try_fold(root, |table, key| descend(table, key))
+}
+
+/// Creates the table of a `[table]` header
+fn define_table(root: &mut Mapping, path: &[String]) -> Result<(), String> {
+    let (last, parents) = path.split_last().ok_or("empty table name")?;
+    let parent = table_at(root, parents)?;
+    match parent.get(last.as_str()) {
+        None | Some(Value::Mapping(_)) => descend(parent, last).map(|_| ()),
+        Some(_) => Err(format!("'{}' is already defined", path.join("."))),
+    }
+}
+
+/// Appends a table for an `[[array]]` header
+fn push_table(root: &mut Mapping, path: &[String]) -> Result<(), String> {
+    let (last, parents) = path.split_last().ok_or("empty table name")?;
+    let parent = table_at(root, parents)?;
+    let entry = parent
+        .entry(Value::String(last.clone()))
+        .or_insert_with(|| Value::Sequence(Vec::new()));
+    match entry {
+        Value::Sequence(items) => {
+            items.push(Value::Mapping(Mapping::new()));
+            Ok(())
+        }
+        _ => Err(format!("'{}' is not an array of tables", path.join("."))),
+    }
+}
+
+/// Inserts a value at a dotted key, creating the intermediate tables
+fn insert(table: &mut Mapping, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().ok_or("empty key")?;
+    let table = parents
+        .iter()
+        .try_fold(table, |table, key| descend(table, key))?;
+    if table.contains_key(last.as_str()) {
+        return Err(format!("duplicate key '{}'", key.join(".")));
+    }
+    table.insert(Value::String(last.clone()), value);
+    Ok(())
+}
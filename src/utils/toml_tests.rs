@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod tests {
+    use crate::core::error::TookaError;
+    use crate::utils::toml::{parse, to_string};
+    use serde::Serialize;
+    use serde_yaml::Value;
+    use std::collections::BTreeMap;
+
+    fn parse_yaml(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let document = parse(
+            r#"# A comment
+title = "Basic \"string\"\t\u00e9"  # trailing comment
+path = 'C:\Users\tooka'
+count = 1_000
+negative = -17
+hex = 0xff
+ratio = 2.5e3
+enabled = true
+date = 2024-03-15
+datetime = 1979-05-27 07:32:00Z
+time = 07:32:00
+empty = []
+nested = [[1, 2], ["a"],]
+multiline = """
+first \
+  second"""
+literal = '''
+raw \n'''
+"dotted.key" = 1
+site.name = "tooka"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            document,
+            parse_yaml(
+                r#"
+title: "Basic \"string\"\té"
+path: 'C:\Users\tooka'
+count: 1000
+negative: -17
+hex: 255
+ratio: 2500.0
+enabled: true
+date: "2024-03-15"
+datetime: "1979-05-27 07:32:00Z"
+time: "07:32:00"
+empty: []
+nested: [[1, 2], ["a"]]
+multiline: "first second"
+literal: "raw \\n"
+dotted.key: 1
+site:
+  name: tooka
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_tables() {
+        let document = parse(
+            r#"
+[server]
+host = "localhost"
+limits = { min = 1, max = { soft = 5 } }
+
+[server.tls]
+enabled = false
+
+[[rules]]
+id = "a"
+
+[[rules.then]]
+action = "skip"
+
+[[rules]]
+id = "b"
+[rules.when]
+any = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            document,
+            parse_yaml(
+                r#"
+server:
+  host: localhost
+  limits: {min: 1, max: {soft: 5}}
+  tls: {enabled: false}
+rules:
+  - id: a
+    then: [{action: skip}]
+  - id: b
+    when: {any: true}
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (toml, message) in [
+            ("a = 1\na = 2", "line 2: duplicate key 'a'"),
+            ("[t]\n[t]", "line 2: table [t] is defined twice"),
+            ("a = \"open", "unterminated string"),
+            ("a = 1 b = 2", "expected the end of the line"),
+            ("a = 012", "invalid value '012'"),
+            ("a = 1\n[a]", "'a' is already defined"),
+            ("a = [1, 2", "expected ']'"),
+            ("= 1", "expected a key"),
+        ] {
+            match parse(toml) {
+                Err(TookaError::Toml(error)) => {
+                    assert!(error.contains(message), "{toml:?}: {error}");
+                }
+                other => panic!("{toml:?} should fail, got {other:?}"),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Document {
+        name: String,
+        missing: Option<String>,
+        tags: Vec<String>,
+        range: BTreeMap<String, u64>,
+        ratio: f64,
+        tables: Vec<BTreeMap<String, Value>>,
+    }
+
+    #[test]
+    fn test_to_string() {
+        let document = Document {
+            name: "line \"one\"\nline two".into(),
+            missing: None,
+            tags: vec!["a b".into(), "c".into()],
+            range: BTreeMap::from([("min".into(), 1)]),
+            ratio: 2.0,
+            tables: vec![BTreeMap::from([
+                ("key.with.dots".into(), Value::Bool(true)),
+                ("list".into(), parse_yaml("[1, {x: 2}]")),
+            ])],
+        };
+
+        let toml = to_string(&document).unwrap();
+
+        assert_eq!(
+            toml,
+            r#"name = "line \"one\"\nline two"
+tags = ["a b", "c"]
+range = { min = 1 }
+ratio = 2.0
+
+[[tables]]
+"key.with.dots" = true
+list = [1, { x = 2 }]
+"#
+        );
+        assert_eq!(parse(&toml).unwrap()["tables"][0]["list"][1]["x"], 2);
+    }
+
+    #[test]
+    fn test_to_string_rejects_unsupported_values() {
+        assert!(matches!(to_string(&vec![1, 2]), Err(TookaError::Toml(_))));
+        assert!(matches!(
+            to_string(&BTreeMap::from([("list", vec![None, Some(1)])])),
+            Err(TookaError::Toml(_))
+        ));
+    }
+}